@@ -16,6 +16,37 @@ fn get_process_mutex() -> &'static Mutex<Option<Child>> {
     RUNNING_PROCESS.get_or_init(|| Mutex::new(None))
 }
 
+// Stdin of the running script, kept separately so input can be fed while the
+// waiter thread holds the process handle
+static RUNNING_PROCESS_STDIN: std::sync::OnceLock<Mutex<Option<ChildStdin>>> =
+    std::sync::OnceLock::new();
+
+fn get_process_stdin_mutex() -> &'static Mutex<Option<ChildStdin>> {
+    RUNNING_PROCESS_STDIN.get_or_init(|| Mutex::new(None))
+}
+
+// What list_running_processes reports about the running script. Kept apart
+// from RUNNING_PROCESS, which the waiter thread holds until the script exits.
+struct RunningScriptInfo {
+    pid: u32,
+    run_id: Option<String>,
+    started_at: std::time::Instant,
+}
+
+static RUNNING_SCRIPT_INFO: std::sync::OnceLock<Mutex<Option<RunningScriptInfo>>> =
+    std::sync::OnceLock::new();
+
+fn get_script_info_mutex() -> &'static Mutex<Option<RunningScriptInfo>> {
+    RUNNING_SCRIPT_INFO.get_or_init(|| Mutex::new(None))
+}
+
+/// Drop the running script's stdin, signalling EOF to the process
+fn close_process_stdin() {
+    if let Ok(mut guard) = get_process_stdin_mutex().lock() {
+        *guard = None;
+    }
+}
+
 // Inference Server state with channel for responses
 struct InferenceProcess {
     child: Child,
@@ -25,6 +56,18 @@ struct InferenceProcess {
     #[allow(dead_code)]
     response_rx: mpsc::Receiver<InferenceResponse>,
     pending_requests: Arc<Mutex<HashMap<String, mpsc::Sender<InferenceResponse>>>>,
+    version_id: String,
+    audit: Option<InferenceAuditMode>, // None = auditing off
+    started_at: std::time::Instant,
+    warmed_up: bool, // answered a prediction successfully
+}
+
+/// How run_inference records requests in the audit trail
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InferenceAuditMode {
+    Full,   // store input and output JSON as-is
+    Hashed, // store only SHA-256 digests of them
 }
 
 static INFERENCE_SERVER: std::sync::OnceLock<Mutex<Option<InferenceProcess>>> =
@@ -66,6 +109,57 @@ pub struct PredictionResult {
     pub probabilities: Option<Vec<Vec<f64>>>,
     pub classes: Option<Vec<serde_json::Value>>,
     pub message: Option<String>,
+    pub code: Option<InferenceErrorCode>, // set when status is "error"
+    pub guidance: Option<String>,
+}
+
+/// Error codes sent by inference_server.py. Anything unrecognised, including
+/// errors from servers that predate codes, falls into `Unknown`.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum InferenceErrorCode {
+    ModelNotLoaded,
+    FeatureMismatch,
+    InvalidDtype,
+    EmptyInput,
+    InvalidRequest,
+    PredictionFailed,
+    #[serde(other)]
+    Unknown,
+}
+
+impl InferenceErrorCode {
+    /// What the user can do about the error
+    pub fn guidance(self) -> &'static str {
+        match self {
+            InferenceErrorCode::ModelNotLoaded => {
+                "The model could not be loaded. Restart the server or re-register the model file."
+            }
+            InferenceErrorCode::FeatureMismatch => {
+                "The input doesn't match the features the model was trained on. Check column names and count."
+            }
+            InferenceErrorCode::InvalidDtype => {
+                "Some input values aren't numeric. Encode categorical columns before predicting."
+            }
+            InferenceErrorCode::EmptyInput => "No input rows were provided.",
+            InferenceErrorCode::InvalidRequest => "The request was malformed. This is likely a bug.",
+            InferenceErrorCode::PredictionFailed => {
+                "The model rejected the input. See the message for details."
+            }
+            InferenceErrorCode::Unknown => "The inference server reported an unexpected error.",
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct BenchmarkResult {
+    pub n_requests: usize,
+    pub concurrency: usize, // client threads; the server still answers one at a time
+    pub errors: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub requests_per_sec: f64,
 }
 
 #[derive(Clone, Deserialize, Debug)]
@@ -79,6 +173,7 @@ struct InferenceResponse {
     probabilities: Option<Vec<Vec<f64>>>,
     classes: Option<Vec<serde_json::Value>>,
     message: Option<String>,
+    code: Option<InferenceErrorCode>,
 }
 
 #[derive(Clone, Serialize)]
@@ -105,7 +200,12 @@ pub enum ScriptEvent {
     #[serde(rename = "complete")]
     Complete,
     #[serde(rename = "exit")]
-    Exit { code: i32 },
+    Exit {
+        code: i32,
+        // Set when the script file was kept for debugging
+        #[serde(rename = "scriptPath", skip_serializing_if = "Option::is_none")]
+        script_path: Option<String>,
+    },
     #[serde(rename = "trial")]
     Trial {
         #[serde(rename = "trialNumber")]
@@ -227,18 +327,136 @@ pub fn find_python(app: AppHandle) -> Option<python::PythonInfo> {
     python::find_python(resource_dir.as_ref())
 }
 
+/// Which interpreters `find_python` checked, why each was skipped, and which won
+#[tauri::command]
+pub fn explain_python_resolution(app: AppHandle) -> python::PythonResolution {
+    let resource_dir = app.path().resource_dir().ok();
+    python::explain_python_resolution(resource_dir.as_ref())
+}
+
+/// Progress of `create_venv`, emitted as `venv-progress`
+#[derive(Clone, Serialize)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum VenvProgress {
+    Creating,
+    Installing { package: String, current: usize, total: usize },
+    Done,
+}
+
+/// Create a virtualenv at `dest_path`, optionally install the ML packages into
+/// it, and save it as the Python to use
+#[tauri::command]
+pub async fn create_venv(
+    app: AppHandle,
+    dest_path: String,
+    install_packages: Option<bool>,
+) -> Result<python::PythonInfo, String> {
+    let resource_dir = app.path().resource_dir().ok();
+    let base_python = python::find_python(resource_dir.as_ref())
+        .ok_or("No Python installation found to create the virtualenv from")?;
+    let dest = std::path::PathBuf::from(&dest_path);
+
+    // venv creation and pip can take minutes; keep them off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let _ = app.emit("venv-progress", VenvProgress::Creating);
+        let info = python::create_venv(&base_python.path, &dest)?;
+
+        if install_packages.unwrap_or(true) {
+            let total = python::VENV_PACKAGES.len();
+            python::install_venv_packages(&info.path, python::VENV_PACKAGES, |i, package| {
+                let _ = app.emit(
+                    "venv-progress",
+                    VenvProgress::Installing { package: package.to_string(), current: i + 1, total },
+                );
+            })?;
+        }
+
+        db::set_setting("python_path", &info.path.to_string_lossy()).map_err(|e| e.to_string())?;
+        let _ = app.emit("venv-progress", VenvProgress::Done);
+        Ok(info)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn get_python_diagnostics(app: AppHandle) -> python::PythonDiagnostics {
+    let resource_dir = app.path().resource_dir().ok();
+    python::python_diagnostics(resource_dir.as_ref())
+}
+
+// Prints the interpreter version and installed versions of the ML packages
+// we care about, skipping ones that aren't installed
+const CAPTURE_ENVIRONMENT_PY: &str = r#"
+import json, sys
+from importlib import metadata
+packages = {}
+for name in ["numpy", "pandas", "scikit-learn", "scipy", "joblib", "xgboost", "lightgbm",
+             "optuna", "shap", "matplotlib", "onnx", "onnxruntime", "skl2onnx", "coremltools",
+             "torch", "tensorflow"]:
+    try:
+        packages[name] = metadata.version(name)
+    except metadata.PackageNotFoundError:
+        pass
+print(json.dumps({"python_version": sys.version, "packages": packages}))
+"#;
+
+/// Ask the interpreter for its version and ML package versions
+fn capture_python_environment(
+    python_path: &std::path::Path,
+) -> Result<(String, serde_json::Value), String> {
+    let output = Command::new(python_path)
+        .args(["-c", CAPTURE_ENVIRONMENT_PY])
+        .output()
+        .map_err(|e| format!("Failed to capture environment: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Environment capture failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value: serde_json::Value = serde_json::from_str(stdout.lines().last().unwrap_or(""))
+        .map_err(|e| format!("Invalid environment output: {}", e))?;
+    let python_version = value["python_version"].as_str().unwrap_or_default().to_string();
+    Ok((python_version, value["packages"].clone()))
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn run_script(
     app: AppHandle,
     script_code: String,
     input_path: String,
+    keep_script: Option<bool>,
+    keep_script_on_failure: Option<bool>,
+    env: Option<HashMap<String, String>>,
+    run_id: Option<String>,
+    session_id: Option<String>,
 ) -> Result<(), String> {
+    if let Some(ref env) = env {
+        validate_env_vars(env)?;
+    }
+    let keep_script = keep_script.unwrap_or(false);
+    let keep_script_on_failure = keep_script_on_failure.unwrap_or(false);
+
     // Get Python path
     let resource_dir = app.path().resource_dir().ok();
     let python_info = python::find_python(resource_dir.as_ref())
         .ok_or_else(|| "No Python installation found".to_string())?;
     let python_path = python_info.path;
 
+    // Record the environment for reproducibility without delaying the run
+    if let Some(run_id) = run_id.clone() {
+        let python_path = python_path.clone();
+        std::thread::spawn(move || {
+            if let Ok((python_version, packages)) = capture_python_environment(&python_path) {
+                let _ = db::save_run_environment(&run_id, &python_version, &packages.to_string());
+            }
+        });
+    }
+
     // Create temp script file
     let app_data_dir = app
         .path()
@@ -253,26 +471,35 @@ pub async fn run_script(
     std::fs::write(&script_path, &script_code).map_err(|e| e.to_string())?;
 
     // Spawn Python process
-    let mut child = Command::new(&python_path)
-        .arg("-u") // Unbuffered output
-        .arg(&script_path)
-        .arg(&input_path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
+    let mut child = script_command(&python_path, &script_path, &input_path, env.as_ref())
         .spawn()
         .map_err(|e| e.to_string())?;
 
+    let stdin = child.stdin.take().ok_or("Failed to capture stdin")?;
     let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
     let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
 
-    // Store process handle for cancellation
+    // Store process handle for cancellation, and stdin for interactive input
+    {
+        let mut guard = get_script_info_mutex().lock().map_err(|e| e.to_string())?;
+        *guard = Some(RunningScriptInfo {
+            pid: child.id(),
+            run_id,
+            started_at: std::time::Instant::now(),
+        });
+    }
     {
         let mut guard = get_process_mutex().lock().map_err(|e| e.to_string())?;
         *guard = Some(child);
     }
+    {
+        let mut guard = get_process_stdin_mutex().lock().map_err(|e| e.to_string())?;
+        *guard = Some(stdin);
+    }
 
     let app_clone = app.clone();
     let script_path_clone = script_path.clone();
+    let stdout_session_id = session_id.clone();
 
     // Spawn thread to read stdout
     std::thread::spawn(move || {
@@ -280,6 +507,7 @@ pub async fn run_script(
         for line in reader.lines() {
             if let Ok(line) = line {
                 let event = parse_output_line(&line);
+                save_trial_event(stdout_session_id.as_deref(), &event);
                 let _ = app_clone.emit("script-output", event);
             }
         }
@@ -292,7 +520,9 @@ pub async fn run_script(
         let reader = BufReader::new(stderr);
         for line in reader.lines() {
             if let Ok(line) = line {
-                let _ = app_clone2.emit("script-output", ScriptEvent::Error { message: line });
+                let event = parse_stderr_line(&line);
+                save_trial_event(session_id.as_deref(), &event);
+                let _ = app_clone2.emit("script-output", event);
             }
         }
     });
@@ -314,20 +544,170 @@ pub async fn run_script(
             let mut guard = get_process_mutex().lock().unwrap();
             *guard = None;
         }
+        if let Ok(mut guard) = get_script_info_mutex().lock() {
+            *guard = None;
+        }
+        close_process_stdin();
 
-        // Clean up temp script file
-        let _ = std::fs::remove_file(&script_path_clone);
+        // Clean up temp script file unless it's kept for debugging
+        let keep = keep_script || (keep_script_on_failure && exit_code != 0);
+        let script_path = finish_script_file(&script_path_clone, keep);
 
         // Emit completion events
         let _ = app_clone3.emit("script-output", ScriptEvent::Complete);
-        let _ = app_clone3.emit("script-output", ScriptEvent::Exit { code: exit_code });
+        let _ = app_clone3.emit("script-output", ScriptEvent::Exit { code: exit_code, script_path });
     });
 
     Ok(())
 }
 
+/// Store a `trial` event of a tuning run in its session and point the session
+/// at its best trial, so trials survive the UI closing mid-run. The frontend
+/// may save the same trial too; the upsert on trial number keeps one row.
+fn save_trial_event(session_id: Option<&str>, event: &ScriptEvent) {
+    let (Some(session_id), ScriptEvent::Trial { trial_number, params, score, duration_ms }) = (session_id, event) else {
+        return;
+    };
+    let saved = db::create_tuning_trial(
+        &uuid::Uuid::new_v4().to_string(),
+        session_id,
+        *trial_number as i32,
+        &params.to_string(),
+        Some(*score),
+        duration_ms.map(|d| d as i64),
+        "completed",
+    )
+    .and_then(|_| db::refresh_best_tuning_trial(session_id));
+    if let Err(e) = saved {
+        tracing::warn!("Failed to save trial {} of tuning session {}: {}", trial_number, session_id, e);
+    }
+}
+
+/// Keys with `=` or NUL can't be passed to a process environment
+fn validate_env_vars(env: &HashMap<String, String>) -> Result<(), String> {
+    for (key, value) in env {
+        if key.is_empty() || key.contains('=') || key.contains('\0') {
+            return Err(format!("Invalid environment variable name: {:?}", key));
+        }
+        if value.contains('\0') {
+            return Err(format!("Environment variable {} contains a NUL byte", key));
+        }
+    }
+    Ok(())
+}
+
+/// Command for a pipeline script run, with piped stdio and `env` merged
+/// over the inherited environment
+fn script_command(
+    python_path: &std::path::Path,
+    script_path: &std::path::Path,
+    input_path: &str,
+    env: Option<&HashMap<String, String>>,
+) -> Command {
+    let mut cmd = Command::new(python_path);
+    cmd.arg("-u") // Unbuffered output
+        .arg(script_path)
+        .arg(input_path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(env) = env {
+        cmd.envs(env);
+    }
+    cmd
+}
+
+// Kept scripts older than this are removed at startup
+const SCRIPT_MAX_AGE_DAYS: u64 = 7;
+
+/// Remove `script_*.py` files in `scripts_dir` last modified more than
+/// `max_age` ago and return the bytes reclaimed. Server scripts and other
+/// files are left alone.
+fn remove_old_scripts(scripts_dir: &std::path::Path, max_age: Duration) -> u64 {
+    let Ok(entries) = std::fs::read_dir(scripts_dir) else {
+        return 0;
+    };
+    let now = std::time::SystemTime::now();
+    let mut reclaimed = 0;
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !(name.starts_with("script_") && name.ends_with(".py")) {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else { continue };
+        let expired = meta
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age > max_age);
+        if !meta.is_file() || !expired {
+            continue;
+        }
+        if std::fs::remove_file(entry.path()).is_ok() {
+            reclaimed += meta.len();
+        }
+    }
+    reclaimed
+}
+
+/// Startup cleanup of old temp scripts left by failed or kept runs
+pub fn cleanup_old_scripts(app_data_dir: &std::path::Path) {
+    remove_old_scripts(
+        &app_data_dir.join("scripts"),
+        Duration::from_secs(SCRIPT_MAX_AGE_DAYS * 24 * 60 * 60),
+    );
+}
+
+#[tauri::command]
+pub fn cleanup_scripts_dir(app: AppHandle, max_age_days: Option<u64>) -> Result<u64, String> {
+    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+    let max_age_days = max_age_days.unwrap_or(SCRIPT_MAX_AGE_DAYS);
+    Ok(remove_old_scripts(
+        &app_data_dir.join("scripts"),
+        Duration::from_secs(max_age_days * 24 * 60 * 60),
+    ))
+}
+
+/// Delete a finished run's temp script, or return its path if `keep` is set
+fn finish_script_file(script_path: &std::path::Path, keep: bool) -> Option<String> {
+    if keep {
+        Some(script_path.to_string_lossy().to_string())
+    } else {
+        let _ = std::fs::remove_file(script_path);
+        None
+    }
+}
+
+/// Write a single line to a process's stdin and flush it
+fn write_input_line(stdin: &mut impl Write, line: &str) -> Result<(), String> {
+    writeln!(stdin, "{}", line.trim_end_matches(['\r', '\n']))
+        .map_err(|e| format!("Failed to write to script stdin: {}", e))?;
+    stdin
+        .flush()
+        .map_err(|e| format!("Failed to flush script stdin: {}", e))
+}
+
+#[tauri::command]
+pub fn send_script_input(line: String) -> Result<(), String> {
+    let mut guard = get_process_stdin_mutex().lock().map_err(|e| e.to_string())?;
+    let stdin = guard.as_mut().ok_or("No script running")?;
+    write_input_line(stdin, &line)
+}
+
+#[tauri::command]
+pub fn close_script_input() -> Result<(), String> {
+    let mut guard = get_process_stdin_mutex().lock().map_err(|e| e.to_string())?;
+    if guard.take().is_some() {
+        Ok(())
+    } else {
+        Err("No script running".to_string())
+    }
+}
+
 #[tauri::command]
 pub fn cancel_script() -> Result<(), String> {
+    close_process_stdin();
     let mut guard = get_process_mutex().lock().map_err(|e| e.to_string())?;
     if let Some(ref mut child) = *guard {
         // Kill the process
@@ -350,6 +730,19 @@ pub fn save_pipeline(id: String, name: String, data: String) -> Result<(), Strin
     db::save_pipeline(&id, &name, &data).map_err(|e| e.to_string())
 }
 
+/// Check pipeline JSON from another user before importing it, against
+/// `node_types` or the built-in node types. Nothing is saved.
+#[tauri::command]
+pub fn validate_pipeline_import(data: String, node_types: Option<Vec<db::NodeTypeSchema>>) -> Vec<db::ValidationIssue> {
+    let schemas = node_types.unwrap_or_else(db::builtin_node_schemas);
+    db::validate_pipeline_import(&data, &schemas)
+}
+
+#[tauri::command]
+pub fn get_pipeline_execution_order(pipeline_id: String) -> Result<Vec<String>, String> {
+    db::get_pipeline_execution_order(&pipeline_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn load_pipeline(id: String) -> Result<Option<String>, String> {
     db::load_pipeline(&id).map_err(|e| e.to_string())
@@ -365,101 +758,118 @@ pub fn delete_pipeline(id: String) -> Result<(), String> {
     db::delete_pipeline(&id).map_err(|e| e.to_string())
 }
 
-fn parse_output_line(line: &str) -> ScriptEvent {
-    // Try to parse as JSON first
+#[tauri::command]
+pub fn purge_pipeline(pipeline_id: String, pipeline_name: String) -> Result<db::PurgeSummary, String> {
+    db::purge_pipeline(&pipeline_id, &pipeline_name).map_err(|e| e.to_string())
+}
+
+/// The event a JSON output line describes, if it is one
+fn parse_json_event(line: &str) -> Option<ScriptEvent> {
     if let Ok(json) = serde_json::from_str::<JsonOutput>(line) {
         match json.event_type.as_str() {
             "log" => {
                 if let Some(message) = json.message {
-                    return ScriptEvent::Log { message };
+                    return Some(ScriptEvent::Log { message });
                 }
             }
             "progress" => {
                 if let (Some(current), Some(total)) = (json.current, json.total) {
-                    return ScriptEvent::Progress { current, total };
+                    return Some(ScriptEvent::Progress { current, total });
                 }
             }
             "error" => {
                 if let Some(message) = json.message {
-                    return ScriptEvent::Error { message };
+                    return Some(ScriptEvent::Error { message });
                 }
             }
             "complete" => {
-                return ScriptEvent::Complete;
+                return Some(ScriptEvent::Complete);
             }
             "metrics" => {
                 if let (Some(model_type), Some(data)) = (json.model_type, json.data) {
-                    return ScriptEvent::Metrics { model_type, data };
+                    return Some(ScriptEvent::Metrics { model_type, data });
                 }
             }
             "dataProfile" => {
                 if let (Some(node_id), Some(data)) = (json.node_id, json.data) {
-                    return ScriptEvent::DataProfile { node_id, data };
+                    return Some(ScriptEvent::DataProfile { node_id, data });
                 }
             }
             "trial" => {
                 if let (Some(trial_number), Some(params), Some(score)) =
                     (json.trial_number, json.params, json.score)
                 {
-                    return ScriptEvent::Trial {
+                    return Some(ScriptEvent::Trial {
                         trial_number,
                         params,
                         score,
                         duration_ms: json.duration_ms,
-                    };
+                    });
                 }
             }
             "tuningComplete" => {
                 if let (Some(best_params), Some(best_score), Some(total_trials)) =
                     (json.best_params, json.best_score, json.total_trials)
                 {
-                    return ScriptEvent::TuningComplete {
+                    return Some(ScriptEvent::TuningComplete {
                         best_params,
                         best_score,
                         total_trials,
                         duration_ms: json.duration_ms,
-                    };
+                    });
                 }
             }
             // Explain events
             "explainProgress" => {
                 if let Some(data) = json.data {
-                    return ScriptEvent::ExplainProgress { data };
+                    return Some(ScriptEvent::ExplainProgress { data });
                 }
             }
             "featureImportance" => {
                 if let Some(data) = json.data {
-                    return ScriptEvent::FeatureImportance { data };
+                    return Some(ScriptEvent::FeatureImportance { data });
                 }
             }
             "shapData" => {
                 if let Some(data) = json.data {
-                    return ScriptEvent::ShapData { data };
+                    return Some(ScriptEvent::ShapData { data });
                 }
             }
             "partialDependence" => {
                 if let Some(data) = json.data {
-                    return ScriptEvent::PartialDependence { data };
+                    return Some(ScriptEvent::PartialDependence { data });
                 }
             }
             "explainMetadata" => {
                 if let Some(data) = json.data {
-                    return ScriptEvent::ExplainMetadata { data };
+                    return Some(ScriptEvent::ExplainMetadata { data });
                 }
             }
             "explainComplete" => {
                 if let Some(duration_ms) = json.duration_ms {
-                    return ScriptEvent::ExplainComplete { duration_ms };
+                    return Some(ScriptEvent::ExplainComplete { duration_ms });
                 }
             }
             _ => {}
         }
     }
 
-    // Fall back to plain log message
-    ScriptEvent::Log {
+    None
+}
+
+/// Parse a stdout line, falling back to a plain log message
+fn parse_output_line(line: &str) -> ScriptEvent {
+    parse_json_event(line).unwrap_or_else(|| ScriptEvent::Log {
         message: line.to_string(),
-    }
+    })
+}
+
+/// Parse a stderr line. Scripts (and tqdm-style helpers) may write JSON events
+/// to stderr, so only non-JSON output is treated as an error.
+fn parse_stderr_line(line: &str) -> ScriptEvent {
+    parse_json_event(line).unwrap_or_else(|| ScriptEvent::Error {
+        message: line.to_string(),
+    })
 }
 
 // Run history commands
@@ -469,6 +879,7 @@ pub struct MetricInput {
     pub name: String,
     pub value: Option<f64>,
     pub value_json: Option<String>,
+    pub kind: Option<String>,
 }
 
 #[tauri::command]
@@ -478,27 +889,43 @@ pub fn create_run(pipeline_name: String, hyperparameters: String, experiment_id:
     Ok(run_id)
 }
 
+// Finishing a run writes any metrics still buffered for it
 #[tauri::command]
 pub fn complete_run(id: String, duration_ms: i64) -> Result<(), String> {
+    db::flush_run_metrics(&id).map_err(|e| e.to_string())?;
     db::update_run(&id, "completed", Some(duration_ms), None).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn fail_run(id: String, error: String) -> Result<(), String> {
+    db::flush_run_metrics(&id).map_err(|e| e.to_string())?;
     db::update_run(&id, "failed", None, Some(&error)).map_err(|e| e.to_string())
 }
 
+/// Save metrics in one batch. With `buffered`, they're queued in memory and
+/// written with later ones (see `flush_run_metrics`).
 #[tauri::command]
-pub fn save_run_metrics(run_id: String, metrics: Vec<MetricInput>) -> Result<(), String> {
+pub fn save_run_metrics(run_id: String, metrics: Vec<MetricInput>, buffered: Option<bool>) -> Result<(), String> {
     let db_metrics: Vec<db::Metric> = metrics
         .into_iter()
         .map(|m| db::Metric {
             name: m.name,
             value: m.value,
             value_json: m.value_json,
+            kind: m.kind,
         })
         .collect();
-    db::save_run_metrics(&run_id, &db_metrics).map_err(|e| e.to_string())
+    if buffered.unwrap_or(false) {
+        db::buffer_run_metrics(&run_id, db_metrics).map(|_| ()).map_err(|e| e.to_string())
+    } else {
+        db::save_run_metrics(&run_id, &db_metrics).map_err(|e| e.to_string())
+    }
+}
+
+/// Write the run's buffered metrics now; returns how many were written
+#[tauri::command]
+pub fn flush_run_metrics(run_id: String) -> Result<usize, String> {
+    db::flush_run_metrics(&run_id).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -511,11 +938,41 @@ pub fn get_run_metrics(run_id: String) -> Result<Vec<db::Metric>, String> {
     db::get_run_metrics(&run_id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn get_latest_metric_for_runs(
+    run_ids: Vec<String>,
+    metric_name: String,
+) -> Result<HashMap<String, Option<f64>>, String> {
+    db::get_latest_metric_for_runs(&run_ids, &metric_name).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn delete_run(id: String) -> Result<(), String> {
     db::delete_run(&id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn delete_runs_for_pipeline(pipeline_name: String) -> Result<usize, String> {
+    db::delete_runs_for_pipeline(&pipeline_name).map_err(|e| e.to_string())
+}
+
+/// Delete failed runs older than `older_than_days`; returns how many
+#[tauri::command]
+pub fn purge_failed_runs(older_than_days: u32) -> Result<usize, String> {
+    db::purge_failed_runs(older_than_days).map_err(|e| e.to_string())
+}
+
+/// Pack a run's artifact directory into artifacts/{run_id}.tar.gz
+#[tauri::command]
+pub fn compress_run_artifacts(run_id: String) -> Result<(), String> {
+    db::compress_run_artifacts(&run_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn decompress_run_artifacts(run_id: String) -> Result<(), String> {
+    db::decompress_run_artifacts(&run_id).map_err(|e| e.to_string())
+}
+
 // Experiment commands
 
 #[tauri::command]
@@ -525,6 +982,13 @@ pub fn create_experiment(name: String, description: Option<String>) -> Result<St
     Ok(id)
 }
 
+/// Like create_experiment, but returns the existing id if the name is taken
+#[tauri::command]
+pub fn get_or_create_experiment(name: String, description: Option<String>) -> Result<String, String> {
+    let id = uuid::Uuid::new_v4().to_string();
+    db::get_or_create_experiment(&id, &name, description.as_deref()).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn update_experiment(
     id: String,
@@ -551,6 +1015,25 @@ pub fn delete_experiment(id: String) -> Result<(), String> {
     db::delete_experiment(&id).map_err(|e| e.to_string())
 }
 
+/// Stream an experiment and its runs to `dest_path` as NDJSON; returns the run count
+#[tauri::command]
+pub fn export_experiment_ndjson(experiment_id: String, dest_path: String) -> Result<usize, String> {
+    db::export_experiment_ndjson(&experiment_id, &dest_path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_experiment_metric_correlations(experiment_id: String) -> Result<db::CorrelationMatrix, String> {
+    db::get_experiment_metric_correlations(&experiment_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_hyperparameter_impact(
+    experiment_id: String,
+    metric_name: String,
+) -> Result<Vec<db::HyperparamImpact>, String> {
+    db::get_hyperparameter_impact(&experiment_id, &metric_name).map_err(|e| e.to_string())
+}
+
 // Run Annotation commands
 
 #[tauri::command]
@@ -598,11 +1081,53 @@ pub fn list_all_tags() -> Result<Vec<String>, String> {
     db::list_all_tags().map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn list_runs_by_tag(tag: String) -> Result<Vec<db::RunMetadata>, String> {
+    db::list_runs_by_tag(&tag).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_runs_by_tags(tags: Vec<String>) -> Result<Vec<db::RunMetadata>, String> {
+    db::list_runs_by_tags(&tags).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_runs_for_comparison(run_ids: Vec<String>) -> Result<db::RunComparisonData, String> {
     db::get_runs_for_comparison(&run_ids).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn diff_confusion_matrices(run_id_a: String, run_id_b: String) -> Result<db::ConfusionMatrixDiff, String> {
+    db::diff_confusion_matrices(&run_id_a, &run_id_b).map_err(|e| e.to_string())
+}
+
+/// Per-class precision/recall/F1 from a run's stored prediction and label arrays
+#[tauri::command]
+pub fn compute_classification_report(
+    run_id: String,
+    predictions_metric: String,
+    labels_metric: String,
+) -> Result<db::ClassificationReport, String> {
+    db::compute_classification_report(&run_id, &predictions_metric, &labels_metric)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_run_metric_smoothed(
+    run_id: String,
+    name: String,
+    window: usize,
+    method: Option<db::SmoothingMethod>,
+) -> Result<db::SmoothedMetric, String> {
+    db::get_run_metric_smoothed(&run_id, &name, window, method.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn find_similar_runs(run_id: String, top_k: usize) -> Result<Vec<db::SimilarRun>, String> {
+    db::find_similar_runs(&run_id, top_k).map_err(|e| e.to_string())
+}
+
 // Example data commands
 
 #[derive(Clone, Serialize)]
@@ -637,105 +1162,537 @@ pub fn get_example_data_path(app: AppHandle, dataset: String) -> Result<String,
     Ok(dest_path.to_string_lossy().to_string())
 }
 
-#[tauri::command]
-pub fn list_example_datasets() -> Vec<ExampleDataset> {
-    vec![
-        ExampleDataset {
-            id: "iris.csv".to_string(),
-            name: "Iris Classification".to_string(),
-            description: "Classify iris flowers (150 samples, 3 classes)".to_string(),
-            task_type: "classification".to_string(),
-            target_column: "species".to_string(),
-            recommended_model: "random_forest".to_string(),
-        },
-        ExampleDataset {
-            id: "california_housing.csv".to_string(),
-            name: "California Housing".to_string(),
-            description: "Predict house values (200 samples)".to_string(),
-            task_type: "regression".to_string(),
-            target_column: "MedHouseVal".to_string(),
-            recommended_model: "linear_regression".to_string(),
-        },
-    ]
+/// Optional `<dataset>.meta.json` sidecar next to an example CSV. Missing
+/// fields fall back to values derived from the file itself.
+#[derive(Deserialize, Default)]
+struct ExampleDatasetMeta {
+    name: Option<String>,
+    description: Option<String>,
+    task_type: Option<String>,
+    target_column: Option<String>,
+    recommended_model: Option<String>,
 }
 
-// Model Registry commands
-
-#[tauri::command]
-pub fn create_model(name: String, description: Option<String>) -> Result<String, String> {
-    let model_id = uuid::Uuid::new_v4().to_string();
-    db::create_model(&model_id, &name, description.as_deref()).map_err(|e| e.to_string())?;
-    Ok(model_id)
+/// "california_housing" -> "California Housing"
+fn dataset_display_name(stem: &str) -> String {
+    stem.split(['_', '-', ' '])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
 }
 
-#[tauri::command]
-pub fn list_models() -> Result<Vec<db::ModelMetadata>, String> {
-    db::list_models().map_err(|e| e.to_string())
-}
+fn read_example_dataset(csv_path: &std::path::Path) -> Result<ExampleDataset, String> {
+    let id = csv_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("Invalid example path: {}", csv_path.display()))?;
+    let stem = csv_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| id.clone());
+
+    let meta_path = csv_path.with_file_name(format!("{}.meta.json", stem));
+    let meta: ExampleDatasetMeta = match std::fs::read_to_string(&meta_path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Invalid metadata in {}: {}", meta_path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => ExampleDatasetMeta::default(),
+        Err(e) => return Err(format!("Failed to read {}: {}", meta_path.display(), e)),
+    };
 
-#[tauri::command]
-pub fn get_model(model_id: String) -> Result<Option<db::ModelMetadata>, String> {
-    db::get_model(&model_id).map_err(|e| e.to_string())
-}
+    // Without a sidecar, assume the target is the last column, as in all bundled examples
+    let target_column = match meta.target_column {
+        Some(column) => column,
+        None => {
+            let file = std::fs::File::open(csv_path).map_err(|e| e.to_string())?;
+            read_csv_record(&mut BufReader::new(file))
+                .map_err(|e| e.to_string())?
+                .and_then(|header| header.last().map(|column| column.trim_start_matches('\u{feff}').to_string()))
+                .unwrap_or_default()
+        }
+    };
 
-#[tauri::command]
-pub fn delete_model(model_id: String) -> Result<(), String> {
-    db::delete_model(&model_id).map_err(|e| e.to_string())
+    Ok(ExampleDataset {
+        id,
+        name: meta.name.unwrap_or_else(|| dataset_display_name(&stem)),
+        description: meta.description.unwrap_or_default(),
+        task_type: meta.task_type.unwrap_or_default(),
+        target_column,
+        recommended_model: meta.recommended_model.unwrap_or_default(),
+    })
 }
 
-#[tauri::command]
-pub fn register_model_version(
-    model_id: String,
-    run_id: Option<String>,
-    source_path: String,
-    format: String,
-    metrics_snapshot: Option<String>,
-    feature_names: Option<String>,
-) -> Result<RegisterVersionResult, String> {
-    let version_id = uuid::Uuid::new_v4().to_string();
-    let version = db::register_model_version(
-        &version_id,
-        &model_id,
-        run_id.as_deref(),
-        &source_path,
-        &format,
-        metrics_snapshot.as_deref(),
-        feature_names.as_deref(),
-    ).map_err(|e| e.to_string())?;
-    Ok(RegisterVersionResult { version_id, version })
-}
+/// Every CSV in `dir`, sorted by file name. A dataset with an unreadable
+/// sidecar is skipped rather than hiding the others.
+fn scan_example_datasets(dir: &std::path::Path) -> Result<Vec<ExampleDataset>, String> {
+    let entries = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read examples directory {}: {}", dir.display(), e))?;
+
+    let mut csv_paths: Vec<std::path::PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+        })
+        .collect();
+    csv_paths.sort();
 
-#[derive(Clone, Serialize)]
-pub struct RegisterVersionResult {
-    pub version_id: String,
-    pub version: i64,
+    let mut datasets = Vec::with_capacity(csv_paths.len());
+    for path in csv_paths {
+        match read_example_dataset(&path) {
+            Ok(dataset) => datasets.push(dataset),
+            Err(e) => tracing::warn!("Skipping example dataset {}: {}", path.display(), e),
+        }
+    }
+    Ok(datasets)
 }
 
 #[tauri::command]
-pub fn list_model_versions(model_id: String) -> Result<Vec<db::ModelVersion>, String> {
-    db::list_model_versions(&model_id).map_err(|e| e.to_string())
+pub fn list_example_datasets(app: AppHandle) -> Result<Vec<ExampleDataset>, String> {
+    let examples_dir = app
+        .path()
+        .resolve("resources/examples", BaseDirectory::Resource)
+        .map_err(|e| format!("Example data not found: {}", e))?;
+    scan_example_datasets(&examples_dir)
+}
+
+const DEFAULT_PREVIEW_ROWS: usize = 20;
+const MAX_PREVIEW_ROWS: usize = 1000;
+
+#[derive(Clone, Serialize, Debug)]
+pub struct DatasetPreview {
+    pub columns: Vec<String>,
+    pub column_types: Vec<String>, // "integer" | "float" | "boolean" | "string", from the sample rows
+    pub rows: Vec<Vec<String>>,
+    pub truncated: bool, // the file has more rows than were read
+}
+
+/// Read one CSV record. Quoted fields may contain commas, doubled quotes and
+/// newlines, in which case the record spans several lines.
+fn read_csv_record<R: BufRead>(reader: &mut R) -> std::io::Result<Option<Vec<String>>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    loop {
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if in_quotes && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' if in_quotes => in_quotes = false,
+                '"' if field.is_empty() => in_quotes = true,
+                ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+                '\r' | '\n' if !in_quotes => {}
+                _ => field.push(c),
+            }
+        }
+        if !in_quotes {
+            break;
+        }
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break; // unterminated quote, keep what we have
+        }
+    }
+    fields.push(field);
+    Ok(Some(fields))
+}
+
+fn infer_column_type<'a>(values: impl Iterator<Item = &'a str>) -> &'static str {
+    let values: Vec<&str> = values.map(str::trim).filter(|v| !v.is_empty()).collect();
+    if values.is_empty() {
+        "string"
+    } else if values.iter().all(|v| v.parse::<i64>().is_ok()) {
+        "integer"
+    } else if values.iter().all(|v| v.parse::<f64>().is_ok()) {
+        "float"
+    } else if values.iter().all(|v| v.eq_ignore_ascii_case("true") || v.eq_ignore_ascii_case("false")) {
+        "boolean"
+    } else {
+        "string"
+    }
+}
+
+/// Header plus the first `n_rows` records. Stops reading as soon as it has them.
+fn preview_csv<R: BufRead>(mut reader: R, n_rows: usize) -> Result<DatasetPreview, String> {
+    let mut columns = read_csv_record(&mut reader)
+        .map_err(|e| format!("Failed to read header: {}", e))?
+        .ok_or("Dataset is empty")?;
+    if let Some(first) = columns.first_mut() {
+        *first = first.trim_start_matches('\u{feff}').to_string();
+    }
+
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    while let Some(record) = read_csv_record(&mut reader).map_err(|e| format!("Failed to read row: {}", e))? {
+        if record.len() == 1 && record[0].is_empty() {
+            continue; // blank line
+        }
+        if rows.len() == n_rows {
+            truncated = true;
+            break;
+        }
+        rows.push(record);
+    }
+
+    let column_types = (0..columns.len())
+        .map(|i| infer_column_type(rows.iter().filter_map(|r| r.get(i).map(String::as_str))).to_string())
+        .collect();
+
+    Ok(DatasetPreview { columns, column_types, rows, truncated })
+}
+
+#[tauri::command]
+pub fn preview_dataset(path: String, n_rows: Option<usize>) -> Result<DatasetPreview, String> {
+    let n_rows = n_rows.unwrap_or(DEFAULT_PREVIEW_ROWS).min(MAX_PREVIEW_ROWS);
+    let file = std::fs::File::open(&path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    preview_csv(BufReader::new(file), n_rows)
+}
+
+// Feature correlation commands
+
+const FEATURE_CORRELATIONS_PY: &str = include_str!("feature_correlations.py");
+
+/// Run the correlation helper on a CSV and parse its JSON output
+fn run_feature_correlations(
+    python_path: &std::path::Path,
+    script_path: &std::path::Path,
+    input_path: &str,
+) -> Result<serde_json::Value, String> {
+    let output = Command::new(python_path)
+        .arg(script_path)
+        .arg(input_path)
+        .output()
+        .map_err(|e| format!("Failed to run correlation script: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "Correlation script failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().last().unwrap_or("");
+    serde_json::from_str(line).map_err(|e| format!("Invalid correlation output: {}", e))
+}
+
+#[tauri::command]
+pub async fn compute_feature_correlations(
+    app: AppHandle,
+    input_path: String,
+    run_id: String,
+) -> Result<serde_json::Value, String> {
+    let resource_dir = app.path().resource_dir().ok();
+    let python_info = python::find_python(resource_dir.as_ref())
+        .ok_or_else(|| "No Python installation found".to_string())?;
+
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    let scripts_dir = app_data_dir.join("scripts");
+    std::fs::create_dir_all(&scripts_dir).map_err(|e| e.to_string())?;
+    let script_path = scripts_dir.join("feature_correlations.py");
+    std::fs::write(&script_path, FEATURE_CORRELATIONS_PY).map_err(|e| e.to_string())?;
+
+    // The script reads the whole CSV; keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = run_feature_correlations(&python_info.path, &script_path, &input_path)?;
+        db::save_feature_correlations(&run_id, &result.to_string()).map_err(|e| e.to_string())?;
+        Ok(result)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn get_run_environment(run_id: String) -> Result<Option<db::RunEnvironment>, String> {
+    db::get_run_environment(&run_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_feature_correlations(run_id: String) -> Result<Option<serde_json::Value>, String> {
+    db::get_feature_correlations(&run_id)
+        .map_err(|e| e.to_string())?
+        .map(|json| serde_json::from_str(&json).map_err(|e| e.to_string()))
+        .transpose()
+}
+
+// Model Registry commands
+
+#[tauri::command]
+pub fn create_model(name: String, description: Option<String>) -> Result<String, String> {
+    let model_id = uuid::Uuid::new_v4().to_string();
+    db::create_model(&model_id, &name, description.as_deref()).map_err(|e| e.to_string())?;
+    Ok(model_id)
+}
+
+#[tauri::command]
+pub fn list_models() -> Result<Vec<db::ModelMetadata>, String> {
+    db::list_models().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_model(model_id: String) -> Result<Option<db::ModelMetadata>, String> {
+    db::get_model(&model_id).map_err(|e| e.to_string())
+}
+
+/// Registry-wide counts for the dashboard
+#[tauri::command]
+pub fn get_registry_summary() -> Result<db::RegistrySummary, String> {
+    db::get_registry_summary().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn delete_model(model_id: String) -> Result<(), String> {
+    db::delete_model(&model_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn register_model_version(
+    model_id: String,
+    run_id: Option<String>,
+    source_path: String,
+    format: String,
+    metrics_snapshot: Option<String>,
+    feature_names: Option<String>,
+    trusted: Option<bool>,
+    snapshot_from_run: Option<bool>,
+) -> Result<RegisterVersionResult, String> {
+    let trusted = trusted.unwrap_or(false);
+    db::check_model_trust(&format, trusted).map_err(|e| e.to_string())?;
+    db::check_model_format(&source_path, &format).map_err(|e| e.to_string())?;
+    let metrics_snapshot = version_metrics_snapshot(run_id.as_deref(), metrics_snapshot, snapshot_from_run)?;
+    let version_id = uuid::Uuid::new_v4().to_string();
+    let version = db::register_model_version(
+        &version_id,
+        &model_id,
+        run_id.as_deref(),
+        &source_path,
+        &format,
+        metrics_snapshot.as_deref(),
+        feature_names.as_deref(),
+        trusted,
+    ).map_err(|e| e.to_string())?;
+    Ok(RegisterVersionResult { version_id, version })
+}
+
+// With snapshot_from_run, build the snapshot from the run's stored metrics
+// instead of trusting the caller's
+fn version_metrics_snapshot(
+    run_id: Option<&str>,
+    metrics_snapshot: Option<String>,
+    snapshot_from_run: Option<bool>,
+) -> Result<Option<String>, String> {
+    if snapshot_from_run.unwrap_or(false) {
+        let run_id = run_id.ok_or("snapshot_from_run requires a run_id")?;
+        db::metrics_snapshot_from_run(run_id).map_err(|e| e.to_string())
+    } else {
+        Ok(metrics_snapshot)
+    }
+}
+
+/// Register a version already in `stage`, as one transaction. Promoting to
+/// production goes through the promotion rule like promote_model.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub fn register_and_promote(
+    model_id: String,
+    run_id: Option<String>,
+    source_path: String,
+    format: String,
+    stage: String,
+    metrics_snapshot: Option<String>,
+    feature_names: Option<String>,
+    trusted: Option<bool>,
+    snapshot_from_run: Option<bool>,
+) -> Result<RegisterVersionResult, String> {
+    let trusted = trusted.unwrap_or(false);
+    db::check_model_trust(&format, trusted).map_err(|e| e.to_string())?;
+    db::check_model_format(&source_path, &format).map_err(|e| e.to_string())?;
+    let metrics_snapshot = version_metrics_snapshot(run_id.as_deref(), metrics_snapshot, snapshot_from_run)?;
+    let version_id = uuid::Uuid::new_v4().to_string();
+    let version = db::register_and_promote(
+        &version_id,
+        &model_id,
+        run_id.as_deref(),
+        &source_path,
+        &format,
+        metrics_snapshot.as_deref(),
+        feature_names.as_deref(),
+        trusted,
+        &stage,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(RegisterVersionResult { version_id, version })
+}
+
+/// Register a model file trained elsewhere, creating the model by name if
+/// needed. feature_names is a list of column names, metrics_json an object.
+#[tauri::command]
+pub fn import_external_model(
+    model_name: String,
+    source_path: String,
+    format: String,
+    feature_names: Option<Vec<String>>,
+    metrics_json: Option<String>,
+    trusted: Option<bool>,
+) -> Result<RegisterVersionResult, String> {
+    let (version_id, version) = db::import_external_model(
+        &model_name,
+        &source_path,
+        &format,
+        feature_names.as_deref(),
+        metrics_json.as_deref(),
+        trusted.unwrap_or(false),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(RegisterVersionResult { version_id, version })
+}
+
+#[tauri::command]
+pub fn detect_model_format(path: String) -> Result<String, String> {
+    db::detect_model_format(&path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn register_version_from_run(
+    model_id: String,
+    run_id: String,
+    format: String,
+    trusted: Option<bool>,
+) -> Result<RegisterVersionResult, String> {
+    let version_id = uuid::Uuid::new_v4().to_string();
+    let version = db::register_version_from_run(
+        &version_id,
+        &model_id,
+        &run_id,
+        &format,
+        trusted.unwrap_or(false),
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(RegisterVersionResult { version_id, version })
+}
+
+#[tauri::command]
+pub fn register_versions_from_run(
+    model_id: String,
+    run_id: String,
+    artifacts: Vec<db::ArtifactSpec>,
+) -> Result<Vec<String>, String> {
+    db::register_versions_from_run(&model_id, &run_id, &artifacts).map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Serialize)]
+pub struct RegisterVersionResult {
+    pub version_id: String,
+    pub version: i64,
+}
+
+#[tauri::command]
+pub fn list_model_versions(model_id: String) -> Result<Vec<db::ModelVersion>, String> {
+    db::list_model_versions(&model_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_run_model_versions(run_id: String) -> Result<Vec<db::ModelVersion>, String> {
+    db::get_run_model_versions(&run_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn list_unused_versions(older_than_days: u32) -> Result<Vec<db::ModelVersion>, String> {
+    db::list_unused_versions(older_than_days).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
 pub fn promote_model(version_id: String, stage: String) -> Result<(), String> {
+    db::validate_stage(&stage).map_err(|e| e.to_string())?;
+    if stage == "production" {
+        db::check_promotion_rule(&version_id).map_err(|e| e.to_string())?;
+    }
     db::promote_model(&version_id, &stage).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn rollback_production(model_id: String) -> Result<db::ModelVersion, String> {
+    db::rollback_production(&model_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn set_promotion_rule(rule: Option<db::PromotionRule>) -> Result<(), String> {
+    db::set_promotion_rule(rule.as_ref()).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_promotion_rule() -> Option<db::PromotionRule> {
+    db::get_promotion_rule()
+}
+
+#[tauri::command]
+pub fn compare_candidate_to_production(version_id: String) -> Result<Vec<db::MetricDelta>, String> {
+    db::compare_candidate_to_production(&version_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn delete_model_version(version_id: String) -> Result<(), String> {
     db::delete_model_version(&version_id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn set_model_max_versions(model_id: String, max_versions: Option<u32>) -> Result<(), String> {
+    db::set_model_max_versions(&model_id, max_versions).map_err(|e| e.to_string())
+}
+
+/// Archive (or with `delete`, remove) the model's oldest versions beyond its
+/// max_versions. The version behind the HTTP server is always kept.
+#[tauri::command]
+pub fn prune_old_versions(model_id: String, delete: Option<bool>) -> Result<Vec<String>, String> {
+    let serving = get_http_server_mutex()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .map(|p| p.version_id.clone());
+    db::prune_old_versions(&model_id, delete.unwrap_or(false), serving.as_deref())
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_model_file_path(version_id: String) -> Result<Option<String>, String> {
     db::get_model_file_path(&version_id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn find_duplicate_versions(model_id: String) -> Result<Vec<Vec<String>>, String> {
+    db::find_duplicate_versions(&model_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_model_version(version_id: String) -> Result<Option<db::ModelVersion>, String> {
     db::get_model_version(&version_id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn get_model_version_provenance(version_id: String) -> Result<Option<db::ModelProvenance>, String> {
+    db::get_model_version_provenance(&version_id).map_err(|e| e.to_string())
+}
+
 // Inference Server commands
 
 fn get_pid_file_path(app_data_dir: &std::path::Path) -> std::path::PathBuf {
@@ -776,10 +1733,83 @@ fn parse_response_line(line: &str) -> Option<InferenceResponse> {
         .and_then(|json_str| serde_json::from_str(json_str).ok())
 }
 
+fn prediction_result(response: InferenceResponse) -> PredictionResult {
+    let code = (response.status == "error")
+        .then(|| response.code.unwrap_or(InferenceErrorCode::Unknown));
+    PredictionResult {
+        request_id: response.request_id,
+        status: response.status,
+        prediction: response.prediction,
+        probabilities: response.probabilities,
+        classes: response.classes,
+        message: response.message,
+        code,
+        guidance: code.map(|c| c.guidance().to_string()),
+    }
+}
+
+fn audit_payload(mode: InferenceAuditMode, value: &serde_json::Value) -> String {
+    let json = value.to_string();
+    match mode {
+        InferenceAuditMode::Full => json,
+        InferenceAuditMode::Hashed => {
+            serde_json::json!({ "sha256": crate::rag::content_hash(&json) }).to_string()
+        }
+    }
+}
+
+/// Write an audit row for a completed request. Returns false without writing
+/// anything when auditing is off.
+fn record_inference_audit(
+    mode: Option<InferenceAuditMode>,
+    version_id: &str,
+    input: &serde_json::Value,
+    result: &PredictionResult,
+    latency_ms: i64,
+) -> Result<bool, String> {
+    let Some(mode) = mode else {
+        return Ok(false);
+    };
+    let output = serde_json::to_value(result).map_err(|e| e.to_string())?;
+    db::log_inference_audit(
+        version_id,
+        &result.request_id,
+        &audit_payload(mode, input),
+        &audit_payload(mode, &output),
+        latency_ms,
+    )
+    .map_err(|e| format!("Failed to write inference audit: {}", e))?;
+    Ok(true)
+}
+
 #[tauri::command]
 pub async fn start_inference_server(
     app: AppHandle,
     version_id: String,
+    audit: Option<bool>,
+    audit_mode: Option<InferenceAuditMode>,
+) -> Result<ServerStatus, String> {
+    // Auditing costs a DB write per request, so it's opt-in
+    let audit = audit
+        .unwrap_or(false)
+        .then(|| audit_mode.unwrap_or(InferenceAuditMode::Full));
+
+    let resource_dir = app.path().resource_dir().ok();
+    let python_info = python::find_python(resource_dir.as_ref())
+        .ok_or_else(|| "No Python installation found".to_string())?;
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    launch_inference_server(&python_info.path, &app_data_dir, &version_id, audit)
+}
+
+/// Spawn inference_server.py for `version_id` and wait for it to load the model
+fn launch_inference_server(
+    python_path: &std::path::Path,
+    app_data_dir: &std::path::Path,
+    version_id: &str,
+    audit: Option<InferenceAuditMode>,
 ) -> Result<ServerStatus, String> {
     // Check if already running
     {
@@ -790,30 +1820,21 @@ pub async fn start_inference_server(
     }
 
     // Get model file path from database
-    let version = db::get_model_version(&version_id)
+    let version = db::get_model_version(version_id)
         .map_err(|e| e.to_string())?
         .ok_or_else(|| "Model version not found".to_string())?;
+    db::check_model_trust(&version.format, version.trusted).map_err(|e| e.to_string())?;
 
     let model_path = version.file_path.clone();
 
-    // Get Python path
-    let resource_dir = app.path().resource_dir().ok();
-    let python_info = python::find_python(resource_dir.as_ref())
-        .ok_or_else(|| "No Python installation found".to_string())?;
-    let python_path = python_info.path;
-
     // Write inference server script to app data dir
-    let app_data_dir = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| e.to_string())?;
     let scripts_dir = app_data_dir.join("scripts");
     std::fs::create_dir_all(&scripts_dir).map_err(|e| e.to_string())?;
     let script_path = scripts_dir.join("inference_server.py");
     std::fs::write(&script_path, INFERENCE_SERVER_PY).map_err(|e| e.to_string())?;
 
     // Spawn Python process
-    let mut child = Command::new(&python_path)
+    let mut child = Command::new(python_path)
         .arg("-u")
         .arg(&script_path)
         .arg(&model_path)
@@ -824,7 +1845,7 @@ pub async fn start_inference_server(
         .map_err(|e| format!("Failed to spawn inference server: {}", e))?;
 
     // Save PID for orphan cleanup
-    write_pid_file(&app_data_dir, child.id())?;
+    write_pid_file(app_data_dir, child.id())?;
 
     let stdin = child.stdin.take().ok_or("Failed to capture stdin")?;
     let stdout = child.stdout.take().ok_or("Failed to capture stdout")?;
@@ -863,7 +1884,7 @@ pub async fn start_inference_server(
     loop {
         if start_time.elapsed() > Duration::from_secs(LOAD_TIMEOUT_SECS) {
             let _ = child.kill();
-            remove_pid_file(&app_data_dir);
+            remove_pid_file(app_data_dir);
             return Err("Timeout waiting for model to load".to_string());
         }
 
@@ -874,14 +1895,14 @@ pub async fn start_inference_server(
                     break;
                 } else if response.status == "error" {
                     let _ = child.kill();
-                    remove_pid_file(&app_data_dir);
+                    remove_pid_file(app_data_dir);
                     return Err(response.message.unwrap_or("Unknown error".to_string()));
                 }
             }
             Err(mpsc::RecvTimeoutError::Timeout) => continue,
             Err(mpsc::RecvTimeoutError::Disconnected) => {
                 let _ = child.kill();
-                remove_pid_file(&app_data_dir);
+                remove_pid_file(app_data_dir);
                 return Err("Inference server process exited unexpectedly".to_string());
             }
         }
@@ -897,8 +1918,13 @@ pub async fn start_inference_server(
             model_info: model_info.clone(),
             response_rx: rx,
             pending_requests,
+            version_id: version_id.to_string(),
+            audit,
+            started_at: std::time::Instant::now(),
+            warmed_up: false,
         });
     }
+    let _ = db::touch_model_version(version_id);
 
     // Parse feature_names from version if available
     let feature_names = version.feature_names
@@ -918,27 +1944,95 @@ pub async fn stop_inference_server(app: AppHandle) -> Result<(), String> {
         .path()
         .app_data_dir()
         .map_err(|e| e.to_string())?;
+    shutdown_inference_server(&app_data_dir)
+}
 
+fn shutdown_inference_server(app_data_dir: &std::path::Path) -> Result<(), String> {
     let mut guard = get_inference_mutex().lock().map_err(|e| e.to_string())?;
     if let Some(mut proc) = guard.take() {
         // Close stdin to signal EOF to Python process
         drop(proc.stdin);
         // Wait for process to exit gracefully
         let _ = proc.child.wait();
-        remove_pid_file(&app_data_dir);
+        remove_pid_file(app_data_dir);
         Ok(())
     } else {
         Err("No inference server running".to_string())
     }
 }
 
+/// Reload the running inference server's version, e.g. after its model file
+/// was re-exported. Requests already sent are answered before the old
+/// process exits on EOF; the audit setting carries over.
 #[tauri::command]
-pub fn get_inference_server_status(version_id: Option<String>) -> Result<ServerStatus, String> {
-    let guard = get_inference_mutex().lock().map_err(|e| e.to_string())?;
-
-    match &*guard {
-        Some(proc) => {
-            // If version_id is provided, also get feature_names from DB
+pub async fn restart_inference_server(app: AppHandle) -> Result<ServerStatus, String> {
+    let resource_dir = app.path().resource_dir().ok();
+    let python_info = python::find_python(resource_dir.as_ref())
+        .ok_or_else(|| "No Python installation found".to_string())?;
+    let app_data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?;
+    relaunch_inference_server(&python_info.path, &app_data_dir)
+}
+
+fn relaunch_inference_server(
+    python_path: &std::path::Path,
+    app_data_dir: &std::path::Path,
+) -> Result<ServerStatus, String> {
+    let (version_id, audit) = {
+        let guard = get_inference_mutex().lock().map_err(|e| e.to_string())?;
+        guard
+            .as_ref()
+            .map(|proc| (proc.version_id.clone(), proc.audit))
+            .ok_or("No inference server running")?
+    };
+    shutdown_inference_server(app_data_dir)?;
+    launch_inference_server(python_path, app_data_dir, &version_id, audit)
+}
+
+/// Error unless the running inference server loaded `expected_version_id`'s
+/// model file. Catches the UI and the server disagreeing after a restart.
+#[tauri::command]
+pub fn assert_serving_version(expected_version_id: String) -> Result<(), String> {
+    let model_path = {
+        let guard = get_inference_mutex().lock().map_err(|e| e.to_string())?;
+        guard
+            .as_ref()
+            .map(|proc| proc.model_path.clone())
+            .ok_or("No inference server running")?
+    };
+    check_serving_version(&expected_version_id, &model_path)
+}
+
+fn check_serving_version(expected_version_id: &str, model_path: &str) -> Result<(), String> {
+    let version = db::get_model_version(expected_version_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Model version {} not found", expected_version_id))?;
+
+    let (serving, expected) = (std::path::Path::new(model_path), std::path::Path::new(&version.file_path));
+    let same_file = serving == expected
+        || matches!(
+            (serving.canonicalize(), expected.canonicalize()),
+            (Ok(a), Ok(b)) if a == b
+        );
+    if same_file {
+        Ok(())
+    } else {
+        Err(format!(
+            "Inference server is serving {}, not version {} ({})",
+            model_path, expected_version_id, version.file_path
+        ))
+    }
+}
+
+#[tauri::command]
+pub fn get_inference_server_status(version_id: Option<String>) -> Result<ServerStatus, String> {
+    let guard = get_inference_mutex().lock().map_err(|e| e.to_string())?;
+
+    match &*guard {
+        Some(proc) => {
+            // If version_id is provided, also get feature_names from DB
             let feature_names = version_id
                 .and_then(|vid| db::get_model_version(&vid).ok())
                 .flatten()
@@ -968,8 +2062,8 @@ pub fn run_inference(
 ) -> Result<PredictionResult, String> {
     // Create a one-shot channel for this request's response
     let (response_tx, response_rx) = mpsc::channel::<InferenceResponse>();
-
-    {
+    let started = std::time::Instant::now();
+    let (version_id, audit) = {
         let mut guard = get_inference_mutex().lock().map_err(|e| e.to_string())?;
         let proc = guard.as_mut().ok_or("Inference server not running")?;
 
@@ -991,18 +2085,28 @@ pub fn run_inference(
             .map_err(|e| format!("Failed to send command: {}", e))?;
         proc.stdin.flush()
             .map_err(|e| format!("Failed to flush stdin: {}", e))?;
-    }
+
+        (proc.version_id.clone(), proc.audit)
+    };
 
     // Wait for response with timeout
     match response_rx.recv_timeout(Duration::from_secs(PREDICT_TIMEOUT_SECS)) {
-        Ok(response) => Ok(PredictionResult {
-            request_id: response.request_id,
-            status: response.status,
-            prediction: response.prediction,
-            probabilities: response.probabilities,
-            classes: response.classes,
-            message: response.message,
-        }),
+        Ok(response) => {
+            let result = prediction_result(response);
+            if result.status == "ok" {
+                if let Ok(mut guard) = get_inference_mutex().lock() {
+                    if let Some(proc) = guard.as_mut().filter(|p| p.version_id == version_id) {
+                        proc.warmed_up = true;
+                    }
+                }
+            }
+            let latency_ms = started.elapsed().as_millis() as i64;
+            // A failed audit write shouldn't cost the caller its prediction
+            if let Err(e) = record_inference_audit(audit, &version_id, &input, &result, latency_ms) {
+                tracing::warn!("{}", e);
+            }
+            Ok(result)
+        }
         Err(mpsc::RecvTimeoutError::Timeout) => {
             // Clean up pending request
             if let Ok(mut guard) = get_inference_mutex().lock() {
@@ -1020,6 +2124,322 @@ pub fn run_inference(
     }
 }
 
+#[tauri::command]
+pub fn get_inference_audit(
+    version_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<db::InferenceAuditEntry>, String> {
+    db::get_inference_audit(&version_id, limit).map_err(|e| e.to_string())
+}
+
+/// Nearest-rank percentile of an ascending slice
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Call `predict` `n_requests` times from `concurrency` worker threads and
+/// summarize per-request latency. Failed requests count toward latency too.
+fn run_benchmark<F>(predict: F, n_requests: usize, concurrency: usize) -> BenchmarkResult
+where
+    F: Fn(usize) -> Result<(), String> + Sync,
+{
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let errors = std::sync::atomic::AtomicUsize::new(0);
+    let latencies = Mutex::new(Vec::with_capacity(n_requests));
+
+    let started = std::time::Instant::now();
+    std::thread::scope(|scope| {
+        for _ in 0..concurrency {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                if i >= n_requests {
+                    break;
+                }
+                let t = std::time::Instant::now();
+                if predict(i).is_err() {
+                    errors.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+                let ms = t.elapsed().as_secs_f64() * 1000.0;
+                latencies.lock().unwrap().push(ms);
+            });
+        }
+    });
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let mut latencies = latencies.into_inner().unwrap();
+    latencies.sort_by(|a, b| a.total_cmp(b));
+    BenchmarkResult {
+        n_requests,
+        concurrency,
+        errors: errors.into_inner(),
+        p50_ms: percentile(&latencies, 50.0),
+        p95_ms: percentile(&latencies, 95.0),
+        p99_ms: percentile(&latencies, 99.0),
+        requests_per_sec: if elapsed > 0.0 { n_requests as f64 / elapsed } else { 0.0 },
+    }
+}
+
+/// Load test the running inference server with `sample_input`.
+///
+/// The stdin inference server handles one request at a time, so
+/// `requests_per_sec` is serial throughput whatever `concurrency` is. Extra
+/// concurrent clients only queue, which shows up as higher latencies.
+#[tauri::command]
+pub async fn benchmark_inference(
+    version_id: String,
+    n_requests: usize,
+    concurrency: usize,
+    sample_input: serde_json::Value,
+) -> Result<BenchmarkResult, String> {
+    if n_requests == 0 || concurrency == 0 {
+        return Err("n_requests and concurrency must be at least 1".to_string());
+    }
+    {
+        let guard = get_inference_mutex().lock().map_err(|e| e.to_string())?;
+        let proc = guard.as_ref().ok_or("Inference server not running")?;
+        if proc.version_id != version_id {
+            return Err("Inference server is serving a different model version".to_string());
+        }
+    }
+
+    let run_id = uuid::Uuid::new_v4().to_string();
+    // Worker threads block on run_inference for the whole benchmark
+    tauri::async_runtime::spawn_blocking(move || {
+        run_benchmark(
+            |i| {
+                let result = run_inference(format!("bench-{}-{}", run_id, i), sample_input.clone())?;
+                if result.status == "error" {
+                    return Err(result.message.unwrap_or_default());
+                }
+                Ok(())
+            },
+            n_requests,
+            concurrency.min(n_requests),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+// Rows sent to the inference server per predict request by predict_csv
+const PREDICT_CSV_BATCH_SIZE: usize = 256;
+
+/// Progress of `predict_csv`, emitted as `predict-csv-progress` after each batch
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct PredictCsvProgress {
+    pub rows_done: usize,
+    pub bytes_read: u64,
+    pub total_bytes: u64,
+}
+
+/// Counts bytes pulled from the CSV, for progress reporting
+struct CountingReader<R> {
+    inner: R,
+    bytes_read: Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl<R: std::io::Read> std::io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes_read.fetch_add(n as u64, std::sync::atomic::Ordering::Relaxed);
+        Ok(n)
+    }
+}
+
+// CSV cell as model input: numbers as numbers, blanks as null
+fn csv_input_value(value: &str) -> serde_json::Value {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        serde_json::Value::Null
+    } else {
+        trimmed
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(serde_json::Value::Number)
+            .unwrap_or_else(|| serde_json::Value::String(value.to_string()))
+    }
+}
+
+/// Copy `reader`'s CSV to `writer` with a `prediction` column appended,
+/// scoring `PREDICT_CSV_BATCH_SIZE` rows at a time with `predict`. Only the
+/// `feature_names` columns are sent (all columns when there are none). Calls
+/// `on_batch(rows_done)` after each batch is written; returns the row count.
+fn predict_csv_rows<R: BufRead, W: Write>(
+    mut reader: R,
+    writer: &mut W,
+    feature_names: Option<&[String]>,
+    mut predict: impl FnMut(serde_json::Value) -> Result<PredictionResult, String>,
+    mut on_batch: impl FnMut(usize),
+) -> Result<usize, String> {
+    let mut columns = read_csv_record(&mut reader)
+        .map_err(|e| format!("Failed to read header: {}", e))?
+        .ok_or("CSV file is empty")?;
+    if let Some(first) = columns.first_mut() {
+        *first = first.trim_start_matches('\u{feff}').to_string();
+    }
+
+    let features: Vec<String> = feature_names.map(<[String]>::to_vec).unwrap_or_else(|| columns.clone());
+    let missing: Vec<&str> = features
+        .iter()
+        .filter(|f| !columns.contains(f))
+        .map(String::as_str)
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("CSV is missing feature columns: {}", missing.join(", ")));
+    }
+    let feature_indices: Vec<usize> = features
+        .iter()
+        .map(|f| columns.iter().position(|c| c == f).unwrap_or_default())
+        .collect();
+
+    let write_record = |writer: &mut W, fields: &[String]| {
+        let line: Vec<String> = fields.iter().map(|f| db::csv_field(f)).collect();
+        writeln!(writer, "{}", line.join(",")).map_err(|e| format!("Failed to write predictions: {}", e))
+    };
+    let mut header = columns.clone();
+    header.push("prediction".to_string());
+    write_record(writer, &header)?;
+
+    let mut rows_done = 0;
+    let mut batch: Vec<Vec<String>> = Vec::with_capacity(PREDICT_CSV_BATCH_SIZE);
+    loop {
+        let record = read_csv_record(&mut reader).map_err(|e| format!("Failed to read row: {}", e))?;
+        if let Some(record) = &record {
+            if !(record.len() == 1 && record[0].is_empty()) {
+                if record.len() != columns.len() {
+                    return Err(format!(
+                        "Row {} has {} fields, expected {}",
+                        rows_done + batch.len() + 1,
+                        record.len(),
+                        columns.len()
+                    ));
+                }
+                batch.push(record.clone());
+            }
+        }
+        if batch.len() < PREDICT_CSV_BATCH_SIZE && record.is_some() {
+            continue;
+        }
+        if batch.is_empty() {
+            break;
+        }
+
+        let input: Vec<serde_json::Value> = batch
+            .iter()
+            .map(|row| {
+                let sample: serde_json::Map<String, serde_json::Value> = features
+                    .iter()
+                    .zip(&feature_indices)
+                    .map(|(name, &i)| (name.clone(), csv_input_value(&row[i])))
+                    .collect();
+                serde_json::Value::Object(sample)
+            })
+            .collect();
+        let rows = format!("rows {}-{}", rows_done + 1, rows_done + batch.len());
+        let result = predict(serde_json::Value::Array(input))?;
+        if result.status == "error" {
+            return Err(format!(
+                "Prediction failed for {}: {}",
+                rows,
+                result.message.unwrap_or_else(|| "unknown error".to_string())
+            ));
+        }
+        let predictions = result.prediction.unwrap_or_default();
+        if predictions.len() != batch.len() {
+            return Err(format!(
+                "Inference server returned {} predictions for {}",
+                predictions.len(),
+                rows
+            ));
+        }
+        rows_done += batch.len();
+        for (mut row, prediction) in batch.drain(..).zip(predictions) {
+            row.push(match prediction {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            });
+            write_record(writer, &row)?;
+        }
+        on_batch(rows_done);
+        if record.is_none() {
+            break;
+        }
+    }
+    writer.flush().map_err(|e| format!("Failed to write predictions: {}", e))?;
+    Ok(rows_done)
+}
+
+/// Score every row of a CSV file with the running inference server, writing
+/// the rows plus a `prediction` column to `dest_path` batch by batch.
+/// Returns the number of rows scored.
+#[tauri::command]
+pub async fn predict_csv(
+    app: AppHandle,
+    version_id: String,
+    csv_path: String,
+    dest_path: String,
+) -> Result<usize, String> {
+    // Every batch blocks on the inference server, so keep it off the async runtime
+    tauri::async_runtime::spawn_blocking(move || predict_csv_file(&app, &version_id, &csv_path, &dest_path))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn predict_csv_file(app: &AppHandle, version_id: &str, csv_path: &str, dest_path: &str) -> Result<usize, String> {
+    {
+        let guard = get_inference_mutex().lock().map_err(|e| e.to_string())?;
+        let proc = guard.as_ref().ok_or("Inference server not running")?;
+        if proc.version_id != version_id {
+            return Err("Inference server is serving a different model version".to_string());
+        }
+    }
+    let feature_names = db::get_model_version(version_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Model version not found".to_string())?
+        .feature_names
+        .and_then(|s| serde_json::from_str::<Vec<String>>(&s).ok());
+
+    let file = std::fs::File::open(csv_path).map_err(|e| format!("Failed to open {}: {}", csv_path, e))?;
+    let total_bytes = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let bytes_read = Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let reader = BufReader::new(CountingReader { inner: file, bytes_read: bytes_read.clone() });
+    let out = std::fs::File::create(dest_path).map_err(|e| format!("Failed to create {}: {}", dest_path, e))?;
+    let mut writer = std::io::BufWriter::new(out);
+
+    let run_id = uuid::Uuid::new_v4().to_string();
+    let mut batch_index = 0;
+    let result = predict_csv_rows(
+        reader,
+        &mut writer,
+        feature_names.as_deref(),
+        |input| {
+            batch_index += 1;
+            run_inference(format!("csv-{}-{}", run_id, batch_index), input)
+        },
+        |rows_done| {
+            let _ = app.emit(
+                "predict-csv-progress",
+                PredictCsvProgress {
+                    rows_done,
+                    bytes_read: bytes_read.load(std::sync::atomic::Ordering::Relaxed).min(total_bytes),
+                    total_bytes,
+                },
+            );
+        },
+    );
+    if result.is_err() {
+        // Don't leave a partial file that looks like a finished one
+        drop(writer);
+        let _ = std::fs::remove_file(dest_path);
+    }
+    result
+}
+
 // Tuning commands
 
 #[tauri::command]
@@ -1030,6 +2450,7 @@ pub fn create_tuning_session(
     n_trials: Option<i32>,
     cv_folds: i32,
     scoring_metric: String,
+    seed: Option<i64>,
 ) -> Result<String, String> {
     let session_id = uuid::Uuid::new_v4().to_string();
     db::create_tuning_session(
@@ -1040,6 +2461,7 @@ pub fn create_tuning_session(
         n_trials,
         cv_folds,
         &scoring_metric,
+        seed,
     )
     .map_err(|e| e.to_string())?;
     Ok(session_id)
@@ -1087,8 +2509,12 @@ pub fn save_tuning_trial(
         duration_ms,
         "completed",
     )
-    .map_err(|e| e.to_string())?;
-    Ok(trial_id)
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn export_tuning_trials_csv(session_id: String, dest_path: String) -> Result<usize, String> {
+    db::export_tuning_trials_csv(&session_id, &dest_path).map_err(|e| e.to_string())
 }
 
 #[tauri::command]
@@ -1101,6 +2527,11 @@ pub fn get_best_trial(session_id: String) -> Result<Option<db::TuningTrial>, Str
     db::get_best_trial(&session_id).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn apply_best_trial_to_pipeline(session_id: String, pipeline_id: String) -> Result<String, String> {
+    db::apply_best_trial_to_pipeline(&session_id, &pipeline_id).map_err(|e| e.to_string())
+}
+
 // Model Metadata & Tags commands (v9)
 
 #[tauri::command]
@@ -1123,6 +2554,11 @@ pub fn update_model_version_training_info(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn validate_model_version_schema(version_id: String) -> Result<(), String> {
+    db::validate_model_version_schema(&version_id).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn update_model_version_export_path(
     version_id: String,
@@ -1133,6 +2569,87 @@ pub fn update_model_version_export_path(
         .map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn set_model_version_trusted(version_id: String, trusted: bool) -> Result<(), String> {
+    db::set_model_version_trusted(&version_id, trusted).map_err(|e| e.to_string())
+}
+
+// Converts a joblib/pickle sklearn model to CoreML: <model> <output> [feature_names_json]
+const COREML_EXPORT_PY: &str = r#"
+import json, sys
+import joblib
+import coremltools as ct
+
+model = joblib.load(sys.argv[1])
+names = json.loads(sys.argv[3]) if len(sys.argv) > 3 else None
+mlmodel = ct.converters.sklearn.convert(model, names) if names else ct.converters.sklearn.convert(model)
+mlmodel.save(sys.argv[2])
+"#;
+
+/// CoreML export needs macOS and the coremltools package
+fn check_coreml_support(is_macos: bool, has_coremltools: impl FnOnce() -> bool) -> Result<(), String> {
+    if !is_macos {
+        return Err("CoreML export is not supported on this platform (requires macOS)".to_string());
+    }
+    if !has_coremltools() {
+        return Err("coremltools not installed; install it with `pip install coremltools`".to_string());
+    }
+    Ok(())
+}
+
+/// The .mlmodel is written next to the version's model file
+fn coreml_export_path(model_path: &str) -> std::path::PathBuf {
+    std::path::Path::new(model_path).with_extension("mlmodel")
+}
+
+#[tauri::command]
+pub async fn export_model_to_coreml(app: AppHandle, version_id: String) -> Result<String, String> {
+    let version = db::get_model_version(&version_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| "Model version not found".to_string())?;
+    if version.format != "joblib" && version.format != "pickle" {
+        return Err(format!("CoreML export is not supported for {} models", version.format));
+    }
+    db::check_model_trust(&version.format, version.trusted).map_err(|e| e.to_string())?;
+
+    let resource_dir = app.path().resource_dir().ok();
+    let python_info = python::find_python(resource_dir.as_ref())
+        .ok_or_else(|| "No Python installation found".to_string())?;
+
+    check_coreml_support(cfg!(target_os = "macos"), || {
+        Command::new(&python_info.path)
+            .args(["-c", "import coremltools"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|s| s.success())
+            .unwrap_or(false)
+    })?;
+
+    let output_path = coreml_export_path(&version.file_path);
+    let mut cmd = Command::new(&python_info.path);
+    cmd.args(["-c", COREML_EXPORT_PY])
+        .arg(&version.file_path)
+        .arg(&output_path);
+    if let Some(feature_names) = &version.feature_names {
+        cmd.arg(feature_names);
+    }
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to run CoreML export: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "CoreML export failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let coreml_path = output_path.to_string_lossy().to_string();
+    db::update_model_version_export_path(&version_id, version.onnx_path.as_deref(), Some(&coreml_path))
+        .map_err(|e| e.to_string())?;
+    Ok(coreml_path)
+}
+
 #[tauri::command]
 pub fn add_model_tag(version_id: String, tag: String) -> Result<(), String> {
     db::add_model_tag(&version_id, &tag).map_err(|e| e.to_string())
@@ -1183,6 +2700,25 @@ pub struct HttpServerConfig {
     pub port: u16,
     pub use_onnx: bool,
     pub cors_origins: Option<Vec<String>>,
+    // Number of recent requests kept for the metrics panel
+    #[serde(default = "default_recent_buffer_size")]
+    pub recent_buffer_size: usize,
+    // slowapi limit for /predict, e.g. "100/minute"; the server's default when None
+    #[serde(default)]
+    pub rate_limit: Option<String>,
+    // Required as X-API-Key or Bearer token on every route but /health
+    #[serde(default)]
+    pub api_key: Option<String>,
+    // Bind to a non-loopback host without an api_key
+    #[serde(default)]
+    pub allow_public: bool,
+}
+
+const DEFAULT_RECENT_BUFFER_SIZE: usize = 100;
+const MAX_RECENT_BUFFER_SIZE: usize = 10_000;
+
+fn default_recent_buffer_size() -> usize {
+    DEFAULT_RECENT_BUFFER_SIZE
 }
 
 impl Default for HttpServerConfig {
@@ -1192,13 +2728,66 @@ impl Default for HttpServerConfig {
             port: 8080,
             use_onnx: false,
             cors_origins: None,
+            recent_buffer_size: DEFAULT_RECENT_BUFFER_SIZE,
+            rate_limit: None,
+            api_key: None,
+            allow_public: false,
         }
     }
 }
 
+const RATE_LIMIT_UNITS: [&str; 6] = ["second", "minute", "hour", "day", "month", "year"];
+
+/// Check a slowapi rate limit string: `<count>/<unit>`, e.g. "100/minute"
+/// or "5000/hours"
+fn validate_rate_limit(limit: &str) -> Result<(), String> {
+    let (count, unit) = limit
+        .split_once('/')
+        .ok_or_else(|| format!("Invalid rate limit '{}': expected <count>/<unit>, e.g. 100/minute", limit))?;
+    match count.trim().parse::<u32>() {
+        Ok(n) if n > 0 => {}
+        _ => return Err(format!("Invalid rate limit '{}': count must be a positive integer", limit)),
+    }
+    let unit = unit.trim();
+    let singular = unit.strip_suffix('s').unwrap_or(unit);
+    if !RATE_LIMIT_UNITS.contains(&singular) {
+        return Err(format!(
+            "Invalid rate limit '{}': unit must be one of {}",
+            limit,
+            RATE_LIMIT_UNITS.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+// Environment variable the API key is handed to http_server.py in, so it
+// doesn't show up in the process list like an argument would
+const HTTP_API_KEY_ENV: &str = "MLOPS_HTTP_API_KEY";
+
+fn is_loopback_host(host: &str) -> bool {
+    let host = host.trim().trim_start_matches('[').trim_end_matches(']');
+    host.eq_ignore_ascii_case("localhost")
+        || host.parse::<std::net::IpAddr>().is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Refuse to expose the model beyond this machine without authentication,
+/// unless the caller explicitly opted in with `allow_public`
+fn check_bind_safety(config: &HttpServerConfig) -> Result<(), String> {
+    let has_api_key = config.api_key.as_deref().is_some_and(|key| !key.trim().is_empty());
+    if is_loopback_host(&config.host) || has_api_key || config.allow_public {
+        return Ok(());
+    }
+    Err(format!(
+        "Refusing to bind the HTTP server to {}: the model would be reachable from the network without authentication. \
+         Set an api_key, or set allow_public to serve it unauthenticated.",
+        config.host
+    ))
+}
+
 #[derive(Clone, Serialize, Debug)]
 pub struct HttpServerStatus {
     pub running: bool,
+    pub starting: bool, // spawned by start_http_server_async, not ready yet
     pub host: Option<String>,
     pub port: Option<u16>,
     pub version_id: Option<String>,
@@ -1206,6 +2795,7 @@ pub struct HttpServerStatus {
     pub runtime: Option<String>,
     pub model_info: Option<ModelInfo>,
     pub url: Option<String>,
+    pub healthy: Option<bool>, // result of the last probe_http_server, if any
 }
 
 #[derive(Clone, Serialize, Debug)]
@@ -1224,11 +2814,43 @@ pub struct HttpServerMetrics {
     pub total_requests: u64,
     pub successful_requests: u64,
     pub failed_requests: u64,
+    pub rate_limited_requests: u64, // 429s, also counted in failed_requests
     pub avg_latency_ms: f64,
     pub requests_per_minute: f64,
     pub recent_requests: Vec<HttpRequestLog>,
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct HttpLogEntry {
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    pub message: String,
+    #[serde(default)]
+    pub timestamp: i64, // ms since epoch
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+// Number of server log lines kept for get_http_server_logs
+const HTTP_LOG_BUFFER_SIZE: usize = 500;
+
+/// Parse the payload of a `__LOG__:` line. Lines that aren't the structured
+/// JSON form are kept verbatim as info messages.
+fn parse_http_log_line(payload: &str) -> HttpLogEntry {
+    let mut entry =
+        serde_json::from_str::<HttpLogEntry>(payload).unwrap_or_else(|_| HttpLogEntry {
+            level: default_log_level(),
+            message: payload.to_string(),
+            timestamp: 0,
+        });
+    if entry.timestamp == 0 {
+        entry.timestamp = chrono::Utc::now().timestamp_millis();
+    }
+    entry
+}
+
 // HTTP Server process state
 struct HttpServerProcess {
     child: Child,
@@ -1238,8 +2860,9 @@ struct HttpServerProcess {
     port: u16,
     runtime: String,
     model_info: Option<ModelInfo>,
-    // Metrics tracking (in-memory)
-    metrics: Arc<Mutex<HttpServerMetricsTracker>>,
+    logs: Arc<Mutex<std::collections::VecDeque<HttpLogEntry>>>,
+    healthy: Option<bool>,
+    started_at: std::time::Instant,
 }
 
 #[derive(Default)]
@@ -1247,15 +2870,19 @@ struct HttpServerMetricsTracker {
     total_requests: u64,
     successful_requests: u64,
     failed_requests: u64,
+    rate_limited_requests: u64,
     total_latency_ms: f64,
     start_time: Option<std::time::Instant>,
     recent_requests: std::collections::VecDeque<HttpRequestLog>,
+    recent_buffer_size: usize,
+    warmed_up: bool, // a /predict request has succeeded
 }
 
 impl HttpServerMetricsTracker {
-    fn new() -> Self {
+    fn new(recent_buffer_size: usize) -> Self {
         Self {
             start_time: Some(std::time::Instant::now()),
+            recent_buffer_size,
             ..Default::default()
         }
     }
@@ -1267,11 +2894,17 @@ impl HttpServerMetricsTracker {
         } else {
             self.failed_requests += 1;
         }
+        if log.status_code == 429 {
+            self.rate_limited_requests += 1;
+        }
+        if log.path == "/predict" && log.status_code >= 200 && log.status_code < 300 {
+            self.warmed_up = true;
+        }
         self.total_latency_ms += log.latency_ms;
 
-        // Keep last 100 requests
+        // Keep the most recent requests, up to the configured buffer size
         self.recent_requests.push_back(log);
-        while self.recent_requests.len() > 100 {
+        while self.recent_requests.len() > self.recent_buffer_size {
             self.recent_requests.pop_front();
         }
     }
@@ -1298,6 +2931,7 @@ impl HttpServerMetricsTracker {
             total_requests: self.total_requests,
             successful_requests: self.successful_requests,
             failed_requests: self.failed_requests,
+            rate_limited_requests: self.rate_limited_requests,
             avg_latency_ms: avg_latency,
             requests_per_minute: rpm,
             recent_requests: self.recent_requests.iter().cloned().collect(),
@@ -1305,7 +2939,7 @@ impl HttpServerMetricsTracker {
     }
 
     fn reset(&mut self) {
-        *self = Self::new();
+        *self = Self::new(self.recent_buffer_size);
     }
 }
 
@@ -1315,6 +2949,21 @@ fn get_http_server_mutex() -> &'static Mutex<Option<HttpServerProcess>> {
     HTTP_SERVER.get_or_init(|| Mutex::new(None))
 }
 
+type SharedHttpMetrics = Arc<Mutex<HttpServerMetricsTracker>>;
+
+// The running server's metrics, kept apart from HTTP_SERVER so reading them
+// never waits on a start or stop that holds the process lock
+static HTTP_METRICS: std::sync::OnceLock<Mutex<Option<SharedHttpMetrics>>> = std::sync::OnceLock::new();
+
+fn get_http_metrics_mutex() -> &'static Mutex<Option<SharedHttpMetrics>> {
+    HTTP_METRICS.get_or_init(|| Mutex::new(None))
+}
+
+/// Clone out the running server's tracker so callers lock only the tracker itself
+fn current_http_metrics() -> Result<Option<SharedHttpMetrics>, String> {
+    Ok(get_http_metrics_mutex().lock().map_err(|e| e.to_string())?.clone())
+}
+
 fn get_http_pid_file_path(app_data_dir: &std::path::Path) -> std::path::PathBuf {
     app_data_dir.join("http_server.pid")
 }
@@ -1372,26 +3021,73 @@ struct HttpErrorJson {
     message: String,
 }
 
-#[tauri::command]
-pub async fn start_http_server(
-    app: AppHandle,
+/// A spawned HTTP server that hasn't reported ready yet
+struct SpawnedHttpServer {
+    child: Child,
     version_id: String,
-    config: Option<HttpServerConfig>,
-) -> Result<HttpServerStatus, String> {
-    // Check if already running
-    {
-        let guard = get_http_server_mutex().lock().map_err(|e| e.to_string())?;
-        if guard.is_some() {
-            return Err("HTTP server already running. Stop it first.".to_string());
-        }
+    model_name: String,
+    host: String,
+    port: u16,
+    app_data_dir: std::path::PathBuf,
+    metrics: Arc<Mutex<HttpServerMetricsTracker>>,
+    logs: Arc<Mutex<std::collections::VecDeque<HttpLogEntry>>>,
+    ready_rx: mpsc::Receiver<Result<HttpReadyResponse, String>>,
+}
+
+// A server started with start_http_server_async that hasn't reported ready
+static HTTP_SERVER_STARTING: std::sync::OnceLock<Mutex<Option<SpawnedHttpServerHandle>>> =
+    std::sync::OnceLock::new();
+
+struct SpawnedHttpServerHandle {
+    child: Child,
+    version_id: String,
+    model_name: String,
+    host: String,
+    port: u16,
+}
+
+fn get_http_starting_mutex() -> &'static Mutex<Option<SpawnedHttpServerHandle>> {
+    HTTP_SERVER_STARTING.get_or_init(|| Mutex::new(None))
+}
+
+fn ensure_no_http_server() -> Result<(), String> {
+    let starting = get_http_starting_mutex().lock().map_err(|e| e.to_string())?;
+    check_no_http_server(&starting)
+}
+
+/// The check behind ensure_no_http_server, for a caller already holding the
+/// starting lock
+fn check_no_http_server(starting: &Option<SpawnedHttpServerHandle>) -> Result<(), String> {
+    let running = get_http_server_mutex().lock().map_err(|e| e.to_string())?.is_some();
+    if running || starting.is_some() {
+        return Err("HTTP server already running. Stop it first.".to_string());
     }
+    Ok(())
+}
 
+/// Validate the config, then spawn the server process and its output readers
+fn spawn_http_server(
+    app: &AppHandle,
+    version_id: &str,
+    config: Option<HttpServerConfig>,
+) -> Result<SpawnedHttpServer, String> {
     let config = config.unwrap_or_default();
+    if config.recent_buffer_size > MAX_RECENT_BUFFER_SIZE {
+        return Err(format!(
+            "recent_buffer_size must be between 0 and {}",
+            MAX_RECENT_BUFFER_SIZE
+        ));
+    }
+    if let Some(limit) = &config.rate_limit {
+        validate_rate_limit(limit)?;
+    }
+    check_bind_safety(&config)?;
 
     // Get model version info
-    let version = db::get_model_version(&version_id)
+    let version = db::get_model_version(version_id)
         .map_err(|e| e.to_string())?
         .ok_or_else(|| "Model version not found".to_string())?;
+    db::check_model_trust(&version.format, version.trusted).map_err(|e| e.to_string())?;
 
     // Get model name
     let model = db::get_model(&version.model_id)
@@ -1443,11 +3139,18 @@ pub async fn start_http_server(
         }
     }
 
+    if let Some(limit) = &config.rate_limit {
+        args.push("--rate-limit".to_string());
+        args.push(limit.trim().to_string());
+    }
+
     // Spawn Python process
-    let mut child = Command::new(&python_path)
-        .args(&args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
+    let mut command = Command::new(&python_path);
+    command.args(&args).stdout(Stdio::piped()).stderr(Stdio::piped());
+    if let Some(key) = config.api_key.as_deref().map(str::trim).filter(|key| !key.is_empty()) {
+        command.env(HTTP_API_KEY_ENV, key);
+    }
+    let mut child = command
         .spawn()
         .map_err(|e| format!("Failed to spawn HTTP server: {}", e))?;
 
@@ -1458,8 +3161,10 @@ pub async fn start_http_server(
     let stderr = child.stderr.take().ok_or("Failed to capture stderr")?;
 
     // Create metrics tracker
-    let metrics = Arc::new(Mutex::new(HttpServerMetricsTracker::new()));
+    let metrics = Arc::new(Mutex::new(HttpServerMetricsTracker::new(config.recent_buffer_size)));
     let metrics_clone = metrics.clone();
+    let logs = Arc::new(Mutex::new(std::collections::VecDeque::new()));
+    let logs_clone = logs.clone();
 
     // Channel for ready signal
     let (ready_tx, ready_rx) = mpsc::channel::<Result<HttpReadyResponse, String>>();
@@ -1509,8 +3214,14 @@ pub async fn start_http_server(
                         let _ = app_clone.emit("http-server-error", &err);
                     }
                 } else if let Some(json_str) = line.strip_prefix("__LOG__:") {
-                    // Just emit log messages
-                    let _ = app_clone.emit("http-server-log", json_str);
+                    let entry = parse_http_log_line(json_str);
+                    if let Ok(mut l) = logs_clone.lock() {
+                        l.push_back(entry.clone());
+                        while l.len() > HTTP_LOG_BUFFER_SIZE {
+                            l.pop_front();
+                        }
+                    }
+                    let _ = app_clone.emit("http-server-log", &entry);
                 }
             }
         }
@@ -1530,60 +3241,187 @@ pub async fn start_http_server(
         }
     });
 
-    // Wait for ready response with timeout
-    let start_time = std::time::Instant::now();
-    let timeout = Duration::from_secs(30);
+    Ok(SpawnedHttpServer {
+        child,
+        version_id: version_id.to_string(),
+        model_name: model.name,
+        host: config.host,
+        port: config.port,
+        app_data_dir,
+        metrics,
+        logs,
+        ready_rx,
+    })
+}
 
-    loop {
-        if start_time.elapsed() > timeout {
-            let _ = child.kill();
-            remove_http_pid_file(&app_data_dir);
-            return Err("Timeout waiting for HTTP server to start".to_string());
+/// Wait for the server's ready (or startup error) message
+fn wait_for_http_ready(
+    ready_rx: &mpsc::Receiver<Result<HttpReadyResponse, String>>,
+    timeout: Duration,
+) -> Result<HttpReadyResponse, String> {
+    match ready_rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            Err("Timeout waiting for HTTP server to start".to_string())
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            Err("HTTP server process exited unexpectedly".to_string())
         }
+    }
+}
 
-        match ready_rx.recv_timeout(Duration::from_millis(100)) {
-            Ok(Ok(ready)) => {
-                let url = format!("http://{}:{}", ready.host, ready.port);
+/// Wait for startup on a background thread and hand the outcome to `on_done`
+fn watch_http_startup<F>(
+    ready_rx: mpsc::Receiver<Result<HttpReadyResponse, String>>,
+    timeout: Duration,
+    on_done: F,
+) where
+    F: FnOnce(Result<HttpReadyResponse, String>) + Send + 'static,
+{
+    std::thread::spawn(move || on_done(wait_for_http_ready(&ready_rx, timeout)));
+}
 
-                // Store process handle
-                {
-                    let mut guard = get_http_server_mutex().lock().map_err(|e| e.to_string())?;
-                    *guard = Some(HttpServerProcess {
-                        child,
-                        version_id: version_id.clone(),
-                        model_name: model.name.clone(),
-                        host: ready.host.clone(),
-                        port: ready.port,
-                        runtime: ready.runtime.clone(),
-                        model_info: ready.model_info.clone(),
-                        metrics,
-                    });
-                }
+const HTTP_START_TIMEOUT: Duration = Duration::from_secs(30);
 
-                return Ok(HttpServerStatus {
-                    running: true,
-                    host: Some(ready.host),
-                    port: Some(ready.port),
-                    version_id: Some(version_id),
-                    model_name: Some(model.name),
-                    runtime: Some(ready.runtime),
-                    model_info: ready.model_info,
-                    url: Some(url),
-                });
-            }
-            Ok(Err(e)) => {
-                let _ = child.kill();
-                remove_http_pid_file(&app_data_dir);
-                return Err(e);
+/// Store a ready server as the running one and return its status
+fn register_http_server(
+    child: Child,
+    version_id: String,
+    model_name: String,
+    metrics: Arc<Mutex<HttpServerMetricsTracker>>,
+    logs: Arc<Mutex<std::collections::VecDeque<HttpLogEntry>>>,
+    ready: HttpReadyResponse,
+) -> Result<HttpServerStatus, String> {
+    let url = format!("http://{}:{}", ready.host, ready.port);
+
+    let mut guard = get_http_server_mutex().lock().map_err(|e| e.to_string())?;
+    *guard = Some(HttpServerProcess {
+        child,
+        version_id: version_id.clone(),
+        model_name: model_name.clone(),
+        host: ready.host.clone(),
+        port: ready.port,
+        runtime: ready.runtime.clone(),
+        model_info: ready.model_info.clone(),
+        logs,
+        healthy: None,
+        started_at: std::time::Instant::now(),
+    });
+    drop(guard);
+    *get_http_metrics_mutex().lock().map_err(|e| e.to_string())? = Some(metrics);
+    let _ = db::touch_model_version(&version_id);
+
+    Ok(HttpServerStatus {
+        running: true,
+        starting: false,
+        host: Some(ready.host),
+        port: Some(ready.port),
+        version_id: Some(version_id),
+        model_name: Some(model_name),
+        runtime: Some(ready.runtime),
+        model_info: ready.model_info,
+        url: Some(url),
+        healthy: None,
+    })
+}
+
+#[tauri::command]
+pub async fn start_http_server(
+    app: AppHandle,
+    version_id: String,
+    config: Option<HttpServerConfig>,
+) -> Result<HttpServerStatus, String> {
+    ensure_no_http_server()?;
+    let mut spawned = spawn_http_server(&app, &version_id, config)?;
+
+    match wait_for_http_ready(&spawned.ready_rx, HTTP_START_TIMEOUT) {
+        Ok(ready) => register_http_server(
+            spawned.child,
+            spawned.version_id,
+            spawned.model_name,
+            spawned.metrics,
+            spawned.logs,
+            ready,
+        ),
+        Err(e) => {
+            let _ = spawned.child.kill();
+            remove_http_pid_file(&spawned.app_data_dir);
+            Err(e)
+        }
+    }
+}
+
+/// Like start_http_server, but returns as soon as the process is spawned.
+/// The outcome arrives as an `http-server-ready` or `http-server-error` event.
+#[tauri::command]
+pub async fn start_http_server_async(
+    app: AppHandle,
+    version_id: String,
+    config: Option<HttpServerConfig>,
+) -> Result<HttpServerStatus, String> {
+    // Held until the child is stored, so a second start can't pass the check
+    // while this one is spawning
+    let mut starting = get_http_starting_mutex().lock().map_err(|e| e.to_string())?;
+    check_no_http_server(&starting)?;
+    let spawned = spawn_http_server(&app, &version_id, config)?;
+
+    let status = HttpServerStatus {
+        running: false,
+        starting: true,
+        host: Some(spawned.host.clone()),
+        port: Some(spawned.port),
+        version_id: Some(spawned.version_id.clone()),
+        model_name: Some(spawned.model_name.clone()),
+        runtime: None,
+        model_info: None,
+        url: None,
+        healthy: None,
+    };
+
+    *starting = Some(SpawnedHttpServerHandle {
+        child: spawned.child,
+        version_id: spawned.version_id,
+        model_name: spawned.model_name,
+        host: spawned.host,
+        port: spawned.port,
+    });
+    drop(starting);
+
+    let (metrics, logs, app_data_dir) = (spawned.metrics, spawned.logs, spawned.app_data_dir);
+    watch_http_startup(spawned.ready_rx, HTTP_START_TIMEOUT, move |result| {
+        // Gone if stop_http_server was called while starting
+        let Some(mut handle) = get_http_starting_mutex().lock().ok().and_then(|mut g| g.take()) else {
+            return;
+        };
+        let outcome = match result {
+            Ok(ready) => register_http_server(
+                handle.child,
+                handle.version_id,
+                handle.model_name,
+                metrics,
+                logs,
+                ready,
+            ),
+            Err(e) => {
+                let _ = handle.child.kill();
+                Err(e)
             }
-            Err(mpsc::RecvTimeoutError::Timeout) => continue,
-            Err(mpsc::RecvTimeoutError::Disconnected) => {
-                let _ = child.kill();
+        };
+        match outcome {
+            Ok(status) => {
+                let _ = app.emit("http-server-ready", &status);
+            }
+            Err(message) => {
                 remove_http_pid_file(&app_data_dir);
-                return Err("HTTP server process exited unexpectedly".to_string());
+                let _ = app.emit(
+                    "http-server-error",
+                    &HttpErrorJson { code: "START_FAILED".to_string(), message },
+                );
             }
         }
-    }
+    });
+
+    Ok(status)
 }
 
 #[tauri::command]
@@ -1593,6 +3431,14 @@ pub async fn stop_http_server(app: AppHandle) -> Result<(), String> {
         .app_data_dir()
         .map_err(|e| e.to_string())?;
 
+    // A server that's still starting is simply killed
+    if let Some(mut handle) = get_http_starting_mutex().lock().map_err(|e| e.to_string())?.take() {
+        let _ = handle.child.kill();
+        let _ = handle.child.wait();
+        remove_http_pid_file(&app_data_dir);
+        return Ok(());
+    }
+
     let mut guard = get_http_server_mutex().lock().map_err(|e| e.to_string())?;
     if let Some(mut proc) = guard.take() {
         // Kill the process
@@ -1606,6 +3452,9 @@ pub async fn stop_http_server(app: AppHandle) -> Result<(), String> {
         // Wait for process to exit
         let _ = proc.child.wait();
         remove_http_pid_file(&app_data_dir);
+        if let Ok(mut metrics) = get_http_metrics_mutex().lock() {
+            *metrics = None;
+        }
         Ok(())
     } else {
         Err("No HTTP server running".to_string())
@@ -1621,6 +3470,7 @@ pub fn get_http_server_status() -> Result<HttpServerStatus, String> {
             let url = format!("http://{}:{}", proc.host, proc.port);
             Ok(HttpServerStatus {
                 running: true,
+                starting: false,
                 host: Some(proc.host.clone()),
                 port: Some(proc.port),
                 version_id: Some(proc.version_id.clone()),
@@ -1628,34 +3478,173 @@ pub fn get_http_server_status() -> Result<HttpServerStatus, String> {
                 runtime: Some(proc.runtime.clone()),
                 model_info: proc.model_info.clone(),
                 url: Some(url),
+                healthy: proc.healthy,
             })
         }
-        None => Ok(HttpServerStatus {
-            running: false,
-            host: None,
-            port: None,
-            version_id: None,
-            model_name: None,
-            runtime: None,
-            model_info: None,
-            url: None,
-        }),
+        None => {
+            let starting = get_http_starting_mutex().lock().map_err(|e| e.to_string())?;
+            let handle = starting.as_ref();
+            Ok(HttpServerStatus {
+                running: false,
+                starting: handle.is_some(),
+                host: handle.map(|h| h.host.clone()),
+                port: handle.map(|h| h.port),
+                version_id: handle.map(|h| h.version_id.clone()),
+                model_name: handle.map(|h| h.model_name.clone()),
+                runtime: None,
+                model_info: None,
+                url: None,
+                healthy: None,
+            })
+        }
+    }
+}
+
+/// Address to reach a server bound to `host` from this machine
+fn probe_host(host: &str) -> &str {
+    match host {
+        "0.0.0.0" | "" => "127.0.0.1",
+        "::" => "[::1]",
+        _ => host,
+    }
+}
+
+/// True if `{base_url}/health` answers 200 with status "healthy"
+async fn check_http_health(base_url: &str) -> bool {
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()
+    {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+
+    let resp = match client.get(format!("{}/health", base_url)).send().await {
+        Ok(resp) if resp.status().is_success() => resp,
+        _ => return false,
+    };
+    match resp.json::<serde_json::Value>().await {
+        Ok(body) => body.get("status").and_then(|s| s.as_str()) == Some("healthy"),
+        Err(_) => false,
     }
 }
 
+/// Check that the running server actually answers on /health and record the
+/// result in its status. `running` only means we hold the process handle.
 #[tauri::command]
-pub fn get_http_server_metrics() -> Result<HttpServerMetrics, String> {
-    let guard = get_http_server_mutex().lock().map_err(|e| e.to_string())?;
+pub async fn probe_http_server() -> Result<bool, String> {
+    let (pid, base_url) = {
+        let mut guard = get_http_server_mutex().lock().map_err(|e| e.to_string())?;
+        let proc = guard.as_mut().ok_or("No HTTP server running")?;
+        if let Ok(Some(_)) = proc.child.try_wait() {
+            proc.healthy = Some(false);
+            return Ok(false);
+        }
+        (proc.child.id(), format!("http://{}:{}", probe_host(&proc.host), proc.port))
+    };
 
-    match &*guard {
-        Some(proc) => {
-            let metrics = proc.metrics.lock().map_err(|e| e.to_string())?;
+    let healthy = check_http_health(&base_url).await;
+
+    // Don't record the result against a server that replaced this one meanwhile
+    let mut guard = get_http_server_mutex().lock().map_err(|e| e.to_string())?;
+    if let Some(proc) = guard.as_mut().filter(|p| p.child.id() == pid) {
+        proc.healthy = Some(healthy);
+    }
+    Ok(healthy)
+}
+
+/// How far along serving a model version is, across the inference and HTTP
+/// servers. A server counts once it serves this version.
+#[derive(Clone, Serialize, Debug, Default, PartialEq)]
+pub struct ServingReadiness {
+    pub process_running: bool,
+    pub model_loaded: bool,           // a server reported the model loaded
+    pub warmup_done: bool,            // a loaded server answered its first prediction
+    pub last_health_ok: Option<bool>, // the HTTP server's last probe, if any
+}
+
+/// One server's state, as `serving_readiness` needs it
+struct ServerReadiness {
+    running: bool,
+    loaded: bool,
+    warmed_up: bool,
+    healthy: Option<bool>,
+}
+
+fn serving_readiness(servers: &[ServerReadiness]) -> ServingReadiness {
+    let running: Vec<&ServerReadiness> = servers.iter().filter(|s| s.running).collect();
+    ServingReadiness {
+        process_running: !running.is_empty(),
+        model_loaded: running.iter().any(|s| s.loaded),
+        warmup_done: running.iter().any(|s| s.loaded && s.warmed_up),
+        last_health_ok: running.iter().find_map(|s| s.healthy),
+    }
+}
+
+/// Whether `version_id` is being served, loaded, warmed up and healthy, for
+/// a readiness badge. Exited processes whose handle is still held count as
+/// not running.
+#[tauri::command]
+pub fn get_serving_readiness(version_id: String) -> Result<ServingReadiness, String> {
+    let mut servers = Vec::new();
+    if let Some(proc) = get_inference_mutex()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_mut()
+        .filter(|p| p.version_id == version_id)
+    {
+        servers.push(ServerReadiness {
+            running: matches!(proc.child.try_wait(), Ok(None)),
+            loaded: true, // stored only after the ready message
+            warmed_up: proc.warmed_up,
+            healthy: None,
+        });
+    }
+    if let Some(proc) = get_http_server_mutex()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_mut()
+        .filter(|p| p.version_id == version_id)
+    {
+        let warmed_up = match current_http_metrics()? {
+            Some(metrics) => metrics.lock().map_err(|e| e.to_string())?.warmed_up,
+            None => false,
+        };
+        servers.push(ServerReadiness {
+            running: matches!(proc.child.try_wait(), Ok(None)),
+            loaded: true,
+            warmed_up,
+            healthy: proc.healthy,
+        });
+    }
+    if let Some(handle) = get_http_starting_mutex()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .as_mut()
+        .filter(|h| h.version_id == version_id)
+    {
+        servers.push(ServerReadiness {
+            running: matches!(handle.child.try_wait(), Ok(None)),
+            loaded: false,
+            warmed_up: false,
+            healthy: None,
+        });
+    }
+    Ok(serving_readiness(&servers))
+}
+
+#[tauri::command]
+pub fn get_http_server_metrics() -> Result<HttpServerMetrics, String> {
+    match current_http_metrics()? {
+        Some(metrics) => {
+            let metrics = metrics.lock().map_err(|e| e.to_string())?;
             Ok(metrics.get_metrics())
         }
         None => Ok(HttpServerMetrics {
             total_requests: 0,
             successful_requests: 0,
             failed_requests: 0,
+            rate_limited_requests: 0,
             avg_latency_ms: 0.0,
             requests_per_minute: 0.0,
             recent_requests: vec![],
@@ -1663,13 +3652,25 @@ pub fn get_http_server_metrics() -> Result<HttpServerMetrics, String> {
     }
 }
 
+/// Most recent server log entries, oldest first
 #[tauri::command]
-pub fn reset_http_server_metrics() -> Result<(), String> {
+pub fn get_http_server_logs(limit: Option<usize>) -> Result<Vec<HttpLogEntry>, String> {
     let guard = get_http_server_mutex().lock().map_err(|e| e.to_string())?;
 
-    if let Some(proc) = &*guard {
-        let mut metrics = proc.metrics.lock().map_err(|e| e.to_string())?;
-        metrics.reset();
+    match &*guard {
+        Some(proc) => {
+            let logs = proc.logs.lock().map_err(|e| e.to_string())?;
+            let skip = limit.map_or(0, |n| logs.len().saturating_sub(n));
+            Ok(logs.iter().skip(skip).cloned().collect())
+        }
+        None => Ok(vec![]),
+    }
+}
+
+#[tauri::command]
+pub fn reset_http_server_metrics() -> Result<(), String> {
+    if let Some(metrics) = current_http_metrics()? {
+        metrics.lock().map_err(|e| e.to_string())?.reset();
     }
 
     Ok(())
@@ -1722,6 +3723,13 @@ pub async fn list_ollama_models(host: Option<String>) -> Result<Vec<String>, Str
 }
 
 #[tauri::command]
+pub async fn ensure_ollama_model(host: Option<String>, model: String) -> Result<(), String> {
+    let h = host.as_deref().unwrap_or("http://localhost:11434");
+    crate::ollama::ensure_model(h, &model).await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn generate_completion(
     request_id: String,
     host: Option<String>,
@@ -1729,17 +3737,27 @@ pub async fn generate_completion(
     context: String,
     cursor_line: String,
     columns: Vec<String>,
+    prompt_template: Option<String>,
+    mode: Option<crate::ollama::PromptMode>,
+    backend: Option<crate::ollama::Backend>,
 ) -> Result<String, String> {
     // Register request for cancellation tracking
     crate::ollama::register_request(&request_id);
 
-    let h = host.as_deref().unwrap_or("http://localhost:11434");
+    let backend = backend.unwrap_or_default();
+    let h = host.as_deref().unwrap_or(backend.default_host());
+    let prompt_options = crate::ollama::PromptOptions {
+        mode: mode.unwrap_or_default(),
+        template: prompt_template,
+    };
     let result = crate::ollama::generate_completion(
+        backend,
         h,
         &model,
         &context,
         &cursor_line,
         &columns,
+        &prompt_options,
         &request_id,
     )
     .await;
@@ -1770,10 +3788,24 @@ pub struct ChunkEmbeddingInput {
     pub symbol_type: Option<String>,
     pub start_line: Option<i32>,
     pub end_line: Option<i32>,
+    // Preprocessing the caller applied before computing the embedding
+    #[serde(default)]
+    pub preprocess: crate::rag::PreprocessMode,
+    // Clear the pipeline's index and re-pin if it was built with another model
+    #[serde(default)]
+    pub force_reindex: bool,
 }
 
 #[tauri::command]
 pub fn upsert_chunk_embedding(input: ChunkEmbeddingInput) -> Result<(), String> {
+    crate::rag::ensure_embedding_pin(
+        &input.pipeline_id,
+        &input.embedding_model,
+        input.embedding_dim,
+        input.preprocess,
+        input.force_reindex,
+    )?;
+
     db::upsert_chunk_embedding(
         &input.node_id,
         &input.pipeline_id,
@@ -1814,3 +3846,1880 @@ pub fn delete_chunks_for_node(node_id: String) -> Result<usize, String> {
 pub fn delete_chunks_for_pipeline(pipeline_id: String) -> Result<usize, String> {
     db::delete_chunks_for_pipeline(&pipeline_id).map_err(|e| e.to_string())
 }
+
+/// Check an Ollama embedding model is pulled before indexing starts. A
+/// missing model would otherwise fail mid-way and leave a partial index.
+async fn ensure_embedding_model(backend: crate::ollama::Backend, host: &str, model: &str) -> Result<(), String> {
+    if backend == crate::ollama::Backend::Ollama {
+        crate::ollama::ensure_model(host, model).await?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn index_node_chunks(
+    host: Option<String>,
+    model: String,
+    pipeline_id: String,
+    node: crate::rag::NodeChunks,
+    preprocess: Option<crate::rag::PreprocessMode>,
+    backend: Option<crate::ollama::Backend>,
+) -> Result<crate::rag::IndexSummary, String> {
+    let backend = backend.unwrap_or_default();
+    let h = host.unwrap_or_else(|| backend.default_host().to_string());
+    ensure_embedding_model(backend, &h, &model).await?;
+    let embed = |text: String| {
+        let (h, model) = (h.clone(), model.clone());
+        async move { crate::ollama::generate_embedding(backend, &h, &model, &text).await }
+    };
+    crate::rag::index_node_chunks(&embed, &pipeline_id, &model, preprocess.unwrap_or_default(), &node).await
+}
+
+#[tauri::command]
+pub async fn reindex_pipeline_incremental(
+    host: Option<String>,
+    model: String,
+    pipeline_id: String,
+    nodes_with_chunks: Vec<crate::rag::NodeChunks>,
+    preprocess: Option<crate::rag::PreprocessMode>,
+    backend: Option<crate::ollama::Backend>,
+) -> Result<crate::rag::IndexSummary, String> {
+    let backend = backend.unwrap_or_default();
+    let h = host.unwrap_or_else(|| backend.default_host().to_string());
+    ensure_embedding_model(backend, &h, &model).await?;
+    let embed = |text: String| {
+        let (h, model) = (h.clone(), model.clone());
+        async move { crate::ollama::generate_embedding(backend, &h, &model, &text).await }
+    };
+    crate::rag::reindex_pipeline_incremental(
+        &embed,
+        &pipeline_id,
+        &model,
+        preprocess.unwrap_or_default(),
+        &nodes_with_chunks,
+    )
+    .await
+}
+
+#[tauri::command]
+pub async fn search_similar_chunks(
+    host: Option<String>,
+    model: String,
+    pipeline_id: String,
+    query: String,
+    top_k: Option<usize>,
+    min_score: Option<f32>,
+    backend: Option<crate::ollama::Backend>,
+) -> Result<Vec<crate::rag::ChunkMatch>, String> {
+    let backend = backend.unwrap_or_default();
+    let h = host.unwrap_or_else(|| backend.default_host().to_string());
+    let embed = |text: String| {
+        let (h, model) = (h.clone(), model.clone());
+        async move { crate::ollama::generate_embedding(backend, &h, &model, &text).await }
+    };
+    crate::rag::search_similar_chunks(&embed, &pipeline_id, &model, &query, top_k.unwrap_or(5), min_score).await
+}
+
+#[tauri::command]
+pub async fn search_similar_nodes(
+    host: Option<String>,
+    model: String,
+    pipeline_id: String,
+    query: String,
+    top_k: Option<usize>,
+    min_score: Option<f32>,
+    backend: Option<crate::ollama::Backend>,
+) -> Result<Vec<crate::rag::NodeMatch>, String> {
+    let backend = backend.unwrap_or_default();
+    let h = host.unwrap_or_else(|| backend.default_host().to_string());
+    let embed = |text: String| {
+        let (h, model) = (h.clone(), model.clone());
+        async move { crate::ollama::generate_embedding(backend, &h, &model, &text).await }
+    };
+    crate::rag::search_similar_nodes(&embed, &pipeline_id, &model, &query, top_k.unwrap_or(5), min_score).await
+}
+
+/// Vector search followed by an LLM rerank of the best `rerank_top_n` nodes.
+/// `model` is the embedding model; `rerank_model` is the model asked to judge
+/// relevance, on the same backend and host.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn search_similar_nodes_reranked(
+    host: Option<String>,
+    model: String,
+    rerank_model: String,
+    pipeline_id: String,
+    query: String,
+    top_k: Option<usize>,
+    rerank_top_n: Option<usize>,
+    backend: Option<crate::ollama::Backend>,
+) -> Result<Vec<crate::rag::RerankedNode>, String> {
+    let backend = backend.unwrap_or_default();
+    let h = host.unwrap_or_else(|| backend.default_host().to_string());
+    let embed = |text: String| {
+        let (h, model) = (h.clone(), model.clone());
+        async move { crate::ollama::generate_embedding(backend, &h, &model, &text).await }
+    };
+    let rerank = |prompt: String| {
+        let (h, rerank_model) = (h.clone(), rerank_model.clone());
+        async move { crate::ollama::generate_text(backend, &h, &rerank_model, &prompt).await }
+    };
+    crate::rag::search_similar_nodes_reranked(
+        &embed,
+        &rerank,
+        &pipeline_id,
+        &model,
+        &rerank_model,
+        &query,
+        top_k.unwrap_or(5),
+        rerank_top_n.unwrap_or(20),
+    )
+    .await
+}
+
+#[tauri::command]
+pub fn get_embedding_config(pipeline_id: String) -> Result<Option<db::EmbeddingConfig>, String> {
+    db::get_embedding_config(&pipeline_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn rag_get_status(pipeline_id: String) -> Result<db::RagStatus, String> {
+    db::get_rag_status(&pipeline_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn rag_get_global_status() -> Result<db::GlobalRagStatus, String> {
+    db::get_global_rag_status().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn rag_estimate_dedup_savings(pipeline_id: String) -> Result<db::DedupEstimate, String> {
+    db::estimate_dedup_savings(&pipeline_id).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn rag_preprocess_query(pipeline_id: String, query: String) -> Result<String, String> {
+    crate::rag::preprocess_query_for_pipeline(&pipeline_id, &query)
+}
+
+#[tauri::command]
+pub fn rag_needs_reindex(pipeline_id: String, model: String) -> Result<bool, String> {
+    db::rag_needs_reindex(&pipeline_id, &model).map_err(|e| e.to_string())
+}
+
+// Database maintenance commands
+
+#[tauri::command]
+pub fn get_db_version() -> Result<i32, String> {
+    db::get_db_version().map_err(|e| e.to_string())
+}
+
+/// Point model file paths recorded under a previous app data directory (e.g.
+/// a backup restored on another machine) at `new_app_data_dir`; returns how
+/// many versions were updated
+#[tauri::command]
+pub fn relocate_paths(new_app_data_dir: String) -> Result<usize, String> {
+    db::relocate_paths(std::path::Path::new(&new_app_data_dir)).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn audit_model_files() -> Result<Vec<db::FileAudit>, String> {
+    db::audit_model_files().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn repair_model_files(strategy: db::RepairStrategy) -> Result<Vec<db::FileAudit>, String> {
+    db::repair_model_files(strategy).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn checkpoint_database(mode: db::CheckpointMode) -> Result<db::CheckpointResult, String> {
+    db::checkpoint_database(mode).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn optimize_database() -> Result<(), String> {
+    db::optimize_database().map_err(|e| e.to_string())
+}
+
+// System status
+
+/// One background subsystem in list_running_processes
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ProcessInfo {
+    pub subsystem: String, // "script", "inference_server", "http_server" or "lsp"
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub version_id: Option<String>, // model version served (inference and HTTP servers)
+    pub run_id: Option<String>,     // run the script belongs to
+    pub pipeline: Option<String>,   // the run's pipeline, or the LSP workspace root
+    pub uptime_secs: Option<u64>,
+}
+
+impl ProcessInfo {
+    fn stopped(subsystem: &str) -> Self {
+        ProcessInfo {
+            subsystem: subsystem.to_string(),
+            running: false,
+            pid: None,
+            version_id: None,
+            run_id: None,
+            pipeline: None,
+            uptime_secs: None,
+        }
+    }
+
+    fn running(subsystem: &str, pid: u32, uptime: Duration) -> Self {
+        ProcessInfo {
+            running: true,
+            pid: Some(pid),
+            uptime_secs: Some(uptime.as_secs()),
+            ..ProcessInfo::stopped(subsystem)
+        }
+    }
+}
+
+/// Every background subsystem and whether it has a live process, in a fixed
+/// order (script, inference server, HTTP server, LSP)
+#[tauri::command]
+pub fn list_running_processes() -> Result<Vec<ProcessInfo>, String> {
+    let script = match &*get_script_info_mutex().lock().map_err(|e| e.to_string())? {
+        Some(info) => {
+            let pipeline = info
+                .run_id
+                .as_deref()
+                .and_then(|id| db::get_run(id).ok().flatten())
+                .map(|run| run.pipeline_name);
+            ProcessInfo {
+                run_id: info.run_id.clone(),
+                pipeline,
+                ..ProcessInfo::running("script", info.pid, info.started_at.elapsed())
+            }
+        }
+        None => ProcessInfo::stopped("script"),
+    };
+
+    let inference = match &*get_inference_mutex().lock().map_err(|e| e.to_string())? {
+        Some(proc) => ProcessInfo {
+            version_id: Some(proc.version_id.clone()),
+            ..ProcessInfo::running("inference_server", proc.child.id(), proc.started_at.elapsed())
+        },
+        None => ProcessInfo::stopped("inference_server"),
+    };
+
+    let http = match &*get_http_server_mutex().lock().map_err(|e| e.to_string())? {
+        Some(proc) => ProcessInfo {
+            version_id: Some(proc.version_id.clone()),
+            ..ProcessInfo::running("http_server", proc.child.id(), proc.started_at.elapsed())
+        },
+        None => ProcessInfo::stopped("http_server"),
+    };
+
+    let lsp = match crate::lsp::process_info() {
+        Some((pid, uptime, workspace_root)) => ProcessInfo {
+            pipeline: workspace_root,
+            ..ProcessInfo::running("lsp", pid, uptime)
+        },
+        None => ProcessInfo::stopped("lsp"),
+    };
+
+    Ok(vec![script, inference, http, lsp])
+}
+
+// Backend log level, adjustable at runtime so debug logging can be turned on
+// without a rebuild
+static LOG_LEVEL: std::sync::OnceLock<
+    tracing_subscriber::reload::Handle<
+        tracing_subscriber::filter::LevelFilter,
+        tracing_subscriber::Registry,
+    >,
+> = std::sync::OnceLock::new();
+
+// Directory holding the rolling log files, set once logging is initialized
+static LOG_DIR: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+
+// Log files are named app.<date>.log and rotated daily
+const LOG_FILE_PREFIX: &str = "app";
+const LOG_FILE_SUFFIX: &str = "log";
+const MAX_LOG_FILES: usize = 7;
+
+fn install_subscriber(
+    log_dir: Option<&std::path::Path>,
+) -> &'static tracing_subscriber::reload::Handle<
+    tracing_subscriber::filter::LevelFilter,
+    tracing_subscriber::Registry,
+> {
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    LOG_LEVEL.get_or_init(|| {
+        let (filter, handle) =
+            tracing_subscriber::reload::Layer::new(tracing_subscriber::filter::LevelFilter::INFO);
+        let file_appender = log_dir.and_then(|dir| {
+            tracing_appender::rolling::RollingFileAppender::builder()
+                .rotation(tracing_appender::rolling::Rotation::DAILY)
+                .filename_prefix(LOG_FILE_PREFIX)
+                .filename_suffix(LOG_FILE_SUFFIX)
+                .max_log_files(MAX_LOG_FILES)
+                .build(dir)
+                .map_err(|e| eprintln!("Failed to open log file in {}: {}", dir.display(), e))
+                .ok()
+        });
+        let file_layer = file_appender.map(|appender| {
+            tracing_subscriber::fmt::layer()
+                .with_ansi(false)
+                .with_writer(appender)
+        });
+        // Fails only if another subscriber is already installed (e.g. in tests)
+        let _ = tracing_subscriber::registry()
+            .with(filter)
+            .with(tracing_subscriber::fmt::layer())
+            .with(file_layer)
+            .try_init();
+        handle
+    })
+}
+
+fn log_level_handle() -> &'static tracing_subscriber::reload::Handle<
+    tracing_subscriber::filter::LevelFilter,
+    tracing_subscriber::Registry,
+> {
+    install_subscriber(None)
+}
+
+/// Install the global tracing subscriber at the default (info) level, logging
+/// to stdout and to rolling files under `<app_data>/logs`
+pub fn init_logging(app_data_dir: &std::path::Path) {
+    let log_dir = app_data_dir.join("logs");
+    let _ = LOG_DIR.set(log_dir.clone());
+    install_subscriber(Some(&log_dir));
+}
+
+// The newest log file in the directory, if any; dated names sort chronologically
+fn latest_log_file(log_dir: &std::path::Path) -> Option<std::path::PathBuf> {
+    std::fs::read_dir(log_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path.file_name().and_then(|n| n.to_str()).is_some_and(|name| {
+                    name.starts_with(&format!("{}.", LOG_FILE_PREFIX))
+                        && name.ends_with(&format!(".{}", LOG_FILE_SUFFIX))
+                })
+        })
+        .max()
+}
+
+// Last `n_lines` lines of a file, oldest first. Reads backwards from the end in
+// blocks so large logs aren't loaded whole.
+fn tail_lines(path: &std::path::Path, n_lines: usize) -> std::io::Result<Vec<String>> {
+    use std::io::{Read, Seek, SeekFrom};
+    const BLOCK_SIZE: u64 = 8192;
+
+    if n_lines == 0 {
+        return Ok(Vec::new());
+    }
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let mut pos = len;
+    let mut buf: Vec<u8> = Vec::new();
+    // The newline before the first wanted line proves it's complete; a
+    // trailing newline ends the last line rather than starting an empty one
+    let mut newlines_needed = n_lines;
+    while pos > 0 {
+        let read_size = BLOCK_SIZE.min(pos);
+        pos -= read_size;
+        file.seek(SeekFrom::Start(pos))?;
+        let mut block = vec![0u8; read_size as usize];
+        file.read_exact(&mut block)?;
+        if pos + read_size == len && block.last() == Some(&b'\n') {
+            newlines_needed += 1;
+        }
+        newlines_needed = newlines_needed.saturating_sub(block.iter().filter(|&&b| b == b'\n').count());
+        block.extend_from_slice(&buf);
+        buf = block;
+        if newlines_needed == 0 {
+            break;
+        }
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let lines: Vec<&str> = text.lines().collect();
+    let skip = lines.len().saturating_sub(n_lines);
+    Ok(lines[skip..].iter().map(|line| line.to_string()).collect())
+}
+
+/// The last `n_lines` lines of the current log file; empty if nothing has been
+/// logged to a file yet
+#[tauri::command]
+pub fn get_recent_logs(n_lines: usize) -> Result<Vec<String>, String> {
+    let Some(log_file) = LOG_DIR.get().and_then(|dir| latest_log_file(dir)) else {
+        return Ok(Vec::new());
+    };
+    tail_lines(&log_file, n_lines).map_err(|e| format!("Failed to read {}: {}", log_file.display(), e))
+}
+
+// Log lines included in a support bundle
+const SUPPORT_BUNDLE_LOG_LINES: usize = 2000;
+
+/// manifest.json of a support bundle
+#[derive(Serialize)]
+struct SupportBundleManifest {
+    created_at: String,
+    app_version: &'static str,
+    db_version: Option<i32>,
+    os: &'static str,
+    arch: &'static str,
+    processes: Vec<ProcessInfo>,
+    entries: Vec<&'static str>,
+}
+
+fn home_dir() -> Option<String> {
+    std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()
+        .filter(|home| !home.is_empty())
+}
+
+/// Path prefixes hidden in a support bundle and their placeholders. The app
+/// data dir comes first since it usually lives under the home directory.
+fn support_bundle_redactions(app_data_dir: Option<&std::path::Path>) -> Vec<(String, String)> {
+    let mut redactions = Vec::new();
+    if let Some(dir) = app_data_dir {
+        redactions.push((dir.to_string_lossy().to_string(), "<app_data>".to_string()));
+    }
+    if let Some(home) = home_dir() {
+        redactions.push((home, "~".to_string()));
+    }
+    redactions
+}
+
+fn redact_paths(text: &str, redactions: &[(String, String)]) -> String {
+    redactions
+        .iter()
+        .filter(|(from, _)| !from.is_empty())
+        .fold(text.to_string(), |text, (from, to)| text.replace(from.as_str(), to))
+}
+
+/// Pack the database, recent logs, Python diagnostics and a manifest of
+/// running processes into a zip at `dest`, with `redactions` applied to all
+/// of them
+fn write_support_bundle(
+    dest: &std::path::Path,
+    diagnostics: &python::PythonDiagnostics,
+    redactions: &[(String, String)],
+) -> Result<(), String> {
+    const ENTRIES: [&str; 4] = ["manifest.json", "settings.db", "python_diagnostics.json", "logs/recent.log"];
+
+    let staging = std::env::temp_dir().join(format!("support-bundle-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&staging).map_err(|e| e.to_string())?;
+    let db_copy = staging.join("settings.db");
+    let partial = dest.with_extension("partial");
+
+    let pack = || -> Result<(), String> {
+        db::backup_database_redacted(&db_copy, redactions).map_err(|e| e.to_string())?;
+
+        let manifest = SupportBundleManifest {
+            created_at: chrono::Utc::now().to_rfc3339(),
+            app_version: env!("CARGO_PKG_VERSION"),
+            db_version: db::get_db_version().ok(),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            processes: list_running_processes()?,
+            entries: ENTRIES.to_vec(),
+        };
+        let manifest = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+        let diagnostics = serde_json::to_string_pretty(diagnostics).map_err(|e| e.to_string())?;
+        let logs = get_recent_logs(SUPPORT_BUNDLE_LOG_LINES)?.join("\n");
+
+        let write = || -> std::io::Result<()> {
+            use std::io::Write;
+            let mut zip = zip::ZipWriter::new(std::fs::File::create(&partial)?);
+            let options = zip::write::SimpleFileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated)
+                .unix_permissions(0o644);
+            for (name, text) in [(ENTRIES[0], &manifest), (ENTRIES[2], &diagnostics), (ENTRIES[3], &logs)] {
+                zip.start_file(name, options)?;
+                zip.write_all(redact_paths(text, redactions).as_bytes())?;
+            }
+            zip.start_file(ENTRIES[1], options.large_file(true))?;
+            std::io::copy(&mut std::fs::File::open(&db_copy)?, &mut zip)?;
+            zip.finish()?.sync_all()
+        };
+        write()
+            .and_then(|_| std::fs::rename(&partial, dest))
+            .map_err(|e| format!("Failed to write support bundle: {}", e))
+    };
+    let result = pack();
+    let _ = std::fs::remove_dir_all(&staging);
+    if result.is_err() {
+        let _ = std::fs::remove_file(&partial);
+    }
+    result
+}
+
+/// Write a `.zip` users can attach to a bug report: a copy of the database,
+/// the recent log, Python diagnostics and a manifest of running processes and
+/// versions. The app data and home directories are replaced with placeholders
+/// throughout.
+#[tauri::command]
+pub fn create_support_bundle(app: AppHandle, dest_path: String) -> Result<(), String> {
+    let resource_dir = app.path().resource_dir().ok();
+    let diagnostics = python::python_diagnostics(resource_dir.as_ref());
+    let app_data_dir = app.path().app_data_dir().ok();
+    write_support_bundle(
+        std::path::Path::new(&dest_path),
+        &diagnostics,
+        &support_bundle_redactions(app_data_dir.as_deref()),
+    )
+}
+
+#[tauri::command]
+pub fn set_log_level(level: String) -> Result<(), String> {
+    let filter = level
+        .trim()
+        .parse::<tracing_subscriber::filter::LevelFilter>()
+        .map_err(|_| {
+            format!(
+                "Invalid log level '{}' (expected off, error, warn, info, debug or trace)",
+                level
+            )
+        })?;
+    log_level_handle()
+        .reload(filter)
+        .map_err(|e| format!("Failed to set log level: {}", e))?;
+    tracing::info!("Log level set to {}", filter);
+    Ok(())
+}
+
+// Completion event commands
+
+#[tauri::command]
+pub fn log_completion_event(
+    pipeline_id: String,
+    node_id: Option<String>,
+    model: String,
+    was_rag: bool,
+    accepted: bool,
+) -> Result<(), String> {
+    db::log_completion_event(&pipeline_id, node_id.as_deref(), &model, was_rag, accepted)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_completion_acceptance_stats(
+    pipeline_id: String,
+) -> Result<db::CompletionAcceptanceStats, String> {
+    db::get_completion_acceptance_stats(&pipeline_id).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::{create_test_model, register_test_version, setup_test_db};
+
+    // Held by tests that install state in HTTP_SERVER or HTTP_METRICS
+    static HTTP_SERVER_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_register_model_version_snapshot_from_run() {
+        setup_test_db();
+        let model_id = uuid::Uuid::new_v4().to_string();
+        db::create_model(&model_id, &format!("snapshot-{}", model_id), None).unwrap();
+        let run_id = uuid::Uuid::new_v4().to_string();
+        db::create_run(&run_id, "Snapshot", "{}", None).unwrap();
+        let metric = |name: &str, value: Option<f64>, json: Option<&str>| db::Metric {
+            name: name.to_string(),
+            value,
+            value_json: json.map(String::from),
+            kind: None,
+        };
+        db::save_run_metrics(
+            &run_id,
+            &[
+                metric("accuracy", Some(0.91), None),
+                metric("f1", Some(0.88), None),
+                metric("confusion_matrix", None, Some("[[1, 0], [0, 1]]")),
+            ],
+        )
+        .unwrap();
+
+        let source = std::env::temp_dir().join(format!("snapshot-{}.joblib", uuid::Uuid::new_v4()));
+        std::fs::write(&source, b"model").unwrap();
+        let register = |snapshot_from_run, run_id: Option<String>| {
+            register_model_version(
+                model_id.clone(),
+                run_id,
+                source.to_string_lossy().to_string(),
+                "joblib".to_string(),
+                Some(r#"{"accuracy": 0.5}"#.to_string()),
+                None,
+                None,
+                snapshot_from_run,
+            )
+        };
+
+        // The stale caller snapshot is ignored in favour of the run's scalar metrics
+        let result = register(Some(true), Some(run_id.clone())).unwrap();
+        let version = db::get_model_version(&result.version_id).unwrap().unwrap();
+        let snapshot: serde_json::Value = serde_json::from_str(&version.metrics_snapshot.unwrap()).unwrap();
+        assert_eq!(snapshot, serde_json::json!({"accuracy": 0.91, "f1": 0.88}));
+
+        // Without the flag the caller's snapshot is stored as given
+        let result = register(None, Some(run_id.clone())).unwrap();
+        let version = db::get_model_version(&result.version_id).unwrap().unwrap();
+        assert_eq!(version.metrics_snapshot.as_deref(), Some(r#"{"accuracy": 0.5}"#));
+
+        assert_eq!(register(Some(true), None).err().unwrap(), "snapshot_from_run requires a run_id");
+        std::fs::remove_file(&source).unwrap();
+    }
+
+    #[test]
+    fn test_register_http_server_marks_version_used() {
+        let _serial = HTTP_SERVER_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        setup_test_db();
+        let model_id = uuid::Uuid::new_v4().to_string();
+        db::create_model(&model_id, &format!("used-{}", model_id), None).unwrap();
+        let source = std::env::temp_dir().join(format!("used-{}.joblib", uuid::Uuid::new_v4()));
+        std::fs::write(&source, b"model").unwrap();
+        let version_id = uuid::Uuid::new_v4().to_string();
+        db::register_model_version(&version_id, &model_id, None, &source.to_string_lossy(), "joblib", None, None, false)
+            .unwrap();
+        assert!(db::get_model_version(&version_id).unwrap().unwrap().last_used_at.is_none());
+
+        // A stand-in for the server process
+        let child = Command::new("sleep").arg("30").spawn().unwrap();
+        let ready = HttpReadyResponse {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            runtime: "sklearn".to_string(),
+            model_info: None,
+        };
+        register_http_server(
+            child,
+            version_id.clone(),
+            "used".to_string(),
+            Arc::new(Mutex::new(HttpServerMetricsTracker::new(1))),
+            Arc::new(Mutex::new(std::collections::VecDeque::new())),
+            ready,
+        )
+        .unwrap();
+        if let Some(mut proc) = get_http_server_mutex().lock().unwrap().take() {
+            let _ = proc.child.kill();
+            let _ = proc.child.wait();
+        }
+        *get_http_metrics_mutex().lock().unwrap() = None;
+
+        assert!(db::get_model_version(&version_id).unwrap().unwrap().last_used_at.is_some());
+        std::fs::remove_file(&source).unwrap();
+    }
+
+    #[test]
+    fn test_trial_events_are_saved_to_session() {
+        setup_test_db();
+        let run_id = format!("tuning-stream-{}", uuid::Uuid::new_v4());
+        db::create_run(&run_id, "Tuning Run", "{}", None).unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        db::create_tuning_session(&session_id, &run_id, "tpe", "{}", Some(3), 3, "accuracy", None).unwrap();
+
+        for line in [
+            r#"{"type": "trial", "trialNumber": 0, "params": {"C": 1.0}, "score": 0.81, "durationMs": 120}"#,
+            r#"{"type": "log", "message": "Trial 0 done"}"#,
+            r#"{"type": "trial", "trialNumber": 1, "params": {"C": 10.0}, "score": 0.9}"#,
+            r#"{"type": "trial", "trialNumber": 2, "params": {"C": 0.1}, "score": 0.7, "durationMs": 80}"#,
+        ] {
+            save_trial_event(Some(&session_id), &parse_output_line(line));
+        }
+        let trials = db::list_tuning_trials(&session_id).unwrap();
+        assert_eq!(trials.iter().map(|t| t.trial_number).collect::<Vec<_>>(), vec![0, 1, 2]);
+        assert_eq!(trials[0].hyperparameters, r#"{"C":1.0}"#);
+        assert_eq!((trials[0].duration_ms, trials[1].duration_ms), (Some(120), None));
+        let best = db::get_tuning_session(&session_id).unwrap().unwrap().best_trial_id;
+        assert_eq!(best.as_deref(), Some(trials[1].id.as_str()));
+
+        // The frontend saving the same trial updates the row instead of adding one
+        save_tuning_trial(session_id.clone(), 1, r#"{"C": 10.0}"#.to_string(), Some(0.9), None).unwrap();
+        assert_eq!(db::list_tuning_trials(&session_id).unwrap().len(), 3);
+
+        // Completing without a best trial keeps the streamed one
+        complete_tuning_session(session_id.clone(), None).unwrap();
+        let session = db::get_tuning_session(&session_id).unwrap().unwrap();
+        assert_eq!((session.status.as_str(), session.best_trial_id), ("completed", best));
+
+        // Runs outside a tuning session store nothing
+        save_trial_event(None, &parse_output_line(r#"{"type": "trial", "trialNumber": 3, "params": {}, "score": 1.0}"#));
+        assert_eq!(db::list_tuning_trials(&session_id).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_stderr_json_events_are_not_errors() {
+        let event = |e: ScriptEvent| serde_json::to_value(e).unwrap();
+
+        assert_eq!(
+            event(parse_stderr_line(r#"{"type": "progress", "current": 3, "total": 10}"#)),
+            serde_json::json!({"type": "progress", "current": 3, "total": 10})
+        );
+        assert_eq!(
+            event(parse_stderr_line(r#"{"type": "log", "message": "Fitting fold 2"}"#)),
+            serde_json::json!({"type": "log", "message": "Fitting fold 2"})
+        );
+
+        // Tracebacks and JSON that isn't a known event are genuine stderr
+        for line in [
+            "Traceback (most recent call last):",
+            "ValueError: could not convert string to float: 'abc'",
+            r#"{"type": "progress"}"#,
+        ] {
+            assert_eq!(event(parse_stderr_line(line)), serde_json::json!({"type": "error", "message": line}));
+        }
+
+        // stdout still falls back to log
+        assert_eq!(
+            event(parse_output_line("Loading data...")),
+            serde_json::json!({"type": "log", "message": "Loading data..."})
+        );
+    }
+
+    #[test]
+    fn test_watch_http_startup_reports_ready() {
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let (done_tx, done_rx) = mpsc::channel();
+
+        let started = std::time::Instant::now();
+        watch_http_startup(ready_rx, Duration::from_secs(5), move |result| {
+            let _ = done_tx.send(result.map(|ready| (ready.host, ready.port, ready.runtime)));
+        });
+        // Returns before the server is ready
+        assert!(started.elapsed() < Duration::from_millis(100));
+        assert!(done_rx.try_recv().is_err());
+
+        // Mock server reports ready after a delay
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            let _ = ready_tx.send(Ok(HttpReadyResponse {
+                host: "127.0.0.1".to_string(),
+                port: 8080,
+                runtime: "sklearn".to_string(),
+                model_info: None,
+            }));
+        });
+        let outcome = done_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(outcome, Ok(("127.0.0.1".to_string(), 8080, "sklearn".to_string())));
+    }
+
+    #[test]
+    fn test_wait_for_http_ready_failures() {
+        let (ready_tx, ready_rx) = mpsc::channel::<Result<HttpReadyResponse, String>>();
+        let err = wait_for_http_ready(&ready_rx, Duration::from_millis(10)).err().unwrap();
+        assert!(err.contains("Timeout"), "{}", err);
+
+        ready_tx.send(Err("PORT_IN_USE: Port 8080 is in use".to_string())).unwrap();
+        let err = wait_for_http_ready(&ready_rx, Duration::from_secs(1)).err().unwrap();
+        assert_eq!(err, "PORT_IN_USE: Port 8080 is in use");
+
+        drop(ready_tx);
+        let err = wait_for_http_ready(&ready_rx, Duration::from_secs(1)).err().unwrap();
+        assert!(err.contains("exited unexpectedly"), "{}", err);
+    }
+
+    /// Python found the way the app finds it, or None (with a note) so tests
+    /// that need an interpreter skip on machines without one
+    fn test_python() -> Option<std::path::PathBuf> {
+        let found = python::find_python(None).map(|info| info.path);
+        if found.is_none() {
+            eprintln!("skipping: no Python installation found");
+        }
+        found
+    }
+
+    #[test]
+    fn test_capture_python_environment() {
+        let Some(python) = test_python() else {
+            return;
+        };
+        let (python_version, packages) = capture_python_environment(&python).unwrap();
+        assert!(python_version.starts_with("3."), "{}", python_version);
+        // Only known ML packages are listed, each with a version string
+        let packages = packages.as_object().unwrap();
+        assert!(packages.values().all(|v| v.is_string()));
+        assert!(packages.keys().all(|k| CAPTURE_ENVIRONMENT_PY.contains(&format!("\"{}\"", k))));
+    }
+
+    #[test]
+    fn test_validate_env_vars() {
+        let env = |key: &str, value: &str| HashMap::from([(key.to_string(), value.to_string())]);
+        assert!(validate_env_vars(&env("CUDA_VISIBLE_DEVICES", "0,1")).is_ok());
+        assert!(validate_env_vars(&env("A=B", "1")).is_err());
+        assert!(validate_env_vars(&env("A\0B", "1")).is_err());
+        assert!(validate_env_vars(&env("", "1")).is_err());
+        assert!(validate_env_vars(&env("OMP_NUM_THREADS", "4\0")).is_err());
+    }
+
+    #[test]
+    fn test_script_command_passes_env() {
+        let Some(python) = test_python() else {
+            return;
+        };
+        let dir = std::env::temp_dir().join(format!("script-env-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("echo_env.py");
+        std::fs::write(
+            &script,
+            "import os, sys\nprint(os.environ['OMP_NUM_THREADS'], sys.argv[1], 'PATH' in os.environ)\n",
+        )
+        .unwrap();
+
+        let env = HashMap::from([("OMP_NUM_THREADS".to_string(), "3".to_string())]);
+        let output = script_command(&python, &script, "data.csv", Some(&env))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        // Inherited variables like PATH are still there
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "3 data.csv True");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove_old_scripts() {
+        let dir = std::env::temp_dir().join(format!("old-scripts-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let ten_days_ago = std::time::SystemTime::now() - Duration::from_secs(10 * 24 * 60 * 60);
+        let write = |name: &str, contents: &str, old: bool| {
+            let path = dir.join(name);
+            std::fs::write(&path, contents).unwrap();
+            if old {
+                std::fs::File::options()
+                    .write(true)
+                    .open(&path)
+                    .unwrap()
+                    .set_modified(ten_days_ago)
+                    .unwrap();
+            }
+            path
+        };
+
+        let old_script = write("script_old.py", "print('old')", true);
+        let new_script = write("script_new.py", "print('new')", false);
+        let server = write("inference_server.py", "# server", true);
+        let http_server = write("http_server.py", "# server", true);
+
+        let reclaimed = remove_old_scripts(&dir, Duration::from_secs(7 * 24 * 60 * 60));
+        assert_eq!(reclaimed, "print('old')".len() as u64);
+        assert!(!old_script.exists());
+        assert!(new_script.exists());
+        assert!(server.exists());
+        assert!(http_server.exists());
+
+        assert_eq!(remove_old_scripts(&dir.join("missing"), Duration::ZERO), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_finish_script_file() {
+        let dir = std::env::temp_dir().join(format!("finish-script-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // Deleted by default
+        let deleted = dir.join("script_ok.py");
+        std::fs::write(&deleted, "print('ok')").unwrap();
+        assert_eq!(finish_script_file(&deleted, false), None);
+        assert!(!deleted.exists());
+
+        // Kept and reported when requested
+        let kept = dir.join("script_keep.py");
+        std::fs::write(&kept, "raise ValueError()").unwrap();
+        assert_eq!(finish_script_file(&kept, true), Some(kept.to_string_lossy().to_string()));
+        assert!(kept.exists());
+
+        let event = serde_json::to_value(ScriptEvent::Exit { code: 1, script_path: Some("s.py".to_string()) }).unwrap();
+        assert_eq!(event, serde_json::json!({"type": "exit", "code": 1, "scriptPath": "s.py"}));
+        let event = serde_json::to_value(ScriptEvent::Exit { code: 0, script_path: None }).unwrap();
+        assert_eq!(event, serde_json::json!({"type": "exit", "code": 0}));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let latencies: Vec<f64> = (1..=100).map(|v| v as f64).collect();
+        assert_eq!(percentile(&latencies, 50.0), 50.0);
+        assert_eq!(percentile(&latencies, 95.0), 95.0);
+        assert_eq!(percentile(&latencies, 99.0), 99.0);
+        assert_eq!(percentile(&[7.0], 99.0), 7.0);
+        assert_eq!(percentile(&[], 50.0), 0.0);
+    }
+
+    #[test]
+    fn test_run_benchmark_with_delayed_mock() {
+        // Every 10th request is slow, and request 0 fails
+        let predict = |i: usize| {
+            let delay = if i % 10 == 9 { 40 } else { 2 };
+            std::thread::sleep(Duration::from_millis(delay));
+            if i == 0 {
+                Err("boom".to_string())
+            } else {
+                Ok(())
+            }
+        };
+
+        let result = run_benchmark(predict, 20, 4);
+        assert_eq!((result.n_requests, result.concurrency, result.errors), (20, 4, 1));
+        // 18 fast and 2 slow requests: p50 is fast, p95/p99 land on the slow ones
+        assert!(result.p50_ms >= 2.0 && result.p50_ms < 40.0, "{:?}", result);
+        assert!(result.p95_ms >= 40.0, "{:?}", result);
+        assert!(result.p99_ms >= 40.0, "{:?}", result);
+        assert!(result.p50_ms <= result.p95_ms && result.p95_ms <= result.p99_ms);
+        assert!(result.requests_per_sec > 0.0);
+    }
+
+    #[test]
+    fn test_prediction_result_maps_error_codes() {
+        let parse = |line: &str| prediction_result(parse_response_line(line).unwrap());
+
+        let ok = parse(r#"__RESPONSE__:{"request_id": "a", "status": "ok", "prediction": [1]}"#);
+        assert_eq!(ok.code, None);
+        assert_eq!(ok.guidance, None);
+
+        let mismatch = parse(
+            r#"__RESPONSE__:{"request_id": "b", "status": "error", "code": "feature_mismatch", "message": "Row 0: Missing features: x"}"#,
+        );
+        assert_eq!(mismatch.code, Some(InferenceErrorCode::FeatureMismatch));
+        assert_eq!(mismatch.message.as_deref(), Some("Row 0: Missing features: x"));
+        assert_eq!(
+            mismatch.guidance.as_deref(),
+            Some(InferenceErrorCode::FeatureMismatch.guidance())
+        );
+
+        let dtype = parse(r#"__RESPONSE__:{"request_id": "c", "status": "error", "code": "invalid_dtype", "message": "x"}"#);
+        assert_eq!(dtype.code, Some(InferenceErrorCode::InvalidDtype));
+
+        // Codes this build doesn't know and responses without a code
+        let future = parse(r#"__RESPONSE__:{"request_id": "d", "status": "error", "code": "gpu_oom", "message": "x"}"#);
+        assert_eq!(future.code, Some(InferenceErrorCode::Unknown));
+        let legacy = parse(r#"__RESPONSE__:{"request_id": "e", "status": "error", "message": "boom"}"#);
+        assert_eq!(legacy.code, Some(InferenceErrorCode::Unknown));
+        assert_eq!(legacy.guidance.as_deref(), Some(InferenceErrorCode::Unknown.guidance()));
+    }
+
+    #[test]
+    fn test_inference_audit_only_when_enabled() {
+        setup_test_db();
+        let version_id = format!("audit-{}", uuid::Uuid::new_v4());
+        let input = serde_json::json!({"sepal_length": 5.1, "sepal_width": 3.5});
+        let result = |request_id: &str| PredictionResult {
+            request_id: request_id.to_string(),
+            status: "ok".to_string(),
+            prediction: Some(vec![serde_json::json!("setosa")]),
+            probabilities: None,
+            classes: None,
+            message: None,
+            code: None,
+            guidance: None,
+        };
+
+        assert!(!record_inference_audit(None, &version_id, &input, &result("req-off"), 3).unwrap());
+        assert!(db::get_inference_audit(&version_id, None).unwrap().is_empty());
+
+        let mode = Some(InferenceAuditMode::Full);
+        assert!(record_inference_audit(mode, &version_id, &input, &result("req-full"), 4).unwrap());
+        let mode = Some(InferenceAuditMode::Hashed);
+        assert!(record_inference_audit(mode, &version_id, &input, &result("req-hashed"), 5).unwrap());
+
+        let entries = db::get_inference_audit(&version_id, None).unwrap();
+        assert_eq!(entries.len(), 2);
+        let (hashed, full) = (&entries[0], &entries[1]);
+        assert_eq!(full.request_id, "req-full");
+        assert_eq!(full.input_json, input.to_string());
+        assert!(full.output_json.contains("setosa"));
+        assert_eq!(full.latency_ms, 4);
+        assert_eq!(hashed.request_id, "req-hashed");
+        assert_eq!(
+            hashed.input_json,
+            serde_json::json!({"sha256": crate::rag::content_hash(&input.to_string())}).to_string()
+        );
+        assert!(!hashed.output_json.contains("setosa"));
+
+        assert_eq!(db::get_inference_audit(&version_id, Some(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_check_serving_version() {
+        setup_test_db();
+        let model_id = create_test_model("serving");
+        let loaded = register_test_version(&model_id, None);
+        let other = register_test_version(&model_id, None);
+        let loaded_path = db::get_model_version(&loaded).unwrap().unwrap().file_path;
+
+        assert!(check_serving_version(&loaded, &loaded_path).is_ok());
+
+        // The UI thinks a different version is loaded
+        let err = check_serving_version(&other, &loaded_path).unwrap_err();
+        assert!(err.contains(&other) && err.contains(&loaded_path), "{}", err);
+
+        let err = check_serving_version("no-such-version", &loaded_path).unwrap_err();
+        assert!(err.contains("not found"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_http_log_line() {
+        let entry = parse_http_log_line(r#"{"level": "warning", "message": "ONNX fallback", "timestamp": 1700000000000}"#);
+        assert_eq!(
+            entry,
+            HttpLogEntry {
+                level: "warning".to_string(),
+                message: "ONNX fallback".to_string(),
+                timestamp: 1700000000000,
+            }
+        );
+
+        // Older servers only send a message
+        let entry = parse_http_log_line(r#"{"message": "Server starting..."}"#);
+        assert_eq!(entry.level, "info");
+        assert_eq!(entry.message, "Server starting...");
+        assert!(entry.timestamp > 0);
+
+        // Malformed payloads are kept verbatim
+        let entry = parse_http_log_line("not json {");
+        assert_eq!(entry.level, "info");
+        assert_eq!(entry.message, "not json {");
+    }
+
+    fn request_log(id: usize) -> HttpRequestLog {
+        HttpRequestLog {
+            id: id.to_string(),
+            timestamp: id as i64,
+            method: "POST".to_string(),
+            path: "/predict".to_string(),
+            status_code: 200,
+            latency_ms: 1.0,
+            batch_size: 1,
+        }
+    }
+
+    #[test]
+    fn test_http_metrics_recent_buffer_size() {
+        let mut tracker = HttpServerMetricsTracker::new(3);
+        for i in 0..5 {
+            tracker.add_request(request_log(i));
+        }
+        let metrics = tracker.get_metrics();
+        assert_eq!(metrics.total_requests, 5);
+        let ids: Vec<&str> = metrics.recent_requests.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["2", "3", "4"]);
+
+        // Reset keeps the configured size
+        tracker.reset();
+        tracker.add_request(request_log(9));
+        assert_eq!(tracker.recent_buffer_size, 3);
+        assert_eq!(tracker.get_metrics().recent_requests.len(), 1);
+
+        // Zero disables the buffer but still counts requests
+        let mut tracker = HttpServerMetricsTracker::new(0);
+        tracker.add_request(request_log(1));
+        assert!(tracker.get_metrics().recent_requests.is_empty());
+        assert_eq!(tracker.get_metrics().total_requests, 1);
+
+        let config: HttpServerConfig =
+            serde_json::from_str(r#"{"host":"127.0.0.1","port":8080,"use_onnx":false,"cors_origins":null}"#)
+                .unwrap();
+        assert_eq!(config.recent_buffer_size, DEFAULT_RECENT_BUFFER_SIZE);
+    }
+
+    #[test]
+    fn test_http_metrics_readable_while_server_locked() {
+        let _serial = HTTP_SERVER_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let metrics: SharedHttpMetrics = Arc::new(Mutex::new(HttpServerMetricsTracker::new(10)));
+        *get_http_metrics_mutex().lock().unwrap() = Some(metrics.clone());
+
+        // Stands in for a slow start/stop holding the process lock throughout
+        let process_guard = get_http_server_mutex().lock().unwrap();
+
+        let writer = std::thread::spawn(move || {
+            for i in 0..200 {
+                let mut log = request_log(i);
+                if i % 4 == 0 {
+                    log.status_code = 500;
+                }
+                metrics.lock().unwrap().add_request(log);
+            }
+        });
+        let (done_tx, done_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut last_total = 0;
+            let result = loop {
+                let m = match get_http_server_metrics() {
+                    Ok(m) => m,
+                    Err(e) => break Err(e),
+                };
+                if m.total_requests != m.successful_requests + m.failed_requests || m.total_requests < last_total {
+                    break Err(format!("inconsistent read after {}: {:?}", last_total, m.total_requests));
+                }
+                last_total = m.total_requests;
+                if last_total == 200 {
+                    break Ok(m);
+                }
+            };
+            let _ = done_tx.send(result);
+        });
+
+        let final_metrics = done_rx
+            .recv_timeout(Duration::from_secs(10))
+            .expect("metrics reads blocked on the server lock")
+            .unwrap();
+        drop(process_guard);
+        writer.join().unwrap();
+        assert_eq!(final_metrics.successful_requests, 150);
+        assert_eq!(final_metrics.failed_requests, 50);
+        assert_eq!(final_metrics.recent_requests.len(), 10);
+
+        reset_http_server_metrics().unwrap();
+        assert_eq!(get_http_server_metrics().unwrap().total_requests, 0);
+        *get_http_metrics_mutex().lock().unwrap() = None;
+        assert_eq!(get_http_server_metrics().unwrap().total_requests, 0);
+    }
+
+    /// Serve a single HTTP response on a free local port
+    fn serve_once(status: &str, body: &str) -> u16 {
+        use std::io::Read;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => request.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn test_check_http_health() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let url = |port: u16| format!("http://127.0.0.1:{}", port);
+
+        let port = serve_once("200 OK", r#"{"status":"healthy","model_type":"RandomForestClassifier"}"#);
+        assert!(rt.block_on(check_http_health(&url(port))));
+        let port = serve_once("503 Service Unavailable", r#"{"detail":"Model not loaded"}"#);
+        assert!(!rt.block_on(check_http_health(&url(port))));
+        let port = serve_once("200 OK", r#"{"status":"starting"}"#);
+        assert!(!rt.block_on(check_http_health(&url(port))));
+
+        // Nothing listening
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        assert!(!rt.block_on(check_http_health(&url(port))));
+
+        assert_eq!(probe_host("0.0.0.0"), "127.0.0.1");
+        assert_eq!(probe_host("192.168.1.5"), "192.168.1.5");
+    }
+
+    #[test]
+    fn test_probe_http_server_updates_status() {
+        let _serial = HTTP_SERVER_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        assert!(rt.block_on(probe_http_server()).is_err());
+
+        let port = serve_once("503 Service Unavailable", r#"{"detail":"Model not loaded"}"#);
+        let ready = HttpReadyResponse {
+            host: "0.0.0.0".to_string(),
+            port,
+            runtime: "sklearn".to_string(),
+            model_info: None,
+        };
+        register_http_server(
+            Command::new("sleep").arg("30").spawn().unwrap(),
+            format!("probe-{}", uuid::Uuid::new_v4()),
+            "probe".to_string(),
+            Arc::new(Mutex::new(HttpServerMetricsTracker::new(1))),
+            Arc::new(Mutex::new(std::collections::VecDeque::new())),
+            ready,
+        )
+        .unwrap();
+        assert_eq!(get_http_server_status().unwrap().healthy, None);
+
+        let result = rt.block_on(probe_http_server());
+        let status = get_http_server_status().unwrap();
+        if let Some(mut proc) = get_http_server_mutex().lock().unwrap().take() {
+            let _ = proc.child.kill();
+            let _ = proc.child.wait();
+        }
+        *get_http_metrics_mutex().lock().unwrap() = None;
+
+        assert!(!result.unwrap());
+        assert!(status.running);
+        assert_eq!(status.healthy, Some(false));
+    }
+
+    #[test]
+    fn test_scan_example_datasets() {
+        let dir = std::env::temp_dir().join(format!("examples-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("penguins.csv"), "bill_length,flipper_length,species\n39.1,181,Adelie\n").unwrap();
+        std::fs::write(
+            dir.join("penguins.meta.json"),
+            r#"{"name": "Palmer Penguins", "description": "Classify penguin species", "task_type": "classification", "target_column": "species", "recommended_model": "random_forest"}"#,
+        )
+        .unwrap();
+        std::fs::write(dir.join("house_prices.csv"), "\u{feff}rooms,area,price\n3,120,250000\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a dataset").unwrap();
+        std::fs::write(dir.join("broken.csv"), "a,b\n1,2\n").unwrap();
+        std::fs::write(dir.join("broken.meta.json"), "{not json").unwrap();
+
+        let datasets = scan_example_datasets(&dir).unwrap();
+        let ids: Vec<&str> = datasets.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, ["house_prices.csv", "penguins.csv"]);
+
+        let derived = &datasets[0];
+        assert_eq!(derived.name, "House Prices");
+        assert_eq!(derived.target_column, "price");
+        assert_eq!(derived.task_type, "");
+
+        let penguins = &datasets[1];
+        assert_eq!(penguins.name, "Palmer Penguins");
+        assert_eq!(penguins.description, "Classify penguin species");
+        assert_eq!(penguins.task_type, "classification");
+        assert_eq!(penguins.target_column, "species");
+        assert_eq!(penguins.recommended_model, "random_forest");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_bundled_example_sidecars() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/examples");
+        let datasets = scan_example_datasets(&dir).unwrap();
+        let iris = datasets.iter().find(|d| d.id == "iris.csv").unwrap();
+        assert_eq!(iris.name, "Iris Classification");
+        assert_eq!(iris.target_column, "species");
+        let housing = datasets.iter().find(|d| d.id == "california_housing.csv").unwrap();
+        assert_eq!(housing.task_type, "regression");
+        assert_eq!(housing.target_column, "MedHouseVal");
+    }
+
+    #[test]
+    fn test_preview_dataset_reads_only_head() {
+        struct CountingReader<R> {
+            inner: R,
+            bytes: Arc<std::sync::atomic::AtomicUsize>,
+        }
+        impl<R: std::io::Read> std::io::Read for CountingReader<R> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let n = self.inner.read(buf)?;
+                self.bytes.fetch_add(n, std::sync::atomic::Ordering::SeqCst);
+                Ok(n)
+            }
+        }
+
+        let path = std::env::temp_dir().join(format!("preview-{}.csv", uuid::Uuid::new_v4()));
+        let mut csv = String::from("\u{feff}id,score,label,active\n");
+        for i in 0..5000 {
+            csv.push_str(&format!("{},{}.5,\"row, number {}\",{}\n", i, i, i, i % 2 == 0));
+        }
+        std::fs::write(&path, &csv).unwrap();
+
+        let bytes = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let reader = CountingReader {
+            inner: std::fs::File::open(&path).unwrap(),
+            bytes: bytes.clone(),
+        };
+        let preview = preview_csv(BufReader::new(reader), 5).unwrap();
+        assert_eq!(preview.columns, vec!["id", "score", "label", "active"]);
+        assert_eq!(preview.column_types, vec!["integer", "float", "string", "boolean"]);
+        assert_eq!(preview.rows.len(), 5);
+        assert_eq!(preview.rows[4], vec!["4", "4.5", "row, number 4", "true"]);
+        assert!(preview.truncated);
+        // One buffer fill, not the whole file
+        assert!(bytes.load(std::sync::atomic::Ordering::SeqCst) < csv.len() / 4);
+
+        // Requests are capped
+        let capped = preview_dataset(path.to_string_lossy().to_string(), Some(1_000_000)).unwrap();
+        assert_eq!(capped.rows.len(), MAX_PREVIEW_ROWS);
+        std::fs::remove_file(&path).unwrap();
+
+        // Quoted newlines and escaped quotes, and a file shorter than the request
+        let small = "name,note\n\"a\",\"line one\nline \"\"two\"\"\"\n\nb,\n";
+        let preview = preview_csv(small.as_bytes(), 10).unwrap();
+        assert_eq!(preview.rows, vec![vec!["a", "line one\nline \"two\""], vec!["b", ""]]);
+        assert!(!preview.truncated);
+        assert_eq!(preview.column_types, vec!["string", "string"]);
+        assert!(preview_csv("".as_bytes(), 10).is_err());
+    }
+
+    #[test]
+    fn test_check_bind_safety() {
+        let config = |host: &str, api_key: Option<&str>, allow_public: bool| HttpServerConfig {
+            host: host.to_string(),
+            api_key: api_key.map(String::from),
+            allow_public,
+            ..HttpServerConfig::default()
+        };
+
+        // Loopback needs nothing
+        for host in ["127.0.0.1", "127.0.0.53", "localhost", "LOCALHOST", "::1", "[::1]"] {
+            assert!(check_bind_safety(&config(host, None, false)).is_ok(), "{}", host);
+        }
+        assert!(check_bind_safety(&HttpServerConfig::default()).is_ok());
+
+        // Public without auth is refused, a blank key doesn't count
+        for host in ["0.0.0.0", "::", "192.168.1.20", "my-laptop.local"] {
+            let err = check_bind_safety(&config(host, None, false)).unwrap_err();
+            assert!(err.contains(host) && err.contains("api_key"), "{}", err);
+        }
+        assert!(check_bind_safety(&config("0.0.0.0", Some("  "), false)).is_err());
+
+        // With a key or an explicit opt-in it's allowed
+        assert!(check_bind_safety(&config("0.0.0.0", Some("s3cret"), false)).is_ok());
+        assert!(check_bind_safety(&config("0.0.0.0", None, true)).is_ok());
+
+        // Older configs without the new fields still deserialize
+        let parsed: HttpServerConfig =
+            serde_json::from_str(r#"{"host": "0.0.0.0", "port": 8080, "use_onnx": false}"#).unwrap();
+        assert_eq!((parsed.api_key, parsed.allow_public), (None, false));
+    }
+
+    #[test]
+    fn test_validate_rate_limit() {
+        for limit in ["100/minute", "1/second", "5000/hours", "10 / day"] {
+            assert!(validate_rate_limit(limit).is_ok(), "{}", limit);
+        }
+
+        let err = validate_rate_limit("100/fortnight").unwrap_err();
+        assert!(err.contains("unit must be one of"), "{}", err);
+        let err = validate_rate_limit("100 per minute").unwrap_err();
+        assert!(err.contains("expected <count>/<unit>"), "{}", err);
+        for limit in ["0/minute", "-5/minute", "many/minute", "/minute"] {
+            let err = validate_rate_limit(limit).unwrap_err();
+            assert!(err.contains("positive integer"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_rate_limited_requests_count_as_failed() {
+        let mut tracker = HttpServerMetricsTracker::new(10);
+        tracker.add_request(request_log(1));
+        let mut limited = request_log(2);
+        limited.status_code = 429;
+        tracker.add_request(limited);
+
+        let metrics = tracker.get_metrics();
+        assert_eq!((metrics.successful_requests, metrics.failed_requests), (1, 1));
+        assert_eq!(metrics.rate_limited_requests, 1);
+    }
+
+    #[test]
+    fn test_coreml_support_gating() {
+        let err = check_coreml_support(false, || true).unwrap_err();
+        assert!(err.contains("not supported on this platform"), "{}", err);
+
+        // The package check is skipped off macOS
+        let err = check_coreml_support(false, || panic!("should not probe")).unwrap_err();
+        assert!(err.contains("macOS"), "{}", err);
+
+        let err = check_coreml_support(true, || false).unwrap_err();
+        assert!(err.contains("coremltools not installed"), "{}", err);
+
+        assert!(check_coreml_support(true, || true).is_ok());
+    }
+
+    #[test]
+    fn test_coreml_export_path() {
+        assert_eq!(
+            coreml_export_path("/data/models/abc/v3/model.joblib"),
+            std::path::PathBuf::from("/data/models/abc/v3/model.mlmodel")
+        );
+        assert_eq!(
+            coreml_export_path("/data/models/abc/v1/model.pkl"),
+            std::path::PathBuf::from("/data/models/abc/v1/model.mlmodel")
+        );
+    }
+
+    #[test]
+    fn test_feature_correlations_exclude_non_numeric() {
+        // The helper itself only uses the stdlib
+        let Some(python) = test_python() else {
+            return;
+        };
+        let dir = std::env::temp_dir().join(format!("correlations-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script_path = dir.join("feature_correlations.py");
+        std::fs::write(&script_path, FEATURE_CORRELATIONS_PY).unwrap();
+        let csv_path = dir.join("data.csv");
+        std::fs::write(
+            &csv_path,
+            "x,y,label,z\n1,2,a,8\n2,4,b,6\n3,6,a,\n4,8,b,2\n",
+        )
+        .unwrap();
+
+        let result =
+            run_feature_correlations(&python, &script_path, csv_path.to_str().unwrap()).unwrap();
+        assert_eq!(result["columns"], serde_json::json!(["x", "y", "z"]));
+        assert_eq!(result["excluded"], serde_json::json!(["label"]));
+        assert!((result["matrix"][0][1].as_f64().unwrap() - 1.0).abs() < 1e-9);
+        // z is missing one value; correlation uses the complete rows only
+        assert!((result["matrix"][0][2].as_f64().unwrap() + 1.0).abs() < 1e-9);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_input_line_echoes_back() {
+        let mut child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        write_input_line(&mut stdin, "first").unwrap();
+        // Trailing newlines are normalized so each call is exactly one line
+        write_input_line(&mut stdin, "second\n").unwrap();
+        drop(stdin);
+
+        let lines: Vec<String> = BufReader::new(stdout).lines().map_while(Result::ok).collect();
+        assert_eq!(lines, vec!["first".to_string(), "second".to_string()]);
+        assert!(child.wait().unwrap().success());
+    }
+
+    #[test]
+    fn test_list_running_processes() {
+        let _serial = HTTP_SERVER_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let sleeper = || {
+            std::process::Command::new("sleep")
+                .arg("30")
+                .stdin(Stdio::piped())
+                .spawn()
+                .unwrap()
+        };
+        let inference_version = format!("inf-{}", uuid::Uuid::new_v4());
+        let http_version = format!("http-{}", uuid::Uuid::new_v4());
+
+        let mut inference_child = sleeper();
+        let inference_pid = inference_child.id();
+        let stdin = inference_child.stdin.take().unwrap();
+        *get_inference_mutex().lock().unwrap() = Some(InferenceProcess {
+            child: inference_child,
+            stdin,
+            model_path: "/models/model.joblib".to_string(),
+            model_info: None,
+            response_rx: mpsc::channel().1,
+            pending_requests: Arc::new(Mutex::new(HashMap::new())),
+            version_id: inference_version.clone(),
+            audit: None,
+            started_at: std::time::Instant::now() - Duration::from_secs(90),
+            warmed_up: false,
+        });
+
+        let http_child = sleeper();
+        let http_pid = http_child.id();
+        *get_http_server_mutex().lock().unwrap() = Some(HttpServerProcess {
+            child: http_child,
+            version_id: http_version.clone(),
+            model_name: "churn".to_string(),
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            runtime: "sklearn".to_string(),
+            model_info: None,
+            logs: Arc::new(Mutex::new(std::collections::VecDeque::new())),
+            healthy: None,
+            started_at: std::time::Instant::now(),
+        });
+
+        let processes = list_running_processes().unwrap();
+        let subsystems: Vec<&str> = processes.iter().map(|p| p.subsystem.as_str()).collect();
+        assert_eq!(subsystems, vec!["script", "inference_server", "http_server", "lsp"]);
+
+        let inference = &processes[1];
+        assert!(inference.running);
+        assert_eq!(inference.pid, Some(inference_pid));
+        assert_eq!(inference.version_id.as_deref(), Some(inference_version.as_str()));
+        assert!(inference.uptime_secs.unwrap() >= 90);
+
+        let http = &processes[2];
+        assert!(http.running);
+        assert_eq!(http.pid, Some(http_pid));
+        assert_eq!(http.version_id.as_deref(), Some(http_version.as_str()));
+        assert!(http.uptime_secs.unwrap() < 90);
+
+        for mut child in [
+            get_inference_mutex().lock().unwrap().take().unwrap().child,
+            get_http_server_mutex().lock().unwrap().take().unwrap().child,
+        ] {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        let processes = list_running_processes().unwrap();
+        assert_eq!(processes[1], ProcessInfo::stopped("inference_server"));
+        assert_eq!(processes[2], ProcessInfo::stopped("http_server"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restart_inference_server_keeps_version() {
+        use std::os::unix::fs::PermissionsExt;
+        let _serial = HTTP_SERVER_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        setup_test_db();
+        let model_id = create_test_model("restart");
+        let version_id = register_test_version(&model_id, None);
+        let model_path = db::get_model_version(&version_id).unwrap().unwrap().file_path;
+
+        // Stands in for Python: logs the model path it was given, reports
+        // ready, answers every predict, and exits on EOF
+        let app_data_dir = std::path::PathBuf::from(format!("target/test-db/restart-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&app_data_dir).unwrap();
+        let mock = app_data_dir.join("mock-python");
+        std::fs::write(
+            &mock,
+            r#"#!/bin/sh
+echo "$3" >> "$(dirname "$0")/launches.log"
+echo '__RESPONSE__:{"request_id":"startup","status":"ok","type":"ready","model_info":{"type":"Mock","is_classifier":false}}'
+while read -r line; do
+  id=$(echo "$line" | sed 's/.*"request_id":"\([^"]*\)".*/\1/')
+  echo "__RESPONSE__:{\"request_id\":\"$id\",\"status\":\"ok\",\"prediction\":[1]}"
+done
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&mock, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let err = relaunch_inference_server(&mock, &app_data_dir).err();
+        assert_eq!(err.as_deref(), Some("No inference server running"));
+
+        let status = launch_inference_server(&mock, &app_data_dir, &version_id, Some(InferenceAuditMode::Hashed)).unwrap();
+        assert!(status.running);
+        let predict = |id: &str| run_inference(id.to_string(), serde_json::json!([[1.0]])).unwrap().status;
+        assert_eq!(predict("before-restart"), "ok");
+        let old_pid = get_inference_mutex().lock().unwrap().as_ref().unwrap().child.id();
+
+        // A request already sent to the old process is still answered
+        let (tx, in_flight) = mpsc::channel();
+        {
+            let mut guard = get_inference_mutex().lock().unwrap();
+            let proc = guard.as_mut().unwrap();
+            proc.pending_requests.lock().unwrap().insert("in-flight".to_string(), tx);
+            writeln!(proc.stdin, r#"{{"cmd":"predict","request_id":"in-flight","input":[[1.0]]}}"#).unwrap();
+            proc.stdin.flush().unwrap();
+        }
+
+        let status = relaunch_inference_server(&mock, &app_data_dir).unwrap();
+        assert!(status.running);
+        assert_eq!(status.model_path.as_deref(), Some(model_path.as_str()));
+        assert_eq!(status.model_info.unwrap().model_type, "Mock");
+        assert_eq!(in_flight.recv_timeout(Duration::from_secs(5)).unwrap().status, "ok");
+
+        {
+            let guard = get_inference_mutex().lock().unwrap();
+            let proc = guard.as_ref().unwrap();
+            assert_eq!(proc.version_id, version_id);
+            assert_eq!(proc.audit, Some(InferenceAuditMode::Hashed));
+            assert_ne!(proc.child.id(), old_pid);
+            assert!(!proc.warmed_up);
+            let pid_file = std::fs::read_to_string(get_pid_file_path(&app_data_dir)).unwrap();
+            assert_eq!(pid_file, proc.child.id().to_string());
+        }
+        let launches = std::fs::read_to_string(app_data_dir.join("launches.log")).unwrap();
+        assert_eq!(launches.lines().collect::<Vec<_>>(), vec![model_path.as_str(), model_path.as_str()]);
+        assert_eq!(predict("after-restart"), "ok");
+
+        shutdown_inference_server(&app_data_dir).unwrap();
+        assert!(!get_inference_server_status(None).unwrap().running);
+        assert!(!get_pid_file_path(&app_data_dir).exists());
+        let _ = std::fs::remove_dir_all(&app_data_dir);
+    }
+
+    #[test]
+    fn test_get_serving_readiness() {
+        let _serial = HTTP_SERVER_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let version_id = format!("ready-{}", uuid::Uuid::new_v4());
+        let readiness = || get_serving_readiness(version_id.clone()).unwrap();
+
+        // Not running
+        assert_eq!(readiness(), ServingReadiness::default());
+
+        // Inference server loaded the model but hasn't predicted yet
+        let mut child = std::process::Command::new("sleep").arg("30").stdin(Stdio::piped()).spawn().unwrap();
+        let stdin = child.stdin.take().unwrap();
+        *get_inference_mutex().lock().unwrap() = Some(InferenceProcess {
+            child,
+            stdin,
+            model_path: "/models/model.joblib".to_string(),
+            model_info: None,
+            response_rx: mpsc::channel().1,
+            pending_requests: Arc::new(Mutex::new(HashMap::new())),
+            version_id: version_id.clone(),
+            audit: None,
+            started_at: std::time::Instant::now(),
+            warmed_up: false,
+        });
+        let cold = ServingReadiness { process_running: true, model_loaded: true, warmup_done: false, last_health_ok: None };
+        assert_eq!(readiness(), cold);
+        // Another version's readiness is unaffected
+        assert_eq!(get_serving_readiness("other".to_string()).unwrap(), ServingReadiness::default());
+
+        get_inference_mutex().lock().unwrap().as_mut().unwrap().warmed_up = true;
+        assert_eq!(readiness(), ServingReadiness { warmup_done: true, ..cold.clone() });
+
+        // A process that exited no longer counts, even with its handle held
+        {
+            let mut guard = get_inference_mutex().lock().unwrap();
+            let proc = guard.as_mut().unwrap();
+            let _ = proc.child.kill();
+            let _ = proc.child.wait();
+        }
+        assert_eq!(readiness(), ServingReadiness::default());
+        get_inference_mutex().lock().unwrap().take();
+
+        // HTTP server: warm once /predict succeeds, health from the last probe
+        let metrics = Arc::new(Mutex::new(HttpServerMetricsTracker::new(10)));
+        *get_http_metrics_mutex().lock().unwrap() = Some(metrics.clone());
+        *get_http_server_mutex().lock().unwrap() = Some(HttpServerProcess {
+            child: std::process::Command::new("sleep").arg("30").spawn().unwrap(),
+            version_id: version_id.clone(),
+            model_name: "churn".to_string(),
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            runtime: "sklearn".to_string(),
+            model_info: None,
+            logs: Arc::new(Mutex::new(std::collections::VecDeque::new())),
+            healthy: Some(true),
+            started_at: std::time::Instant::now(),
+        });
+        assert_eq!(readiness(), ServingReadiness { last_health_ok: Some(true), ..cold.clone() });
+        metrics.lock().unwrap().add_request(HttpRequestLog { path: "/health".to_string(), ..request_log(1) });
+        metrics.lock().unwrap().add_request(HttpRequestLog { status_code: 500, ..request_log(2) });
+        assert!(!readiness().warmup_done);
+        metrics.lock().unwrap().add_request(request_log(3));
+        assert_eq!(
+            readiness(),
+            ServingReadiness { process_running: true, model_loaded: true, warmup_done: true, last_health_ok: Some(true) }
+        );
+
+        let mut child = get_http_server_mutex().lock().unwrap().take().unwrap().child;
+        let _ = child.kill();
+        let _ = child.wait();
+        *get_http_metrics_mutex().lock().unwrap() = None;
+        assert_eq!(readiness(), ServingReadiness::default());
+    }
+
+    #[test]
+    fn test_create_support_bundle() {
+        use std::io::Read;
+        setup_test_db();
+        let pipeline_id = format!("bundle-{}", uuid::Uuid::new_v4());
+        let data = r#"{"nodes": [{"id": "load", "data": {"filePath": "/home/alice/data/churn.csv"}}], "edges": []}"#;
+        db::save_pipeline(&pipeline_id, "bundle", data).unwrap();
+
+        let diagnostics = python::PythonDiagnostics {
+            code: python::PythonDiagnosticCode::NotBundled,
+            message: Some("No bundle at /home/alice/app/python".to_string()),
+            bundle_path: None,
+            fallback: None,
+            fallback_source: None,
+        };
+        let redactions = vec![
+            ("/home/alice/app".to_string(), "<app_data>".to_string()),
+            ("/home/alice".to_string(), "~".to_string()),
+        ];
+        let dest = std::env::temp_dir().join(format!("{}.zip", pipeline_id));
+        write_support_bundle(&dest, &diagnostics, &redactions).unwrap();
+
+        let mut entries = HashMap::new();
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        for i in 0..archive.len() {
+            let mut entry = archive.by_index(i).unwrap();
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes).unwrap();
+            entries.insert(entry.name().to_string(), bytes);
+        }
+        let mut names: Vec<&str> = entries.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, vec!["logs/recent.log", "manifest.json", "python_diagnostics.json", "settings.db"]);
+
+        let manifest: serde_json::Value = serde_json::from_slice(&entries["manifest.json"]).unwrap();
+        assert_eq!(manifest["db_version"], db::get_db_version().unwrap());
+        assert_eq!(manifest["processes"].as_array().unwrap().len(), 4);
+        let python = String::from_utf8(entries["python_diagnostics.json"].clone()).unwrap();
+        assert!(python.contains("No bundle at <app_data>/python"), "{}", python);
+
+        // The database copy opens cleanly, with user paths redacted
+        let db_path = std::env::temp_dir().join(format!("{}.db", pipeline_id));
+        std::fs::write(&db_path, &entries["settings.db"]).unwrap();
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        let integrity: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0)).unwrap();
+        assert_eq!(integrity, "ok");
+        let stored: String = conn.query_row("SELECT data FROM pipelines WHERE id = ?1", [&pipeline_id], |row| row.get(0)).unwrap();
+        assert!(stored.contains(r#""filePath": "~/data/churn.csv""#), "{}", stored);
+        // ...but the live database is untouched
+        assert_eq!(db::load_pipeline(&pipeline_id).unwrap().unwrap(), data);
+
+        // An unwritable destination is an error rather than a missing file
+        let blocked = std::env::temp_dir().join(format!("{}-missing-dir", pipeline_id)).join("bundle.zip");
+        assert!(write_support_bundle(&blocked, &diagnostics, &redactions).is_err());
+        let _ = std::fs::remove_file(&dest);
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_set_log_level() {
+        let err = set_log_level("verbose".to_string()).unwrap_err();
+        assert!(err.contains("Invalid log level 'verbose'"), "{}", err);
+
+        set_log_level("debug".to_string()).unwrap();
+        assert_eq!(
+            *LOG_LEVEL.get().unwrap().clone_current().as_ref().unwrap(),
+            tracing_subscriber::filter::LevelFilter::DEBUG
+        );
+        set_log_level("INFO".to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_tail_lines() {
+        let dir = std::path::PathBuf::from("target/test-db/logs");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.log", uuid::Uuid::new_v4()));
+
+        // Long enough that the tail spans several read blocks
+        let lines: Vec<String> = (0..2000).map(|i| format!("line {} {}", i, "x".repeat(40))).collect();
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        assert_eq!(tail_lines(&path, 3).unwrap(), lines[1997..].to_vec());
+        assert_eq!(tail_lines(&path, 500).unwrap(), lines[1500..].to_vec());
+        assert_eq!(tail_lines(&path, 5000).unwrap(), lines);
+        assert!(tail_lines(&path, 0).unwrap().is_empty());
+
+        // No trailing newline
+        std::fs::write(&path, "first\nsecond\nthird").unwrap();
+        assert_eq!(tail_lines(&path, 2).unwrap(), vec!["second", "third"]);
+
+        std::fs::write(&path, "").unwrap();
+        assert!(tail_lines(&path, 10).unwrap().is_empty());
+        std::fs::remove_file(&path).unwrap();
+        assert!(tail_lines(&path, 10).is_err());
+    }
+
+    #[test]
+    fn test_latest_log_file() {
+        let dir = std::path::PathBuf::from("target/test-db/logs").join(uuid::Uuid::new_v4().to_string());
+        assert!(latest_log_file(&dir).is_none());
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(latest_log_file(&dir).is_none());
+
+        for name in ["app.2026-01-01.log", "app.2026-01-03.log", "app.2026-01-02.log", "other.2026-02-01.log"] {
+            std::fs::write(dir.join(name), "entry\n").unwrap();
+        }
+        assert_eq!(latest_log_file(&dir).unwrap(), dir.join("app.2026-01-03.log"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Stand-in for the inference server: predicts a + b for each sample
+    fn mock_predict(
+        inputs: &Mutex<Vec<serde_json::Value>>,
+    ) -> impl FnMut(serde_json::Value) -> Result<PredictionResult, String> + '_ {
+        move |input| {
+            let prediction = input
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|sample| serde_json::json!(sample["a"].as_f64().unwrap() + sample["b"].as_f64().unwrap()))
+                .collect();
+            inputs.lock().unwrap().push(input);
+            Ok(PredictionResult {
+                request_id: "mock".to_string(),
+                status: "ok".to_string(),
+                prediction: Some(prediction),
+                probabilities: None,
+                classes: None,
+                message: None,
+                code: None,
+                guidance: None,
+            })
+        }
+    }
+
+    #[test]
+    fn test_predict_csv_rows() {
+        let mut csv = String::from("\u{feff}id,a,note,b\n");
+        for i in 0..600 {
+            csv.push_str(&format!("{},{},\"row, {}\",{}.5\n", i, i, i, i * 2));
+        }
+        let feature_names = vec!["b".to_string(), "a".to_string()];
+        let inputs = Mutex::new(Vec::new());
+        let mut batches = Vec::new();
+        let mut out = Vec::new();
+
+        let rows = predict_csv_rows(csv.as_bytes(), &mut out, Some(&feature_names), mock_predict(&inputs), |done| {
+            batches.push(done)
+        })
+        .unwrap();
+        assert_eq!(rows, 600);
+        assert_eq!(batches, vec![256, 512, 600]);
+
+        // Only the model's features are sent, numbers as numbers
+        let inputs = inputs.into_inner().unwrap();
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(inputs[2].as_array().unwrap().len(), 88);
+        assert_eq!(inputs[0][1], serde_json::json!({"b": 2.5, "a": 1.0}));
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 601);
+        assert_eq!(lines[0], "id,a,note,b,prediction");
+        assert_eq!(lines[1], "0,0,\"row, 0\",0.5,0.5");
+        assert_eq!(lines[600], "599,599,\"row, 599\",1198.5,1797.5");
+    }
+
+    #[test]
+    fn test_predict_csv_rows_errors() {
+        let inputs = Mutex::new(Vec::new());
+        let features = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let err = predict_csv_rows("a,b\n1,2\n".as_bytes(), &mut Vec::new(), Some(&features), mock_predict(&inputs), |_| {})
+            .unwrap_err();
+        assert_eq!(err, "CSV is missing feature columns: c");
+
+        let err = predict_csv_rows("a,b\n1,2\n3\n".as_bytes(), &mut Vec::new(), None, mock_predict(&inputs), |_| {})
+            .unwrap_err();
+        assert_eq!(err, "Row 2 has 1 fields, expected 2");
+
+        // Server errors name the batch's rows
+        let mut csv = String::from("a,b\n");
+        for i in 0..300 {
+            csv.push_str(&format!("{},{}\n", i, i));
+        }
+        let mut calls = 0;
+        let failing = |_| {
+            calls += 1;
+            Ok(PredictionResult {
+                request_id: "mock".to_string(),
+                status: if calls == 2 { "error" } else { "ok" }.to_string(),
+                prediction: Some(vec![serde_json::json!(0); PREDICT_CSV_BATCH_SIZE]),
+                probabilities: None,
+                classes: None,
+                message: Some("Input values must be numeric".to_string()),
+                code: Some(InferenceErrorCode::InvalidDtype),
+                guidance: None,
+            })
+        };
+        let err = predict_csv_rows(csv.as_bytes(), &mut Vec::new(), None, failing, |_| {}).unwrap_err();
+        assert_eq!(err, "Prediction failed for rows 257-300: Input values must be numeric");
+
+        // Blank lines are skipped and an empty file has no header
+        let mut out = Vec::new();
+        assert_eq!(predict_csv_rows("a,b\n\n1,2\n\n".as_bytes(), &mut out, None, mock_predict(&inputs), |_| {}), Ok(1));
+        assert_eq!(String::from_utf8(out).unwrap(), "a,b,prediction\n1,2,3.0\n");
+        assert!(predict_csv_rows("".as_bytes(), &mut Vec::new(), None, mock_predict(&inputs), |_| {}).is_err());
+    }
+}
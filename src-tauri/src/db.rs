@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::Mutex;
 
-const DB_VERSION: i32 = 8; // v1 = settings+pipelines, v2 = +runs+metrics, v3 = +models+model_versions, v4 = +feature_names, v5 = +tuning_sessions+tuning_trials, v6 = +experiments+run_annotations, v7 = +model_metadata+model_tags+export_paths, v8 = +chunk_embeddings
+const DB_VERSION: i32 = 24; // v1 = settings+pipelines, v2 = +runs+metrics, v3 = +models+model_versions, v4 = +feature_names, v5 = +tuning_sessions+tuning_trials, v6 = +experiments+run_annotations, v7 = +model_metadata+model_tags+export_paths, v8 = +chunk_embeddings, v9 = +completion_events, v10 = +embedding_config, v11 = +model_stage_history, v12 = +feature_correlations, v13 = +run_metrics.kind, v14 = +inference_audit, v15 = +model_versions.trusted, v16 = +run_environment, v17 = unique tuning trial numbers, v18 = +embedding_config.preprocess, v19 = +model_versions.last_used_at, v20 = model_versions.stage triggers, v21 = +tuning_sessions.seed, v22 = +runs.artifacts_compressed, v23 = +model_versions.sha256, v24 = +models.max_versions
 
 #[derive(Serialize, Deserialize)]
 pub struct PipelineMetadata {
@@ -46,6 +46,36 @@ pub struct Metric {
     pub name: String,
     pub value: Option<f64>,
     pub value_json: Option<String>,
+    #[serde(default)]
+    pub kind: Option<String>, // 'scalar' | 'array' | 'matrix' | 'artifact'; inferred when None
+}
+
+pub const METRIC_KINDS: [&str; 4] = ["scalar", "array", "matrix", "artifact"];
+
+/// Kind for a metric saved without one: scalar if it has a value, otherwise
+/// matrix or array depending on the shape of its JSON
+pub fn infer_metric_kind(value: Option<f64>, value_json: Option<&str>) -> &'static str {
+    match (value, value_json) {
+        (Some(_), _) | (None, None) => "scalar",
+        (None, Some(json)) if json.trim_start().starts_with("[[") => "matrix",
+        (None, Some(_)) => "array",
+    }
+}
+
+// Reject the first metric whose explicit kind isn't one of METRIC_KINDS
+fn validate_metric_kinds(metrics: &[Metric]) -> Result<()> {
+    match metrics
+        .iter()
+        .find(|m| m.kind.as_deref().is_some_and(|k| !METRIC_KINDS.contains(&k)))
+    {
+        Some(m) => Err(constraint_error(format!(
+            "Invalid kind '{}' for metric '{}': expected one of {}",
+            m.kind.as_deref().unwrap_or_default(),
+            m.name,
+            METRIC_KINDS.join(", ")
+        ))),
+        None => Ok(()),
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -58,6 +88,17 @@ pub struct ModelMetadata {
     pub version_count: i64,
     pub latest_version: Option<i64>,
     pub production_version: Option<i64>,
+    pub max_versions: Option<i64>, // retention count for prune_old_versions, None = keep all
+}
+
+/// Registry-wide totals for the dashboard
+#[derive(Serialize, Deserialize, Clone)]
+pub struct RegistrySummary {
+    pub model_count: i64,
+    pub version_count: i64,
+    pub stage_counts: std::collections::HashMap<String, i64>, // every MODEL_STAGES entry, zero if unused
+    pub total_file_bytes: i64, // sum of model_versions.file_size
+    pub latest_model: Option<ModelMetadata>, // most recently updated
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -81,6 +122,8 @@ pub struct ModelVersion {
     pub coreml_path: Option<String>,
     pub n_features: Option<i64>,
     pub tags: Option<Vec<String>>, // Populated separately from model_tags table
+    pub trusted: bool, // v15: pickle versions must be trusted to be served
+    pub last_used_at: Option<String>, // v19: last time a server loaded this version
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -96,6 +139,7 @@ pub struct TuningSession {
     pub best_trial_id: Option<String>,
     pub created_at: String,
     pub completed_at: Option<String>,
+    pub seed: Option<i64>, // v21: sampler seed, for reproducing the study
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -128,7 +172,104 @@ pub struct ChunkEmbedding {
     pub created_at: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RagStatus {
+    pub pipeline_id: String,
+    pub chunk_count: i64,
+    pub node_count: i64,
+    pub embedding_model: Option<String>,
+    pub embedding_dim: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EmbeddingModelUsage {
+    pub embedding_model: String,
+    pub chunk_count: i64,
+    pub pipeline_count: i64,
+    pub embedding_bytes: i64,
+}
+
+/// What storing each distinct chunk embedding once would save for a pipeline
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DedupEstimate {
+    pub pipeline_id: String,
+    pub chunk_count: i64,
+    pub distinct_hashes: i64,
+    pub embedding_bytes: i64,
+    pub reclaimable_bytes: i64, // BLOB bytes held by redundant copies
+}
+
+/// Index totals across every pipeline
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GlobalRagStatus {
+    pub chunk_count: i64,
+    pub node_count: i64,
+    pub embedding_bytes: i64, // total size of the embedding BLOBs
+    pub models: Vec<EmbeddingModelUsage>, // largest first
+    pub pipeline_ids: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AcceptanceRate {
+    pub shown: i64,
+    pub accepted: i64,
+    pub rate: Option<f64>, // None when nothing was shown
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CompletionAcceptanceStats {
+    pub overall: AcceptanceRate,
+    pub rag: AcceptanceRate,
+    pub non_rag: AcceptanceRate,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RunEnvironment {
+    pub run_id: String,
+    pub python_version: String,
+    pub packages_json: String, // {"package": "version"} for installed ML packages
+    pub captured_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct InferenceAuditEntry {
+    pub id: i64,
+    pub version_id: String,
+    pub request_id: String,
+    pub input_json: String,  // full input, or {"sha256": ...} in hashed mode
+    pub output_json: String, // same for the prediction result
+    pub latency_ms: i64,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct EmbeddingConfig {
+    pub pipeline_id: String,
+    pub model: String,
+    pub dim: i32,
+    pub preprocess: String,
+    pub pinned_at: String,
+}
+
 static DB: std::sync::OnceLock<Mutex<Connection>> = std::sync::OnceLock::new();
+
+/// A rule violation surfaced the way a failed CHECK or RAISE() is, so its
+/// message reaches the UI unchanged through `to_string()`
+pub(crate) fn constraint_error(message: impl Into<String>) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CONSTRAINT),
+        Some(message.into()),
+    )
+}
+
+/// A filesystem failure with what was being done, e.g. "Failed to open x: ..."
+pub(crate) fn io_error(context: impl std::fmt::Display, e: std::io::Error) -> rusqlite::Error {
+    rusqlite::Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_IOERR),
+        Some(format!("{}: {}", context, e)),
+    )
+}
+
 static APP_DATA_DIR: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
 
 pub fn init_db(app_data_dir: &Path) -> Result<()> {
@@ -138,11 +279,33 @@ pub fn init_db(app_data_dir: &Path) -> Result<()> {
     let db_path = app_data_dir.join("settings.db");
     let conn = Connection::open(&db_path)?;
 
+    migrate(&conn)?;
+
+    DB.set(Mutex::new(conn)).map_err(|_| {
+        rusqlite::Error::InvalidParameterName("DB already initialized".to_string())
+    })?;
+
+    Ok(())
+}
+
+/// Bring the schema up to DB_VERSION. Refuses a database written by a newer build.
+fn migrate(conn: &Connection) -> Result<()> {
     // Check current version
     let version: i32 = conn
         .pragma_query_value(None, "user_version", |row| row.get(0))
         .unwrap_or(0);
 
+    // Running the migrations and stamping DB_VERSION would quietly downgrade it
+    if version > DB_VERSION {
+        return Err(rusqlite::Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+            Some(format!(
+                "Database schema version {} is newer than this app supports ({}). Update the app to open it.",
+                version, DB_VERSION
+            )),
+        ));
+    }
+
     // v1 tables (settings, pipelines)
     if version < 1 {
         conn.execute(
@@ -441,14 +604,282 @@ pub fn init_db(app_data_dir: &Path) -> Result<()> {
         )?;
     }
 
+    // v9 migration (completion_events for measuring RAG completion quality)
+    if version < 9 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS completion_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                pipeline_id TEXT NOT NULL,
+                node_id TEXT,
+                model TEXT NOT NULL,
+                was_rag INTEGER NOT NULL,
+                accepted INTEGER NOT NULL,
+                created_at TEXT DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_completion_events_pipeline ON completion_events(pipeline_id)",
+            [],
+        )?;
+    }
+
+    // v10 migration (embedding_config pins one embedding model per pipeline)
+    if version < 10 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS embedding_config (
+                pipeline_id TEXT PRIMARY KEY,
+                model TEXT NOT NULL,
+                dim INTEGER NOT NULL,
+                pinned_at TEXT DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+    }
+
+    // v11 migration (model_stage_history records every stage transition)
+    if version < 11 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_stage_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                model_id TEXT NOT NULL REFERENCES models(id) ON DELETE CASCADE,
+                version_id TEXT NOT NULL REFERENCES model_versions(id) ON DELETE CASCADE,
+                from_stage TEXT NOT NULL,
+                to_stage TEXT NOT NULL,
+                changed_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_stage_history_model ON model_stage_history(model_id)",
+            [],
+        )?;
+    }
+
+    // v12 migration (feature_correlations stores a run's correlation matrix)
+    if version < 12 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS feature_correlations (
+                run_id TEXT PRIMARY KEY,
+                matrix_json TEXT NOT NULL,
+                created_at TEXT DEFAULT CURRENT_TIMESTAMP
+            )",
+            [],
+        )?;
+    }
+
+    // v13 migration (run_metrics.kind tells the UI how to render value_json)
+    if version < 13 {
+        conn.execute(
+            "ALTER TABLE run_metrics ADD COLUMN kind TEXT",
+            [],
+        )?;
+        // Same inference as infer_metric_kind for rows saved before kinds existed
+        conn.execute(
+            "UPDATE run_metrics SET kind = CASE
+                WHEN value IS NOT NULL OR value_json IS NULL THEN 'scalar'
+                WHEN ltrim(value_json) LIKE '[[%' THEN 'matrix'
+                ELSE 'array'
+             END
+             WHERE kind IS NULL",
+            [],
+        )?;
+    }
+
+    // v14 migration (inference_audit records requests when auditing is enabled)
+    if version < 14 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS inference_audit (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                version_id TEXT NOT NULL,
+                request_id TEXT NOT NULL,
+                input_json TEXT NOT NULL,
+                output_json TEXT NOT NULL,
+                latency_ms INTEGER NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_inference_audit_version ON inference_audit(version_id)",
+            [],
+        )?;
+    }
+
+    // v15 migration (trusted flag gates serving pickle versions)
+    if version < 15 {
+        conn.execute(
+            "ALTER TABLE model_versions ADD COLUMN trusted INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+
+    // v16 migration (run_environment records the interpreter a run used)
+    if version < 16 {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS run_environment (
+                run_id TEXT PRIMARY KEY,
+                python_version TEXT NOT NULL,
+                packages_json TEXT NOT NULL,
+                captured_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+    }
+
+    // v17 migration (one row per trial number in a tuning session)
+    if version < 17 {
+        // Keep the most recently saved copy of any duplicated trial
+        conn.execute(
+            "DELETE FROM tuning_trials WHERE rowid NOT IN (
+                SELECT MAX(rowid) FROM tuning_trials GROUP BY session_id, trial_number
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS idx_tuning_trials_session_number ON tuning_trials(session_id, trial_number)",
+            [],
+        )?;
+    }
+
+    // v18 migration (embedding_config records the chunk preprocessing mode)
+    if version < 18 {
+        conn.execute(
+            "ALTER TABLE embedding_config ADD COLUMN preprocess TEXT NOT NULL DEFAULT 'none'",
+            [],
+        )?;
+    }
+
+    // v19 migration (last_used_at records when a server last loaded a version)
+    if version < 19 {
+        conn.execute(
+            "ALTER TABLE model_versions ADD COLUMN last_used_at TEXT",
+            [],
+        )?;
+    }
+
+    // v20 migration (reject model_versions.stage values outside MODEL_STAGES).
+    // SQLite can't add a CHECK to an existing column, so triggers stand in for one.
+    if version < 20 {
+        // Typos like 'prod' were never servable; reset them before enforcing,
+        // leaving a history entry so the old value isn't lost
+        conn.execute(
+            "INSERT INTO model_stage_history (model_id, version_id, from_stage, to_stage, changed_at)
+             SELECT model_id, id, COALESCE(stage, ''), 'none', ?1 FROM model_versions
+             WHERE stage IS NULL OR stage NOT IN ('none', 'staging', 'production', 'archived')",
+            [chrono::Utc::now().to_rfc3339()],
+        )?;
+        conn.execute(
+            "UPDATE model_versions SET stage = 'none', promoted_at = NULL
+             WHERE stage IS NULL OR stage NOT IN ('none', 'staging', 'production', 'archived')",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS model_versions_stage_insert
+             BEFORE INSERT ON model_versions
+             WHEN NEW.stage IS NULL OR NEW.stage NOT IN ('none', 'staging', 'production', 'archived')
+             BEGIN
+                 SELECT RAISE(ABORT, 'invalid model stage');
+             END",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS model_versions_stage_update
+             BEFORE UPDATE OF stage ON model_versions
+             WHEN NEW.stage IS NULL OR NEW.stage NOT IN ('none', 'staging', 'production', 'archived')
+             BEGIN
+                 SELECT RAISE(ABORT, 'invalid model stage');
+             END",
+            [],
+        )?;
+    }
+
+    // v21 migration (record the sampler seed of tuning sessions)
+    if version < 21 {
+        conn.execute("ALTER TABLE tuning_sessions ADD COLUMN seed INTEGER", [])?;
+    }
+
+    // v22 migration (flag runs whose artifact directory is stored as a .tar.gz)
+    if version < 22 {
+        conn.execute(
+            "ALTER TABLE runs ADD COLUMN artifacts_compressed INTEGER NOT NULL DEFAULT 0",
+            [],
+        )?;
+    }
+
+    // v23 migration (checksum of each version's model file)
+    if version < 23 {
+        conn.execute("ALTER TABLE model_versions ADD COLUMN sha256 TEXT", [])?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_model_versions_sha256 ON model_versions(model_id, sha256)",
+            [],
+        )?;
+    }
+
+    // v24 migration (per-model version retention count)
+    if version < 24 {
+        conn.execute("ALTER TABLE models ADD COLUMN max_versions INTEGER", [])?;
+    }
+
     // Update version
     conn.pragma_update(None, "user_version", DB_VERSION)?;
 
-    DB.set(Mutex::new(conn)).map_err(|_| {
-        rusqlite::Error::InvalidParameterName("DB already initialized".to_string())
+    Ok(())
+}
+
+/// Schema version recorded in the open database
+pub fn get_db_version() -> Result<i32> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
     })?;
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+}
 
-    Ok(())
+/// Write a consistent copy of the open database to `dest` (which must not
+/// exist) while it stays in use, then replace each `(from, to)` prefix in
+/// every text column of the copy, e.g. to hide the user's home directory
+pub fn backup_database_redacted(dest: &Path, redactions: &[(String, String)]) -> Result<()> {
+    {
+        let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+            rusqlite::Error::InvalidQuery
+        })?;
+        conn.execute("VACUUM INTO ?1", [dest.to_string_lossy()])?;
+    }
+
+    let mut copy = Connection::open(dest)?;
+    let tx = copy.transaction()?;
+    let tables: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'")?;
+        let rows = stmt.query_map([], |row| row.get(0))?.collect::<Result<Vec<_>>>()?;
+        rows
+    };
+    for table in tables {
+        // Any column can hold text whatever its declared type, so the
+        // check is on each value's storage class
+        let columns: Vec<String> = {
+            let mut stmt = tx.prepare(&format!("PRAGMA table_info(\"{}\")", table))?;
+            let rows = stmt.query_map([], |row| row.get(1))?.collect::<Result<Vec<_>>>()?;
+            rows
+        };
+        for column in columns {
+            for (from, to) in redactions.iter().filter(|(from, _)| !from.is_empty()) {
+                tx.execute(
+                    &format!(
+                        "UPDATE \"{table}\" SET \"{column}\" = replace(\"{column}\", ?1, ?2)
+                         WHERE typeof(\"{column}\") = 'text' AND instr(\"{column}\", ?1) > 0"
+                    ),
+                    [from, to],
+                )?;
+            }
+        }
+    }
+    tx.commit()
 }
 
 fn get_artifacts_dir() -> Result<std::path::PathBuf> {
@@ -479,9 +910,212 @@ pub fn set_setting(key: &str, value: &str) -> Result<()> {
     Ok(())
 }
 
+pub fn delete_setting(key: &str) -> Result<()> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.execute("DELETE FROM settings WHERE key = ?1", [key])?;
+    Ok(())
+}
+
 // Pipeline CRUD operations
 
+/// Check that pipeline `data` is a JSON object with `nodes` and `edges` arrays
+/// and that every node has an `id`. Unknown fields are allowed.
+pub fn validate_pipeline_data(data: &str) -> Result<()> {
+    let value: serde_json::Value = serde_json::from_str(data)
+        .map_err(|e| constraint_error(format!("Pipeline data is not valid JSON: {}", e)))?;
+    let obj = value
+        .as_object()
+        .ok_or_else(|| constraint_error("Pipeline data must be a JSON object"))?;
+
+    let nodes = obj
+        .get("nodes")
+        .and_then(|v| v.as_array())
+        .ok_or_else(|| constraint_error("Pipeline data must have a 'nodes' array"))?;
+    if !obj.get("edges").is_some_and(|v| v.is_array()) {
+        return Err(constraint_error("Pipeline data must have an 'edges' array"));
+    }
+
+    for (i, node) in nodes.iter().enumerate() {
+        let has_id = node
+            .get("id")
+            .and_then(|id| id.as_str())
+            .is_some_and(|id| !id.is_empty());
+        if !has_id {
+            return Err(constraint_error(format!("Pipeline node at index {} is missing an 'id'", i)));
+        }
+    }
+
+    Ok(())
+}
+
+/// A node type a pipeline may contain and the `data` fields it can't run without
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct NodeTypeSchema {
+    pub node_type: String,
+    pub required_fields: Vec<String>,
+}
+
+/// The node types the canvas provides
+pub fn builtin_node_schemas() -> Vec<NodeTypeSchema> {
+    [
+        ("dataLoader", &[][..]),
+        ("script", &["code"][..]),
+        ("dataSplit", &["splitRatio"][..]),
+        ("trainer", &["modelType"][..]),
+        ("evaluator", &[][..]),
+        ("modelExporter", &["exportFormat"][..]),
+    ]
+    .into_iter()
+    .map(|(node_type, fields)| NodeTypeSchema {
+        node_type: node_type.to_string(),
+        required_fields: fields.iter().map(|f| f.to_string()).collect(),
+    })
+    .collect()
+}
+
+/// A problem found in imported pipeline data. `node_id` is None for problems
+/// with the pipeline as a whole.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ValidationIssue {
+    pub node_id: Option<String>,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+/// Every problem that would make imported pipeline `data` fail at run time:
+/// the structural checks of `validate_pipeline_data`, node types missing from
+/// `schemas`, required fields that are missing or null, and edges between
+/// nodes that don't exist. Nothing is saved.
+pub fn validate_pipeline_import(data: &str, schemas: &[NodeTypeSchema]) -> Vec<ValidationIssue> {
+    let issue = |node_id: Option<&str>, field: Option<&str>, message: String| ValidationIssue {
+        node_id: node_id.map(str::to_string),
+        field: field.map(str::to_string),
+        message,
+    };
+    if let Err(e) = validate_pipeline_data(data) {
+        return vec![issue(None, None, e.to_string())];
+    }
+    let value: serde_json::Value = serde_json::from_str(data).unwrap_or_default();
+    let nodes = value["nodes"].as_array().cloned().unwrap_or_default();
+
+    let mut issues = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for node in &nodes {
+        let id = node["id"].as_str().unwrap_or_default();
+        if !seen.insert(id) {
+            issues.push(issue(Some(id), None, format!("Duplicate node id '{}'", id)));
+        }
+        let Some(node_type) = node["type"].as_str() else {
+            issues.push(issue(Some(id), None, "Node has no 'type'".to_string()));
+            continue;
+        };
+        let Some(schema) = schemas.iter().find(|s| s.node_type == node_type) else {
+            issues.push(issue(Some(id), None, format!("Unknown node type '{}'", node_type)));
+            continue;
+        };
+        for field in &schema.required_fields {
+            if node["data"].get(field).is_none_or(|v| v.is_null()) {
+                issues.push(issue(
+                    Some(id),
+                    Some(field),
+                    format!("{} node is missing required field '{}'", node_type, field),
+                ));
+            }
+        }
+    }
+
+    for edge in value["edges"].as_array().into_iter().flatten() {
+        for key in ["source", "target"] {
+            let endpoint = edge[key].as_str().unwrap_or_default();
+            if !seen.contains(endpoint) {
+                issues.push(issue(
+                    None,
+                    None,
+                    format!("Edge {} references unknown node '{}'", key, endpoint),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// Node ids of pipeline `data` in execution order (every edge's source before
+/// its target). Ties keep the order nodes appear in `nodes`.
+pub fn pipeline_execution_order(data: &str) -> Result<Vec<String>> {
+    validate_pipeline_data(data)?;
+    let value: serde_json::Value = serde_json::from_str(data).map_err(|e| constraint_error(e.to_string()))?;
+
+    let ids: Vec<&str> = value["nodes"]
+        .as_array()
+        .map(|nodes| nodes.iter().filter_map(|n| n["id"].as_str()).collect())
+        .unwrap_or_default();
+    let index: std::collections::HashMap<&str, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    for edge in value["edges"].as_array().into_iter().flatten() {
+        let endpoint = |key: &str| {
+            let id = edge[key].as_str().unwrap_or_default();
+            index
+                .get(id)
+                .copied()
+                .ok_or_else(|| constraint_error(format!("Edge {} references unknown node '{}'", key, id)))
+        };
+        let (source, target) = (endpoint("source")?, endpoint("target")?);
+        successors[source].push(target);
+        predecessors[target].push(source);
+    }
+
+    // Kahn's algorithm, always taking the earliest ready node
+    let mut in_degree: Vec<usize> = predecessors.iter().map(|p| p.len()).collect();
+    let mut ready: std::collections::BTreeSet<usize> =
+        (0..ids.len()).filter(|&i| in_degree[i] == 0).collect();
+    let mut order = Vec::with_capacity(ids.len());
+    while let Some(node) = ready.pop_first() {
+        order.push(ids[node].to_string());
+        for &next in &successors[node] {
+            in_degree[next] -= 1;
+            if in_degree[next] == 0 {
+                ready.insert(next);
+            }
+        }
+    }
+
+    if order.len() < ids.len() {
+        // Every unprocessed node still has an unprocessed predecessor, so
+        // walking predecessors from any of them must revisit a node
+        let start = (0..ids.len()).find(|&i| in_degree[i] > 0).unwrap_or_default();
+        let mut path = vec![start];
+        let mut node = start;
+        loop {
+            node = predecessors[node]
+                .iter()
+                .copied()
+                .find(|&p| in_degree[p] > 0)
+                .unwrap_or(node);
+            if let Some(pos) = path.iter().position(|&n| n == node) {
+                let mut cycle: Vec<&str> = path[pos..].iter().rev().map(|&i| ids[i]).collect();
+                cycle.push(cycle[0]);
+                return Err(constraint_error(format!("Pipeline has a cycle: {}", cycle.join(" -> "))));
+            }
+            path.push(node);
+        }
+    }
+
+    Ok(order)
+}
+
+pub fn get_pipeline_execution_order(pipeline_id: &str) -> Result<Vec<String>> {
+    let data = load_pipeline(pipeline_id)?
+        .ok_or_else(|| constraint_error(format!("Pipeline {} not found", pipeline_id)))?;
+    pipeline_execution_order(&data)
+}
+
+/// Save a pipeline, refusing data that fails `validate_pipeline_data`
 pub fn save_pipeline(id: &str, name: &str, data: &str) -> Result<()> {
+    validate_pipeline_data(data)?;
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
@@ -536,6 +1170,44 @@ pub fn delete_pipeline(id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Rows removed by `purge_pipeline`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PurgeSummary {
+    pub pipelines_deleted: usize, // 0 if the definition was already gone
+    pub runs_deleted: usize,
+    pub chunks_deleted: usize,
+}
+
+/// Delete a pipeline together with everything recorded against it: its runs
+/// (by name) with their artifacts, and its chunk embeddings (by id). All rows
+/// go in one transaction; artifacts are removed only after it commits.
+pub fn purge_pipeline(pipeline_id: &str, pipeline_name: &str) -> Result<PurgeSummary> {
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+    let run_ids: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT id FROM runs WHERE pipeline_name = ?1")?;
+        let ids = stmt.query_map([pipeline_name], |row| row.get(0))?.collect::<Result<Vec<_>>>()?;
+        ids
+    };
+    tx.execute("DELETE FROM runs WHERE pipeline_name = ?1", [pipeline_name])?;
+    let chunks_deleted = tx.execute("DELETE FROM chunk_embeddings WHERE pipeline_id = ?1", [pipeline_id])?;
+    tx.execute("DELETE FROM embedding_config WHERE pipeline_id = ?1", [pipeline_id])?;
+    let pipelines_deleted = tx.execute("DELETE FROM pipelines WHERE id = ?1", [pipeline_id])?;
+    tx.commit()?;
+
+    remove_run_artifacts(&run_ids);
+    discard_buffered_metrics(&run_ids);
+    bump_chunk_index_generation(pipeline_id);
+
+    Ok(PurgeSummary {
+        pipelines_deleted,
+        runs_deleted: run_ids.len(),
+        chunks_deleted,
+    })
+}
+
 // Run CRUD operations
 
 pub fn create_run(id: &str, pipeline_name: &str, hyperparameters: &str, experiment_id: Option<&str>) -> Result<()> {
@@ -563,49 +1235,125 @@ pub fn update_run(id: &str, status: &str, duration_ms: Option<i64>, error: Optio
     Ok(())
 }
 
+/// Save a batch of metrics in one transaction: all of them or none
 pub fn save_run_metrics(run_id: &str, metrics: &[Metric]) -> Result<()> {
-    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
-    for metric in metrics {
-        conn.execute(
-            "INSERT OR REPLACE INTO run_metrics (run_id, name, value, value_json)
-             VALUES (?1, ?2, ?3, ?4)",
-            rusqlite::params![run_id, metric.name, metric.value, metric.value_json],
+    insert_run_metrics(&mut conn, run_id, metrics)
+}
+
+fn insert_run_metrics(conn: &mut Connection, run_id: &str, metrics: &[Metric]) -> Result<()> {
+    validate_metric_kinds(metrics)?;
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR REPLACE INTO run_metrics (run_id, name, value, value_json, kind)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
         )?;
+        for metric in metrics {
+            let kind = metric
+                .kind
+                .as_deref()
+                .unwrap_or_else(|| infer_metric_kind(metric.value, metric.value_json.as_deref()));
+            stmt.execute(rusqlite::params![run_id, metric.name, metric.value, metric.value_json, kind])?;
+        }
     }
-    Ok(())
+    tx.commit()
 }
 
-pub fn list_runs(pipeline_name: Option<&str>, experiment_id: Option<&str>) -> Result<Vec<RunMetadata>> {
-    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
-        rusqlite::Error::InvalidQuery
-    })?;
+// A run's buffered metrics are written once this many are pending
+pub const METRIC_BUFFER_FLUSH_SIZE: usize = 500;
 
-    // Build query with LEFT JOINs to include experiment name and notes
-    let base_query = "SELECT r.id, r.pipeline_name, r.status, r.started_at, r.completed_at,
-                             r.duration_ms, r.hyperparameters, r.error_message,
-                             r.experiment_id, e.name as experiment_name, r.display_name,
-                             rn.content as notes
+// Metrics logged with buffering, by run, not yet written
+static METRIC_BUFFER: std::sync::OnceLock<Mutex<std::collections::HashMap<String, Vec<Metric>>>> =
+    std::sync::OnceLock::new();
+
+fn metric_buffer() -> &'static Mutex<std::collections::HashMap<String, Vec<Metric>>> {
+    METRIC_BUFFER.get_or_init(|| Mutex::new(std::collections::HashMap::new()))
+}
+
+/// Queue metrics for `run_id` instead of writing them, for high-frequency
+/// logging. The run's queue is flushed once it reaches
+/// METRIC_BUFFER_FLUSH_SIZE; returns how many metrics that wrote.
+pub fn buffer_run_metrics(run_id: &str, metrics: Vec<Metric>) -> Result<usize> {
+    // Checked now so a bad kind can't sit in the buffer and fail every flush
+    validate_metric_kinds(&metrics)?;
+    let full = {
+        let mut buffer = metric_buffer().lock().map_err(|_| rusqlite::Error::InvalidQuery)?;
+        let pending = buffer.entry(run_id.to_string()).or_default();
+        pending.extend(metrics);
+        pending.len() >= METRIC_BUFFER_FLUSH_SIZE
+    };
+    if full {
+        flush_run_metrics(run_id)
+    } else {
+        Ok(0)
+    }
+}
+
+/// Write `run_id`'s buffered metrics in one batch, returning how many were
+/// written. If the write fails they stay buffered.
+pub fn flush_run_metrics(run_id: &str) -> Result<usize> {
+    let pending = metric_buffer()
+        .lock()
+        .map_err(|_| rusqlite::Error::InvalidQuery)?
+        .remove(run_id)
+        .unwrap_or_default();
+    if pending.is_empty() {
+        return Ok(0);
+    }
+    match save_run_metrics(run_id, &pending) {
+        Ok(()) => Ok(pending.len()),
+        Err(e) => {
+            // Ahead of anything buffered meanwhile, so order is kept
+            if let Ok(mut buffer) = metric_buffer().lock() {
+                let newer = buffer.remove(run_id).unwrap_or_default();
+                buffer.insert(run_id.to_string(), pending.into_iter().chain(newer).collect());
+            }
+            Err(e)
+        }
+    }
+}
+
+// Drop deleted runs' buffered metrics; flushing them would only fail
+fn discard_buffered_metrics(run_ids: &[String]) {
+    if let Ok(mut buffer) = metric_buffer().lock() {
+        for id in run_ids {
+            buffer.remove(id);
+        }
+    }
+}
+
+// Build query with LEFT JOINs to include experiment name and notes
+const RUN_METADATA_QUERY: &str = "SELECT r.id, r.pipeline_name, r.status, r.started_at, r.completed_at,
+                             r.duration_ms, r.hyperparameters, r.error_message,
+                             r.experiment_id, e.name as experiment_name, r.display_name,
+                             rn.content as notes
                       FROM runs r
                       LEFT JOIN experiments e ON r.experiment_id = e.id
                       LEFT JOIN run_notes rn ON r.id = rn.run_id";
 
+pub fn list_runs(pipeline_name: Option<&str>, experiment_id: Option<&str>) -> Result<Vec<RunMetadata>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+
     let (query, params): (String, Vec<&str>) = match (pipeline_name, experiment_id) {
         (Some(pn), Some(eid)) => (
-            format!("{} WHERE r.pipeline_name = ?1 AND r.experiment_id = ?2 ORDER BY r.started_at DESC", base_query),
+            format!("{} WHERE r.pipeline_name = ?1 AND r.experiment_id = ?2 ORDER BY r.started_at DESC", RUN_METADATA_QUERY),
             vec![pn, eid],
         ),
         (Some(pn), None) => (
-            format!("{} WHERE r.pipeline_name = ?1 ORDER BY r.started_at DESC", base_query),
+            format!("{} WHERE r.pipeline_name = ?1 ORDER BY r.started_at DESC", RUN_METADATA_QUERY),
             vec![pn],
         ),
         (None, Some(eid)) => (
-            format!("{} WHERE r.experiment_id = ?1 ORDER BY r.started_at DESC", base_query),
+            format!("{} WHERE r.experiment_id = ?1 ORDER BY r.started_at DESC", RUN_METADATA_QUERY),
             vec![eid],
         ),
         (None, None) => (
-            format!("{} ORDER BY r.started_at DESC", base_query),
+            format!("{} ORDER BY r.started_at DESC", RUN_METADATA_QUERY),
             vec![],
         ),
     };
@@ -620,20 +1368,67 @@ pub fn list_runs(pipeline_name: Option<&str>, experiment_id: Option<&str>) -> Re
         _ => vec![],
     };
 
-    // Fetch tags for each run
-    for run in &mut runs {
-        run.tags = Some(get_run_tags_internal(&conn, &run.id)?);
-    }
+    attach_run_tags(&conn, &mut runs)?;
 
     Ok(runs)
 }
 
 fn get_run_tags_internal(conn: &Connection, run_id: &str) -> Result<Vec<String>> {
+    note_tag_query();
     let mut stmt = conn.prepare("SELECT tag FROM run_tags WHERE run_id = ?1 ORDER BY tag")?;
     let rows = stmt.query_map([run_id], |row| row.get(0))?;
     rows.collect()
 }
 
+// Ids per tag query; stays well under SQLite's bound parameter limit
+const TAG_BATCH_SIZE: usize = 500;
+
+#[cfg(test)]
+thread_local! {
+    static TAG_QUERIES: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Counts tag queries on this thread so tests can check for N+1 loads
+fn note_tag_query() {
+    #[cfg(test)]
+    TAG_QUERIES.with(|count| count.set(count.get() + 1));
+}
+
+/// Tags for many ids at once, keyed by id. `table` and `key` are always
+/// literals from this file, never user input.
+fn load_tags_by_id(
+    conn: &Connection,
+    table: &str,
+    key: &str,
+    ids: &[&str],
+) -> Result<std::collections::HashMap<String, Vec<String>>> {
+    let mut tags: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
+    for batch in ids.chunks(TAG_BATCH_SIZE) {
+        note_tag_query();
+        let placeholders = vec!["?"; batch.len()].join(",");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {key}, tag FROM {table} WHERE {key} IN ({placeholders}) ORDER BY tag"
+        ))?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(batch.iter()), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (id, tag) = row?;
+            tags.entry(id).or_default().push(tag);
+        }
+    }
+    Ok(tags)
+}
+
+fn attach_run_tags(conn: &Connection, runs: &mut [RunMetadata]) -> Result<()> {
+    let ids: Vec<&str> = runs.iter().map(|r| r.id.as_str()).collect();
+    let mut tags = load_tags_by_id(conn, "run_tags", "run_id", &ids)?;
+    for run in runs.iter_mut() {
+        run.tags = Some(tags.remove(&run.id).unwrap_or_default());
+    }
+    Ok(())
+}
+
 fn map_run_row(row: &rusqlite::Row) -> Result<RunMetadata> {
     Ok(RunMetadata {
         id: row.get(0)?,
@@ -657,35 +1452,235 @@ pub fn get_run_metrics(run_id: &str) -> Result<Vec<Metric>> {
         rusqlite::Error::InvalidQuery
     })?;
     let mut stmt = conn.prepare(
-        "SELECT name, value, value_json FROM run_metrics WHERE run_id = ?1"
+        "SELECT name, value, value_json, kind FROM run_metrics WHERE run_id = ?1"
     )?;
     let rows = stmt.query_map([run_id], |row| {
         Ok(Metric {
             name: row.get(0)?,
             value: row.get(1)?,
             value_json: row.get(2)?,
+            kind: row.get(3)?,
         })
     })?;
     rows.collect()
 }
 
+// Run ids per metric query, under SQLite's bound parameter limit with the metric name
+const METRIC_BATCH_SIZE: usize = 500;
+
+/// One metric for many runs at once, e.g. for sparklines in the run list.
+/// Every requested run is in the map, with None when it has no numeric
+/// value for the metric.
+pub fn get_latest_metric_for_runs(
+    run_ids: &[String],
+    metric_name: &str,
+) -> Result<std::collections::HashMap<String, Option<f64>>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let mut values: std::collections::HashMap<String, Option<f64>> =
+        run_ids.iter().map(|id| (id.clone(), None)).collect();
+    for batch in run_ids.chunks(METRIC_BATCH_SIZE) {
+        let placeholders = vec!["?"; batch.len()].join(",");
+        let mut stmt = conn.prepare(&format!(
+            "SELECT run_id, value FROM run_metrics WHERE name = ? AND run_id IN ({placeholders})"
+        ))?;
+        let params = std::iter::once(metric_name).chain(batch.iter().map(String::as_str));
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, Option<f64>>(1)?))
+        })?;
+        for row in rows {
+            let (run_id, value) = row?;
+            values.insert(run_id, value);
+        }
+    }
+    Ok(values)
+}
+
 pub fn delete_run(id: &str) -> Result<()> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
     conn.execute("DELETE FROM runs WHERE id = ?1", [id])?;
 
-    // Delete artifact directory
+    // Delete artifact directory (or its archive)
+    remove_run_artifacts(&[id.to_string()]);
+    discard_buffered_metrics(&[id.to_string()]);
+
+    Ok(())
+}
+
+/// Delete every run of a pipeline and their artifacts, returning how many
+/// runs were removed. The pipeline itself is kept.
+pub fn delete_runs_for_pipeline(pipeline_name: &str) -> Result<usize> {
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+    let run_ids: Vec<String> = {
+        let mut stmt = tx.prepare("SELECT id FROM runs WHERE pipeline_name = ?1")?;
+        let ids = stmt.query_map([pipeline_name], |row| row.get(0))?.collect::<Result<Vec<_>>>()?;
+        ids
+    };
+    tx.execute("DELETE FROM runs WHERE pipeline_name = ?1", [pipeline_name])?;
+    tx.commit()?;
+
+    // Artifacts go only once the rows are gone for good
+    remove_run_artifacts(&run_ids);
+    discard_buffered_metrics(&run_ids);
+
+    Ok(run_ids.len())
+}
+
+/// Delete failed runs started more than `older_than_days` ago, with their
+/// artifacts, returning how many were removed. Other statuses are never touched.
+pub fn purge_failed_runs(older_than_days: u32) -> Result<usize> {
+    let run_ids = {
+        let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+            rusqlite::Error::InvalidQuery
+        })?;
+        delete_failed_runs(&mut conn, older_than_days)?
+    };
+
+    remove_run_artifacts(&run_ids);
+    discard_buffered_metrics(&run_ids);
+
+    Ok(run_ids.len())
+}
+
+/// Delete the rows of failed runs older than the cutoff, returning their ids
+fn delete_failed_runs(conn: &mut Connection, older_than_days: u32) -> Result<Vec<String>> {
+    let tx = conn.transaction()?;
+    let run_ids: Vec<String> = {
+        let mut stmt = tx.prepare(
+            "SELECT id FROM runs
+             WHERE status = 'failed' AND julianday(started_at) < julianday('now', '-' || ?1 || ' days')",
+        )?;
+        let ids = stmt.query_map([older_than_days], |row| row.get(0))?.collect::<Result<Vec<_>>>()?;
+        ids
+    };
+    for id in &run_ids {
+        tx.execute("DELETE FROM runs WHERE id = ?1", [id])?;
+    }
+    tx.commit()?;
+    Ok(run_ids)
+}
+
+fn remove_run_artifacts(run_ids: &[String]) {
     if let Ok(artifacts_dir) = get_artifacts_dir() {
-        let run_artifacts = artifacts_dir.join(id);
-        if run_artifacts.exists() {
-            let _ = std::fs::remove_dir_all(&run_artifacts);
+        for id in run_ids {
+            let run_artifacts = artifacts_dir.join(id);
+            if run_artifacts.exists() {
+                let _ = std::fs::remove_dir_all(&run_artifacts);
+            }
+            let archive = run_artifacts_archive(&artifacts_dir, id);
+            if archive.exists() {
+                let _ = std::fs::remove_file(&archive);
+            }
         }
     }
+}
+
+fn run_artifacts_archive(artifacts_dir: &Path, run_id: &str) -> std::path::PathBuf {
+    artifacts_dir.join(format!("{}.tar.gz", run_id))
+}
+
+/// Whether the run's artifacts are packed into `artifacts/{run_id}.tar.gz`
+pub fn run_artifacts_compressed(run_id: &str) -> Result<bool> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.query_row(
+        "SELECT artifacts_compressed FROM runs WHERE id = ?1",
+        [run_id],
+        |row| row.get(0),
+    )
+}
 
+fn set_run_artifacts_compressed(run_id: &str, compressed: bool) -> Result<()> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.execute(
+        "UPDATE runs SET artifacts_compressed = ?2 WHERE id = ?1",
+        rusqlite::params![run_id, compressed],
+    )?;
     Ok(())
 }
 
+/// Pack a run's artifact directory into `artifacts/{run_id}.tar.gz` and
+/// remove the directory
+pub fn compress_run_artifacts(run_id: &str) -> Result<()> {
+    match run_artifacts_compressed(run_id) {
+        Ok(false) => {}
+        Ok(true) => {
+            return Err(constraint_error(format!(
+                "Artifacts of run {} are already compressed",
+                run_id
+            )))
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return Err(constraint_error(format!("Run {} not found", run_id)))
+        }
+        Err(e) => return Err(e),
+    }
+    let artifacts_dir = get_artifacts_dir()?;
+    let run_dir = artifacts_dir.join(run_id);
+    if !run_dir.is_dir() {
+        return Err(constraint_error(format!("Run {} has no artifacts", run_id)));
+    }
+
+    // Write under a temp name so a failed pack never looks like a finished archive
+    let archive = run_artifacts_archive(&artifacts_dir, run_id);
+    let partial = archive.with_extension("gz.partial");
+    let pack = || -> std::io::Result<()> {
+        let file = std::fs::File::create(&partial)?;
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        builder.append_dir_all(".", &run_dir)?;
+        builder.into_inner()?.finish()?.sync_all()
+    };
+    if let Err(e) = pack().and_then(|_| std::fs::rename(&partial, &archive)) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(io_error(format!("Failed to compress artifacts of run {}", run_id), e));
+    }
+
+    set_run_artifacts_compressed(run_id, true)?;
+    std::fs::remove_dir_all(&run_dir)
+        .map_err(|e| io_error(format!("Failed to remove {}", run_dir.display()), e))
+}
+
+/// Unpack `artifacts/{run_id}.tar.gz` back into the run's artifact directory
+pub fn decompress_run_artifacts(run_id: &str) -> Result<()> {
+    match run_artifacts_compressed(run_id) {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(constraint_error(format!(
+                "Artifacts of run {} are not compressed",
+                run_id
+            )))
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return Err(constraint_error(format!("Run {} not found", run_id)))
+        }
+        Err(e) => return Err(e),
+    }
+    let artifacts_dir = get_artifacts_dir()?;
+    let archive = run_artifacts_archive(&artifacts_dir, run_id);
+    let run_dir = artifacts_dir.join(run_id);
+
+    let unpack = || -> std::io::Result<()> {
+        std::fs::create_dir_all(&run_dir)?;
+        let file = std::fs::File::open(&archive)?;
+        tar::Archive::new(flate2::read::GzDecoder::new(file)).unpack(&run_dir)
+    };
+    unpack().map_err(|e| io_error(format!("Failed to decompress artifacts of run {}", run_id), e))?;
+
+    set_run_artifacts_compressed(run_id, false)?;
+    std::fs::remove_file(&archive)
+        .map_err(|e| io_error(format!("Failed to remove {}", archive.display()), e))
+}
+
 // Experiment CRUD operations
 
 pub fn create_experiment(id: &str, name: &str, description: Option<&str>) -> Result<()> {
@@ -700,6 +1695,34 @@ pub fn create_experiment(id: &str, name: &str, description: Option<&str>) -> Res
     Ok(())
 }
 
+/// Id of the experiment named `name` (ignoring case), creating it with `id`
+/// if there is none
+pub fn get_or_create_experiment(id: &str, name: &str, description: Option<&str>) -> Result<String> {
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction_with_behavior(rusqlite::TransactionBehavior::Immediate)?;
+    let existing = tx.query_row(
+        "SELECT id FROM experiments WHERE name = ?1 COLLATE NOCASE ORDER BY created_at LIMIT 1",
+        [name],
+        |row| row.get(0),
+    );
+    let id = match existing {
+        Ok(existing_id) => existing_id,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            tx.execute(
+                "INSERT INTO experiments (id, name, description, status, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, 'active', datetime('now'), datetime('now'))",
+                rusqlite::params![id, name, description],
+            )?;
+            id.to_string()
+        }
+        Err(e) => return Err(e),
+    };
+    tx.commit()?;
+    Ok(id)
+}
+
 pub fn update_experiment(id: &str, name: Option<&str>, description: Option<&str>, status: Option<&str>) -> Result<()> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
@@ -830,6 +1853,297 @@ pub fn delete_experiment(id: &str) -> Result<()> {
     Ok(())
 }
 
+/// One line of an NDJSON experiment export
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ExportLine<'a> {
+    Experiment(&'a Experiment),
+    Run {
+        run: &'a RunMetadata,
+        metrics: &'a [Metric],
+    },
+}
+
+/// Write an experiment to `dest_path` as NDJSON: its metadata on the first
+/// line, then one line per run with the run's metrics. Runs are read and
+/// written one at a time so memory use doesn't grow with the run count.
+/// Returns how many runs were written.
+pub fn export_experiment_ndjson(experiment_id: &str, dest_path: &str) -> Result<usize> {
+    use std::io::Write;
+
+    let experiment = get_experiment(experiment_id)?
+        .ok_or_else(|| constraint_error(format!("Experiment {} not found", experiment_id)))?;
+    let file = std::fs::File::create(dest_path)
+        .map_err(|e| io_error(format!("Failed to create {}", dest_path), e))?;
+    let mut out = std::io::BufWriter::new(file);
+    let write_err = |e: std::io::Error| io_error(format!("Failed to write {}", dest_path), e);
+    let write_line = |out: &mut std::io::BufWriter<std::fs::File>, line: &ExportLine| {
+        serde_json::to_writer(&mut *out, line).map_err(|e| write_err(e.into()))?;
+        out.write_all(b"\n").map_err(write_err)
+    };
+    write_line(&mut out, &ExportLine::Experiment(&experiment))?;
+
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let mut runs = conn.prepare(&format!(
+        "{} WHERE r.experiment_id = ?1 ORDER BY r.started_at, r.id",
+        RUN_METADATA_QUERY
+    ))?;
+    let mut metrics_stmt = conn
+        .prepare("SELECT name, value, value_json, kind FROM run_metrics WHERE run_id = ?1 ORDER BY name")?;
+    let mut count = 0;
+    for run in runs.query_map([experiment_id], map_run_row)? {
+        let mut run = run?;
+        run.tags = Some(get_run_tags_internal(&conn, &run.id)?);
+        let metrics = metrics_stmt
+            .query_map([&run.id], |row| {
+                Ok(Metric {
+                    name: row.get(0)?,
+                    value: row.get(1)?,
+                    value_json: row.get(2)?,
+                    kind: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        write_line(&mut out, &ExportLine::Run { run: &run, metrics: &metrics })?;
+        count += 1;
+    }
+    out.flush().map_err(write_err)?;
+    Ok(count)
+}
+
+// Pairs of metrics need at least this many runs in common to be correlated;
+// any two points lie on a line
+const MIN_CORRELATION_OVERLAP: usize = 3;
+
+/// Pearson correlations between an experiment's scalar metrics across its runs
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CorrelationMatrix {
+    pub columns: Vec<String>, // metric names, sorted
+    // matrix[i][j] for columns i and j; None when the pair has fewer than
+    // MIN_CORRELATION_OVERLAP runs in common or either metric is constant over them
+    pub matrix: Vec<Vec<Option<f64>>>,
+    pub run_count: usize, // runs with at least one scalar metric
+}
+
+/// Pearson correlation of paired samples, None if either side has no variance
+fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    let n = pairs.len() as f64;
+    let (mean_x, mean_y) = pairs
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + x / n, sy + y / n));
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        let (dx, dy) = (x - mean_x, y - mean_y);
+        cov += dx * dy;
+        var_x += dx * dx;
+        var_y += dy * dy;
+    }
+    if var_x <= f64::EPSILON || var_y <= f64::EPSILON {
+        return None;
+    }
+    Some((cov / (var_x.sqrt() * var_y.sqrt())).clamp(-1.0, 1.0))
+}
+
+/// Correlate every pair of scalar metrics recorded by the experiment's runs,
+/// using only the runs that recorded both
+pub fn get_experiment_metric_correlations(experiment_id: &str) -> Result<CorrelationMatrix> {
+    if get_experiment(experiment_id)?.is_none() {
+        return Err(constraint_error(format!("Experiment {} not found", experiment_id)));
+    }
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let mut stmt = conn.prepare(
+        "SELECT rm.run_id, rm.name, rm.value FROM run_metrics rm
+         JOIN runs r ON r.id = rm.run_id
+         WHERE r.experiment_id = ?1 AND rm.value IS NOT NULL",
+    )?;
+    let rows = stmt
+        .query_map([experiment_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, f64>(2)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut runs: std::collections::HashMap<String, std::collections::HashMap<String, f64>> =
+        std::collections::HashMap::new();
+    for (run_id, name, value) in rows {
+        if value.is_finite() {
+            runs.entry(run_id).or_default().insert(name, value);
+        }
+    }
+    let columns: Vec<String> = runs
+        .values()
+        .flat_map(|metrics| metrics.keys().cloned())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+
+    let n = columns.len();
+    let mut matrix = vec![vec![None; n]; n];
+    for i in 0..n {
+        for j in i..n {
+            let pairs: Vec<(f64, f64)> = runs
+                .values()
+                .filter_map(|metrics| Some((*metrics.get(&columns[i])?, *metrics.get(&columns[j])?)))
+                .collect();
+            if pairs.len() < MIN_CORRELATION_OVERLAP {
+                continue;
+            }
+            let r = pearson(&pairs);
+            matrix[i][j] = r;
+            matrix[j][i] = r;
+        }
+    }
+
+    Ok(CorrelationMatrix {
+        columns,
+        matrix,
+        run_count: runs.len(),
+    })
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HyperparamKind {
+    Numeric,
+    Categorical,
+}
+
+/// How much one hyperparameter explains a metric across an experiment's runs
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HyperparamImpact {
+    pub name: String,
+    pub kind: HyperparamKind,
+    // Share of the metric's variance explained, 0..1: r² for numeric
+    // hyperparameters, between-group variance (eta²) for categorical ones
+    pub impact: f64,
+    pub correlation: Option<f64>, // signed Pearson r, numeric only
+    pub run_count: usize,         // runs that set the hyperparameter, recorded the metric and were compared
+}
+
+/// Between-group share of variance of `values` grouped by label, None when
+/// there's a single group or the values don't vary
+fn eta_squared(samples: &[(String, f64)]) -> Option<f64> {
+    let n = samples.len() as f64;
+    let mean = samples.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let total: f64 = samples.iter().map(|(_, y)| (y - mean).powi(2)).sum();
+    let mut groups: std::collections::HashMap<&str, (f64, f64)> = std::collections::HashMap::new();
+    for (label, y) in samples {
+        let group = groups.entry(label.as_str()).or_default();
+        group.0 += 1.0;
+        group.1 += y;
+    }
+    if groups.len() < 2 || total <= f64::EPSILON {
+        return None;
+    }
+    let between: f64 = groups
+        .values()
+        .map(|(count, sum)| count * (sum / count - mean).powi(2))
+        .sum();
+    Some((between / total).clamp(0.0, 1.0))
+}
+
+/// Rank the hyperparameters of an experiment's runs by how much they explain
+/// `metric_name`. Each hyperparameter only uses the runs that set it, and is
+/// left out when fewer than MIN_CORRELATION_OVERLAP such runs recorded the
+/// metric or either side doesn't vary. A hyperparameter is numeric when every
+/// value it takes is a number, otherwise values are compared as categories,
+/// leaving out categories only one run used.
+pub fn get_hyperparameter_impact(
+    experiment_id: &str,
+    metric_name: &str,
+) -> Result<Vec<HyperparamImpact>> {
+    if get_experiment(experiment_id)?.is_none() {
+        return Err(constraint_error(format!("Experiment {} not found", experiment_id)));
+    }
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let mut stmt = conn.prepare(
+        "SELECT r.hyperparameters, rm.value FROM runs r
+         JOIN run_metrics rm ON rm.run_id = r.id
+         WHERE r.experiment_id = ?1 AND rm.name = ?2 AND rm.value IS NOT NULL",
+    )?;
+    let rows = stmt
+        .query_map(rusqlite::params![experiment_id, metric_name], |row| {
+            Ok((row.get::<_, Option<String>>(0)?, row.get::<_, f64>(1)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    // Hyperparameter name -> (value, metric) for each run that set it
+    let mut samples: std::collections::BTreeMap<String, Vec<(serde_json::Value, f64)>> =
+        std::collections::BTreeMap::new();
+    for (hyperparameters, value) in rows {
+        if !value.is_finite() {
+            continue;
+        }
+        let params: serde_json::Map<String, serde_json::Value> = hyperparameters
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        for (name, param) in params {
+            if !param.is_null() {
+                samples.entry(name).or_default().push((param, value));
+            }
+        }
+    }
+
+    let mut impacts: Vec<HyperparamImpact> = samples
+        .into_iter()
+        .filter(|(_, values)| values.len() >= MIN_CORRELATION_OVERLAP)
+        .filter_map(|(name, values)| {
+            let numeric: Option<Vec<(f64, f64)>> =
+                values.iter().map(|(param, y)| Some((param.as_f64()?, *y))).collect();
+            let run_count = values.len();
+            match numeric {
+                Some(pairs) => {
+                    let r = pearson(&pairs)?;
+                    Some(HyperparamImpact {
+                        name,
+                        kind: HyperparamKind::Numeric,
+                        impact: r * r,
+                        correlation: Some(r),
+                        run_count,
+                    })
+                }
+                None => {
+                    let labelled: Vec<(String, f64)> = values
+                        .into_iter()
+                        .map(|(param, y)| match param {
+                            serde_json::Value::String(label) => (label, y),
+                            other => (other.to_string(), y),
+                        })
+                        .collect();
+                    // A category seen on one run explains that run perfectly,
+                    // so only categories shared by 2+ runs are compared
+                    let mut group_sizes: std::collections::HashMap<&str, usize> = std::collections::HashMap::new();
+                    for (label, _) in &labelled {
+                        *group_sizes.entry(label.as_str()).or_default() += 1;
+                    }
+                    let shared: Vec<(String, f64)> = labelled
+                        .iter()
+                        .filter(|(label, _)| group_sizes[label.as_str()] >= 2)
+                        .cloned()
+                        .collect();
+                    if shared.len() < MIN_CORRELATION_OVERLAP {
+                        return None;
+                    }
+                    Some(HyperparamImpact {
+                        name,
+                        kind: HyperparamKind::Categorical,
+                        impact: eta_squared(&shared)?,
+                        correlation: None,
+                        run_count: shared.len(),
+                    })
+                }
+            }
+        })
+        .collect();
+    impacts.sort_by(|a, b| b.impact.total_cmp(&a.impact).then_with(|| a.name.cmp(&b.name)));
+    Ok(impacts)
+}
+
 // Run Annotation operations
 
 pub fn update_run_display_name(id: &str, display_name: Option<&str>) -> Result<()> {
@@ -928,6 +2242,114 @@ pub fn list_all_tags() -> Result<Vec<String>> {
     rows.collect()
 }
 
+pub fn list_runs_by_tag(tag: &str) -> Result<Vec<RunMetadata>> {
+    list_runs_by_tags(&[tag.to_string()])
+}
+
+/// Runs carrying every one of `tags` (case-insensitive)
+pub fn list_runs_by_tags(tags: &[String]) -> Result<Vec<RunMetadata>> {
+    let mut unique: Vec<String> = tags.iter().map(|t| t.to_lowercase()).collect();
+    unique.sort();
+    unique.dedup();
+    if unique.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+
+    let placeholders: String = (1..=unique.len())
+        .map(|i| format!("?{}", i))
+        .collect::<Vec<_>>()
+        .join(",");
+    let query = format!(
+        "{} WHERE r.id IN (
+             SELECT run_id FROM run_tags WHERE tag IN ({})
+             GROUP BY run_id HAVING COUNT(DISTINCT tag) = {}
+         ) ORDER BY r.started_at DESC",
+        RUN_METADATA_QUERY,
+        placeholders,
+        unique.len()
+    );
+
+    let mut stmt = conn.prepare(&query)?;
+    let mut runs = stmt
+        .query_map(rusqlite::params_from_iter(unique.iter()), map_run_row)?
+        .collect::<Result<Vec<_>>>()?;
+
+    attach_run_tags(&conn, &mut runs)?;
+
+    Ok(runs)
+}
+
+// Feature correlation operations (v12)
+
+pub fn save_feature_correlations(run_id: &str, matrix_json: &str) -> Result<()> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.execute(
+        "INSERT OR REPLACE INTO feature_correlations (run_id, matrix_json, created_at)
+         VALUES (?1, ?2, datetime('now'))",
+        [run_id, matrix_json],
+    )?;
+    Ok(())
+}
+
+pub fn get_feature_correlations(run_id: &str) -> Result<Option<String>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let result = conn.query_row(
+        "SELECT matrix_json FROM feature_correlations WHERE run_id = ?1",
+        [run_id],
+        |row| row.get(0),
+    );
+    match result {
+        Ok(json) => Ok(Some(json)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Run environment operations (v16)
+
+pub fn save_run_environment(run_id: &str, python_version: &str, packages_json: &str) -> Result<()> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.execute(
+        "INSERT OR REPLACE INTO run_environment (run_id, python_version, packages_json, captured_at)
+         VALUES (?1, ?2, ?3, datetime('now'))",
+        [run_id, python_version, packages_json],
+    )?;
+    Ok(())
+}
+
+pub fn get_run_environment(run_id: &str) -> Result<Option<RunEnvironment>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let result = conn.query_row(
+        "SELECT run_id, python_version, packages_json, captured_at FROM run_environment WHERE run_id = ?1",
+        [run_id],
+        |row| {
+            Ok(RunEnvironment {
+                run_id: row.get(0)?,
+                python_version: row.get(1)?,
+                packages_json: row.get(2)?,
+                captured_at: row.get(3)?,
+            })
+        },
+    );
+    match result {
+        Ok(env) => Ok(Some(env)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
 // Run Comparison operations
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -980,15 +2402,638 @@ pub fn get_runs_for_comparison(run_ids: &[String]) -> Result<RunComparisonData>
     })
 }
 
-// Model Registry CRUD operations
+// Confusion matrix comparison
 
-fn get_models_dir() -> Result<std::path::PathBuf> {
-    let app_data_dir = APP_DATA_DIR
+pub const CONFUSION_MATRIX_METRIC: &str = "confusion_matrix";
+/// JSON array of class labels in confusion matrix order, saved alongside it
+pub const CLASS_LABELS_METRIC: &str = "class_labels";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClassChange {
+    pub label: String,
+    pub correct_a: i64,
+    pub correct_b: i64,
+    pub correct_delta: i64,
+    pub recall_a: Option<f64>, // None when the class has no samples
+    pub recall_b: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConfusionMatrixDiff {
+    pub labels: Vec<String>,
+    pub delta: Vec<Vec<i64>>, // b - a, cell by cell
+    pub per_class: Vec<ClassChange>,
+}
+
+/// A run's confusion matrix and its class labels. Runs saved without labels
+/// get positional ones ("0", "1", ...).
+fn load_confusion_matrix(run_id: &str) -> Result<(Vec<String>, Vec<Vec<i64>>)> {
+    let metrics = get_run_metrics(run_id)?;
+    let json_of = |name: &str| {
+        metrics
+            .iter()
+            .find(|m| m.name == name)
+            .and_then(|m| m.value_json.clone())
+    };
+
+    let matrix_json = json_of(CONFUSION_MATRIX_METRIC)
+        .ok_or_else(|| constraint_error(format!("Run {} has no confusion matrix", run_id)))?;
+    let matrix: Vec<Vec<i64>> = serde_json::from_str(&matrix_json)
+        .map_err(|e| constraint_error(format!("Run {} has an invalid confusion matrix: {}", run_id, e)))?;
+    if matrix.iter().any(|row| row.len() != matrix.len()) {
+        return Err(constraint_error(format!("Run {} has a non-square confusion matrix", run_id)));
+    }
+
+    let labels: Vec<String> = match json_of(CLASS_LABELS_METRIC) {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| constraint_error(format!("Run {} has invalid class labels: {}", run_id, e)))?,
+        None => (0..matrix.len()).map(|i| i.to_string()).collect(),
+    };
+    if labels.len() != matrix.len() {
+        return Err(constraint_error(format!(
+            "Run {} has {} class labels for a {}x{} confusion matrix",
+            run_id,
+            labels.len(),
+            matrix.len(),
+            matrix.len()
+        )));
+    }
+    Ok((labels, matrix))
+}
+
+fn row_recall(matrix: &[Vec<i64>], i: usize) -> Option<f64> {
+    let total: i64 = matrix[i].iter().sum();
+    (total > 0).then(|| matrix[i][i] as f64 / total as f64)
+}
+
+/// Cell-by-cell change from run A's confusion matrix to run B's
+pub fn diff_confusion_matrices(run_id_a: &str, run_id_b: &str) -> Result<ConfusionMatrixDiff> {
+    let (labels_a, a) = load_confusion_matrix(run_id_a)?;
+    let (labels_b, b) = load_confusion_matrix(run_id_b)?;
+    if labels_a != labels_b {
+        return Err(constraint_error(format!(
+            "Class labels differ: [{}] vs [{}]",
+            labels_a.join(", "),
+            labels_b.join(", ")
+        )));
+    }
+
+    let delta = a
+        .iter()
+        .zip(&b)
+        .map(|(row_a, row_b)| row_a.iter().zip(row_b).map(|(x, y)| y - x).collect())
+        .collect();
+    let per_class = labels_a
+        .iter()
+        .enumerate()
+        .map(|(i, label)| ClassChange {
+            label: label.clone(),
+            correct_a: a[i][i],
+            correct_b: b[i][i],
+            correct_delta: b[i][i] - a[i][i],
+            recall_a: row_recall(&a, i),
+            recall_b: row_recall(&b, i),
+        })
+        .collect();
+
+    Ok(ConfusionMatrixDiff {
+        labels: labels_a,
+        delta,
+        per_class,
+    })
+}
+
+// Classification report
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClassMetrics {
+    pub label: String,
+    pub precision: f64, // 0 when the class was never predicted, as in sklearn
+    pub recall: f64,    // 0 when the class never occurs
+    pub f1: f64,
+    pub support: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct AveragedMetrics {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ClassificationReport {
+    pub classes: Vec<ClassMetrics>, // every label seen in either array
+    pub macro_avg: AveragedMetrics,
+    pub micro_avg: AveragedMetrics,
+    pub accuracy: f64,
+    pub support: i64,
+}
+
+/// Label of one array element. Whole numbers lose their fraction so `1.0`
+/// predictions match `1` labels.
+fn class_label(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Number(n) => match n.as_f64() {
+            Some(f) if f.fract() == 0.0 && f.abs() < 1e15 => format!("{}", f as i64),
+            _ => n.to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+fn f1_score(precision: f64, recall: f64) -> f64 {
+    if precision + recall > 0.0 {
+        2.0 * precision * recall / (precision + recall)
+    } else {
+        0.0
+    }
+}
+
+fn ratio(numerator: i64, denominator: i64) -> f64 {
+    if denominator > 0 {
+        numerator as f64 / denominator as f64
+    } else {
+        0.0
+    }
+}
+
+/// Per-class precision/recall/F1 with macro and micro averages
+fn classification_report(
+    labels: &[String],
+    predictions: &[String],
+) -> Result<ClassificationReport> {
+    if labels.len() != predictions.len() {
+        return Err(constraint_error(format!(
+            "{} labels but {} predictions",
+            labels.len(),
+            predictions.len()
+        )));
+    }
+    if labels.is_empty() {
+        return Err(constraint_error("No predictions to report on"));
+    }
+
+    let mut classes: Vec<&String> = labels.iter().chain(predictions).collect();
+    classes.sort_by(|a, b| match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(x), Ok(y)) => x.total_cmp(&y),
+        _ => a.cmp(b),
+    });
+    classes.dedup();
+
+    let count = |f: &dyn Fn(&String, &String) -> bool| -> i64 {
+        labels.iter().zip(predictions).filter(|(l, p)| f(l, p)).count() as i64
+    };
+    let per_class: Vec<ClassMetrics> = classes
+        .iter()
+        .map(|&class| {
+            let true_positives = count(&|l, p| l == class && p == class);
+            let predicted = count(&|_, p| p == class);
+            let support = count(&|l, _| l == class);
+            let (precision, recall) = (ratio(true_positives, predicted), ratio(true_positives, support));
+            ClassMetrics {
+                label: class.clone(),
+                precision,
+                recall,
+                f1: f1_score(precision, recall),
+                support,
+            }
+        })
+        .collect();
+
+    let n = per_class.len() as f64;
+    let macro_avg = AveragedMetrics {
+        precision: per_class.iter().map(|c| c.precision).sum::<f64>() / n,
+        recall: per_class.iter().map(|c| c.recall).sum::<f64>() / n,
+        f1: per_class.iter().map(|c| c.f1).sum::<f64>() / n,
+    };
+    // Every sample is one prediction, so pooled precision and recall are both accuracy
+    let accuracy = ratio(count(&|l, p| l == p), labels.len() as i64);
+    let micro_avg = AveragedMetrics {
+        precision: accuracy,
+        recall: accuracy,
+        f1: accuracy,
+    };
+
+    Ok(ClassificationReport {
+        classes: per_class,
+        macro_avg,
+        micro_avg,
+        accuracy,
+        support: labels.len() as i64,
+    })
+}
+
+/// Classification report from two array metrics of a run holding the
+/// predicted and true label of each sample
+pub fn compute_classification_report(
+    run_id: &str,
+    predictions_metric: &str,
+    labels_metric: &str,
+) -> Result<ClassificationReport> {
+    let metrics = get_run_metrics(run_id)?;
+    let labels_of = |name: &str| -> Result<Vec<String>> {
+        let json = metrics
+            .iter()
+            .find(|m| m.name == name)
+            .ok_or_else(|| constraint_error(format!("Run {} has no metric '{}'", run_id, name)))?
+            .value_json
+            .as_deref()
+            .ok_or_else(|| constraint_error(format!("Metric '{}' is not an array", name)))?;
+        let values: Vec<serde_json::Value> = serde_json::from_str(json)
+            .map_err(|_| constraint_error(format!("Metric '{}' is not an array", name)))?;
+        Ok(values.iter().map(class_label).collect())
+    };
+
+    classification_report(&labels_of(labels_metric)?, &labels_of(predictions_metric)?)
+}
+
+// Metric smoothing
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SmoothingMethod {
+    #[default]
+    Simple,      // mean of the trailing window
+    Exponential, // EMA with alpha = 2 / (window + 1)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SmoothedPoint {
+    pub step: usize,
+    pub value: f64,
+    pub smoothed: f64,
+    pub window_min: f64, // raw min/max over the trailing window
+    pub window_max: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SmoothedMetric {
+    pub name: String,
+    pub window: usize,
+    pub method: SmoothingMethod,
+    pub points: Vec<SmoothedPoint>,
+}
+
+/// Smooth a step series. Early steps, and every step when the series is
+/// shorter than `window`, use the values seen so far.
+pub fn smooth_series(values: &[f64], window: usize, method: SmoothingMethod) -> Vec<SmoothedPoint> {
+    let window = window.max(1);
+    let alpha = 2.0 / (window as f64 + 1.0);
+    let mut ema = None;
+    values
+        .iter()
+        .enumerate()
+        .map(|(step, &value)| {
+            let trailing = &values[(step + 1).saturating_sub(window)..=step];
+            let smoothed = match method {
+                SmoothingMethod::Simple => trailing.iter().sum::<f64>() / trailing.len() as f64,
+                SmoothingMethod::Exponential => {
+                    let next = ema.map_or(value, |prev| alpha * value + (1.0 - alpha) * prev);
+                    ema = Some(next);
+                    next
+                }
+            };
+            SmoothedPoint {
+                step,
+                value,
+                smoothed,
+                window_min: trailing.iter().cloned().fold(f64::INFINITY, f64::min),
+                window_max: trailing.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+            }
+        })
+        .collect()
+}
+
+/// Smooth an array metric, treating each element as one step
+pub fn get_run_metric_smoothed(
+    run_id: &str,
+    name: &str,
+    window: usize,
+    method: SmoothingMethod,
+) -> Result<SmoothedMetric> {
+    if window == 0 {
+        return Err(constraint_error("Smoothing window must be at least 1"));
+    }
+    let metrics = get_run_metrics(run_id)?;
+    let metric = metrics
+        .iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| constraint_error(format!("Run {} has no metric '{}'", run_id, name)))?;
+    let values: Vec<f64> = metric
+        .value_json
+        .as_deref()
+        .and_then(|json| serde_json::from_str(json).ok())
+        .ok_or_else(|| constraint_error(format!("Metric '{}' is not a series of numbers", name)))?;
+
+    Ok(SmoothedMetric {
+        name: name.to_string(),
+        window,
+        method,
+        points: smooth_series(&values, window, method),
+    })
+}
+
+// Similar run search
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SimilarRun {
+    pub run_id: String,
+    pub pipeline_name: String,
+    pub display_name: Option<String>,
+    pub hyperparameters: std::collections::HashMap<String, serde_json::Value>,
+    pub score: f64, // 0.0 (nothing in common) to 1.0 (identical)
+}
+
+/// Similarity of two hyperparameter sets over the union of their keys.
+/// Numeric values score by distance normalized by the key's range across all
+/// runs; other values score 1 on an exact match. Keys present in only one set
+/// score 0.
+fn hyperparameter_similarity(
+    a: &std::collections::HashMap<String, serde_json::Value>,
+    b: &std::collections::HashMap<String, serde_json::Value>,
+    ranges: &std::collections::HashMap<String, f64>,
+) -> f64 {
+    let keys: std::collections::HashSet<&String> = a.keys().chain(b.keys()).collect();
+    if keys.is_empty() {
+        return 0.0;
+    }
+    let total: f64 = keys
+        .iter()
+        .map(|key| match (a.get(*key), b.get(*key)) {
+            (Some(x), Some(y)) => match (x.as_f64(), y.as_f64()) {
+                (Some(x), Some(y)) => {
+                    let range = ranges.get(*key).copied().unwrap_or(0.0);
+                    if range > 0.0 {
+                        1.0 - ((x - y).abs() / range).min(1.0)
+                    } else if x == y {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                }
+                _ if x == y => 1.0,
+                _ => 0.0,
+            },
+            _ => 0.0,
+        })
+        .sum();
+    total / keys.len() as f64
+}
+
+pub fn find_similar_runs(run_id: &str, top_k: usize) -> Result<Vec<SimilarRun>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, pipeline_name, display_name, hyperparameters FROM runs WHERE hyperparameters IS NOT NULL",
+    )?;
+    let runs = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let parsed: Vec<_> = runs
+        .into_iter()
+        .filter_map(|(id, pipeline_name, display_name, hp_json)| {
+            let hp: std::collections::HashMap<String, serde_json::Value> =
+                serde_json::from_str(&hp_json).ok()?;
+            Some((id, pipeline_name, display_name, hp))
+        })
+        .collect();
+
+    let target = match parsed.iter().find(|(id, ..)| id == run_id) {
+        Some((.., hp)) => hp,
+        None => return Ok(vec![]),
+    };
+
+    // Numeric range per key across all runs, for distance normalization
+    let mut bounds: std::collections::HashMap<String, (f64, f64)> = std::collections::HashMap::new();
+    for (.., hp) in &parsed {
+        for (key, value) in hp {
+            if let Some(v) = value.as_f64() {
+                let entry = bounds.entry(key.clone()).or_insert((v, v));
+                entry.0 = entry.0.min(v);
+                entry.1 = entry.1.max(v);
+            }
+        }
+    }
+    let ranges: std::collections::HashMap<String, f64> =
+        bounds.into_iter().map(|(k, (min, max))| (k, max - min)).collect();
+
+    let mut similar: Vec<SimilarRun> = parsed
+        .iter()
+        .filter(|(id, .., hp)| id != run_id && hp.keys().any(|k| target.contains_key(k)))
+        .map(|(id, pipeline_name, display_name, hp)| SimilarRun {
+            run_id: id.clone(),
+            pipeline_name: pipeline_name.clone(),
+            display_name: display_name.clone(),
+            hyperparameters: hp.clone(),
+            score: hyperparameter_similarity(target, hp, &ranges),
+        })
+        .collect();
+
+    similar.sort_by(|a, b| b.score.total_cmp(&a.score));
+    similar.truncate(top_k);
+    Ok(similar)
+}
+
+// Model Registry CRUD operations
+
+fn get_models_dir() -> Result<std::path::PathBuf> {
+    let app_data_dir = APP_DATA_DIR
         .get()
         .ok_or(rusqlite::Error::InvalidQuery)?;
     Ok(app_data_dir.join("models"))
 }
 
+/// Form a model file path is stored in: relative to the app data directory
+/// when it lives inside it, so moving that directory doesn't break the row
+fn to_stored_path(path: &str) -> String {
+    APP_DATA_DIR
+        .get()
+        .and_then(|root| Path::new(path).strip_prefix(root).ok())
+        .map_or_else(|| path.to_string(), |rel| rel.to_string_lossy().to_string())
+}
+
+/// Absolute path for a stored model file path (older rows are already absolute)
+fn resolve_stored_path(stored: &str) -> String {
+    match APP_DATA_DIR.get() {
+        Some(root) if Path::new(stored).is_relative() => root.join(stored).to_string_lossy().to_string(),
+        _ => stored.to_string(),
+    }
+}
+
+/// `path` moved under `new_root`, if it's an absolute path to a file of
+/// `model_id` in some other app data directory (`<old root>/models/<model_id>/...`)
+fn relocated_path(path: &str, model_id: &str, new_root: &Path) -> Option<std::path::PathBuf> {
+    let path = Path::new(path);
+    if path.is_relative() || path.starts_with(new_root) {
+        return None;
+    }
+    let components: Vec<_> = path.components().collect();
+    let start = components
+        .windows(2)
+        .rposition(|pair| pair[0].as_os_str() == "models" && pair[1].as_os_str() == model_id)?;
+    Some(components[start..].iter().fold(new_root.to_path_buf(), |acc, c| acc.join(c)))
+}
+
+/// Rewrite model file, ONNX and Core ML paths that point into a previous app
+/// data directory so they point into `new_app_data_dir`. Returns how many
+/// versions changed.
+pub fn relocate_paths(new_app_data_dir: &Path) -> Result<usize> {
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+    type PathRow = (String, String, String, Option<String>, Option<String>);
+    let rows: Vec<PathRow> = {
+        let mut stmt = tx.prepare("SELECT id, model_id, file_path, onnx_path, coreml_path FROM model_versions")?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)))?
+            .collect::<Result<Vec<_>>>()?;
+        rows
+    };
+
+    let mut changed = 0;
+    for (id, model_id, file_path, onnx_path, coreml_path) in rows {
+        let relocate = |path: &str| {
+            relocated_path(path, &model_id, new_app_data_dir).map(|p| to_stored_path(&p.to_string_lossy()))
+        };
+        let new_file = relocate(&file_path);
+        let new_onnx = onnx_path.as_deref().and_then(relocate);
+        let new_coreml = coreml_path.as_deref().and_then(relocate);
+        if new_file.is_none() && new_onnx.is_none() && new_coreml.is_none() {
+            continue;
+        }
+        tx.execute(
+            "UPDATE model_versions SET file_path = ?2, onnx_path = ?3, coreml_path = ?4 WHERE id = ?1",
+            rusqlite::params![
+                id,
+                new_file.unwrap_or(file_path),
+                new_onnx.or(onnx_path),
+                new_coreml.or(coreml_path),
+            ],
+        )?;
+        changed += 1;
+    }
+    tx.commit()?;
+    Ok(changed)
+}
+
+/// A model version whose files are missing on disk
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FileAudit {
+    pub version_id: String,
+    pub model_id: String,
+    pub version: i64,
+    pub stage: String,
+    pub model_file_missing: bool,
+    pub onnx_missing: bool,
+    pub coreml_missing: bool,
+    pub missing_paths: Vec<String>, // absolute paths of the files above that are gone
+}
+
+/// What `repair_model_files` does with a version whose model file is gone
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RepairStrategy {
+    Archive,
+    Delete,
+}
+
+fn audit_model_files_in(conn: &Connection) -> Result<Vec<FileAudit>> {
+    type AuditRow = (String, String, i64, String, String, Option<String>, Option<String>);
+    let mut stmt = conn.prepare(
+        "SELECT id, model_id, version, stage, file_path, onnx_path, coreml_path
+         FROM model_versions ORDER BY model_id, version",
+    )?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?))
+        })?
+        .collect::<Result<Vec<AuditRow>>>()?;
+
+    let mut audits = Vec::new();
+    for (version_id, model_id, version, stage, file_path, onnx_path, coreml_path) in rows {
+        let mut missing_paths = Vec::new();
+        let mut is_missing = |stored: Option<&str>| match stored {
+            Some(stored) => {
+                let path = resolve_stored_path(stored);
+                let missing = !Path::new(&path).exists();
+                if missing {
+                    missing_paths.push(path);
+                }
+                missing
+            }
+            None => false,
+        };
+        let model_file_missing = is_missing(Some(&file_path));
+        let onnx_missing = is_missing(onnx_path.as_deref());
+        let coreml_missing = is_missing(coreml_path.as_deref());
+        if !missing_paths.is_empty() {
+            audits.push(FileAudit {
+                version_id,
+                model_id,
+                version,
+                stage,
+                model_file_missing,
+                onnx_missing,
+                coreml_missing,
+                missing_paths,
+            });
+        }
+    }
+    Ok(audits)
+}
+
+/// Versions whose model file, ONNX export or Core ML export no longer exists,
+/// e.g. because it was deleted outside the app
+pub fn audit_model_files() -> Result<Vec<FileAudit>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    audit_model_files_in(&conn)
+}
+
+/// Fix what `audit_model_files` reports and return those audits. Missing
+/// exports only clear their path. A version whose model file is gone is
+/// archived or deleted, depending on `strategy`; archived ones keep being
+/// reported until deleted.
+pub fn repair_model_files(strategy: RepairStrategy) -> Result<Vec<FileAudit>> {
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+    let audits = audit_model_files_in(&tx)?;
+    for audit in &audits {
+        if audit.model_file_missing && strategy == RepairStrategy::Delete {
+            tx.execute("DELETE FROM model_versions WHERE id = ?1", [&audit.version_id])?;
+            continue;
+        }
+        if audit.onnx_missing {
+            tx.execute("UPDATE model_versions SET onnx_path = NULL WHERE id = ?1", [&audit.version_id])?;
+        }
+        if audit.coreml_missing {
+            tx.execute("UPDATE model_versions SET coreml_path = NULL WHERE id = ?1", [&audit.version_id])?;
+        }
+        if audit.model_file_missing && audit.stage != "archived" {
+            tx.execute("UPDATE model_versions SET stage = 'archived' WHERE id = ?1", [&audit.version_id])?;
+            record_stage_change(&tx, &audit.model_id, &audit.version_id, &audit.stage, "archived")?;
+        }
+    }
+    tx.commit()?;
+    Ok(audits)
+}
+
 pub fn create_model(id: &str, name: &str, description: Option<&str>) -> Result<()> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
@@ -1010,7 +3055,8 @@ pub fn list_models() -> Result<Vec<ModelMetadata>> {
             m.id, m.name, m.description, m.created_at, m.updated_at,
             COUNT(mv.id) as version_count,
             MAX(mv.version) as latest_version,
-            (SELECT version FROM model_versions WHERE model_id = m.id AND stage = 'production' LIMIT 1) as production_version
+            (SELECT version FROM model_versions WHERE model_id = m.id AND stage = 'production' LIMIT 1) as production_version,
+            m.max_versions
          FROM models m
          LEFT JOIN model_versions mv ON mv.model_id = m.id
          GROUP BY m.id
@@ -1026,6 +3072,7 @@ pub fn list_models() -> Result<Vec<ModelMetadata>> {
             version_count: row.get(5)?,
             latest_version: row.get(6)?,
             production_version: row.get(7)?,
+            max_versions: row.get(8)?,
         })
     })?;
     rows.collect()
@@ -1040,7 +3087,8 @@ pub fn get_model(id: &str) -> Result<Option<ModelMetadata>> {
             m.id, m.name, m.description, m.created_at, m.updated_at,
             COUNT(mv.id) as version_count,
             MAX(mv.version) as latest_version,
-            (SELECT version FROM model_versions WHERE model_id = m.id AND stage = 'production' LIMIT 1) as production_version
+            (SELECT version FROM model_versions WHERE model_id = m.id AND stage = 'production' LIMIT 1) as production_version,
+            m.max_versions
          FROM models m
          LEFT JOIN model_versions mv ON mv.model_id = m.id
          WHERE m.id = ?1
@@ -1056,6 +3104,7 @@ pub fn get_model(id: &str) -> Result<Option<ModelMetadata>> {
                 version_count: row.get(5)?,
                 latest_version: row.get(6)?,
                 production_version: row.get(7)?,
+                max_versions: row.get(8)?,
             })
         },
     );
@@ -1066,6 +3115,70 @@ pub fn get_model(id: &str) -> Result<Option<ModelMetadata>> {
     }
 }
 
+pub fn get_registry_summary() -> Result<RegistrySummary> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    registry_summary(&conn)
+}
+
+fn registry_summary(conn: &Connection) -> Result<RegistrySummary> {
+    let model_count = conn.query_row("SELECT COUNT(*) FROM models", [], |row| row.get(0))?;
+    let (version_count, total_file_bytes) = conn.query_row(
+        "SELECT COUNT(*), COALESCE(SUM(file_size), 0) FROM model_versions",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let mut stage_counts: std::collections::HashMap<String, i64> =
+        MODEL_STAGES.iter().map(|stage| (stage.to_string(), 0)).collect();
+    let mut stmt = conn.prepare("SELECT stage, COUNT(*) FROM model_versions GROUP BY stage")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?;
+    for row in rows {
+        let (stage, count) = row?;
+        stage_counts.insert(stage, count);
+    }
+
+    let latest_model = conn.query_row(
+        "SELECT
+            m.id, m.name, m.description, m.created_at, m.updated_at,
+            (SELECT COUNT(*) FROM model_versions WHERE model_id = m.id) as version_count,
+            (SELECT MAX(version) FROM model_versions WHERE model_id = m.id) as latest_version,
+            (SELECT version FROM model_versions WHERE model_id = m.id AND stage = 'production' LIMIT 1) as production_version,
+            m.max_versions
+         FROM models m
+         ORDER BY m.updated_at DESC, m.rowid DESC
+         LIMIT 1",
+        [],
+        |row| {
+            Ok(ModelMetadata {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                created_at: row.get(3)?,
+                updated_at: row.get(4)?,
+                version_count: row.get(5)?,
+                latest_version: row.get(6)?,
+                production_version: row.get(7)?,
+                max_versions: row.get(8)?,
+            })
+        },
+    );
+    let latest_model = match latest_model {
+        Ok(model) => Some(model),
+        Err(rusqlite::Error::QueryReturnedNoRows) => None,
+        Err(e) => return Err(e),
+    };
+
+    Ok(RegistrySummary {
+        model_count,
+        version_count,
+        stage_counts,
+        total_file_bytes,
+        latest_model,
+    })
+}
+
 pub fn delete_model(id: &str) -> Result<()> {
     // First get all version file paths for cleanup
     let file_paths = {
@@ -1075,6 +3188,7 @@ pub fn delete_model(id: &str) -> Result<()> {
         let mut stmt = conn.prepare("SELECT file_path FROM model_versions WHERE model_id = ?1")?;
         let paths: Vec<String> = stmt.query_map([id], |row| row.get(0))?
             .filter_map(|r| r.ok())
+            .map(|path: String| resolve_stored_path(&path))
             .collect();
         paths
     };
@@ -1099,6 +3213,140 @@ pub fn delete_model(id: &str) -> Result<()> {
     Ok(())
 }
 
+/// Model file formats the registry can store and serve
+pub const MODEL_FORMATS: [&str; 4] = ["joblib", "pickle", "onnx", "coreml"];
+
+/// File extension used for a model format
+fn model_file_extension(format: &str) -> &'static str {
+    match format {
+        "joblib" => "joblib",
+        "pickle" => "pkl",
+        "onnx" => "onnx",
+        "coreml" => "mlmodel",
+        _ => "bin",
+    }
+}
+
+/// Unpickling runs arbitrary code, so pickle versions need explicit trust
+/// to be registered or served. joblib and onnx are always allowed.
+pub fn check_model_trust(format: &str, trusted: bool) -> Result<()> {
+    if format == "pickle" && !trusted {
+        return Err(constraint_error(
+            "Pickle models can execute arbitrary code when loaded. Only load pickle files you trust, and confirm by setting trusted.",
+        ));
+    }
+    Ok(())
+}
+
+fn read_varint<R: std::io::Read>(reader: &mut R) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte).ok()?;
+        value |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// Top-level field numbers of a protobuf file, reading only tags and lengths
+fn protobuf_field_numbers(path: &Path, max_fields: usize) -> Vec<u64> {
+    let Ok(file) = std::fs::File::open(path) else {
+        return vec![];
+    };
+    let mut reader = std::io::BufReader::new(file);
+    let mut fields = Vec::new();
+    while fields.len() < max_fields {
+        let Some(tag) = read_varint(&mut reader) else { break };
+        let skip = match tag & 7 {
+            0 => match read_varint(&mut reader) {
+                Some(_) => 0,
+                None => break,
+            },
+            1 => 8,
+            2 => match read_varint(&mut reader) {
+                Some(len) => len as i64,
+                None => break,
+            },
+            5 => 4,
+            _ => break, // groups aren't used by either format
+        };
+        if tag >> 3 == 0 || reader.seek_relative(skip).is_err() {
+            break;
+        }
+        fields.push(tag >> 3);
+    }
+    fields
+}
+
+/// Sniff a model file's format from its contents: "pickle" (including
+/// uncompressed joblib), "joblib" (compressed), "onnx" or "coreml".
+pub fn detect_model_format(path: &str) -> Result<String> {
+    let path = Path::new(path);
+    if path.is_dir() {
+        // An .mlpackage bundle
+        if path.join("Manifest.json").exists() {
+            return Ok("coreml".to_string());
+        }
+        return Err(constraint_error(format!("Unrecognized model directory: {}", path.display())));
+    }
+
+    let mut file =
+        std::fs::File::open(path).map_err(|e| io_error(format!("Failed to open {}", path.display()), e))?;
+    let mut header = [0u8; 6];
+    let n = std::io::Read::read(&mut file, &mut header)
+        .map_err(|e| io_error(format!("Failed to read {}", path.display()), e))?;
+    let header = &header[..n];
+
+    let format = match header {
+        [0x80, 2..=5, ..] => "pickle",
+        [0x78, 0x01 | 0x5e | 0x9c | 0xda, ..] // zlib
+        | [0x1f, 0x8b, ..] // gzip
+        | [b'B', b'Z', b'h', ..] // bz2
+        | [0xfd, b'7', b'z', b'X', b'Z', 0x00] // xz
+        | [0x04, 0x22, 0x4d, 0x18, ..] => "joblib", // lz4
+        [b'P', b'K', 0x03, 0x04, ..] => "coreml", // zipped .mlpackage
+        // Both are protobufs starting with field 1 (ir_version / specificationVersion)
+        [0x08, ..] => {
+            let fields = protobuf_field_numbers(path, 64);
+            if fields.iter().any(|&f| f >= 200) {
+                "coreml" // model types are fields 200 and up in Model
+            } else if fields.iter().any(|&f| f == 7 || f == 8) {
+                "onnx" // ModelProto.graph / opset_import
+            } else {
+                return Err(constraint_error("Unrecognized protobuf model file"));
+            }
+        }
+        _ => return Err(constraint_error("Unrecognized model file format")),
+    };
+    Ok(format.to_string())
+}
+
+/// Error if the file at `path` is clearly not `declared`. Files whose format
+/// can't be determined are let through.
+pub fn check_model_format(path: &str, declared: &str) -> Result<()> {
+    let Ok(detected) = detect_model_format(path) else {
+        return Ok(());
+    };
+    // joblib and pickle files both load with joblib
+    fn family(format: &str) -> &str {
+        match format {
+            "joblib" | "pickle" => "pickle",
+            other => other,
+        }
+    }
+    if family(&detected) != family(declared) {
+        return Err(constraint_error(format!(
+            "Model file looks like {} but was registered as {}",
+            detected, declared
+        )));
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn register_model_version(
     version_id: &str,
     model_id: &str,
@@ -1107,11 +3355,27 @@ pub fn register_model_version(
     format: &str,
     metrics_snapshot: Option<&str>,
     feature_names: Option<&str>,
+    trusted: bool,
 ) -> Result<i64> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
+    insert_model_version(&conn, version_id, model_id, run_id, source_path, format, metrics_snapshot, feature_names, trusted)
+}
 
+// Copy the model file into the next version's directory and insert its row
+#[allow(clippy::too_many_arguments)]
+fn insert_model_version(
+    conn: &Connection,
+    version_id: &str,
+    model_id: &str,
+    run_id: Option<&str>,
+    source_path: &str,
+    format: &str,
+    metrics_snapshot: Option<&str>,
+    feature_names: Option<&str>,
+    trusted: bool,
+) -> Result<i64> {
     // Get next version number
     let next_version: i64 = conn
         .query_row(
@@ -1126,15 +3390,7 @@ pub fn register_model_version(
     let version_dir = models_dir.join(model_id).join(format!("v{}", next_version));
     std::fs::create_dir_all(&version_dir).map_err(|_| rusqlite::Error::InvalidQuery)?;
 
-    // Determine file extension from format
-    let extension = match format {
-        "joblib" => "joblib",
-        "pickle" => "pkl",
-        "onnx" => "onnx",
-        "coreml" => "mlmodel",
-        _ => "bin",
-    };
-    let dest_path = version_dir.join(format!("model.{}", extension));
+    let dest_path = version_dir.join(format!("model.{}", model_file_extension(format)));
 
     // Copy file
     std::fs::copy(source_path, &dest_path).map_err(|_| rusqlite::Error::InvalidQuery)?;
@@ -1143,14 +3399,15 @@ pub fn register_model_version(
     let file_size = std::fs::metadata(&dest_path)
         .map(|m| m.len() as i64)
         .ok();
+    let sha256 = file_sha256(&dest_path).ok();
 
-    let dest_path_str = dest_path.to_string_lossy().to_string();
+    let dest_path_str = to_stored_path(&dest_path.to_string_lossy());
 
     // Insert version record
     conn.execute(
-        "INSERT INTO model_versions (id, model_id, version, run_id, file_path, file_size, format, stage, metrics_snapshot, feature_names, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'none', ?8, ?9, datetime('now'))",
-        rusqlite::params![version_id, model_id, next_version, run_id, dest_path_str, file_size, format, metrics_snapshot, feature_names],
+        "INSERT INTO model_versions (id, model_id, version, run_id, file_path, file_size, format, stage, metrics_snapshot, feature_names, trusted, sha256, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'none', ?8, ?9, ?10, ?11, datetime('now'))",
+        rusqlite::params![version_id, model_id, next_version, run_id, dest_path_str, file_size, format, metrics_snapshot, feature_names, trusted, sha256],
     )?;
 
     // Update model's updated_at
@@ -1162,20 +3419,437 @@ pub fn register_model_version(
     Ok(next_version)
 }
 
+/// Hex SHA-256 of a file's contents
+fn file_sha256(path: &Path) -> std::io::Result<String> {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut std::fs::File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Groups of a model's version ids whose files have the same checksum, each
+/// in version order. Versions registered before checksums were recorded are
+/// skipped.
+pub fn find_duplicate_versions(model_id: &str) -> Result<Vec<Vec<String>>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let mut stmt = conn.prepare(
+        "SELECT id, sha256 FROM model_versions
+         WHERE model_id = ?1 AND sha256 IN (
+             SELECT sha256 FROM model_versions
+             WHERE model_id = ?1 AND sha256 IS NOT NULL
+             GROUP BY sha256 HAVING COUNT(*) > 1
+         )
+         ORDER BY version",
+    )?;
+    let rows = stmt
+        .query_map([model_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<Result<Vec<_>>>()?;
+
+    // Groups come out in the order of their oldest version
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for (id, sha256) in rows {
+        match groups.iter_mut().find(|(hash, _)| *hash == sha256) {
+            Some((_, ids)) => ids.push(id),
+            None => groups.push((sha256, vec![id])),
+        }
+    }
+    Ok(groups.into_iter().map(|(_, ids)| ids).collect())
+}
+
+/// Snapshot JSON (`{"name": value}`) of the scalar metrics, or None if there are none
+fn metrics_snapshot_json(metrics: &[Metric]) -> Option<String> {
+    let snapshot: serde_json::Map<String, serde_json::Value> = metrics
+        .iter()
+        .filter_map(|m| Some((m.name.clone(), serde_json::json!(m.value?))))
+        .collect();
+    (!snapshot.is_empty()).then(|| serde_json::Value::Object(snapshot).to_string())
+}
+
+/// Build a version's metrics snapshot from what the run actually recorded
+pub fn metrics_snapshot_from_run(run_id: &str) -> Result<Option<String>> {
+    Ok(metrics_snapshot_json(&get_run_metrics(run_id)?))
+}
+
+// A run's metrics snapshot and its `feature_names` metric, if recorded
+fn run_version_metadata(run_id: &str) -> Result<(Option<String>, Option<Vec<String>>)> {
+    let metrics = get_run_metrics(run_id)?;
+    let feature_names = metrics
+        .iter()
+        .find(|m| m.name == "feature_names")
+        .and_then(|m| m.value_json.as_deref())
+        .and_then(|json| serde_json::from_str(json).ok());
+    Ok((metrics_snapshot_json(&metrics), feature_names))
+}
+
+/// Register a version from the model artifact a run saved in its artifact
+/// directory, copying the run's metrics (and `feature_names` metric, if any).
+/// The row and its feature count are written in one transaction.
+pub fn register_version_from_run(
+    version_id: &str,
+    model_id: &str,
+    run_id: &str,
+    format: &str,
+    trusted: bool,
+) -> Result<i64> {
+    check_model_trust(format, trusted)?;
+    if run_artifacts_compressed(run_id).unwrap_or(false) {
+        return Err(constraint_error(format!(
+            "Artifacts of run {} are compressed; decompress them first",
+            run_id
+        )));
+    }
+    let extension = model_file_extension(format);
+    let run_dir = get_artifacts_dir()?.join(run_id);
+    let mut candidates: Vec<std::path::PathBuf> = std::fs::read_dir(&run_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext == extension))
+                .collect()
+        })
+        .unwrap_or_default();
+    candidates.sort();
+    let source = candidates.first().ok_or_else(|| {
+        constraint_error(format!("No .{} model artifact found for run {}", extension, run_id))
+    })?;
+
+    let (metrics_snapshot, feature_names) = run_version_metadata(run_id)?;
+    let feature_names_json = feature_names
+        .as_ref()
+        .map(|names| serde_json::to_string(names).unwrap_or_default());
+    let n_features = feature_names.as_ref().map(|names| names.len() as i64);
+
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+    let next_version: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(version), 0) + 1 FROM model_versions WHERE model_id = ?1",
+        [model_id],
+        |row| row.get(0),
+    )?;
+
+    let result = insert_model_version(
+        &tx,
+        version_id,
+        model_id,
+        Some(run_id),
+        &source.to_string_lossy(),
+        format,
+        metrics_snapshot.as_deref(),
+        feature_names_json.as_deref(),
+        trusted,
+    )
+    .and_then(|_| {
+        tx.execute(
+            "UPDATE model_versions SET n_features = ?2 WHERE id = ?1",
+            rusqlite::params![version_id, n_features],
+        )
+    })
+    .and_then(|_| tx.commit());
+
+    match result {
+        Ok(()) => Ok(next_version),
+        Err(e) => {
+            // The row was rolled back; the copied file has to go too
+            if let Ok(models_dir) = get_models_dir() {
+                let _ = std::fs::remove_dir_all(models_dir.join(model_id).join(format!("v{}", next_version)));
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Register a version and move it to `stage` in one transaction, so it is
+/// never visible un-promoted. Promoting to production demotes the current
+/// production version and must pass the promotion rule; if it doesn't, the
+/// registration is rolled back and the copied file removed.
+#[allow(clippy::too_many_arguments)]
+pub fn register_and_promote(
+    version_id: &str,
+    model_id: &str,
+    run_id: Option<&str>,
+    source_path: &str,
+    format: &str,
+    metrics_snapshot: Option<&str>,
+    feature_names: Option<&str>,
+    trusted: bool,
+    stage: &str,
+) -> Result<i64> {
+    validate_stage(stage)?;
+    let rule = if stage == "production" { get_promotion_rule() } else { None };
+
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+    let next_version: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(version), 0) + 1 FROM model_versions WHERE model_id = ?1",
+        [model_id],
+        |row| row.get(0),
+    )?;
+
+    let register = || -> Result<()> {
+        insert_model_version(
+            &tx,
+            version_id,
+            model_id,
+            run_id,
+            source_path,
+            format,
+            metrics_snapshot,
+            feature_names,
+            trusted,
+        )?;
+        if let Some(rule) = &rule {
+            rule.check(metrics_snapshot)?;
+        }
+        set_version_stage(&tx, version_id, stage)?;
+        tx.commit()
+    };
+
+    match register() {
+        Ok(()) => Ok(next_version),
+        Err(e) => {
+            // The row was rolled back; the copied file has to go too
+            if let Ok(models_dir) = get_models_dir() {
+                let _ = std::fs::remove_dir_all(models_dir.join(model_id).join(format!("v{}", next_version)));
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Register a model file trained outside the app (e.g. in a notebook) as a
+/// new version of the model called `model_name`, creating the model first if
+/// no model has that name. Both happen in one transaction. Returns the new
+/// version's id and number.
+pub fn import_external_model(
+    model_name: &str,
+    source_path: &str,
+    format: &str,
+    feature_names: Option<&[String]>,
+    metrics_json: Option<&str>,
+    trusted: bool,
+) -> Result<(String, i64)> {
+    let model_name = model_name.trim();
+    if model_name.is_empty() {
+        return Err(constraint_error("Model name must not be empty"));
+    }
+    if !Path::new(source_path).is_file() {
+        return Err(constraint_error(format!("Model file not found: {}", source_path)));
+    }
+    if !MODEL_FORMATS.contains(&format) {
+        return Err(constraint_error(format!(
+            "Unrecognized model format '{}'. Expected one of: {}",
+            format,
+            MODEL_FORMATS.join(", ")
+        )));
+    }
+    check_model_trust(format, trusted)?;
+    check_model_format(source_path, format)?;
+    if let Some(metrics) = metrics_json {
+        match serde_json::from_str::<serde_json::Value>(metrics) {
+            Ok(serde_json::Value::Object(_)) => {}
+            _ => return Err(constraint_error("metrics_json must be a JSON object of metric values")),
+        }
+    }
+    let feature_names_json = feature_names.map(|names| serde_json::to_string(names).unwrap_or_default());
+    let n_features = feature_names.map(|names| names.len() as i64);
+
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+    let existing = tx.query_row("SELECT id FROM models WHERE name = ?1", [model_name], |row| {
+        row.get::<_, String>(0)
+    });
+    let model_id = match existing {
+        Ok(id) => id,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            let id = uuid::Uuid::new_v4().to_string();
+            tx.execute(
+                "INSERT INTO models (id, name, description, created_at, updated_at)
+                 VALUES (?1, ?2, NULL, datetime('now'), datetime('now'))",
+                rusqlite::params![id, model_name],
+            )?;
+            id
+        }
+        Err(e) => return Err(e),
+    };
+
+    let next_version: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(version), 0) + 1 FROM model_versions WHERE model_id = ?1",
+        [&model_id],
+        |row| row.get(0),
+    )?;
+
+    let version_id = uuid::Uuid::new_v4().to_string();
+    let result = insert_model_version(
+        &tx,
+        &version_id,
+        &model_id,
+        None,
+        source_path,
+        format,
+        metrics_json,
+        feature_names_json.as_deref(),
+        trusted,
+    )
+    .and_then(|version| {
+        tx.execute(
+            "UPDATE model_versions SET n_features = ?2 WHERE id = ?1",
+            rusqlite::params![version_id, n_features],
+        )?;
+        Ok(version)
+    });
+
+    match result.and_then(|version| tx.commit().map(|_| version)) {
+        Ok(version) => Ok((version_id, version)),
+        Err(e) => {
+            // Nothing was committed, so any copied file is an orphan
+            if let Ok(models_dir) = get_models_dir() {
+                let _ = std::fs::remove_dir_all(models_dir.join(&model_id).join(format!("v{}", next_version)));
+            }
+            Err(constraint_error(format!("Failed to import {}: {}", source_path, e)))
+        }
+    }
+}
+
+/// One model file in a run's artifact directory to register as a version
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArtifactSpec {
+    pub file_name: String, // relative to the run's artifact directory
+    pub format: String,
+    #[serde(default)]
+    pub trusted: bool,
+}
+
+/// Register several of a run's artifacts (e.g. the members of an ensemble) as
+/// consecutive versions of a model. Either all are registered or none are.
+/// Returns the new version ids in version order.
+pub fn register_versions_from_run(
+    model_id: &str,
+    run_id: &str,
+    artifacts: &[ArtifactSpec],
+) -> Result<Vec<String>> {
+    if artifacts.is_empty() {
+        return Err(constraint_error("No artifacts to register"));
+    }
+    if run_artifacts_compressed(run_id).unwrap_or(false) {
+        return Err(constraint_error(format!(
+            "Artifacts of run {} are compressed; decompress them first",
+            run_id
+        )));
+    }
+    let run_dir = get_artifacts_dir()?.join(run_id);
+    let mut sources = Vec::with_capacity(artifacts.len());
+    for artifact in artifacts {
+        check_model_trust(&artifact.format, artifact.trusted)?;
+        // Only plain file names, so a spec can't reach outside the run's directory
+        if Path::new(&artifact.file_name).file_name() != Some(std::ffi::OsStr::new(&artifact.file_name)) {
+            return Err(constraint_error(format!("Invalid artifact file name '{}'", artifact.file_name)));
+        }
+        let source = run_dir.join(&artifact.file_name);
+        if !source.is_file() {
+            return Err(constraint_error(format!(
+                "Artifact {} not found for run {}",
+                artifact.file_name, run_id
+            )));
+        }
+        let source = source.to_string_lossy().to_string();
+        check_model_format(&source, &artifact.format)?;
+        sources.push(source);
+    }
+
+    let (metrics_snapshot, feature_names) = run_version_metadata(run_id)?;
+    let feature_names_json = feature_names
+        .as_ref()
+        .map(|names| serde_json::to_string(names).unwrap_or_default());
+    let n_features = feature_names.as_ref().map(|names| names.len() as i64);
+
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+    let first_version: i64 = tx.query_row(
+        "SELECT COALESCE(MAX(version), 0) + 1 FROM model_versions WHERE model_id = ?1",
+        [model_id],
+        |row| row.get(0),
+    )?;
+
+    let mut version_ids = Vec::with_capacity(artifacts.len());
+    let mut result = Ok(());
+    for (artifact, source) in artifacts.iter().zip(&sources) {
+        let version_id = uuid::Uuid::new_v4().to_string();
+        result = insert_model_version(
+            &tx,
+            &version_id,
+            model_id,
+            Some(run_id),
+            source,
+            &artifact.format,
+            metrics_snapshot.as_deref(),
+            feature_names_json.as_deref(),
+            artifact.trusted,
+        )
+        .and_then(|_| {
+            tx.execute(
+                "UPDATE model_versions SET n_features = ?2 WHERE id = ?1",
+                rusqlite::params![version_id, n_features],
+            )
+        })
+        .map(|_| ());
+        if result.is_err() {
+            break;
+        }
+        version_ids.push(version_id);
+    }
+
+    match result.and_then(|_| tx.commit()) {
+        Ok(()) => Ok(version_ids),
+        Err(e) => {
+            // The rows were rolled back; remove the files copied so far,
+            // including any left by the version that failed
+            if let Ok(models_dir) = get_models_dir() {
+                for version in first_version..=first_version + version_ids.len() as i64 {
+                    let _ = std::fs::remove_dir_all(models_dir.join(model_id).join(format!("v{}", version)));
+                }
+            }
+            Err(constraint_error(format!("Failed to register artifacts of run {}: {}", run_id, e)))
+        }
+    }
+}
+
 pub fn list_model_versions(model_id: &str) -> Result<Vec<ModelVersion>> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
     let mut stmt = conn.prepare(
-        "SELECT id, model_id, version, run_id, file_path, file_size, format, stage, metrics_snapshot, feature_names, created_at, promoted_at, description, notes, onnx_path, coreml_path, n_features
+        "SELECT id, model_id, version, run_id, file_path, file_size, format, stage, metrics_snapshot, feature_names, created_at, promoted_at, description, notes, onnx_path, coreml_path, n_features, trusted, last_used_at
          FROM model_versions WHERE model_id = ?1 ORDER BY version DESC"
     )?;
     let mut versions: Vec<ModelVersion> = stmt.query_map([model_id], map_model_version_row)?.collect::<Result<Vec<_>>>()?;
 
-    // Fetch tags for each version
-    for version in &mut versions {
-        version.tags = Some(get_model_tags_internal(&conn, &version.id)?);
-    }
+    attach_model_tags(&conn, &mut versions)?;
+
+    Ok(versions)
+}
+
+/// Versions registered from `run_id`, newest first
+pub fn get_run_model_versions(run_id: &str) -> Result<Vec<ModelVersion>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let mut stmt = conn.prepare(
+        "SELECT id, model_id, version, run_id, file_path, file_size, format, stage, metrics_snapshot, feature_names, created_at, promoted_at, description, notes, onnx_path, coreml_path, n_features, trusted, last_used_at
+         FROM model_versions WHERE run_id = ?1 ORDER BY created_at DESC, version DESC"
+    )?;
+    let mut versions: Vec<ModelVersion> = stmt.query_map([run_id], map_model_version_row)?.collect::<Result<Vec<_>>>()?;
+
+    attach_model_tags(&conn, &mut versions)?;
 
     Ok(versions)
 }
@@ -1186,7 +3860,7 @@ fn map_model_version_row(row: &rusqlite::Row) -> Result<ModelVersion> {
         model_id: row.get(1)?,
         version: row.get(2)?,
         run_id: row.get(3)?,
-        file_path: row.get(4)?,
+        file_path: resolve_stored_path(&row.get::<_, String>(4)?),
         file_size: row.get(5)?,
         format: row.get(6)?,
         stage: row.get(7)?,
@@ -1196,32 +3870,98 @@ fn map_model_version_row(row: &rusqlite::Row) -> Result<ModelVersion> {
         promoted_at: row.get(11)?,
         description: row.get(12)?,
         notes: row.get(13)?,
-        onnx_path: row.get(14)?,
-        coreml_path: row.get(15)?,
+        onnx_path: row.get::<_, Option<String>>(14)?.as_deref().map(resolve_stored_path),
+        coreml_path: row.get::<_, Option<String>>(15)?.as_deref().map(resolve_stored_path),
         n_features: row.get(16)?,
         tags: None, // Populated separately
+        trusted: row.get(17)?,
+        last_used_at: row.get(18)?,
     })
 }
 
-pub fn promote_model(version_id: &str, new_stage: &str) -> Result<()> {
+/// Record that a server just loaded `version_id`
+pub fn touch_model_version(version_id: &str) -> Result<()> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
+    conn.execute(
+        "UPDATE model_versions SET last_used_at = datetime('now') WHERE id = ?1",
+        [version_id],
+    )?;
+    Ok(())
+}
 
-    if new_stage == "production" {
-        // Get model_id for this version
-        let model_id: String = conn.query_row(
-            "SELECT model_id FROM model_versions WHERE id = ?1",
-            [version_id],
-            |row| row.get(0),
-        )?;
+/// Versions not loaded by a server in the last `older_than_days` days. Versions
+/// that were never loaded count from their creation, so new ones aren't listed.
+pub fn list_unused_versions(older_than_days: u32) -> Result<Vec<ModelVersion>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let mut stmt = conn.prepare(
+        "SELECT id, model_id, version, run_id, file_path, file_size, format, stage, metrics_snapshot, feature_names, created_at, promoted_at, description, notes, onnx_path, coreml_path, n_features, trusted, last_used_at
+         FROM model_versions
+         WHERE COALESCE(last_used_at, created_at) < datetime('now', '-' || ?1 || ' days')
+         ORDER BY COALESCE(last_used_at, created_at) ASC"
+    )?;
+    let mut versions: Vec<ModelVersion> = stmt.query_map([older_than_days], map_model_version_row)?.collect::<Result<Vec<_>>>()?;
+
+    attach_model_tags(&conn, &mut versions)?;
+
+    Ok(versions)
+}
+
+/// Stages a model version can be in. The v20 triggers enforce the same list.
+pub const MODEL_STAGES: [&str; 4] = ["none", "staging", "production", "archived"];
+
+pub fn validate_stage(stage: &str) -> Result<()> {
+    if MODEL_STAGES.contains(&stage) {
+        Ok(())
+    } else {
+        Err(constraint_error(format!(
+            "Invalid stage '{}'. Expected one of: {}",
+            stage,
+            MODEL_STAGES.join(", ")
+        )))
+    }
+}
 
+pub fn promote_model(version_id: &str, new_stage: &str) -> Result<()> {
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+    set_version_stage(&tx, version_id, new_stage)?;
+    tx.commit()
+}
+
+// Move a version to `new_stage`, demoting the model's current production
+// version to staging when promoting to production. Runs in the caller's transaction.
+fn set_version_stage(tx: &Connection, version_id: &str, new_stage: &str) -> Result<()> {
+    // Get model_id and current stage for this version
+    let (model_id, old_stage): (String, String) = tx.query_row(
+        "SELECT model_id, stage FROM model_versions WHERE id = ?1",
+        [version_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    if new_stage == "production" {
         // Demote current production version (if any) to staging
-        conn.execute(
-            "UPDATE model_versions SET stage = 'staging', promoted_at = NULL
-             WHERE model_id = ?1 AND stage = 'production'",
-            [&model_id],
-        )?;
+        let current: Option<String> = match tx.query_row(
+            "SELECT id FROM model_versions WHERE model_id = ?1 AND stage = 'production' AND id != ?2",
+            [&model_id, version_id],
+            |row| row.get(0),
+        ) {
+            Ok(id) => Some(id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e),
+        };
+        if let Some(current_id) = current {
+            tx.execute(
+                "UPDATE model_versions SET stage = 'staging', promoted_at = NULL WHERE id = ?1",
+                [&current_id],
+            )?;
+            record_stage_change(tx, &model_id, &current_id, "production", "staging")?;
+        }
     }
 
     // Now promote the requested version
@@ -1231,14 +3971,286 @@ pub fn promote_model(version_id: &str, new_stage: &str) -> Result<()> {
         Some(chrono::Utc::now().to_rfc3339())
     };
 
-    conn.execute(
+    tx.execute(
         "UPDATE model_versions SET stage = ?1, promoted_at = ?2 WHERE id = ?3",
         rusqlite::params![new_stage, promoted_at, version_id],
     )?;
+    if old_stage != new_stage {
+        record_stage_change(tx, &model_id, version_id, &old_stage, new_stage)?;
+    }
 
     Ok(())
 }
 
+fn record_stage_change(
+    conn: &Connection,
+    model_id: &str,
+    version_id: &str,
+    from_stage: &str,
+    to_stage: &str,
+) -> Result<()> {
+    conn.execute(
+        "INSERT INTO model_stage_history (model_id, version_id, from_stage, to_stage, changed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![model_id, version_id, from_stage, to_stage, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(())
+}
+
+/// Archive the model's production version and restore the one that was in
+/// production before it. Falls back to the most recently promoted staging
+/// version when the stage history has no usable candidate. The restored
+/// version has to pass the promotion rule like any promotion to production.
+pub fn rollback_production(model_id: &str) -> Result<ModelVersion> {
+    // Read before locking; get_setting takes the same lock
+    let rule = get_promotion_rule();
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+
+    let current: String = match tx.query_row(
+        "SELECT id FROM model_versions WHERE model_id = ?1 AND stage = 'production'",
+        [model_id],
+        |row| row.get(0),
+    ) {
+        Ok(id) => id,
+        Err(rusqlite::Error::QueryReturnedNoRows) => {
+            return Err(constraint_error("Model has no production version to roll back"))
+        }
+        Err(e) => return Err(e),
+    };
+
+    // Latest earlier production version that still exists and isn't archived
+    let from_history = tx.query_row(
+        "SELECT h.version_id FROM model_stage_history h
+         JOIN model_versions mv ON mv.id = h.version_id
+         WHERE h.model_id = ?1 AND h.to_stage = 'production' AND h.version_id != ?2
+           AND mv.stage != 'archived'
+         ORDER BY h.id DESC LIMIT 1",
+        [model_id, &current],
+        |row| row.get::<_, String>(0),
+    );
+    let candidate = match from_history {
+        Ok(id) => id,
+        Err(rusqlite::Error::QueryReturnedNoRows) => match tx.query_row(
+            "SELECT id FROM model_versions
+             WHERE model_id = ?1 AND stage = 'staging' AND promoted_at IS NOT NULL
+             ORDER BY promoted_at DESC LIMIT 1",
+            [model_id],
+            |row| row.get::<_, String>(0),
+        ) {
+            Ok(id) => id,
+            Err(rusqlite::Error::QueryReturnedNoRows) => {
+                return Err(constraint_error("No previous production or staging version to roll back to"))
+            }
+            Err(e) => return Err(e),
+        },
+        Err(e) => return Err(e),
+    };
+
+    let (candidate_stage, metrics_snapshot): (String, Option<String>) = tx.query_row(
+        "SELECT stage, metrics_snapshot FROM model_versions WHERE id = ?1",
+        [&candidate],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    if let Some(rule) = rule {
+        rule.check(metrics_snapshot.as_deref())?;
+    }
+
+    // The archived version keeps its promoted_at; only the restored one is stamped
+    tx.execute("UPDATE model_versions SET stage = 'archived' WHERE id = ?1", [&current])?;
+    record_stage_change(&tx, model_id, &current, "production", "archived")?;
+
+    tx.execute(
+        "UPDATE model_versions SET stage = 'production', promoted_at = ?2 WHERE id = ?1",
+        rusqlite::params![candidate, chrono::Utc::now().to_rfc3339()],
+    )?;
+    record_stage_change(&tx, model_id, &candidate, &candidate_stage, "production")?;
+
+    let mut version = tx.query_row(
+        "SELECT id, model_id, version, run_id, file_path, file_size, format, stage, metrics_snapshot, feature_names, created_at, promoted_at, description, notes, onnx_path, coreml_path, n_features, trusted, last_used_at
+         FROM model_versions WHERE id = ?1",
+        [&candidate],
+        map_model_version_row,
+    )?;
+    version.tags = Some(get_model_tags_internal(&tx, &version.id)?);
+
+    tx.commit()?;
+    Ok(version)
+}
+
+// Promotion gate (a metric threshold a version must meet to reach production)
+
+const PROMOTION_RULE_KEY: &str = "promotion_rules";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PromotionRule {
+    pub metric: String,
+    pub comparison: String, // '>=' | '>' | '<=' | '<'
+    pub threshold: f64,
+}
+
+impl PromotionRule {
+    pub fn validate(&self) -> Result<()> {
+        if self.metric.trim().is_empty() {
+            return Err(constraint_error("Promotion rule metric name cannot be empty"));
+        }
+        if !matches!(self.comparison.as_str(), ">=" | ">" | "<=" | "<") {
+            return Err(constraint_error(format!(
+                "Invalid comparison '{}'. Expected one of: >=, >, <=, <",
+                self.comparison
+            )));
+        }
+        if !self.threshold.is_finite() {
+            return Err(constraint_error("Promotion rule threshold must be a finite number"));
+        }
+        Ok(())
+    }
+
+    /// Check a version's metrics_snapshot JSON against this rule
+    pub fn check(&self, metrics_snapshot: Option<&str>) -> Result<()> {
+        let metrics: std::collections::HashMap<String, serde_json::Value> = metrics_snapshot
+            .and_then(|s| serde_json::from_str(s).ok())
+            .unwrap_or_default();
+
+        let value = metrics
+            .get(&self.metric)
+            .and_then(|v| v.as_f64())
+            .ok_or_else(|| {
+                constraint_error(format!(
+                    "Cannot promote to production: metric '{}' is missing from the version's metrics snapshot",
+                    self.metric
+                ))
+            })?;
+
+        let passed = match self.comparison.as_str() {
+            ">=" => value >= self.threshold,
+            ">" => value > self.threshold,
+            "<=" => value <= self.threshold,
+            "<" => value < self.threshold,
+            _ => false,
+        };
+
+        if passed {
+            Ok(())
+        } else {
+            Err(constraint_error(format!(
+                "Cannot promote to production: {} = {} does not satisfy the promotion rule ({} {} {})",
+                self.metric, value, self.metric, self.comparison, self.threshold
+            )))
+        }
+    }
+}
+
+pub fn get_promotion_rule() -> Option<PromotionRule> {
+    get_setting(PROMOTION_RULE_KEY).and_then(|s| serde_json::from_str(&s).ok())
+}
+
+pub fn set_promotion_rule(rule: Option<&PromotionRule>) -> Result<()> {
+    match rule {
+        Some(r) => {
+            r.validate()?;
+            let json = serde_json::to_string(r).map_err(|_| rusqlite::Error::InvalidQuery)?;
+            set_setting(PROMOTION_RULE_KEY, &json)
+        }
+        None => delete_setting(PROMOTION_RULE_KEY),
+    }
+}
+
+/// Check a version against the configured promotion rule (no rule = always allowed)
+pub fn check_promotion_rule(version_id: &str) -> Result<()> {
+    let rule = match get_promotion_rule() {
+        Some(r) => r,
+        None => return Ok(()),
+    };
+
+    let version = get_model_version(version_id)?.ok_or_else(|| constraint_error("Model version not found"))?;
+
+    rule.check(version.metrics_snapshot.as_deref())
+}
+
+// Metric direction, matching the highlighting in the comparison views
+const HIGHER_IS_BETTER: &[&str] = &["accuracy", "precision", "recall", "f1", "r2"];
+const LOWER_IS_BETTER: &[&str] = &["mse", "rmse", "mae"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct MetricDelta {
+    pub metric: String,
+    pub candidate: Option<f64>, // None when the version didn't record it
+    pub production: Option<f64>,
+    pub delta: Option<f64>, // candidate - production, when both are present
+    pub regression: bool,   // only for metrics with a known direction
+}
+
+fn metric_deltas(candidate: Option<&str>, production: Option<&str>) -> Vec<MetricDelta> {
+    let parse = |snapshot: Option<&str>| -> std::collections::BTreeMap<String, f64> {
+        snapshot
+            .and_then(|s| serde_json::from_str::<std::collections::BTreeMap<String, serde_json::Value>>(s).ok())
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value.as_f64()?)))
+            .collect()
+    };
+    let (candidate, production) = (parse(candidate), parse(production));
+    let names: std::collections::BTreeSet<&String> = candidate.keys().chain(production.keys()).collect();
+
+    names
+        .into_iter()
+        .map(|name| {
+            let (c, p) = (candidate.get(name).copied(), production.get(name).copied());
+            let delta = c.zip(p).map(|(c, p)| c - p);
+            let regression = delta.is_some_and(|d| {
+                (HIGHER_IS_BETTER.contains(&name.as_str()) && d < 0.0)
+                    || (LOWER_IS_BETTER.contains(&name.as_str()) && d > 0.0)
+            });
+            MetricDelta {
+                metric: name.clone(),
+                candidate: c,
+                production: p,
+                delta,
+                regression,
+            }
+        })
+        .collect()
+}
+
+/// Per-metric difference between a version's snapshot and that of its model's
+/// current production version, sorted by metric name
+pub fn compare_candidate_to_production(version_id: &str) -> Result<Vec<MetricDelta>> {
+    let candidate = get_model_version(version_id)?
+        .ok_or_else(|| constraint_error("Model version not found"))?;
+    let production_id: Option<String> = {
+        let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+            rusqlite::Error::InvalidQuery
+        })?;
+        match conn.query_row(
+            "SELECT id FROM model_versions WHERE model_id = ?1 AND stage = 'production'",
+            [&candidate.model_id],
+            |row| row.get(0),
+        ) {
+            Ok(id) => Some(id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => None,
+            Err(e) => return Err(e),
+        }
+    };
+    let production_id = production_id
+        .ok_or_else(|| constraint_error("Model has no production version to compare against"))?;
+    if production_id == version_id {
+        return Err(constraint_error(format!(
+            "Version v{} is already in production",
+            candidate.version
+        )));
+    }
+    let production = get_model_version(&production_id)?
+        .ok_or_else(|| constraint_error("Model version not found"))?;
+
+    Ok(metric_deltas(
+        candidate.metrics_snapshot.as_deref(),
+        production.metrics_snapshot.as_deref(),
+    ))
+}
+
 pub fn delete_model_version(version_id: &str) -> Result<()> {
     // Get file path first
     let file_path: Option<String> = {
@@ -1249,7 +4261,7 @@ pub fn delete_model_version(version_id: &str) -> Result<()> {
             "SELECT file_path FROM model_versions WHERE id = ?1",
             [version_id],
             |row| row.get(0),
-        ).ok()
+        ).ok().map(|path: String| resolve_stored_path(&path))
     };
 
     // Delete from database
@@ -1260,16 +4272,113 @@ pub fn delete_model_version(version_id: &str) -> Result<()> {
 
     // Delete file
     if let Some(path) = file_path {
-        let _ = std::fs::remove_file(&path);
-        // Try to remove parent directory if empty
-        if let Some(parent) = std::path::Path::new(&path).parent() {
-            let _ = std::fs::remove_dir(parent);
-        }
+        remove_version_file(&path);
+    }
+
+    Ok(())
+}
+
+/// Remove a version's model file, and its directory once empty
+fn remove_version_file(path: &str) {
+    let _ = std::fs::remove_file(path);
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        let _ = std::fs::remove_dir(parent);
     }
+}
 
+/// Set how many versions `prune_old_versions` keeps for a model (None = all)
+pub fn set_model_max_versions(model_id: &str, max_versions: Option<u32>) -> Result<()> {
+    if max_versions == Some(0) {
+        return Err(constraint_error("max_versions must be at least 1"));
+    }
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let updated = conn.execute(
+        "UPDATE models SET max_versions = ?2 WHERE id = ?1",
+        rusqlite::params![model_id, max_versions],
+    )?;
+    if updated == 0 {
+        return Err(constraint_error("Model not found"));
+    }
     Ok(())
 }
 
+/// Bring a model down to its `max_versions` by pruning its oldest versions,
+/// returning the pruned ids oldest first. Production and staging versions
+/// (and `keep_version_id`, e.g. the one being served) are never pruned but
+/// count toward the limit. With `delete` the versions and their files are
+/// removed; otherwise they are archived, and already archived versions no
+/// longer count.
+pub fn prune_old_versions(
+    model_id: &str,
+    delete: bool,
+    keep_version_id: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
+
+    let max_versions: Option<i64> = match tx.query_row(
+        "SELECT max_versions FROM models WHERE id = ?1",
+        [model_id],
+        |row| row.get(0),
+    ) {
+        Ok(max) => max,
+        Err(rusqlite::Error::QueryReturnedNoRows) => return Err(constraint_error("Model not found")),
+        Err(e) => return Err(e),
+    };
+    let max_versions = match max_versions {
+        Some(max) => max.max(0) as usize,
+        None => return Ok(Vec::new()),
+    };
+
+    // Oldest first
+    let versions: Vec<(String, String, String)> = {
+        let mut stmt = tx.prepare(
+            "SELECT id, stage, file_path FROM model_versions
+             WHERE model_id = ?1 AND (?2 OR stage != 'archived')
+             ORDER BY version",
+        )?;
+        let rows = stmt
+            .query_map(rusqlite::params![model_id, delete], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+        rows
+    };
+
+    let excess = versions.len().saturating_sub(max_versions);
+    let pruned: Vec<&(String, String, String)> = versions
+        .iter()
+        .filter(|(id, stage, _)| {
+            stage != "production" && stage != "staging" && Some(id.as_str()) != keep_version_id
+        })
+        .take(excess)
+        .collect();
+
+    for (id, stage, _) in &pruned {
+        if delete {
+            tx.execute("DELETE FROM model_versions WHERE id = ?1", [id])?;
+        } else {
+            // Archiving isn't a promotion, so promoted_at is left alone
+            tx.execute("UPDATE model_versions SET stage = 'archived' WHERE id = ?1", [id])?;
+            record_stage_change(&tx, model_id, id, stage, "archived")?;
+        }
+    }
+    tx.commit()?;
+
+    // Files go only once the rows are gone for good
+    if delete {
+        for (_, _, file_path) in &pruned {
+            remove_version_file(&resolve_stored_path(file_path));
+        }
+    }
+
+    Ok(pruned.into_iter().map(|(id, _, _)| id.clone()).collect())
+}
+
 pub fn get_model_file_path(version_id: &str) -> Result<Option<String>> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
@@ -1277,10 +4386,10 @@ pub fn get_model_file_path(version_id: &str) -> Result<Option<String>> {
     let result = conn.query_row(
         "SELECT file_path FROM model_versions WHERE id = ?1",
         [version_id],
-        |row| row.get(0),
+        |row| row.get::<_, String>(0),
     );
     match result {
-        Ok(path) => Ok(Some(path)),
+        Ok(path) => Ok(Some(resolve_stored_path(&path))),
         Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
         Err(e) => Err(e),
     }
@@ -1291,7 +4400,7 @@ pub fn get_model_version(version_id: &str) -> Result<Option<ModelVersion>> {
         rusqlite::Error::InvalidQuery
     })?;
     let result = conn.query_row(
-        "SELECT id, model_id, version, run_id, file_path, file_size, format, stage, metrics_snapshot, feature_names, created_at, promoted_at, description, notes, onnx_path, coreml_path, n_features
+        "SELECT id, model_id, version, run_id, file_path, file_size, format, stage, metrics_snapshot, feature_names, created_at, promoted_at, description, notes, onnx_path, coreml_path, n_features, trusted, last_used_at
          FROM model_versions WHERE id = ?1",
         [version_id],
         map_model_version_row,
@@ -1306,8 +4415,100 @@ pub fn get_model_version(version_id: &str) -> Result<Option<ModelVersion>> {
     }
 }
 
+// Model provenance
+
+#[derive(Serialize, Deserialize)]
+pub struct ModelProvenance {
+    pub version: ModelVersion,
+    pub model: Option<ModelMetadata>,
+    pub run: Option<RunMetadata>,
+    pub metrics: Vec<Metric>,
+    pub environment: Option<RunEnvironment>,
+    pub pipeline: Option<PipelineMetadata>, // Latest pipeline saved under the run's pipeline_name
+    pub tuning_session: Option<TuningSession>,
+}
+
+pub fn get_run(run_id: &str) -> Result<Option<RunMetadata>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let result = conn.query_row(
+        &format!("{} WHERE r.id = ?1", RUN_METADATA_QUERY),
+        [run_id],
+        map_run_row,
+    );
+    match result {
+        Ok(mut run) => {
+            run.tags = Some(get_run_tags_internal(&conn, &run.id)?);
+            Ok(Some(run))
+        }
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Most recently updated pipeline with the given name
+pub fn find_pipeline_by_name(name: &str) -> Result<Option<PipelineMetadata>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let result = conn.query_row(
+        "SELECT id, name, created_at, updated_at FROM pipelines WHERE name = ?1
+         ORDER BY updated_at DESC LIMIT 1",
+        [name],
+        |row| {
+            Ok(PipelineMetadata {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                created_at: row.get(2)?,
+                updated_at: row.get(3)?,
+            })
+        },
+    );
+    match result {
+        Ok(pipeline) => Ok(Some(pipeline)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Everything known about where a version came from. Links that can't be
+/// resolved (no source run, deleted pipeline, no tuning) are left as None.
+pub fn get_model_version_provenance(version_id: &str) -> Result<Option<ModelProvenance>> {
+    let version = match get_model_version(version_id)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let model = get_model(&version.model_id)?;
+
+    let run = match version.run_id.as_deref() {
+        Some(run_id) => get_run(run_id)?,
+        None => None,
+    };
+    let (metrics, environment, pipeline, tuning_session) = match &run {
+        Some(run) => (
+            get_run_metrics(&run.id)?,
+            get_run_environment(&run.id)?,
+            find_pipeline_by_name(&run.pipeline_name)?,
+            get_tuning_session_by_run(&run.id)?,
+        ),
+        None => (vec![], None, None, None),
+    };
+
+    Ok(Some(ModelProvenance {
+        version,
+        model,
+        run,
+        metrics,
+        environment,
+        pipeline,
+        tuning_session,
+    }))
+}
+
 // Tuning Session CRUD operations
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_tuning_session(
     id: &str,
     run_id: &str,
@@ -1316,18 +4517,21 @@ pub fn create_tuning_session(
     n_trials: Option<i32>,
     cv_folds: i32,
     scoring_metric: &str,
+    seed: Option<i64>,
 ) -> Result<()> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
     conn.execute(
-        "INSERT INTO tuning_sessions (id, run_id, sampler, search_space, n_trials, cv_folds, scoring_metric, status, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 'running', datetime('now'))",
-        rusqlite::params![id, run_id, sampler, search_space, n_trials, cv_folds, scoring_metric],
+        "INSERT INTO tuning_sessions (id, run_id, sampler, search_space, n_trials, cv_folds, scoring_metric, seed, status, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, 'running', datetime('now'))",
+        rusqlite::params![id, run_id, sampler, search_space, n_trials, cv_folds, scoring_metric, seed],
     )?;
     Ok(())
 }
 
+/// Finish a session. A `None` best trial keeps the one already recorded
+/// (e.g. by `refresh_best_tuning_trial` while trials streamed in).
 pub fn update_tuning_session(
     id: &str,
     status: &str,
@@ -1338,7 +4542,7 @@ pub fn update_tuning_session(
     })?;
     let now = chrono::Utc::now().to_rfc3339();
     conn.execute(
-        "UPDATE tuning_sessions SET status = ?2, best_trial_id = ?3, completed_at = ?4 WHERE id = ?1",
+        "UPDATE tuning_sessions SET status = ?2, best_trial_id = COALESCE(?3, best_trial_id), completed_at = ?4 WHERE id = ?1",
         rusqlite::params![id, status, best_trial_id, now],
     )?;
     Ok(())
@@ -1349,7 +4553,7 @@ pub fn get_tuning_session(session_id: &str) -> Result<Option<TuningSession>> {
         rusqlite::Error::InvalidQuery
     })?;
     let result = conn.query_row(
-        "SELECT id, run_id, sampler, search_space, n_trials, cv_folds, scoring_metric, status, best_trial_id, created_at, completed_at
+        "SELECT id, run_id, sampler, search_space, n_trials, cv_folds, scoring_metric, status, best_trial_id, created_at, completed_at, seed
          FROM tuning_sessions WHERE id = ?1",
         [session_id],
         |row| {
@@ -1365,6 +4569,7 @@ pub fn get_tuning_session(session_id: &str) -> Result<Option<TuningSession>> {
                 best_trial_id: row.get(8)?,
                 created_at: row.get(9)?,
                 completed_at: row.get(10)?,
+                seed: row.get(11)?,
             })
         },
     );
@@ -1380,7 +4585,7 @@ pub fn get_tuning_session_by_run(run_id: &str) -> Result<Option<TuningSession>>
         rusqlite::Error::InvalidQuery
     })?;
     let result = conn.query_row(
-        "SELECT id, run_id, sampler, search_space, n_trials, cv_folds, scoring_metric, status, best_trial_id, created_at, completed_at
+        "SELECT id, run_id, sampler, search_space, n_trials, cv_folds, scoring_metric, status, best_trial_id, created_at, completed_at, seed
          FROM tuning_sessions WHERE run_id = ?1",
         [run_id],
         |row| {
@@ -1396,6 +4601,7 @@ pub fn get_tuning_session_by_run(run_id: &str) -> Result<Option<TuningSession>>
                 best_trial_id: row.get(8)?,
                 created_at: row.get(9)?,
                 completed_at: row.get(10)?,
+                seed: row.get(11)?,
             })
         },
     );
@@ -1408,6 +4614,9 @@ pub fn get_tuning_session_by_run(run_id: &str) -> Result<Option<TuningSession>>
 
 // Tuning Trial CRUD operations
 
+/// Insert a trial, or update it if the session already has this trial
+/// number (e.g. a retried save). Returns the id of the stored row, which is
+/// the existing id on update.
 pub fn create_tuning_trial(
     id: &str,
     session_id: &str,
@@ -1416,16 +4625,22 @@ pub fn create_tuning_trial(
     score: Option<f64>,
     duration_ms: Option<i64>,
     status: &str,
-) -> Result<()> {
+) -> Result<String> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
-    conn.execute(
+    conn.query_row(
         "INSERT INTO tuning_trials (id, session_id, trial_number, hyperparameters, score, duration_ms, status, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now'))",
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, datetime('now'))
+         ON CONFLICT(session_id, trial_number) DO UPDATE SET
+            hyperparameters = excluded.hyperparameters,
+            score = excluded.score,
+            duration_ms = excluded.duration_ms,
+            status = excluded.status
+         RETURNING id",
         rusqlite::params![id, session_id, trial_number, hyperparameters, score, duration_ms, status],
-    )?;
-    Ok(())
+        |row| row.get(0),
+    )
 }
 
 pub fn list_tuning_trials(session_id: &str) -> Result<Vec<TuningTrial>> {
@@ -1481,9 +4696,162 @@ pub fn get_best_trial(session_id: &str) -> Result<Option<TuningTrial>> {
     }
 }
 
+/// Point the session at its highest-scoring trial, returning that trial's id
+pub fn refresh_best_tuning_trial(session_id: &str) -> Result<Option<String>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let result = conn.query_row(
+        "UPDATE tuning_sessions SET best_trial_id = (
+            SELECT id FROM tuning_trials WHERE session_id = ?1 AND score IS NOT NULL ORDER BY score DESC LIMIT 1
+         ) WHERE id = ?1
+         RETURNING best_trial_id",
+        [session_id],
+        |row| row.get(0),
+    );
+    match result {
+        Ok(best_trial_id) => Ok(best_trial_id),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn csv_param_value(value: Option<&serde_json::Value>) -> String {
+    match value {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(s)) => csv_field(s),
+        Some(other) => csv_field(&other.to_string()),
+    }
+}
+
+/// Render trials as CSV, one row per trial with a `params_<name>` column for
+/// every hyperparameter any trial used. The first line is a `#` comment with
+/// the sampler and scoring metric (read with `pd.read_csv(path, comment="#")`).
+pub fn tuning_trials_csv(session: &TuningSession, trials: &[TuningTrial]) -> String {
+    let params: Vec<serde_json::Map<String, serde_json::Value>> = trials
+        .iter()
+        .map(|t| serde_json::from_str(&t.hyperparameters).unwrap_or_default())
+        .collect();
+    let mut param_names: Vec<&String> = Vec::new();
+    for name in params.iter().flat_map(|p| p.keys()) {
+        if !param_names.contains(&name) {
+            param_names.push(name);
+        }
+    }
+
+    let mut csv = format!(
+        "# sampler: {}, scoring_metric: {}\n",
+        session.sampler, session.scoring_metric
+    );
+    let mut header = vec!["trial_number".to_string(), "score".into(), "status".into(), "duration_ms".into()];
+    header.extend(param_names.iter().map(|name| csv_field(&format!("params_{}", name))));
+    csv.push_str(&header.join(","));
+    csv.push('\n');
+
+    for (trial, trial_params) in trials.iter().zip(&params) {
+        let mut row = vec![
+            trial.trial_number.to_string(),
+            trial.score.map(|v| v.to_string()).unwrap_or_default(),
+            csv_field(&trial.status),
+            trial.duration_ms.map(|v| v.to_string()).unwrap_or_default(),
+        ];
+        row.extend(param_names.iter().map(|name| csv_param_value(trial_params.get(*name))));
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Write a session's trials to `dest_path` as CSV and return the row count
+pub fn export_tuning_trials_csv(session_id: &str, dest_path: &str) -> Result<usize> {
+    let session = get_tuning_session(session_id)?
+        .ok_or_else(|| constraint_error(format!("Tuning session {} not found", session_id)))?;
+    let trials = list_tuning_trials(session_id)?;
+    std::fs::write(dest_path, tuning_trials_csv(&session, &trials))
+        .map_err(|e| io_error(format!("Failed to write {}", dest_path), e))?;
+    Ok(trials.len())
+}
+
+/// Merge tuned hyperparameters into the pipeline's trainer node, under
+/// `data.hyperparameters`. When the trainer has a tuning search space, params
+/// outside it are skipped rather than applied.
+pub fn merge_params_into_pipeline(
+    pipeline_data: &str,
+    params: &serde_json::Map<String, serde_json::Value>,
+) -> Result<String> {
+    let mut pipeline: serde_json::Value = serde_json::from_str(pipeline_data)
+        .map_err(|e| constraint_error(format!("Pipeline data is not valid JSON: {}", e)))?;
+    let nodes = pipeline
+        .get_mut("nodes")
+        .and_then(|n| n.as_array_mut())
+        .ok_or_else(|| constraint_error("Pipeline data must have a 'nodes' array"))?;
+
+    let is_trainer = |n: &serde_json::Value| n.get("type").and_then(|t| t.as_str()) == Some("trainer");
+    let is_tuning = |n: &serde_json::Value| {
+        n.pointer("/data/trainerMode").and_then(|m| m.as_str()) == Some("tune")
+    };
+    // Prefer the trainer that was tuning, otherwise the first trainer
+    let index = nodes
+        .iter()
+        .position(|n| is_trainer(n) && is_tuning(n))
+        .or_else(|| nodes.iter().position(is_trainer))
+        .ok_or_else(|| constraint_error("Pipeline has no trainer node to apply parameters to"))?;
+
+    let node = &mut nodes[index];
+    if !node.get("data").is_some_and(|d| d.is_object()) {
+        node["data"] = serde_json::json!({});
+    }
+    let search_space: Option<Vec<String>> = node
+        .pointer("/data/tuningConfig/searchSpace")
+        .and_then(|s| s.as_object())
+        .map(|s| s.keys().cloned().collect());
+
+    let data = &mut node["data"];
+    if !data.get("hyperparameters").is_some_and(|h| h.is_object()) {
+        data["hyperparameters"] = serde_json::json!({});
+    }
+    for (name, value) in params {
+        if search_space.as_ref().is_some_and(|keys| !keys.contains(name)) {
+            tracing::warn!("Skipping param '{}' not in the trainer's search space", name);
+            continue;
+        }
+        data["hyperparameters"][name] = value.clone();
+    }
+
+    serde_json::to_string(&pipeline).map_err(|e| constraint_error(e.to_string()))
+}
+
+/// Pipeline data with the session's best trial params merged in (not saved)
+pub fn apply_best_trial_to_pipeline(session_id: &str, pipeline_id: &str) -> Result<String> {
+    let trial = get_best_trial(session_id)?
+        .ok_or_else(|| constraint_error("Tuning session has no scored trials yet"))?;
+    let params: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&trial.hyperparameters)
+        .map_err(|e| constraint_error(format!("Best trial hyperparameters are not a JSON object: {}", e)))?;
+    let pipeline_data = load_pipeline(pipeline_id)?.ok_or_else(|| constraint_error("Pipeline not found"))?;
+    merge_params_into_pipeline(&pipeline_data, &params)
+}
+
 // Model Metadata & Tags operations (v9)
 
+fn attach_model_tags(conn: &Connection, versions: &mut [ModelVersion]) -> Result<()> {
+    let ids: Vec<&str> = versions.iter().map(|v| v.id.as_str()).collect();
+    let mut tags = load_tags_by_id(conn, "model_tags", "version_id", &ids)?;
+    for version in versions.iter_mut() {
+        version.tags = Some(tags.remove(&version.id).unwrap_or_default());
+    }
+    Ok(())
+}
+
 fn get_model_tags_internal(conn: &Connection, version_id: &str) -> Result<Vec<String>> {
+    note_tag_query();
     let mut stmt = conn.prepare("SELECT tag FROM model_tags WHERE version_id = ?1 ORDER BY tag")?;
     let rows = stmt.query_map([version_id], |row| row.get(0))?;
     rows.collect()
@@ -1504,11 +4872,30 @@ pub fn update_model_version_metadata(
     Ok(())
 }
 
+/// Check that `feature_names` (a JSON array) agrees with `n_features`. Either
+/// being unset is fine, since only one of them may be known.
+pub fn check_feature_schema(n_features: Option<i64>, feature_names: Option<&str>) -> Result<()> {
+    let Some(feature_names) = feature_names else {
+        return Ok(());
+    };
+    let names: Vec<serde_json::Value> = serde_json::from_str(feature_names)
+        .map_err(|_| constraint_error("feature_names must be a JSON array"))?;
+    match n_features {
+        Some(n) if n != names.len() as i64 => Err(constraint_error(format!(
+            "feature_names lists {} features but n_features is {}",
+            names.len(),
+            n
+        ))),
+        _ => Ok(()),
+    }
+}
+
 pub fn update_model_version_training_info(
     version_id: &str,
     n_features: Option<i64>,
     feature_names: Option<&str>,
 ) -> Result<()> {
+    check_feature_schema(n_features, feature_names)?;
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
@@ -1519,6 +4906,14 @@ pub fn update_model_version_training_info(
     Ok(())
 }
 
+/// Check a stored version's feature schema, e.g. before ONNX export
+pub fn validate_model_version_schema(version_id: &str) -> Result<()> {
+    let version = get_model_version(version_id)?
+        .ok_or_else(|| constraint_error(format!("Model version {} not found", version_id)))?;
+    check_feature_schema(version.n_features, version.feature_names.as_deref())
+        .map_err(|e| constraint_error(format!("Model version {}: {}", version_id, e)))
+}
+
 pub fn update_model_version_export_path(
     version_id: &str,
     onnx_path: Option<&str>,
@@ -1529,7 +4924,18 @@ pub fn update_model_version_export_path(
     })?;
     conn.execute(
         "UPDATE model_versions SET onnx_path = ?2, coreml_path = ?3 WHERE id = ?1",
-        rusqlite::params![version_id, onnx_path, coreml_path],
+        rusqlite::params![version_id, onnx_path.map(to_stored_path), coreml_path.map(to_stored_path)],
+    )?;
+    Ok(())
+}
+
+pub fn set_model_version_trusted(version_id: &str, trusted: bool) -> Result<()> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.execute(
+        "UPDATE model_versions SET trusted = ?2 WHERE id = ?1",
+        rusqlite::params![version_id, trusted],
     )?;
     Ok(())
 }
@@ -1588,7 +4994,7 @@ pub fn list_all_model_versions_filtered(filters: Option<ModelVersionFilters>) ->
     })?;
 
     // Base query with all columns
-    let base_query = "SELECT mv.id, mv.model_id, mv.version, mv.run_id, mv.file_path, mv.file_size, mv.format, mv.stage, mv.metrics_snapshot, mv.feature_names, mv.created_at, mv.promoted_at, mv.description, mv.notes, mv.onnx_path, mv.coreml_path, mv.n_features, m.name as model_name
+    let base_query = "SELECT mv.id, mv.model_id, mv.version, mv.run_id, mv.file_path, mv.file_size, mv.format, mv.stage, mv.metrics_snapshot, mv.feature_names, mv.created_at, mv.promoted_at, mv.description, mv.notes, mv.onnx_path, mv.coreml_path, mv.n_features, mv.trusted, mv.last_used_at, m.name as model_name
          FROM model_versions mv
          JOIN models m ON mv.model_id = m.id";
 
@@ -1637,7 +5043,7 @@ pub fn list_all_model_versions_filtered(filters: Option<ModelVersionFilters>) ->
             model_id: row.get(1)?,
             version: row.get(2)?,
             run_id: row.get(3)?,
-            file_path: row.get(4)?,
+            file_path: resolve_stored_path(&row.get::<_, String>(4)?),
             file_size: row.get(5)?,
             format: row.get(6)?,
             stage: row.get(7)?,
@@ -1647,17 +5053,16 @@ pub fn list_all_model_versions_filtered(filters: Option<ModelVersionFilters>) ->
             promoted_at: row.get(11)?,
             description: row.get(12)?,
             notes: row.get(13)?,
-            onnx_path: row.get(14)?,
-            coreml_path: row.get(15)?,
+            onnx_path: row.get::<_, Option<String>>(14)?.as_deref().map(resolve_stored_path),
+            coreml_path: row.get::<_, Option<String>>(15)?.as_deref().map(resolve_stored_path),
             n_features: row.get(16)?,
             tags: None,
+            trusted: row.get(17)?,
+            last_used_at: row.get(18)?,
         })
     })?.collect::<Result<Vec<_>>>()?;
 
-    // Fetch tags for each version
-    for version in &mut versions {
-        version.tags = Some(get_model_tags_internal(&conn, &version.id)?);
-    }
+    attach_model_tags(&conn, &mut versions)?;
 
     Ok(versions)
 }
@@ -1761,6 +5166,33 @@ pub fn get_comparable_versions(model_id: &str) -> Result<Vec<ModelVersion>> {
 
 // Chunk Embedding CRUD operations (v8)
 
+// Bumped whenever a pipeline's chunk embeddings change, so search results
+// cached against an older index can be recognized as stale
+static CHUNK_INDEX_GENERATIONS: std::sync::OnceLock<Mutex<std::collections::HashMap<String, u64>>> =
+    std::sync::OnceLock::new();
+
+fn bump_chunk_index_generation(pipeline_id: &str) {
+    let generations = CHUNK_INDEX_GENERATIONS.get_or_init(|| Mutex::new(std::collections::HashMap::new()));
+    if let Ok(mut generations) = generations.lock() {
+        *generations.entry(pipeline_id.to_string()).or_insert(0) += 1;
+    }
+}
+
+/// Current generation of the pipeline's chunk index; changes on every write
+pub fn chunk_index_generation(pipeline_id: &str) -> u64 {
+    CHUNK_INDEX_GENERATIONS
+        .get()
+        .and_then(|generations| generations.lock().ok()?.get(pipeline_id).copied())
+        .unwrap_or(0)
+}
+
+// Pipelines a node has indexed chunks in
+fn chunk_pipelines_for_node(conn: &Connection, node_id: &str) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT DISTINCT pipeline_id FROM chunk_embeddings WHERE node_id = ?1")?;
+    let pipelines = stmt.query_map([node_id], |row| row.get(0))?.collect();
+    pipelines
+}
+
 pub fn upsert_chunk_embedding(
     node_id: &str,
     pipeline_id: &str,
@@ -1796,15 +5228,19 @@ pub fn upsert_chunk_embedding(
             start_line, end_line
         ],
     )?;
+    bump_chunk_index_generation(pipeline_id);
     Ok(())
 }
 
+/// Content hash of an indexed chunk. A chunk whose blob doesn't match its
+/// dimension reports no hash, so the next index re-embeds it.
 pub fn get_chunk_embedding_hash(node_id: &str, chunk_id: &str) -> Result<Option<String>> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
     let result = conn.query_row(
-        "SELECT content_hash FROM chunk_embeddings WHERE node_id = ?1 AND chunk_id = ?2",
+        "SELECT content_hash FROM chunk_embeddings
+         WHERE node_id = ?1 AND chunk_id = ?2 AND LENGTH(embedding) = embedding_dim * 4",
         [node_id, chunk_id],
         |row| row.get(0),
     );
@@ -1815,6 +5251,8 @@ pub fn get_chunk_embedding_hash(node_id: &str, chunk_id: &str) -> Result<Option<
     }
 }
 
+/// All chunk embeddings for a pipeline. Rows whose blob doesn't match their
+/// dimension (e.g. a write cut short by a crash) are logged and omitted.
 pub fn list_chunk_embeddings_for_pipeline(pipeline_id: &str) -> Result<Vec<ChunkEmbedding>> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
@@ -1840,13 +5278,26 @@ pub fn list_chunk_embeddings_for_pipeline(pipeline_id: &str) -> Result<Vec<Chunk
             created_at: row.get(12)?,
         })
     })?;
-    rows.collect()
+    let mut chunks = Vec::new();
+    for chunk in rows {
+        let chunk = chunk?;
+        if chunk.embedding.len() != chunk.embedding_dim.max(0) as usize * 4 {
+            tracing::warn!(
+                "Omitting corrupt embedding for {}/{}: {} bytes for {} dimensions",
+                chunk.node_id, chunk.chunk_id, chunk.embedding.len(), chunk.embedding_dim
+            );
+            continue;
+        }
+        chunks.push(chunk);
+    }
+    Ok(chunks)
 }
 
 pub fn delete_orphan_chunks(node_id: &str, keep_chunk_ids: Vec<String>) -> Result<usize> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
+    let pipelines = chunk_pipelines_for_node(&conn, node_id)?;
 
     if keep_chunk_ids.is_empty() {
         // Delete all chunks for this node
@@ -1854,6 +5305,9 @@ pub fn delete_orphan_chunks(node_id: &str, keep_chunk_ids: Vec<String>) -> Resul
             "DELETE FROM chunk_embeddings WHERE node_id = ?1",
             [node_id],
         )?;
+        if deleted > 0 {
+            pipelines.iter().for_each(|p| bump_chunk_index_generation(p));
+        }
         return Ok(deleted);
     }
 
@@ -1881,6 +5335,9 @@ pub fn delete_orphan_chunks(node_id: &str, keep_chunk_ids: Vec<String>) -> Resul
     // Execute with dynamic params
     let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
     let deleted = conn.execute(&query, param_refs.as_slice())?;
+    if deleted > 0 {
+        pipelines.iter().for_each(|p| bump_chunk_index_generation(p));
+    }
 
     Ok(deleted)
 }
@@ -1889,10 +5346,12 @@ pub fn delete_chunks_for_node(node_id: &str) -> Result<usize> {
     let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
         rusqlite::Error::InvalidQuery
     })?;
+    let pipelines = chunk_pipelines_for_node(&conn, node_id)?;
     let deleted = conn.execute(
         "DELETE FROM chunk_embeddings WHERE node_id = ?1",
         [node_id],
     )?;
+    pipelines.iter().for_each(|p| bump_chunk_index_generation(p));
     Ok(deleted)
 }
 
@@ -1904,69 +5363,611 @@ pub fn delete_chunks_for_pipeline(pipeline_id: &str) -> Result<usize> {
         "DELETE FROM chunk_embeddings WHERE pipeline_id = ?1",
         [pipeline_id],
     )?;
+    // An empty index is free to be pinned to any model again
+    conn.execute(
+        "DELETE FROM embedding_config WHERE pipeline_id = ?1",
+        [pipeline_id],
+    )?;
+    bump_chunk_index_generation(pipeline_id);
     Ok(deleted)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::sync::Once;
-
-    static INIT: Once = Once::new();
+/// Delete chunks of nodes in the pipeline that aren't in `keep_node_ids`
+pub fn delete_chunks_for_missing_nodes(pipeline_id: &str, keep_node_ids: &[String]) -> Result<usize> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
 
-    fn setup_test_db() {
-        INIT.call_once(|| {
-            // Use the target directory for test db
-            let test_dir = std::path::PathBuf::from("target/test-db");
-            std::fs::create_dir_all(&test_dir).unwrap();
-            init_db(&test_dir).unwrap();
-        });
+    if keep_node_ids.is_empty() {
+        let deleted = conn.execute(
+            "DELETE FROM chunk_embeddings WHERE pipeline_id = ?1",
+            [pipeline_id],
+        )?;
+        if deleted > 0 {
+            bump_chunk_index_generation(pipeline_id);
+        }
+        return Ok(deleted);
     }
 
-    #[test]
-    fn test_settings_crud() {
-        setup_test_db();
+    let placeholders: String = keep_node_ids
+        .iter()
+        .enumerate()
+        .map(|(i, _)| format!("?{}", i + 2))
+        .collect::<Vec<_>>()
+        .join(",");
+    let query = format!(
+        "DELETE FROM chunk_embeddings WHERE pipeline_id = ?1 AND node_id NOT IN ({})",
+        placeholders
+    );
+    let params: Vec<&dyn rusqlite::ToSql> = std::iter::once(&pipeline_id as &dyn rusqlite::ToSql)
+        .chain(keep_node_ids.iter().map(|id| id as &dyn rusqlite::ToSql))
+        .collect();
+    let deleted = conn.execute(&query, params.as_slice())?;
+    if deleted > 0 {
+        bump_chunk_index_generation(pipeline_id);
+    }
+    Ok(deleted)
+}
 
-        // Set a setting
-        set_setting("test_key", "test_value").unwrap();
+pub fn get_embedding_config(pipeline_id: &str) -> Result<Option<EmbeddingConfig>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    get_embedding_config_internal(&conn, pipeline_id)
+}
 
-        // Get it back
-        let value = get_setting("test_key");
-        assert_eq!(value, Some("test_value".to_string()));
+fn get_embedding_config_internal(conn: &Connection, pipeline_id: &str) -> Result<Option<EmbeddingConfig>> {
+    let result = conn.query_row(
+        "SELECT pipeline_id, model, dim, preprocess, pinned_at FROM embedding_config WHERE pipeline_id = ?1",
+        [pipeline_id],
+        |row| {
+            Ok(EmbeddingConfig {
+                pipeline_id: row.get(0)?,
+                model: row.get(1)?,
+                dim: row.get(2)?,
+                preprocess: row.get(3)?,
+                pinned_at: row.get(4)?,
+            })
+        },
+    );
+    match result {
+        Ok(config) => Ok(Some(config)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
 
-        // Update it
-        set_setting("test_key", "updated_value").unwrap();
-        let value = get_setting("test_key");
-        assert_eq!(value, Some("updated_value".to_string()));
+/// Pin the pipeline's embedding model on first index. Returns the existing pin
+/// when it conflicts with `model`/`dim`/`preprocess` and `force_reindex` is not
+/// set; with `force_reindex` the pipeline's chunks are cleared and the pin replaced.
+pub fn pin_embedding_model(
+    pipeline_id: &str,
+    model: &str,
+    dim: i32,
+    preprocess: &str,
+    force_reindex: bool,
+) -> Result<Option<EmbeddingConfig>> {
+    let mut conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let tx = conn.transaction()?;
 
-        // Non-existent key
-        let missing = get_setting("nonexistent");
-        assert_eq!(missing, None);
+    if let Some(existing) = get_embedding_config_internal(&tx, pipeline_id)? {
+        if existing.model == model && existing.dim == dim && existing.preprocess == preprocess {
+            return Ok(None);
+        }
+        if !force_reindex {
+            return Ok(Some(existing));
+        }
+        tx.execute(
+            "DELETE FROM chunk_embeddings WHERE pipeline_id = ?1",
+            [pipeline_id],
+        )?;
+        bump_chunk_index_generation(pipeline_id);
     }
 
-    #[test]
-    fn test_pipeline_save_and_load() {
-        setup_test_db();
+    tx.execute(
+        "INSERT OR REPLACE INTO embedding_config (pipeline_id, model, dim, preprocess, pinned_at)
+         VALUES (?1, ?2, ?3, ?4, datetime('now'))",
+        rusqlite::params![pipeline_id, model, dim, preprocess],
+    )?;
+    tx.commit()?;
+    Ok(None)
+}
 
-        let id = "test-pipeline-1";
-        let name = "Test Pipeline";
-        let data = r#"{"nodes":[],"edges":[]}"#;
+pub fn get_rag_status(pipeline_id: &str) -> Result<RagStatus> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let (chunk_count, node_count) = conn.query_row(
+        "SELECT COUNT(*), COUNT(DISTINCT node_id) FROM chunk_embeddings WHERE pipeline_id = ?1",
+        [pipeline_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    // Model and dimension are read from any row; a healthy index has one of each
+    let model_info = conn.query_row(
+        "SELECT embedding_model, embedding_dim FROM chunk_embeddings WHERE pipeline_id = ?1 LIMIT 1",
+        [pipeline_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    );
+    let (embedding_model, embedding_dim) = match model_info {
+        Ok((model, dim)) => (Some(model), Some(dim)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => (None, None),
+        Err(e) => return Err(e),
+    };
+    Ok(RagStatus {
+        pipeline_id: pipeline_id.to_string(),
+        chunk_count,
+        node_count,
+        embedding_model,
+        embedding_dim,
+    })
+}
 
-        // Save pipeline
-        save_pipeline(id, name, data).unwrap();
+pub fn get_global_rag_status() -> Result<GlobalRagStatus> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let (chunk_count, node_count, embedding_bytes) = conn.query_row(
+        "SELECT COUNT(*), COUNT(DISTINCT node_id), COALESCE(SUM(LENGTH(embedding)), 0) FROM chunk_embeddings",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
 
-        // Load it back
-        let loaded = load_pipeline(id).unwrap();
-        assert_eq!(loaded, Some(data.to_string()));
-    }
+    let mut stmt = conn.prepare(
+        "SELECT embedding_model, COUNT(*), COUNT(DISTINCT pipeline_id), COALESCE(SUM(LENGTH(embedding)), 0)
+         FROM chunk_embeddings
+         GROUP BY embedding_model
+         ORDER BY COUNT(*) DESC, embedding_model",
+    )?;
+    let models = stmt
+        .query_map([], |row| {
+            Ok(EmbeddingModelUsage {
+                embedding_model: row.get(0)?,
+                chunk_count: row.get(1)?,
+                pipeline_count: row.get(2)?,
+                embedding_bytes: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT pipeline_id FROM chunk_embeddings GROUP BY pipeline_id ORDER BY pipeline_id",
+    )?;
+    let pipeline_ids = stmt.query_map([], |row| row.get(0))?.collect::<Result<Vec<_>>>()?;
+
+    Ok(GlobalRagStatus {
+        chunk_count,
+        node_count,
+        embedding_bytes,
+        models,
+        pipeline_ids,
+    })
+}
+
+/// True if any chunk in the pipeline was embedded with a different model
+pub fn rag_model_mismatch(pipeline_id: &str, model: &str) -> Result<bool> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM chunk_embeddings WHERE pipeline_id = ?1 AND embedding_model != ?2)",
+        [pipeline_id, model],
+        |row| row.get(0),
+    )
+}
+
+/// True if the pipeline's index can't be searched with `model` as-is: either
+/// another model produced some of it, or its vectors don't share one dimension
+/// (the model was updated under the same name)
+pub fn rag_needs_reindex(pipeline_id: &str, model: &str) -> Result<bool> {
+    if rag_model_mismatch(pipeline_id, model)? {
+        return Ok(true);
+    }
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let dim_count: i64 = conn.query_row(
+        "SELECT COUNT(DISTINCT embedding_dim) FROM chunk_embeddings WHERE pipeline_id = ?1",
+        [pipeline_id],
+        |row| row.get(0),
+    )?;
+    Ok(dim_count > 1)
+}
+
+/// Chunks sharing a content hash (under the same embedding model) carry the
+/// same vector, so every copy after the first is reclaimable.
+pub fn estimate_dedup_savings(pipeline_id: &str) -> Result<DedupEstimate> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let (chunk_count, distinct_hashes, embedding_bytes) = conn.query_row(
+        "SELECT COUNT(*), COUNT(DISTINCT content_hash), COALESCE(SUM(LENGTH(embedding)), 0)
+         FROM chunk_embeddings WHERE pipeline_id = ?1",
+        [pipeline_id],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let reclaimable_bytes = conn.query_row(
+        "SELECT COALESCE(SUM(redundant), 0) FROM (
+             SELECT SUM(LENGTH(embedding)) - MAX(LENGTH(embedding)) AS redundant
+             FROM chunk_embeddings
+             WHERE pipeline_id = ?1
+             GROUP BY content_hash, embedding_model
+         )",
+        [pipeline_id],
+        |row| row.get(0),
+    )?;
+    Ok(DedupEstimate {
+        pipeline_id: pipeline_id.to_string(),
+        chunk_count,
+        distinct_hashes,
+        embedding_bytes,
+        reclaimable_bytes,
+    })
+}
+
+// Database maintenance
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckpointMode {
+    Passive,
+    Full,
+    Truncate,
+}
+
+impl CheckpointMode {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            CheckpointMode::Passive => "PASSIVE",
+            CheckpointMode::Full => "FULL",
+            CheckpointMode::Truncate => "TRUNCATE",
+        }
+    }
+}
+
+/// Row returned by `PRAGMA wal_checkpoint`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CheckpointResult {
+    pub busy: bool,
+    pub log_frames: i64,
+    pub checkpointed_frames: i64,
+}
+
+pub fn checkpoint_database(mode: CheckpointMode) -> Result<CheckpointResult> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    checkpoint(&conn, mode)
+}
+
+fn checkpoint(conn: &Connection, mode: CheckpointMode) -> Result<CheckpointResult> {
+    // Outside WAL mode SQLite reports -1 frames rather than an error, which
+    // would read as a successful checkpoint
+    let journal_mode: String = conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?;
+    if !journal_mode.eq_ignore_ascii_case("wal") {
+        return Err(constraint_error(format!(
+            "Database is not in WAL mode (journal_mode is {}), so there is nothing to checkpoint",
+            journal_mode
+        )));
+    }
+    conn.query_row(
+        &format!("PRAGMA wal_checkpoint({})", mode.as_sql()),
+        [],
+        |row| {
+            Ok(CheckpointResult {
+                busy: row.get::<_, i64>(0)? != 0,
+                log_frames: row.get(1)?,
+                checkpointed_frames: row.get(2)?,
+            })
+        },
+    )
+}
+
+pub fn optimize_database() -> Result<()> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.execute_batch("PRAGMA optimize")
+}
+
+// Completion event operations (v9)
+
+/// Record the outcome of one shown completion suggestion
+pub fn log_completion_event(
+    pipeline_id: &str,
+    node_id: Option<&str>,
+    model: &str,
+    was_rag: bool,
+    accepted: bool,
+) -> Result<()> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.execute(
+        "INSERT INTO completion_events (pipeline_id, node_id, model, was_rag, accepted, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'))",
+        rusqlite::params![pipeline_id, node_id, model, was_rag, accepted],
+    )?;
+    Ok(())
+}
+
+pub fn get_completion_acceptance_stats(pipeline_id: &str) -> Result<CompletionAcceptanceStats> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let mut stmt = conn.prepare(
+        "SELECT was_rag, COUNT(*), SUM(accepted) FROM completion_events
+         WHERE pipeline_id = ?1 GROUP BY was_rag",
+    )?;
+    let rows = stmt
+        .query_map([pipeline_id], |row| {
+            Ok((row.get::<_, bool>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+        })?
+        .collect::<Result<Vec<_>>>()?;
+
+    let rate = |shown: i64, accepted: i64| AcceptanceRate {
+        shown,
+        accepted,
+        rate: (shown > 0).then(|| accepted as f64 / shown as f64),
+    };
+    let (mut rag, mut non_rag) = ((0, 0), (0, 0));
+    for (was_rag, shown, accepted) in rows {
+        if was_rag {
+            rag = (shown, accepted);
+        } else {
+            non_rag = (shown, accepted);
+        }
+    }
+    Ok(CompletionAcceptanceStats {
+        overall: rate(rag.0 + non_rag.0, rag.1 + non_rag.1),
+        rag: rate(rag.0, rag.1),
+        non_rag: rate(non_rag.0, non_rag.1),
+    })
+}
+
+// Inference audit operations (v14)
+
+pub fn log_inference_audit(
+    version_id: &str,
+    request_id: &str,
+    input_json: &str,
+    output_json: &str,
+    latency_ms: i64,
+) -> Result<()> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    conn.execute(
+        "INSERT INTO inference_audit (version_id, request_id, input_json, output_json, latency_ms, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'))",
+        rusqlite::params![version_id, request_id, input_json, output_json, latency_ms],
+    )?;
+    Ok(())
+}
+
+/// Audit records for a version, newest first
+pub fn get_inference_audit(version_id: &str, limit: Option<i64>) -> Result<Vec<InferenceAuditEntry>> {
+    let conn = DB.get().ok_or(rusqlite::Error::InvalidQuery)?.lock().map_err(|_| {
+        rusqlite::Error::InvalidQuery
+    })?;
+    let mut stmt = conn.prepare(
+        "SELECT id, version_id, request_id, input_json, output_json, latency_ms, created_at
+         FROM inference_audit WHERE version_id = ?1
+         ORDER BY id DESC LIMIT ?2",
+    )?;
+    let entries = stmt
+        .query_map(rusqlite::params![version_id, limit.unwrap_or(-1)], |row| {
+            Ok(InferenceAuditEntry {
+                id: row.get(0)?,
+                version_id: row.get(1)?,
+                request_id: row.get(2)?,
+                input_json: row.get(3)?,
+                output_json: row.get(4)?,
+                latency_ms: row.get(5)?,
+                created_at: row.get(6)?,
+            })
+        })?
+        .collect::<Result<Vec<_>>>()?;
+    Ok(entries)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::sync::Once;
+
+    static INIT: Once = Once::new();
+
+    pub(crate) fn setup_test_db() {
+        INIT.call_once(|| {
+            // Use the target directory for test db
+            let test_dir = std::path::PathBuf::from("target/test-db");
+            std::fs::create_dir_all(&test_dir).unwrap();
+            init_db(&test_dir).unwrap();
+        });
+    }
+
+    #[test]
+    fn test_backup_redacts_text_by_value_type() {
+        setup_test_db();
+        let run_id = format!("redact-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Redact", "{}", None).unwrap();
+        // `value` is declared REAL but still stores this as text
+        DB.get().unwrap().lock().unwrap().execute(
+            "INSERT INTO run_metrics (run_id, name, value, kind) VALUES (?1, 'output', '/home/alice/out', 'scalar')",
+            [&run_id],
+        )
+        .unwrap();
+
+        let dest = std::env::temp_dir().join(format!("{}.db", run_id));
+        backup_database_redacted(&dest, &[("/home/alice".to_string(), "~".to_string())]).unwrap();
+        let copy = Connection::open(&dest).unwrap();
+        let output: String = copy
+            .query_row("SELECT value FROM run_metrics WHERE run_id = ?1 AND name = 'output'", [&run_id], |row| row.get(0))
+            .unwrap();
+        assert_eq!(output, "~/out");
+        drop(copy);
+        let _ = std::fs::remove_file(&dest);
+        delete_run(&run_id).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_upgrades_and_refuses_newer() {
+        let user_version = |conn: &Connection| -> i32 {
+            conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap()
+        };
+
+        // Fresh database goes all the way up, and migrating again is a no-op
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        assert_eq!(user_version(&conn), DB_VERSION);
+        migrate(&conn).unwrap();
+        assert_eq!(user_version(&conn), DB_VERSION);
+
+        // Older (v19) database is upgraded
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn.execute("DROP TRIGGER model_versions_stage_insert", []).unwrap();
+        conn.execute("ALTER TABLE tuning_sessions DROP COLUMN seed", []).unwrap();
+        conn.execute("ALTER TABLE runs DROP COLUMN artifacts_compressed", []).unwrap();
+        conn.execute("DROP INDEX idx_model_versions_sha256", []).unwrap();
+        conn.execute("ALTER TABLE model_versions DROP COLUMN sha256", []).unwrap();
+        conn.execute("ALTER TABLE models DROP COLUMN max_versions", []).unwrap();
+        // ...with a stage v20 no longer allows
+        conn.execute_batch(
+            "INSERT INTO models (id, name) VALUES ('m', 'Legacy');
+             INSERT INTO model_versions (id, model_id, version, file_path, format, stage, promoted_at)
+             VALUES ('v', 'm', 1, 'model.joblib', 'joblib', 'prod', '2024-01-01T00:00:00Z');",
+        )
+        .unwrap();
+        conn.pragma_update(None, "user_version", 19).unwrap();
+        migrate(&conn).unwrap();
+        assert_eq!(user_version(&conn), DB_VERSION);
+        let stage: String = conn.query_row("SELECT stage FROM model_versions WHERE id = 'v'", [], |row| row.get(0)).unwrap();
+        assert_eq!(stage, "none");
+        // The reset is recorded like any other stage change
+        let history: (String, String, String) = conn
+            .query_row(
+                "SELECT version_id, from_stage, to_stage FROM model_stage_history WHERE model_id = 'm'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(history, ("v".to_string(), "prod".to_string(), "none".to_string()));
+        let trigger: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'trigger' AND name = 'model_versions_stage_insert'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(trigger, 1);
+        let seed_column: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('tuning_sessions') WHERE name = 'seed'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(seed_column, 1);
+
+        // Database from a newer build is left alone
+        let conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", DB_VERSION + 1).unwrap();
+        let err = migrate(&conn).unwrap_err().to_string();
+        assert!(err.contains("newer than this app supports"), "{}", err);
+        assert_eq!(user_version(&conn), DB_VERSION + 1);
+        let tables: i64 = conn
+            .query_row("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(tables, 0);
+
+        setup_test_db();
+        assert_eq!(get_db_version().unwrap(), DB_VERSION);
+    }
+
+    #[test]
+    fn test_checkpoint_after_many_writes() {
+        setup_test_db();
+        let prefix = format!("checkpoint-{}", uuid::Uuid::new_v4());
+        for i in 0..200 {
+            set_setting(&format!("{}-{}", prefix, i), &"x".repeat(256)).unwrap();
+        }
+
+        // The app database isn't in WAL mode, so checkpointing it is an
+        // error rather than a no-op; frame counts are checked on a private
+        // database that is
+        let err = checkpoint_database(CheckpointMode::Truncate).unwrap_err().to_string();
+        assert!(err.contains("not in WAL mode"), "{}", err);
+        optimize_database().unwrap();
+        assert_eq!(get_setting(&format!("{}-199", prefix)), Some("x".repeat(256)));
+
+        let path = std::env::temp_dir().join(format!("{}.db", prefix));
+        let conn = Connection::open(&path).unwrap();
+        let mode: String = conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get(0)).unwrap();
+        assert_eq!(mode, "wal");
+        conn.execute_batch("CREATE TABLE t (v TEXT)").unwrap();
+        for _ in 0..50 {
+            conn.execute("INSERT INTO t (v) VALUES (?1)", ["x".repeat(256)]).unwrap();
+        }
+        let result = checkpoint(&conn, CheckpointMode::Passive).unwrap();
+        assert!(!result.busy);
+        assert!(result.log_frames > 0, "{:?}", result);
+        assert_eq!(result.checkpointed_frames, result.log_frames);
+        // Truncating empties the log
+        let result = checkpoint(&conn, CheckpointMode::Truncate).unwrap();
+        assert_eq!((result.log_frames, result.checkpointed_frames), (0, 0));
+        drop(conn);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+    }
+
+    #[test]
+    fn test_settings_crud() {
+        setup_test_db();
+
+        // Set a setting
+        set_setting("test_key", "test_value").unwrap();
+
+        // Get it back
+        let value = get_setting("test_key");
+        assert_eq!(value, Some("test_value".to_string()));
+
+        // Update it
+        set_setting("test_key", "updated_value").unwrap();
+        let value = get_setting("test_key");
+        assert_eq!(value, Some("updated_value".to_string()));
+
+        // Non-existent key
+        let missing = get_setting("nonexistent");
+        assert_eq!(missing, None);
+    }
+
+    #[test]
+    fn test_pipeline_save_and_load() {
+        setup_test_db();
+
+        let id = "test-pipeline-1";
+        let name = "Test Pipeline";
+        let data = r#"{"nodes":[],"edges":[]}"#;
+
+        // Save pipeline
+        save_pipeline(id, name, data).unwrap();
+
+        // Load it back
+        let loaded = load_pipeline(id).unwrap();
+        assert_eq!(loaded, Some(data.to_string()));
+    }
 
     #[test]
     fn test_pipeline_list() {
         setup_test_db();
 
         // Save a couple pipelines
-        save_pipeline("list-test-1", "Pipeline A", "{}").unwrap();
-        save_pipeline("list-test-2", "Pipeline B", "{}").unwrap();
+        save_pipeline("list-test-1", "Pipeline A", r#"{"nodes":[],"edges":[]}"#).unwrap();
+        save_pipeline("list-test-2", "Pipeline B", r#"{"nodes":[],"edges":[]}"#).unwrap();
 
         // List them
         let pipelines = list_pipelines().unwrap();
@@ -1984,14 +5985,14 @@ mod tests {
         let id = "update-test";
 
         // Create
-        save_pipeline(id, "Original Name", r#"{"v":1}"#).unwrap();
+        save_pipeline(id, "Original Name", r#"{"nodes":[],"edges":[],"v":1}"#).unwrap();
 
         // Update
-        save_pipeline(id, "Updated Name", r#"{"v":2}"#).unwrap();
+        save_pipeline(id, "Updated Name", r#"{"nodes":[],"edges":[],"v":2}"#).unwrap();
 
         // Verify update
         let loaded = load_pipeline(id).unwrap();
-        assert_eq!(loaded, Some(r#"{"v":2}"#.to_string()));
+        assert_eq!(loaded, Some(r#"{"nodes":[],"edges":[],"v":2}"#.to_string()));
 
         // Verify only one entry
         let pipelines = list_pipelines().unwrap();
@@ -2004,7 +6005,7 @@ mod tests {
         setup_test_db();
 
         let id = "delete-test";
-        save_pipeline(id, "To Delete", "{}").unwrap();
+        save_pipeline(id, "To Delete", r#"{"nodes":[],"edges":[]}"#).unwrap();
 
         // Verify exists
         let loaded = load_pipeline(id).unwrap();
@@ -2025,4 +6026,2108 @@ mod tests {
         let loaded = load_pipeline("does-not-exist").unwrap();
         assert!(loaded.is_none());
     }
+
+    /// Write a throwaway model file and return its path
+    fn create_test_model_file(contents: &[u8]) -> String {
+        let dir = std::path::PathBuf::from("target/test-db/fixtures");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.joblib", uuid::Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    /// Create a model with a unique name and return its id
+    pub(crate) fn create_test_model(prefix: &str) -> String {
+        let model_id = uuid::Uuid::new_v4().to_string();
+        create_model(&model_id, &format!("{}-{}", prefix, model_id), None).unwrap();
+        model_id
+    }
+
+    /// Register a version of `model_id` with the given metrics snapshot and return its id
+    pub(crate) fn register_test_version(model_id: &str, metrics_snapshot: Option<&str>) -> String {
+        let version_id = uuid::Uuid::new_v4().to_string();
+        let source = create_test_model_file(b"model");
+        register_model_version(&version_id, model_id, None, &source, "joblib", metrics_snapshot, None, false)
+            .unwrap();
+        version_id
+    }
+
+    #[test]
+    fn test_promotion_rule_comparisons() {
+        let rule = PromotionRule {
+            metric: "accuracy".to_string(),
+            comparison: ">=".to_string(),
+            threshold: 0.9,
+        };
+        assert!(rule.validate().is_ok());
+        assert!(rule.check(Some(r#"{"accuracy": 0.95}"#)).is_ok());
+        assert!(rule.check(Some(r#"{"accuracy": 0.9}"#)).is_ok());
+        assert!(rule.check(Some(r#"{"accuracy": 0.85}"#)).is_err());
+        assert!(rule.check(Some(r#"{"f1": 0.99}"#)).is_err());
+        assert!(rule.check(None).is_err());
+
+        let lower_is_better = PromotionRule {
+            metric: "rmse".to_string(),
+            comparison: "<".to_string(),
+            threshold: 1.0,
+        };
+        assert!(lower_is_better.check(Some(r#"{"rmse": 0.5}"#)).is_ok());
+        assert!(lower_is_better.check(Some(r#"{"rmse": 1.0}"#)).is_err());
+
+        let invalid = PromotionRule {
+            metric: "accuracy".to_string(),
+            comparison: "=>".to_string(),
+            threshold: 0.9,
+        };
+        assert!(invalid.validate().is_err());
+        // ...and never saved, so it can't change the shared rule
+        assert!(set_promotion_rule(Some(&invalid)).is_err());
+    }
+
+    // Held by tests that set the global promotion rule
+    static PROMOTION_RULE_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_promotion_rule_gate() {
+        setup_test_db();
+        let _serial = PROMOTION_RULE_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+        let model_id = create_test_model("gate");
+        let good = register_test_version(&model_id, Some(r#"{"accuracy": 0.93}"#));
+        let bad = register_test_version(&model_id, Some(r#"{"accuracy": 0.71}"#));
+
+        let rule = PromotionRule {
+            metric: "accuracy".to_string(),
+            comparison: ">=".to_string(),
+            threshold: 0.9,
+        };
+        set_promotion_rule(Some(&rule)).unwrap();
+        assert_eq!(get_promotion_rule(), Some(rule));
+
+        // Passing version can be promoted
+        assert!(check_promotion_rule(&good).is_ok());
+        promote_model(&good, "production").unwrap();
+        assert_eq!(get_model_version(&good).unwrap().unwrap().stage, "production");
+
+        // Failing version is refused with a descriptive error
+        let err = check_promotion_rule(&bad).unwrap_err().to_string();
+        assert!(err.contains("accuracy"));
+        assert!(err.contains("0.71"));
+
+        // Without a rule, anything goes
+        set_promotion_rule(None).unwrap();
+        assert_eq!(get_promotion_rule(), None);
+        assert!(check_promotion_rule(&bad).is_ok());
+    }
+
+    fn insert_test_chunk(pipeline_id: &str, node_id: &str, chunk_id: &str, model: &str, dim: i32) {
+        let embedding = vec![0u8; dim as usize * 4];
+        upsert_chunk_embedding(
+            node_id, pipeline_id, chunk_id, "hash", &embedding, model, dim, None, None, None, None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_estimate_dedup_savings() {
+        setup_test_db();
+        let pipeline_id = format!("dedup-{}", uuid::Uuid::new_v4());
+        let node = format!("{}-a", pipeline_id);
+
+        let empty = estimate_dedup_savings(&pipeline_id).unwrap();
+        assert_eq!(empty.chunk_count, 0);
+        assert_eq!(empty.reclaimable_bytes, 0);
+
+        // Three chunks with identical content, two unique ones
+        let blob = vec![0u8; 16];
+        for (chunk_id, hash) in [
+            ("func:a", "same"),
+            ("func:b", "same"),
+            ("func:c", "same"),
+            ("func:d", "unique-1"),
+            ("func:e", "unique-2"),
+        ] {
+            upsert_chunk_embedding(
+                &node, &pipeline_id, chunk_id, hash, &blob, "nomic-embed-text", 4, None, None, None,
+                None,
+            )
+            .unwrap();
+        }
+        // Same content under another model is a different vector, not a duplicate
+        upsert_chunk_embedding(
+            &node, &pipeline_id, "func:f", "same", &blob, "mxbai-embed-large", 4, None, None, None,
+            None,
+        )
+        .unwrap();
+
+        let estimate = estimate_dedup_savings(&pipeline_id).unwrap();
+        assert_eq!(estimate.chunk_count, 6);
+        assert_eq!(estimate.distinct_hashes, 3);
+        assert_eq!(estimate.embedding_bytes, 6 * 16);
+        // Two redundant copies of "same" under nomic-embed-text
+        assert_eq!(estimate.reclaimable_bytes, 2 * 16);
+
+        delete_chunks_for_pipeline(&pipeline_id).unwrap();
+    }
+
+    #[test]
+    fn test_rag_status_and_reindex_check() {
+        setup_test_db();
+        let pipeline_id = format!("rag-{}", uuid::Uuid::new_v4());
+        let node_a = format!("{}-a", pipeline_id);
+        let node_b = format!("{}-b", pipeline_id);
+
+        // Empty index: nothing to report, nothing to reindex
+        let status = get_rag_status(&pipeline_id).unwrap();
+        assert_eq!(status.chunk_count, 0);
+        assert_eq!(status.embedding_dim, None);
+        assert!(!rag_needs_reindex(&pipeline_id, "nomic-embed-text").unwrap());
+
+        insert_test_chunk(&pipeline_id, &node_a, "func:train", "nomic-embed-text", 4);
+        insert_test_chunk(&pipeline_id, &node_b, "toplevel:0", "nomic-embed-text", 4);
+
+        let status = get_rag_status(&pipeline_id).unwrap();
+        assert_eq!(status.chunk_count, 2);
+        assert_eq!(status.node_count, 2);
+        assert_eq!(status.embedding_model.as_deref(), Some("nomic-embed-text"));
+        assert_eq!(status.embedding_dim, Some(4));
+
+        // Matching model
+        assert!(!rag_needs_reindex(&pipeline_id, "nomic-embed-text").unwrap());
+
+        // Different model
+        assert!(rag_model_mismatch(&pipeline_id, "mxbai-embed-large").unwrap());
+        assert!(rag_needs_reindex(&pipeline_id, "mxbai-embed-large").unwrap());
+
+        // Same model name, changed dimension
+        insert_test_chunk(&pipeline_id, &node_b, "func:predict", "nomic-embed-text", 8);
+        assert!(!rag_model_mismatch(&pipeline_id, "nomic-embed-text").unwrap());
+        assert!(rag_needs_reindex(&pipeline_id, "nomic-embed-text").unwrap());
+    }
+
+    #[test]
+    fn test_global_rag_status() {
+        setup_test_db();
+        let suffix = uuid::Uuid::new_v4();
+        let (p1, p2) = (format!("grag-1-{}", suffix), format!("grag-2-{}", suffix));
+        let (small, large) = (format!("small-{}", suffix), format!("large-{}", suffix));
+
+        insert_test_chunk(&p1, &format!("{}-a", p1), "func:a", &small, 4);
+        insert_test_chunk(&p1, &format!("{}-a", p1), "func:b", &small, 4);
+        insert_test_chunk(&p1, &format!("{}-b", p1), "toplevel:0", &large, 8);
+        insert_test_chunk(&p2, &format!("{}-a", p2), "func:a", &small, 4);
+
+        // Other tests share the database, so check only what this one inserted
+        let status = get_global_rag_status().unwrap();
+        assert!(status.pipeline_ids.contains(&p1) && status.pipeline_ids.contains(&p2));
+        assert!(status.chunk_count >= 4 && status.node_count >= 3);
+        assert!(status.embedding_bytes >= 3 * 16 + 32);
+
+        let usage = |model: &str| status.models.iter().find(|m| m.embedding_model == model).unwrap().clone();
+        assert_eq!(
+            usage(&small),
+            EmbeddingModelUsage {
+                embedding_model: small.clone(),
+                chunk_count: 3,
+                pipeline_count: 2,
+                embedding_bytes: 48,
+            }
+        );
+        let large_usage = usage(&large);
+        assert_eq!((large_usage.chunk_count, large_usage.pipeline_count), (1, 1));
+        assert_eq!(large_usage.embedding_bytes, 32);
+    }
+
+    #[test]
+    fn test_completion_acceptance_stats() {
+        setup_test_db();
+        let pipeline_id = format!("completions-{}", uuid::Uuid::new_v4());
+
+        let empty = get_completion_acceptance_stats(&pipeline_id).unwrap();
+        assert_eq!(empty.overall.shown, 0);
+        assert_eq!(empty.overall.rate, None);
+
+        // RAG: 3 of 4 accepted; plain: 1 of 4 accepted
+        for accepted in [true, true, true, false] {
+            log_completion_event(&pipeline_id, Some("node-1"), "qwen2.5-coder", true, accepted).unwrap();
+        }
+        for accepted in [true, false, false, false] {
+            log_completion_event(&pipeline_id, None, "qwen2.5-coder", false, accepted).unwrap();
+        }
+
+        let stats = get_completion_acceptance_stats(&pipeline_id).unwrap();
+        assert_eq!(stats.overall, AcceptanceRate { shown: 8, accepted: 4, rate: Some(0.5) });
+        assert_eq!(stats.rag, AcceptanceRate { shown: 4, accepted: 3, rate: Some(0.75) });
+        assert_eq!(stats.non_rag, AcceptanceRate { shown: 4, accepted: 1, rate: Some(0.25) });
+    }
+
+    #[test]
+    fn test_embedding_model_pinning() {
+        setup_test_db();
+        let pipeline_id = format!("pin-{}", uuid::Uuid::new_v4());
+        let node_id = format!("{}-node", pipeline_id);
+
+        // First index pins the model
+        assert_eq!(pin_embedding_model(&pipeline_id, "nomic-embed-text", 4, "none", false).unwrap(), None);
+        insert_test_chunk(&pipeline_id, &node_id, "func:train", "nomic-embed-text", 4);
+        let pin = get_embedding_config(&pipeline_id).unwrap().unwrap();
+        assert_eq!((pin.model.as_str(), pin.dim), ("nomic-embed-text", 4));
+
+        // Re-indexing with the pinned model is accepted and keeps chunks
+        assert_eq!(pin_embedding_model(&pipeline_id, "nomic-embed-text", 4, "none", false).unwrap(), None);
+        assert_eq!(get_rag_status(&pipeline_id).unwrap().chunk_count, 1);
+
+        // A different model is refused without force
+        let conflict = pin_embedding_model(&pipeline_id, "mxbai-embed-large", 8, "none", false).unwrap();
+        assert_eq!(conflict.map(|c| c.model), Some("nomic-embed-text".to_string()));
+        assert_eq!(get_rag_status(&pipeline_id).unwrap().chunk_count, 1);
+
+        // Forcing clears the index and re-pins
+        assert_eq!(pin_embedding_model(&pipeline_id, "mxbai-embed-large", 8, "none", true).unwrap(), None);
+        assert_eq!(get_rag_status(&pipeline_id).unwrap().chunk_count, 0);
+        let pin = get_embedding_config(&pipeline_id).unwrap().unwrap();
+        assert_eq!((pin.model.as_str(), pin.dim), ("mxbai-embed-large", 8));
+    }
+
+    #[test]
+    fn test_find_similar_runs() {
+        setup_test_db();
+        let prefix = uuid::Uuid::new_v4().to_string();
+        let run = |suffix: &str, hp: &str| {
+            let id = format!("{}-{}", prefix, suffix);
+            create_run(&id, "Similar Runs", hp, None).unwrap();
+            id
+        };
+
+        let target = run("target", r#"{"n_estimators": 100, "max_depth": 5, "criterion": "gini"}"#);
+        let near = run("near", r#"{"n_estimators": 110, "max_depth": 5, "criterion": "gini"}"#);
+        let far = run("far", r#"{"n_estimators": 500, "max_depth": 20, "criterion": "entropy"}"#);
+        // Shares one key out of four, so the union dilutes its score
+        let partial = run("partial", r#"{"max_depth": 5, "learning_rate": 0.1}"#);
+        let disjoint = run("disjoint", r#"{"alpha": 1.0}"#);
+
+        let results = find_similar_runs(&target, 100).unwrap();
+        let ours: Vec<&SimilarRun> = results.iter().filter(|r| r.run_id.starts_with(&prefix)).collect();
+        let ids: Vec<&str> = ours.iter().map(|r| r.run_id.as_str()).collect();
+
+        assert_eq!(ids, vec![near.as_str(), partial.as_str(), far.as_str()]);
+        assert!(!ids.contains(&disjoint.as_str()));
+        assert!(!ids.contains(&target.as_str()));
+        assert!(ours[0].score > 0.9 && ours[0].score < 1.0);
+        assert_eq!(ours[1].score, 0.25);
+        assert_eq!(ours[2].score, 0.0);
+
+        assert_eq!(find_similar_runs(&target, 1).unwrap().len(), 1);
+        assert!(find_similar_runs("missing-run", 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_runs_by_tags() {
+        setup_test_db();
+        let prefix = uuid::Uuid::new_v4().to_string();
+        let (tag_a, tag_b) = (format!("{}-baseline", prefix), format!("{}-GPU", prefix));
+
+        let both = format!("{}-both", prefix);
+        let only_a = format!("{}-only-a", prefix);
+        let untagged = format!("{}-untagged", prefix);
+        for id in [&both, &only_a, &untagged] {
+            create_run(id, "Tagged Runs", "{}", None).unwrap();
+        }
+        add_run_tag(&both, &tag_a).unwrap();
+        add_run_tag(&both, &tag_b).unwrap();
+        add_run_tag(&only_a, &tag_a).unwrap();
+
+        // Single tag, matched case-insensitively
+        let mut ids: Vec<String> = list_runs_by_tag(&tag_a.to_uppercase())
+            .unwrap()
+            .into_iter()
+            .map(|r| r.id)
+            .collect();
+        ids.sort();
+        let mut expected = vec![both.clone(), only_a.clone()];
+        expected.sort();
+        assert_eq!(ids, expected);
+
+        // AND of tags, with the run's full tag list populated
+        let runs = list_runs_by_tags(&[tag_a.clone(), tag_b.to_lowercase()]).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].id, both);
+        assert_eq!(runs[0].tags.as_ref().map(|t| t.len()), Some(2));
+
+        // Duplicate tags don't break the AND count; no tags matches nothing
+        assert_eq!(list_runs_by_tags(&[tag_b.clone(), tag_b.clone()]).unwrap().len(), 1);
+        assert!(list_runs_by_tags(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_promote_model_stages() {
+        setup_test_db();
+        let model_id = create_test_model("stages");
+        let version_id = register_test_version(&model_id, None);
+        assert_eq!(get_model_version(&version_id).unwrap().unwrap().stage, "none");
+
+        for stage in MODEL_STAGES {
+            validate_stage(stage).unwrap();
+            promote_model(&version_id, stage).unwrap();
+            assert_eq!(get_model_version(&version_id).unwrap().unwrap().stage, stage);
+        }
+
+        let err = validate_stage("prod").unwrap_err().to_string();
+        assert!(err.contains("'prod'") && err.contains("production"), "{}", err);
+
+        // The triggers guard writes that skip validate_stage
+        assert!(promote_model(&version_id, "prod").is_err());
+        {
+            let conn = DB.get().unwrap().lock().unwrap();
+            let err = conn
+                .execute("UPDATE model_versions SET stage = 'Production' WHERE id = ?1", [&version_id])
+                .unwrap_err();
+            assert!(err.to_string().contains("invalid model stage"), "{}", err);
+        }
+        assert_eq!(get_model_version(&version_id).unwrap().unwrap().stage, "archived");
+    }
+
+    #[test]
+    fn test_rollback_production() {
+        setup_test_db();
+        let _serial = PROMOTION_RULE_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        set_promotion_rule(None).unwrap();
+        let model_id = create_test_model("rollback");
+        let v1 = register_test_version(&model_id, None);
+        let v2 = register_test_version(&model_id, None);
+        let v3 = register_test_version(&model_id, None);
+        let stage = |id: &str| get_model_version(id).unwrap().unwrap().stage;
+
+        // Nothing in production yet
+        assert!(rollback_production(&model_id).is_err());
+
+        promote_model(&v1, "production").unwrap();
+        promote_model(&v2, "production").unwrap();
+        promote_model(&v3, "staging").unwrap();
+        let v2_promoted_at = get_model_version(&v2).unwrap().unwrap().promoted_at;
+
+        // The restored version must pass the promotion rule; nothing changes if it doesn't
+        set_promotion_rule(Some(&PromotionRule {
+            metric: "accuracy".to_string(),
+            comparison: ">=".to_string(),
+            threshold: 0.9,
+        }))
+        .unwrap();
+        let err = rollback_production(&model_id).err().unwrap().to_string();
+        assert!(err.starts_with("Cannot promote to production"), "{}", err);
+        assert_eq!(stage(&v2), "production");
+        set_promotion_rule(None).unwrap();
+
+        // History wins over the more recently promoted staging version
+        let restored = rollback_production(&model_id).unwrap();
+        assert_eq!(restored.id, v1);
+        assert_eq!(restored.stage, "production");
+        assert!(restored.promoted_at.is_some());
+        assert_eq!(stage(&v2), "archived");
+        assert_eq!(stage(&v3), "staging");
+        // Archiving doesn't rewrite when v2 was promoted
+        assert_eq!(get_model_version(&v2).unwrap().unwrap().promoted_at, v2_promoted_at);
+
+        // v2 is archived, so fall back to the staging version
+        assert_eq!(rollback_production(&model_id).unwrap().id, v3);
+        assert_eq!(stage(&v1), "archived");
+
+        // Every other version is archived now
+        let err = rollback_production(&model_id).err().unwrap().to_string();
+        assert_eq!(err, "No previous production or staging version to roll back to");
+        assert_eq!(stage(&v3), "production");
+    }
+
+    #[test]
+    fn test_register_and_promote() {
+        setup_test_db();
+        let _serial = PROMOTION_RULE_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let model_id = create_test_model("register-promote");
+        let register = |stage: &str, snapshot: Option<&str>| {
+            let version_id = uuid::Uuid::new_v4().to_string();
+            let source = create_test_model_file(b"model");
+            register_and_promote(&version_id, &model_id, None, &source, "joblib", snapshot, None, false, stage)
+                .map(|version| (version_id, version))
+        };
+
+        let (v1, n1) = register("production", None).unwrap();
+        assert_eq!(n1, 1);
+        let version = get_model_version(&v1).unwrap().unwrap();
+        assert_eq!(version.stage, "production");
+        assert!(version.promoted_at.is_some());
+
+        // A second production version demotes the first
+        let (v2, n2) = register("production", None).unwrap();
+        assert_eq!(n2, 2);
+        assert_eq!(get_model_version(&v1).unwrap().unwrap().stage, "staging");
+        assert_eq!(get_model_version(&v2).unwrap().unwrap().stage, "production");
+        let recorded: i64 = DB
+            .get()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM model_stage_history
+                 WHERE version_id = ?1 AND from_stage = 'none' AND to_stage = 'production'",
+                [&v2],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(recorded, 1);
+
+        assert!(register("Production", None).unwrap_err().to_string().contains("Invalid stage"));
+
+        // A rejected promotion leaves no version, no file and production untouched
+        let rule = PromotionRule { metric: "accuracy".to_string(), comparison: ">=".to_string(), threshold: 0.9 };
+        set_promotion_rule(Some(&rule)).unwrap();
+        let err = register("production", Some(r#"{"accuracy": 0.5}"#)).unwrap_err().to_string();
+        set_promotion_rule(None).unwrap();
+        assert!(err.contains("promotion rule"), "{}", err);
+        assert_eq!(get_model(&model_id).unwrap().unwrap().version_count, 2);
+        let v3_dir = get_models_dir().unwrap().join(&model_id).join("v3");
+        assert!(!v3_dir.exists());
+        assert_eq!(get_model_version(&v2).unwrap().unwrap().stage, "production");
+
+        // The rule only gates production
+        let (v3, n3) = register("staging", Some(r#"{"accuracy": 0.5}"#)).unwrap();
+        assert_eq!(n3, 3);
+        assert_eq!(get_model_version(&v3).unwrap().unwrap().stage, "staging");
+    }
+
+    #[test]
+    fn test_import_external_model() {
+        setup_test_db();
+        let name = format!("colab-{}", uuid::Uuid::new_v4());
+        let features = vec!["age".to_string(), "income".to_string()];
+        let model_by_name = |name: &str| list_models().unwrap().into_iter().find(|m| m.name == name);
+        assert!(model_by_name(&name).is_none());
+
+        // A new name creates the model
+        let source = create_test_model_file(b"model");
+        let (v1, n1) =
+            import_external_model(&name, &source, "joblib", Some(&features), Some(r#"{"accuracy": 0.88}"#), false)
+                .unwrap();
+        assert_eq!(n1, 1);
+        let model = model_by_name(&name).unwrap();
+        let version = get_model_version(&v1).unwrap().unwrap();
+        assert_eq!(version.model_id, model.id);
+        assert_eq!(version.run_id, None);
+        assert_eq!(version.format, "joblib");
+        assert_eq!(version.feature_names.as_deref(), Some(r#"["age","income"]"#));
+        assert_eq!(version.n_features, Some(2));
+        assert_eq!(version.metrics_snapshot.as_deref(), Some(r#"{"accuracy": 0.88}"#));
+        assert!(Path::new(&version.file_path).exists());
+
+        // The same name adds a version to the existing model
+        let (v2, n2) = import_external_model(&format!("  {} ", name), &source, "joblib", None, None, false).unwrap();
+        assert_eq!(n2, 2);
+        assert_eq!(get_model_version(&v2).unwrap().unwrap().model_id, model.id);
+        assert_eq!(model_by_name(&name).unwrap().version_count, 2);
+
+        // Rejected before anything is created
+        let other = format!("rejected-{}", uuid::Uuid::new_v4());
+        let err = import_external_model(&other, "/no/such/model.joblib", "joblib", None, None, false).unwrap_err().to_string();
+        assert!(err.contains("not found"), "{}", err);
+        let err = import_external_model(&other, &source, "safetensors", None, None, false).unwrap_err().to_string();
+        assert!(err.contains("Unrecognized model format"), "{}", err);
+        let err = import_external_model(&other, &source, "joblib", None, Some("[0.9]"), false).unwrap_err().to_string();
+        assert!(err.contains("JSON object"), "{}", err);
+        assert!(import_external_model(&other, &source, "pickle", None, None, false).is_err());
+        assert!(import_external_model(" ", &source, "joblib", None, None, false).is_err());
+        assert!(model_by_name(&other).is_none());
+    }
+
+    #[test]
+    fn test_prune_old_versions() {
+        setup_test_db();
+        let model_id = create_test_model("prune");
+        let versions: Vec<String> = (0..10).map(|_| register_test_version(&model_id, None)).collect();
+        let files: Vec<String> = versions.iter().map(|id| get_model_file_path(id).unwrap().unwrap()).collect();
+        promote_model(&versions[1], "production").unwrap();
+
+        // No retention configured: nothing to prune
+        assert!(prune_old_versions(&model_id, true, None).unwrap().is_empty());
+        assert_eq!(
+            set_model_max_versions(&model_id, Some(0)).unwrap_err().to_string(),
+            "max_versions must be at least 1"
+        );
+        assert_eq!(set_model_max_versions("missing-model", Some(5)).unwrap_err().to_string(), "Model not found");
+        set_model_max_versions(&model_id, Some(5)).unwrap();
+        assert_eq!(get_model(&model_id).unwrap().unwrap().max_versions, Some(5));
+
+        // v1 and v3..v6 go; production v2 is kept and counts toward the limit
+        let pruned = prune_old_versions(&model_id, true, None).unwrap();
+        let expected: Vec<String> = [0, 2, 3, 4, 5].iter().map(|&i| versions[i].clone()).collect();
+        assert_eq!(pruned, expected);
+        for &i in &[0, 2, 3, 4, 5] {
+            assert!(get_model_version(&versions[i]).unwrap().is_none());
+            assert!(!std::path::Path::new(&files[i]).exists());
+        }
+        for &i in &[1, 6, 7, 8, 9] {
+            assert!(get_model_version(&versions[i]).unwrap().is_some());
+            assert!(std::path::Path::new(&files[i]).exists());
+        }
+        assert_eq!(get_model(&model_id).unwrap().unwrap().version_count, 5);
+        assert!(prune_old_versions(&model_id, true, None).unwrap().is_empty());
+
+        // Archiving skips staging and the kept version, and archived versions stop counting
+        set_model_max_versions(&model_id, Some(2)).unwrap();
+        promote_model(&versions[6], "staging").unwrap();
+        let pruned = prune_old_versions(&model_id, false, Some(&versions[7])).unwrap();
+        assert_eq!(pruned, vec![versions[8].clone(), versions[9].clone()]);
+        let archived = get_model_version(&versions[8]).unwrap().unwrap();
+        assert_eq!(archived.stage, "archived");
+        assert_eq!(archived.promoted_at, None); // never promoted, and archiving isn't a promotion
+        assert_eq!(get_model(&model_id).unwrap().unwrap().version_count, 5);
+        assert_eq!(prune_old_versions(&model_id, false, None).unwrap(), vec![versions[7].clone()]);
+        assert!(prune_old_versions(&model_id, false, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_pipeline_data() {
+        // Valid, including forward-compatible extra fields
+        assert!(validate_pipeline_data(r#"{"nodes":[],"edges":[]}"#).is_ok());
+        assert!(validate_pipeline_data(
+            r#"{"nodes":[{"id":"n1","type":"dataLoader","data":{}}],"edges":[{"id":"e1"}],"name":"P","viewport":{}}"#
+        )
+        .is_ok());
+
+        // Missing or mistyped arrays
+        let err = validate_pipeline_data(r#"{"edges":[]}"#).unwrap_err().to_string();
+        assert!(err.contains("'nodes'"), "{}", err);
+        let err = validate_pipeline_data(r#"{"nodes":[],"edges":{}}"#).unwrap_err().to_string();
+        assert!(err.contains("'edges'"), "{}", err);
+        let err = validate_pipeline_data(r#"{"nodes":[{"type":"trainer"}],"edges":[]}"#).unwrap_err().to_string();
+        assert!(err.contains("index 0"), "{}", err);
+
+        // Not JSON / not an object
+        assert!(validate_pipeline_data("not json").unwrap_err().to_string().contains("not valid JSON"));
+        assert!(validate_pipeline_data("[]").is_err());
+
+        // Saving validates too, whoever the caller is, and writes nothing on failure
+        setup_test_db();
+        let id = format!("invalid-{}", uuid::Uuid::new_v4());
+        let err = save_pipeline(&id, "Invalid", r#"{"nodes":[]}"#).unwrap_err().to_string();
+        assert_eq!(err, "Pipeline data must have an 'edges' array");
+        assert_eq!(load_pipeline(&id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_pipeline_import() {
+        let schemas = builtin_node_schemas();
+        let valid = r#"{"nodes":[
+            {"id":"load","type":"dataLoader","data":{"label":"Data Loader"}},
+            {"id":"train","type":"trainer","data":{"modelType":"random_forest","targetColumn":""}}
+        ],"edges":[{"id":"e1","source":"load","target":"train"}]}"#;
+        assert_eq!(validate_pipeline_import(valid, &schemas), vec![]);
+
+        let data = r#"{"nodes":[
+            {"id":"load","type":"dataLoader","data":{}},
+            {"id":"fe","type":"featureEngineering","data":{}},
+            {"id":"split","type":"dataSplit","data":{"splitRatio":null}},
+            {"id":"export","type":"modelExporter","data":{"exportFormat":"onnx"}}
+        ],"edges":[{"id":"e1","source":"load","target":"gone"}]}"#;
+        let issues = validate_pipeline_import(data, &schemas);
+        assert_eq!(
+            issues,
+            vec![
+                ValidationIssue {
+                    node_id: Some("fe".to_string()),
+                    field: None,
+                    message: "Unknown node type 'featureEngineering'".to_string(),
+                },
+                ValidationIssue {
+                    node_id: Some("split".to_string()),
+                    field: Some("splitRatio".to_string()),
+                    message: "dataSplit node is missing required field 'splitRatio'".to_string(),
+                },
+                ValidationIssue {
+                    node_id: None,
+                    field: None,
+                    message: "Edge target references unknown node 'gone'".to_string(),
+                },
+            ]
+        );
+
+        // A caller-supplied set replaces the built-in one
+        let custom = vec![NodeTypeSchema { node_type: "featureEngineering".to_string(), required_fields: vec!["steps".to_string()] }];
+        let issues = validate_pipeline_import(r#"{"nodes":[{"id":"fe","type":"featureEngineering","data":{}}],"edges":[]}"#, &custom);
+        assert_eq!(issues.iter().map(|i| i.field.as_deref()).collect::<Vec<_>>(), vec![Some("steps")]);
+
+        // Structural problems are a single pipeline-level issue
+        let issues = validate_pipeline_import("not json", &schemas);
+        assert_eq!((issues.len(), issues[0].node_id.clone()), (1, None));
+    }
+
+    #[test]
+    fn test_feature_correlations_storage() {
+        setup_test_db();
+        let run_id = format!("corr-{}", uuid::Uuid::new_v4());
+        assert_eq!(get_feature_correlations(&run_id).unwrap(), None);
+
+        let first = r#"{"columns":["a","b"],"matrix":[[1.0,0.5],[0.5,1.0]],"excluded":[]}"#;
+        save_feature_correlations(&run_id, first).unwrap();
+        assert_eq!(get_feature_correlations(&run_id).unwrap().as_deref(), Some(first));
+
+        // Recomputing replaces the stored matrix
+        let second = r#"{"columns":["a"],"matrix":[[1.0]],"excluded":["b"]}"#;
+        save_feature_correlations(&run_id, second).unwrap();
+        assert_eq!(get_feature_correlations(&run_id).unwrap().as_deref(), Some(second));
+    }
+
+    fn pipeline_json(nodes: &[&str], edges: &[(&str, &str)]) -> String {
+        serde_json::json!({
+            "nodes": nodes.iter().map(|id| serde_json::json!({"id": id})).collect::<Vec<_>>(),
+            "edges": edges
+                .iter()
+                .map(|(s, t)| serde_json::json!({"id": format!("{}-{}", s, t), "source": s, "target": t}))
+                .collect::<Vec<_>>(),
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_pipeline_execution_order() {
+        // Linear chain, declared out of order
+        let chain = pipeline_json(&["train", "load", "evaluate"], &[("load", "train"), ("train", "evaluate")]);
+        assert_eq!(pipeline_execution_order(&chain).unwrap(), vec!["load", "train", "evaluate"]);
+
+        // Diamond: both branches run after load and before merge
+        let diamond = pipeline_json(
+            &["load", "scale", "encode", "merge"],
+            &[("load", "scale"), ("load", "encode"), ("scale", "merge"), ("encode", "merge")],
+        );
+        assert_eq!(
+            pipeline_execution_order(&diamond).unwrap(),
+            vec!["load", "scale", "encode", "merge"]
+        );
+
+        // Cycle b -> c -> d -> b downstream of a
+        let cyclic = pipeline_json(&["a", "b", "c", "d"], &[("a", "b"), ("b", "c"), ("c", "d"), ("d", "b")]);
+        let err = pipeline_execution_order(&cyclic).unwrap_err().to_string();
+        assert!(err.starts_with("Pipeline has a cycle: "), "{}", err);
+        let mut cycle: Vec<&str> = err["Pipeline has a cycle: ".len()..].split(" -> ").collect();
+        assert_eq!(cycle.first(), cycle.last());
+        cycle.pop();
+        cycle.sort();
+        assert_eq!(cycle, vec!["b", "c", "d"]);
+
+        let self_loop = pipeline_json(&["a"], &[("a", "a")]);
+        assert_eq!(pipeline_execution_order(&self_loop).unwrap_err().to_string(), "Pipeline has a cycle: a -> a");
+
+        let dangling = pipeline_json(&["a"], &[("a", "missing")]);
+        assert!(pipeline_execution_order(&dangling).unwrap_err().to_string().contains("unknown node 'missing'"));
+
+        setup_test_db();
+        let pipeline_id = format!("order-{}", uuid::Uuid::new_v4());
+        save_pipeline(&pipeline_id, "Order", &chain).unwrap();
+        assert_eq!(get_pipeline_execution_order(&pipeline_id).unwrap(), vec!["load", "train", "evaluate"]);
+        assert!(get_pipeline_execution_order("no-such-pipeline").is_err());
+    }
+
+    #[test]
+    fn test_run_environment_roundtrip() {
+        setup_test_db();
+        let run_id = format!("env-{}", uuid::Uuid::new_v4());
+        assert!(get_run_environment(&run_id).unwrap().is_none());
+
+        save_run_environment(&run_id, "3.11.4 (main)", r#"{"numpy": "1.26.0"}"#).unwrap();
+        // Re-capturing replaces the previous row
+        save_run_environment(&run_id, "3.11.4 (main)", r#"{"numpy": "1.26.0", "scikit-learn": "1.4.0"}"#).unwrap();
+
+        let env = get_run_environment(&run_id).unwrap().unwrap();
+        assert_eq!(env.run_id, run_id);
+        assert_eq!(env.python_version, "3.11.4 (main)");
+        let packages: serde_json::Value = serde_json::from_str(&env.packages_json).unwrap();
+        assert_eq!(packages, serde_json::json!({"numpy": "1.26.0", "scikit-learn": "1.4.0"}));
+    }
+
+    #[test]
+    fn test_model_version_provenance() {
+        setup_test_db();
+        let pipeline_name = format!("provenance-{}", uuid::Uuid::new_v4());
+        let pipeline_id = uuid::Uuid::new_v4().to_string();
+        save_pipeline(&pipeline_id, &pipeline_name, r#"{"nodes":[],"edges":[]}"#).unwrap();
+
+        let run_id = uuid::Uuid::new_v4().to_string();
+        create_run(&run_id, &pipeline_name, r#"{"n_estimators": 100}"#, None).unwrap();
+        save_run_metrics(
+            &run_id,
+            &[Metric { name: "accuracy".to_string(), value: Some(0.93), value_json: None, kind: None }],
+        )
+        .unwrap();
+        save_run_environment(&run_id, "3.11.4", r#"{"numpy": "1.26.0"}"#).unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        create_tuning_session(&session_id, &run_id, "tpe", "{}", Some(5), 3, "accuracy", None).unwrap();
+
+        let model_id = create_test_model("provenance");
+        let version_id = uuid::Uuid::new_v4().to_string();
+        let source = create_test_model_file(b"model");
+        register_model_version(&version_id, &model_id, Some(&run_id), &source, "joblib", None, None, false)
+            .unwrap();
+
+        let provenance = get_model_version_provenance(&version_id).unwrap().unwrap();
+        assert_eq!(provenance.version.id, version_id);
+        assert_eq!(provenance.model.map(|m| m.id), Some(model_id.clone()));
+        let run = provenance.run.unwrap();
+        assert_eq!(run.id, run_id);
+        assert_eq!(run.hyperparameters.as_deref(), Some(r#"{"n_estimators": 100}"#));
+        assert_eq!(provenance.metrics.len(), 1);
+        assert_eq!(provenance.metrics[0].value, Some(0.93));
+        assert_eq!(provenance.environment.map(|e| e.python_version), Some("3.11.4".to_string()));
+        assert_eq!(provenance.pipeline.map(|p| p.id), Some(pipeline_id));
+        assert_eq!(provenance.tuning_session.map(|t| t.id), Some(session_id));
+
+        // A version registered without a run has no lineage beyond its model
+        let orphan_id = register_test_version(&model_id, None);
+        let provenance = get_model_version_provenance(&orphan_id).unwrap().unwrap();
+        assert!(provenance.model.is_some());
+        assert!(provenance.run.is_none() && provenance.pipeline.is_none() && provenance.tuning_session.is_none());
+        assert!(provenance.metrics.is_empty() && provenance.environment.is_none());
+
+        assert!(get_model_version_provenance("missing-version").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_get_run_model_versions() {
+        setup_test_db();
+        let run_id = uuid::Uuid::new_v4().to_string();
+        create_run(&run_id, "Run Versions", "{}", None).unwrap();
+        assert!(get_run_model_versions(&run_id).unwrap().is_empty());
+
+        let model_id = create_test_model("run-versions");
+        let mut expected = Vec::new();
+        for _ in 0..2 {
+            let version_id = uuid::Uuid::new_v4().to_string();
+            let source = create_test_model_file(b"model");
+            register_model_version(&version_id, &model_id, Some(&run_id), &source, "joblib", None, None, false)
+                .unwrap();
+            expected.push(version_id);
+        }
+        add_model_tag(&expected[0], "baseline").unwrap();
+        // A version from no run isn't included
+        register_test_version(&model_id, None);
+
+        let versions = get_run_model_versions(&run_id).unwrap();
+        let mut ids: Vec<String> = versions.iter().map(|v| v.id.clone()).collect();
+        ids.sort();
+        expected.sort();
+        assert_eq!(ids, expected);
+        assert!(versions.iter().all(|v| v.run_id.as_deref() == Some(run_id.as_str())));
+        let tagged = versions.iter().find(|v| v.tags.as_ref().unwrap().contains(&"baseline".to_string()));
+        assert!(tagged.is_some());
+    }
+
+    #[test]
+    fn test_list_unused_versions() {
+        setup_test_db();
+        let model_id = create_test_model("unused");
+        let fresh = register_test_version(&model_id, None);
+        let stale = register_test_version(&model_id, None);
+        let used_long_ago = register_test_version(&model_id, None);
+        let used_recently = register_test_version(&model_id, None);
+        {
+            let conn = DB.get().unwrap().lock().unwrap();
+            conn.execute(
+                "UPDATE model_versions SET created_at = datetime('now', '-90 days') WHERE id IN (?1, ?2, ?3)",
+                [&stale, &used_long_ago, &used_recently],
+            )
+            .unwrap();
+            conn.execute(
+                "UPDATE model_versions SET last_used_at = datetime('now', '-60 days') WHERE id = ?1",
+                [&used_long_ago],
+            )
+            .unwrap();
+        }
+        touch_model_version(&used_recently).unwrap();
+        assert!(get_model_version(&used_recently).unwrap().unwrap().last_used_at.is_some());
+
+        let unused: Vec<String> = list_unused_versions(30)
+            .unwrap()
+            .into_iter()
+            .map(|v| v.id)
+            .filter(|id| [&fresh, &stale, &used_long_ago, &used_recently].contains(&id))
+            .collect();
+        assert_eq!(unused, vec![stale.clone(), used_long_ago.clone()]);
+
+        let unused_90: Vec<String> = list_unused_versions(75).unwrap().into_iter().map(|v| v.id).collect();
+        assert!(unused_90.contains(&stale) && !unused_90.contains(&used_long_ago));
+    }
+
+    #[test]
+    fn test_detect_model_format() {
+        let fixture = |contents: &[u8]| create_test_model_file(contents);
+        let onnx = fixture(&[
+            0x08, 0x07, // ir_version
+            0x12, 0x08, b's', b'k', b'l', b'2', b'o', b'n', b'n', b'x', // producer_name
+            0x3a, 0x02, 0x0a, 0x00, // graph
+            0x42, 0x02, 0x10, 0x0d, // opset_import
+        ]);
+        let coreml = fixture(&[
+            0x08, 0x04, // specificationVersion
+            0x12, 0x00, // description
+            0xe2, 0x12, 0x00, // glmRegressor (field 300)
+        ]);
+        let pickle = fixture(&[0x80, 0x04, 0x95, 0x10, 0x00]);
+        let joblib = fixture(&[0x78, 0x9c, 0x6b, 0x60]);
+        let mlpackage = fixture(b"PK\x03\x04\x14\x00");
+        let garbage = fixture(b"not a model");
+
+        assert_eq!(detect_model_format(&onnx).unwrap(), "onnx");
+        assert_eq!(detect_model_format(&coreml).unwrap(), "coreml");
+        assert_eq!(detect_model_format(&pickle).unwrap(), "pickle");
+        assert_eq!(detect_model_format(&joblib).unwrap(), "joblib");
+        assert_eq!(detect_model_format(&mlpackage).unwrap(), "coreml");
+        assert!(detect_model_format(&garbage).is_err());
+        assert!(detect_model_format(&fixture(&[0x08, 0x01])).is_err());
+
+        assert!(check_model_format(&onnx, "onnx").is_ok());
+        assert!(check_model_format(&pickle, "joblib").is_ok());
+        assert!(check_model_format(&joblib, "pickle").is_ok());
+        let err = check_model_format(&pickle, "onnx").unwrap_err().to_string();
+        assert_eq!(err, "Model file looks like pickle but was registered as onnx");
+        let err = check_model_format(&onnx, "joblib").unwrap_err().to_string();
+        assert!(err.contains("looks like onnx"), "{}", err);
+        assert!(check_model_format(&coreml, "onnx").is_err());
+        // Unknown contents aren't blocked
+        assert!(check_model_format(&garbage, "joblib").is_ok());
+    }
+
+    #[test]
+    fn test_pickle_versions_require_trust() {
+        setup_test_db();
+        let model_id = create_test_model("pickle-trust");
+
+        assert!(check_model_trust("pickle", false).is_err());
+        assert!(check_model_trust("pickle", true).is_ok());
+        assert!(check_model_trust("joblib", false).is_ok());
+        assert!(check_model_trust("onnx", false).is_ok());
+
+        let version_id = uuid::Uuid::new_v4().to_string();
+        let source = create_test_model_file(b"pickled");
+        register_model_version(&version_id, &model_id, None, &source, "pickle", None, None, true).unwrap();
+        let version = get_model_version(&version_id).unwrap().unwrap();
+        assert!(version.trusted);
+        assert!(version.file_path.ends_with("model.pkl"));
+        assert!(check_model_trust(&version.format, version.trusted).is_ok());
+
+        // Revoking trust blocks serving
+        set_model_version_trusted(&version_id, false).unwrap();
+        let version = get_model_version(&version_id).unwrap().unwrap();
+        assert!(check_model_trust(&version.format, version.trusted).is_err());
+
+        let joblib_id = register_test_version(&model_id, None);
+        let version = get_model_version(&joblib_id).unwrap().unwrap();
+        assert!(!version.trusted);
+        assert!(check_model_trust(&version.format, version.trusted).is_ok());
+    }
+
+    #[test]
+    fn test_bulk_tag_loading() {
+        setup_test_db();
+        let tag_queries = || TAG_QUERIES.with(|count| count.get());
+
+        let model_id = create_test_model("bulk-tags");
+        let versions: Vec<String> = (0..4).map(|_| register_test_version(&model_id, None)).collect();
+        add_model_tag(&versions[0], "baseline").unwrap();
+        add_model_tag(&versions[0], "approved").unwrap();
+        add_model_tag(&versions[2], "candidate").unwrap();
+
+        let before = tag_queries();
+        let listed = list_model_versions(&model_id).unwrap();
+        assert_eq!(tag_queries() - before, 1);
+        let tags = |id: &str| listed.iter().find(|v| v.id == id).unwrap().tags.clone().unwrap();
+        assert_eq!(tags(&versions[0]), vec!["approved", "baseline"]);
+        assert!(tags(&versions[1]).is_empty());
+        assert_eq!(tags(&versions[2]), vec!["candidate"]);
+        assert!(tags(&versions[3]).is_empty());
+
+        let pipeline = format!("Bulk Tags {}", uuid::Uuid::new_v4());
+        let runs: Vec<String> = (0..3)
+            .map(|_| {
+                let run_id = format!("run-{}", uuid::Uuid::new_v4());
+                create_run(&run_id, &pipeline, "{}", None).unwrap();
+                run_id
+            })
+            .collect();
+        add_run_tag(&runs[1], "noisy").unwrap();
+        add_run_tag(&runs[2], "best").unwrap();
+        add_run_tag(&runs[2], "final").unwrap();
+
+        let before = tag_queries();
+        let listed = list_runs(Some(&pipeline), None).unwrap();
+        assert_eq!(tag_queries() - before, 1);
+        let tags = |id: &str| listed.iter().find(|r| r.id == id).unwrap().tags.clone().unwrap();
+        assert!(tags(&runs[0]).is_empty());
+        assert_eq!(tags(&runs[1]), vec!["noisy"]);
+        assert_eq!(tags(&runs[2]), vec!["best", "final"]);
+
+        delete_runs_for_pipeline(&pipeline).unwrap();
+    }
+
+    #[test]
+    fn test_get_or_create_experiment() {
+        setup_test_db();
+        let name = format!("Churn {}", uuid::Uuid::new_v4());
+        let first_id = uuid::Uuid::new_v4().to_string();
+
+        let id = get_or_create_experiment(&first_id, &name, Some("first")).unwrap();
+        assert_eq!(id, first_id);
+        let again = get_or_create_experiment(&uuid::Uuid::new_v4().to_string(), &name, Some("second")).unwrap();
+        assert_eq!(again, first_id);
+        let upper = get_or_create_experiment(&uuid::Uuid::new_v4().to_string(), &name.to_uppercase(), None).unwrap();
+        assert_eq!(upper, first_id);
+
+        let matching: Vec<Experiment> = list_experiments(true)
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.name.eq_ignore_ascii_case(&name))
+            .collect();
+        assert_eq!(matching.len(), 1);
+        assert_eq!(matching[0].description.as_deref(), Some("first"));
+        delete_experiment(&first_id).unwrap();
+    }
+
+    #[test]
+    fn test_delete_runs_for_pipeline() {
+        setup_test_db();
+        let suffix = uuid::Uuid::new_v4();
+        let (noisy, kept) = (format!("Noisy {}", suffix), format!("Kept {}", suffix));
+        let pipeline_id = format!("pipeline-{}", suffix);
+        save_pipeline(&pipeline_id, &noisy, r#"{"nodes":[],"edges":[]}"#).unwrap();
+
+        let artifacts_dir = get_artifacts_dir().unwrap();
+        let mut run_ids = Vec::new();
+        for name in [&noisy, &noisy, &kept] {
+            let run_id = format!("run-{}", uuid::Uuid::new_v4());
+            create_run(&run_id, name, "{}", None).unwrap();
+            std::fs::create_dir_all(artifacts_dir.join(&run_id)).unwrap();
+            std::fs::write(artifacts_dir.join(&run_id).join("model.joblib"), b"model").unwrap();
+            run_ids.push(run_id);
+        }
+
+        assert_eq!(delete_runs_for_pipeline(&noisy).unwrap(), 2);
+        for run_id in &run_ids[..2] {
+            assert!(get_run(run_id).unwrap().is_none());
+            assert!(!artifacts_dir.join(run_id).exists());
+        }
+        assert!(get_run(&run_ids[2]).unwrap().is_some());
+        assert!(artifacts_dir.join(&run_ids[2]).join("model.joblib").exists());
+        assert!(load_pipeline(&pipeline_id).unwrap().is_some());
+
+        assert_eq!(delete_runs_for_pipeline(&noisy).unwrap(), 0);
+        delete_run(&run_ids[2]).unwrap();
+    }
+
+    #[test]
+    fn test_purge_pipeline() {
+        setup_test_db();
+        let suffix = uuid::Uuid::new_v4();
+        let (name, other_name) = (format!("Purged {}", suffix), format!("Other {}", suffix));
+        let (pipeline_id, other_id) = (format!("purge-{}", suffix), format!("other-{}", suffix));
+        save_pipeline(&pipeline_id, &name, r#"{"nodes":[],"edges":[]}"#).unwrap();
+        save_pipeline(&other_id, &other_name, r#"{"nodes":[],"edges":[]}"#).unwrap();
+
+        let artifacts_dir = get_artifacts_dir().unwrap();
+        let mut run_ids = Vec::new();
+        for run_name in [&name, &name, &other_name] {
+            let run_id = format!("run-{}", uuid::Uuid::new_v4());
+            create_run(&run_id, run_name, "{}", None).unwrap();
+            std::fs::create_dir_all(artifacts_dir.join(&run_id)).unwrap();
+            std::fs::write(artifacts_dir.join(&run_id).join("model.joblib"), b"model").unwrap();
+            run_ids.push(run_id);
+        }
+        for chunk_id in ["c1", "c2", "c3"] {
+            insert_test_chunk(&pipeline_id, &format!("{}-node", pipeline_id), chunk_id, "nomic-embed-text", 4);
+        }
+        insert_test_chunk(&other_id, &format!("{}-node", other_id), "c1", "nomic-embed-text", 4);
+
+        let summary = purge_pipeline(&pipeline_id, &name).unwrap();
+        assert_eq!(
+            summary,
+            PurgeSummary { pipelines_deleted: 1, runs_deleted: 2, chunks_deleted: 3 }
+        );
+        assert!(load_pipeline(&pipeline_id).unwrap().is_none());
+        for run_id in &run_ids[..2] {
+            assert!(get_run(run_id).unwrap().is_none());
+            assert!(!artifacts_dir.join(run_id).exists());
+        }
+        assert_eq!(get_rag_status(&pipeline_id).unwrap().chunk_count, 0);
+
+        // The other pipeline is untouched
+        assert!(load_pipeline(&other_id).unwrap().is_some());
+        assert!(get_run(&run_ids[2]).unwrap().is_some());
+        assert!(artifacts_dir.join(&run_ids[2]).join("model.joblib").exists());
+        assert_eq!(get_rag_status(&other_id).unwrap().chunk_count, 1);
+
+        // Purging again finds nothing left
+        assert_eq!(
+            purge_pipeline(&pipeline_id, &name).unwrap(),
+            PurgeSummary { pipelines_deleted: 0, runs_deleted: 0, chunks_deleted: 0 }
+        );
+        purge_pipeline(&other_id, &other_name).unwrap();
+    }
+
+    #[test]
+    fn test_purge_failed_runs() {
+        // A private database, so other tests' failed runs can't be purged
+        // here or have this test purge theirs
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        let days_ago = |days: i64| (chrono::Utc::now() - chrono::Duration::days(days)).to_rfc3339();
+        for (id, status, age) in [
+            ("old-failed", "failed", 40),
+            ("new-failed", "failed", 2),
+            ("old-completed", "completed", 40),
+            ("old-running", "running", 40),
+        ] {
+            conn.execute(
+                "INSERT INTO runs (id, pipeline_name, status, started_at) VALUES (?1, 'Purge Pipeline', ?2, ?3)",
+                rusqlite::params![id, status, days_ago(age)],
+            )
+            .unwrap();
+        }
+        let remaining = |conn: &Connection| -> Vec<String> {
+            let mut stmt = conn.prepare("SELECT id FROM runs ORDER BY id").unwrap();
+            let ids = stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<Vec<_>>>().unwrap();
+            ids
+        };
+
+        assert_eq!(delete_failed_runs(&mut conn, 30).unwrap(), vec!["old-failed".to_string()]);
+        assert_eq!(remaining(&conn), vec!["new-failed", "old-completed", "old-running"]);
+
+        // A shorter cutoff reaches the recent failure, and still nothing else
+        assert_eq!(delete_failed_runs(&mut conn, 1).unwrap(), vec!["new-failed".to_string()]);
+        assert_eq!(remaining(&conn), vec!["old-completed", "old-running"]);
+        assert!(delete_failed_runs(&mut conn, 1).unwrap().is_empty());
+
+        // Only the purged runs' artifacts are removed
+        setup_test_db();
+        let artifacts_dir = get_artifacts_dir().unwrap();
+        let purged = format!("purged-{}", uuid::Uuid::new_v4());
+        let kept = format!("kept-{}", uuid::Uuid::new_v4());
+        for run_id in [&purged, &kept] {
+            std::fs::create_dir_all(artifacts_dir.join(run_id)).unwrap();
+        }
+        remove_run_artifacts(std::slice::from_ref(&purged));
+        assert!(!artifacts_dir.join(&purged).exists());
+        assert!(artifacts_dir.join(&kept).exists());
+        std::fs::remove_dir_all(artifacts_dir.join(&kept)).unwrap();
+    }
+
+    #[test]
+    fn test_compress_run_artifacts_round_trip() {
+        setup_test_db();
+        let artifacts_dir = get_artifacts_dir().unwrap();
+        let run_id = format!("run-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Compressed Run", "{}", None).unwrap();
+        let run_dir = artifacts_dir.join(&run_id);
+        std::fs::create_dir_all(run_dir.join("plots")).unwrap();
+        let files: Vec<(&str, Vec<u8>)> = vec![
+            ("model.joblib", (0..=255u8).cycle().take(10_000).collect()),
+            ("metrics.json", br#"{"accuracy": 0.91}"#.to_vec()),
+            ("plots/roc.png", vec![0x89, b'P', b'N', b'G', 0, 0, 0xff]),
+            ("empty.txt", Vec::new()),
+        ];
+        for (name, bytes) in &files {
+            std::fs::write(run_dir.join(name), bytes).unwrap();
+        }
+
+        compress_run_artifacts(&run_id).unwrap();
+        let archive = run_artifacts_archive(&artifacts_dir, &run_id);
+        assert!(archive.exists());
+        assert!(!run_dir.exists());
+        assert!(run_artifacts_compressed(&run_id).unwrap());
+        assert!(compress_run_artifacts(&run_id).unwrap_err().to_string().contains("already compressed"));
+        let err = register_version_from_run("v", "m", &run_id, "joblib", true).unwrap_err().to_string();
+        assert!(err.contains("decompress"), "{}", err);
+
+        decompress_run_artifacts(&run_id).unwrap();
+        assert!(!archive.exists());
+        assert!(!run_artifacts_compressed(&run_id).unwrap());
+        for (name, bytes) in &files {
+            assert_eq!(&std::fs::read(run_dir.join(name)).unwrap(), bytes, "{}", name);
+        }
+        assert!(decompress_run_artifacts(&run_id).unwrap_err().to_string().contains("not compressed"));
+
+        // Deleting a run also removes its archive
+        compress_run_artifacts(&run_id).unwrap();
+        delete_run(&run_id).unwrap();
+        assert!(!archive.exists());
+        assert!(compress_run_artifacts(&run_id).unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_register_version_from_run() {
+        setup_test_db();
+        let model_id = create_test_model("from-run");
+        let run_id = format!("run-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Artifact Run", "{}", None).unwrap();
+        save_run_metrics(
+            &run_id,
+            &[
+                Metric { name: "accuracy".to_string(), value: Some(0.91), value_json: None, kind: None },
+                Metric {
+                    name: "feature_names".to_string(),
+                    value: None,
+                    value_json: Some(r#"["age","income"]"#.to_string()),
+                    kind: None,
+                },
+            ],
+        )
+        .unwrap();
+
+        // No artifact yet
+        let err = register_version_from_run("unused", &model_id, &run_id, "joblib", false).unwrap_err().to_string();
+        assert!(err.contains("No .joblib model artifact"), "{}", err);
+
+        let run_dir = get_artifacts_dir().unwrap().join(&run_id);
+        std::fs::create_dir_all(&run_dir).unwrap();
+        std::fs::write(run_dir.join("metrics.json"), b"{}").unwrap();
+        std::fs::write(run_dir.join("model.joblib"), b"fake model").unwrap();
+
+        let version_id = uuid::Uuid::new_v4().to_string();
+        assert_eq!(register_version_from_run(&version_id, &model_id, &run_id, "joblib", false).unwrap(), 1);
+
+        let version = get_model_version(&version_id).unwrap().unwrap();
+        assert_eq!(version.run_id.as_deref(), Some(run_id.as_str()));
+        assert_eq!(std::fs::read(&version.file_path).unwrap(), b"fake model");
+        let snapshot: serde_json::Value = serde_json::from_str(version.metrics_snapshot.as_deref().unwrap()).unwrap();
+        assert_eq!(snapshot, serde_json::json!({"accuracy": 0.91}));
+        assert_eq!(version.feature_names.as_deref(), Some(r#"["age","income"]"#));
+        assert_eq!(version.n_features, Some(2));
+
+        // A failed insert rolls back and removes the copied file
+        assert!(register_version_from_run(&version_id, &model_id, &run_id, "joblib", false).is_err());
+        assert_eq!(list_model_versions(&model_id).unwrap().len(), 1);
+        assert!(!get_models_dir().unwrap().join(&model_id).join("v2").exists());
+
+        // A different format needs its own artifact
+        assert!(register_version_from_run("unused", &model_id, &run_id, "onnx", false).is_err());
+    }
+
+    #[test]
+    fn test_merge_params_into_pipeline() {
+        let pipeline = r#"{
+            "nodes": [
+                {"id": "loader", "type": "dataLoader", "data": {"label": "Data"}},
+                {"id": "plain", "type": "trainer", "data": {"label": "Trainer"}},
+                {"id": "tuned", "type": "trainer", "data": {
+                    "label": "Tuner",
+                    "trainerMode": "tune",
+                    "hyperparameters": {"n_estimators": 10, "random_state": 42},
+                    "tuningConfig": {"searchSpace": {"n_estimators": {}, "max_depth": {}}}
+                }}
+            ],
+            "edges": []
+        }"#;
+        let params: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(r#"{"n_estimators": 200, "max_depth": 7, "bogus": 1}"#).unwrap();
+
+        let merged: serde_json::Value =
+            serde_json::from_str(&merge_params_into_pipeline(pipeline, &params).unwrap()).unwrap();
+        // Tuning trainer gets in-space params; existing values are kept or overwritten
+        assert_eq!(
+            merged["nodes"][2]["data"]["hyperparameters"],
+            serde_json::json!({"n_estimators": 200, "max_depth": 7, "random_state": 42})
+        );
+        assert!(merged["nodes"][1]["data"].get("hyperparameters").is_none());
+
+        // Without a search space every param applies, to the first trainer
+        let simple = r#"{"nodes":[{"id":"t","type":"trainer"}],"edges":[]}"#;
+        let merged: serde_json::Value =
+            serde_json::from_str(&merge_params_into_pipeline(simple, &params).unwrap()).unwrap();
+        assert_eq!(merged["nodes"][0]["data"]["hyperparameters"]["bogus"], 1);
+
+        let no_trainer = r#"{"nodes":[{"id":"l","type":"dataLoader"}],"edges":[]}"#;
+        assert!(merge_params_into_pipeline(no_trainer, &params).unwrap_err().to_string().contains("no trainer"));
+    }
+
+    #[test]
+    fn test_apply_best_trial_without_trials() {
+        setup_test_db();
+        let err = apply_best_trial_to_pipeline(&uuid::Uuid::new_v4().to_string(), "missing").unwrap_err().to_string();
+        assert!(err.contains("no scored trials"), "{}", err);
+    }
+
+    #[test]
+    fn test_tuning_session_seed_round_trips() {
+        setup_test_db();
+        let run_id = format!("tuning-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Tuning Run", "{}", None).unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        create_tuning_session(&session_id, &run_id, "random", "{}", Some(20), 5, "r2", Some(1234)).unwrap();
+
+        assert_eq!(get_tuning_session(&session_id).unwrap().unwrap().seed, Some(1234));
+        assert_eq!(get_tuning_session_by_run(&run_id).unwrap().unwrap().seed, Some(1234));
+
+        // Sessions created without a seed (e.g. grid search) keep it empty
+        let grid_run = format!("tuning-{}", uuid::Uuid::new_v4());
+        create_run(&grid_run, "Grid Run", "{}", None).unwrap();
+        let grid_session = uuid::Uuid::new_v4().to_string();
+        create_tuning_session(&grid_session, &grid_run, "grid", "{}", None, 3, "accuracy", None).unwrap();
+        assert_eq!(get_tuning_session(&grid_session).unwrap().unwrap().seed, None);
+    }
+
+    #[test]
+    fn test_resaving_tuning_trial_updates_it() {
+        setup_test_db();
+        let run_id = format!("tuning-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Tuning Run", "{}", None).unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        create_tuning_session(&session_id, &run_id, "tpe", "{}", Some(10), 3, "accuracy", None).unwrap();
+
+        let first_id = uuid::Uuid::new_v4().to_string();
+        let stored = create_tuning_trial(&first_id, &session_id, 0, r#"{"C": 1.0}"#, Some(0.7), Some(100), "completed").unwrap();
+        assert_eq!(stored, first_id);
+
+        // Retried save of the same trial number with newer values
+        let retry_id = uuid::Uuid::new_v4().to_string();
+        let stored = create_tuning_trial(&retry_id, &session_id, 0, r#"{"C": 2.0}"#, Some(0.9), Some(120), "completed").unwrap();
+        assert_eq!(stored, first_id);
+
+        let trials = list_tuning_trials(&session_id).unwrap();
+        assert_eq!(trials.len(), 1);
+        assert_eq!(trials[0].id, first_id);
+        assert_eq!(trials[0].hyperparameters, r#"{"C": 2.0}"#);
+        assert_eq!(trials[0].score, Some(0.9));
+        assert_eq!(trials[0].duration_ms, Some(120));
+
+        // Other trial numbers are separate rows
+        create_tuning_trial(&retry_id, &session_id, 1, "{}", Some(0.5), None, "completed").unwrap();
+        assert_eq!(list_tuning_trials(&session_id).unwrap().len(), 2);
+        assert_eq!(get_best_trial(&session_id).unwrap().unwrap().score, Some(0.9));
+    }
+
+    #[test]
+    fn test_export_tuning_trials_csv() {
+        setup_test_db();
+        let run_id = format!("tuning-csv-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Tuning Run", "{}", None).unwrap();
+        let session_id = uuid::Uuid::new_v4().to_string();
+        create_tuning_session(&session_id, &run_id, "tpe", "{}", Some(3), 3, "f1_macro", None).unwrap();
+
+        let trial = |n: i32, params: &str, score: Option<f64>, duration: Option<i64>| {
+            create_tuning_trial(&uuid::Uuid::new_v4().to_string(), &session_id, n, params, score, duration, "completed")
+                .unwrap();
+        };
+        trial(0, r#"{"n_estimators": 100, "max_depth": 5}"#, Some(0.81), Some(1200));
+        trial(1, r#"{"n_estimators": 200, "criterion": "gini"}"#, Some(0.85), None);
+        trial(2, r#"{"max_features": "sqrt, log2"}"#, None, Some(30));
+
+        let dest = std::env::temp_dir().join(format!("trials-{}.csv", session_id));
+        let rows = export_tuning_trials_csv(&session_id, dest.to_str().unwrap()).unwrap();
+        assert_eq!(rows, 3);
+
+        let csv = std::fs::read_to_string(&dest).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "# sampler: tpe, scoring_metric: f1_macro");
+        assert_eq!(
+            lines[1],
+            "trial_number,score,status,duration_ms,params_max_depth,params_n_estimators,params_criterion,params_max_features"
+        );
+        assert_eq!(lines[2], "0,0.81,completed,1200,5,100,,");
+        assert_eq!(lines[3], "1,0.85,completed,,,200,gini,");
+        assert_eq!(lines[4], "2,,completed,30,,,,\"sqrt, log2\"");
+        assert_eq!(lines.len(), 5);
+
+        std::fs::remove_file(&dest).unwrap();
+        assert!(export_tuning_trials_csv("no-such-session", dest.to_str().unwrap()).is_err());
+        let unwritable = dest.with_extension("missing-dir").join("trials.csv");
+        let err = export_tuning_trials_csv(&session_id, unwritable.to_str().unwrap()).unwrap_err().to_string();
+        assert!(err.starts_with("Failed to write"), "{}", err);
+    }
+
+    #[test]
+    fn test_smooth_series() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let column = |points: &[SmoothedPoint], f: fn(&SmoothedPoint) -> f64| -> Vec<f64> {
+            points.iter().map(f).collect()
+        };
+
+        let points = smooth_series(&values, 1, SmoothingMethod::Simple);
+        assert_eq!(column(&points, |p| p.smoothed), values.to_vec());
+        assert_eq!(column(&points, |p| p.window_min), values.to_vec());
+
+        let points = smooth_series(&values, 3, SmoothingMethod::Simple);
+        assert_eq!(column(&points, |p| p.smoothed), vec![1.0, 1.5, 2.0, 3.0, 4.0]);
+        assert_eq!(column(&points, |p| p.window_min), vec![1.0, 1.0, 1.0, 2.0, 3.0]);
+        assert_eq!(column(&points, |p| p.window_max), values.to_vec());
+        assert_eq!(points[4].step, 4);
+        assert_eq!(points[4].value, 5.0);
+
+        // Window longer than the series: running mean of everything so far
+        let points = smooth_series(&values, 10, SmoothingMethod::Simple);
+        assert_eq!(column(&points, |p| p.smoothed), vec![1.0, 1.5, 2.0, 2.5, 3.0]);
+        assert!(points.iter().all(|p| p.window_min == 1.0));
+
+        // alpha = 2 / (3 + 1) = 0.5
+        let points = smooth_series(&values, 3, SmoothingMethod::Exponential);
+        assert_eq!(column(&points, |p| p.smoothed), vec![1.0, 1.5, 2.25, 3.125, 4.0625]);
+
+        assert!(smooth_series(&[], 3, SmoothingMethod::Simple).is_empty());
+    }
+
+    #[test]
+    fn test_get_run_metric_smoothed() {
+        setup_test_db();
+        let run_id = uuid::Uuid::new_v4().to_string();
+        create_run(&run_id, "Smoothing", "{}", None).unwrap();
+        let metric = |name: &str, value: Option<f64>, json: Option<&str>| Metric {
+            name: name.to_string(),
+            value,
+            value_json: json.map(str::to_string),
+            kind: None,
+        };
+        save_run_metrics(
+            &run_id,
+            &[
+                metric("loss_curve", None, Some("[0.9, 0.5, 0.4, 0.2]")),
+                metric("accuracy", Some(0.9), None),
+            ],
+        )
+        .unwrap();
+
+        let smoothed = get_run_metric_smoothed(&run_id, "loss_curve", 2, SmoothingMethod::Simple).unwrap();
+        let means: Vec<f64> = smoothed.points.iter().map(|p| (p.smoothed * 100.0).round() / 100.0).collect();
+        assert_eq!(means, vec![0.9, 0.7, 0.45, 0.3]);
+        assert_eq!(smoothed.points[3].window_max, 0.4);
+
+        assert!(get_run_metric_smoothed(&run_id, "loss_curve", 0, SmoothingMethod::Simple).is_err());
+        let err = get_run_metric_smoothed(&run_id, "accuracy", 2, SmoothingMethod::Simple).unwrap_err().to_string();
+        assert!(err.contains("not a series"), "{}", err);
+        let err = get_run_metric_smoothed(&run_id, "missing", 2, SmoothingMethod::Simple).unwrap_err().to_string();
+        assert!(err.contains("no metric 'missing'"), "{}", err);
+    }
+
+    #[test]
+    fn test_diff_confusion_matrices() {
+        setup_test_db();
+        let save_run = |matrix: &str, labels: Option<&str>| {
+            let run_id = uuid::Uuid::new_v4().to_string();
+            create_run(&run_id, "Confusion Diff", "{}", None).unwrap();
+            let mut metrics = vec![Metric {
+                name: CONFUSION_MATRIX_METRIC.to_string(),
+                value: None,
+                value_json: Some(matrix.to_string()),
+                kind: None,
+            }];
+            if let Some(labels) = labels {
+                metrics.push(Metric {
+                    name: CLASS_LABELS_METRIC.to_string(),
+                    value: None,
+                    value_json: Some(labels.to_string()),
+                    kind: None,
+                });
+            }
+            save_run_metrics(&run_id, &metrics).unwrap();
+            run_id
+        };
+
+        let a = save_run("[[5, 1], [2, 2]]", Some(r#"["cat", "dog"]"#));
+        let b = save_run("[[6, 0], [1, 3]]", Some(r#"["cat", "dog"]"#));
+        let diff = diff_confusion_matrices(&a, &b).unwrap();
+        assert_eq!(diff.labels, vec!["cat", "dog"]);
+        assert_eq!(diff.delta, vec![vec![1, -1], vec![-1, 1]]);
+        assert_eq!(
+            diff.per_class[1],
+            ClassChange {
+                label: "dog".to_string(),
+                correct_a: 2,
+                correct_b: 3,
+                correct_delta: 1,
+                recall_a: Some(0.5),
+                recall_b: Some(0.75),
+            }
+        );
+
+        // Different labels, or a different number of classes, can't be compared
+        let c = save_run("[[6, 0], [1, 3]]", Some(r#"["cat", "bird"]"#));
+        assert_eq!(
+            diff_confusion_matrices(&a, &c).unwrap_err().to_string(),
+            "Class labels differ: [cat, dog] vs [cat, bird]"
+        );
+        let d = save_run("[[1, 0, 0], [0, 1, 0], [0, 0, 1]]", None);
+        assert!(diff_confusion_matrices(&a, &d).unwrap_err().to_string().starts_with("Class labels differ"));
+
+        let no_matrix = uuid::Uuid::new_v4().to_string();
+        create_run(&no_matrix, "Confusion Diff", "{}", None).unwrap();
+        assert_eq!(
+            diff_confusion_matrices(&a, &no_matrix).unwrap_err().to_string(),
+            format!("Run {} has no confusion matrix", no_matrix)
+        );
+    }
+
+    #[test]
+    fn test_get_latest_metric_for_runs() {
+        setup_test_db();
+        let metric = |name: &str, value: Option<f64>, json: Option<&str>| Metric {
+            name: name.to_string(),
+            value,
+            value_json: json.map(String::from),
+            kind: None,
+        };
+        let run_ids: Vec<String> = (0..4).map(|i| format!("sparkline-{}-{}", i, uuid::Uuid::new_v4())).collect();
+        for run_id in &run_ids {
+            create_run(run_id, "Sparklines", "{}", None).unwrap();
+        }
+        save_run_metrics(&run_ids[0], &[metric("accuracy", Some(0.91), None), metric("f1", Some(0.5), None)]).unwrap();
+        save_run_metrics(&run_ids[1], &[metric("accuracy", Some(0.84), None)]).unwrap();
+        save_run_metrics(&run_ids[2], &[metric("f1", Some(0.7), None)]).unwrap();
+        save_run_metrics(&run_ids[3], &[metric("accuracy", None, Some("[0.8, 0.9]"))]).unwrap();
+
+        let mut requested = run_ids.clone();
+        requested.push("missing-run".to_string());
+        let values = get_latest_metric_for_runs(&requested, "accuracy").unwrap();
+        assert_eq!(values.len(), 5);
+        assert_eq!(values[&run_ids[0]], Some(0.91));
+        assert_eq!(values[&run_ids[1]], Some(0.84));
+        assert_eq!(values[&run_ids[2]], None);
+        assert_eq!(values[&run_ids[3]], None); // non-scalar
+        assert_eq!(values["missing-run"], None);
+
+        assert_eq!(get_latest_metric_for_runs(&run_ids, "f1").unwrap()[&run_ids[2]], Some(0.7));
+        assert!(get_latest_metric_for_runs(&[], "accuracy").unwrap().is_empty());
+
+        // More ids than fit in one query
+        let many: Vec<String> = (0..METRIC_BATCH_SIZE + 1)
+            .map(|i| if i == METRIC_BATCH_SIZE { run_ids[1].clone() } else { format!("absent-{}", i) })
+            .collect();
+        assert_eq!(get_latest_metric_for_runs(&many, "accuracy").unwrap()[&run_ids[1]], Some(0.84));
+    }
+
+    #[test]
+    fn test_save_run_metrics_batch() {
+        setup_test_db();
+        let metric = |i: usize| Metric { name: format!("loss_step_{}", i), value: Some(i as f64), value_json: None, kind: None };
+        let batch: Vec<Metric> = (0..1000).map(metric).collect();
+
+        let run_id = format!("batch-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Batch", "{}", None).unwrap();
+        save_run_metrics(&run_id, &batch).unwrap();
+        assert_eq!(get_run_metrics(&run_id).unwrap().len(), 1000);
+
+        // One row change per metric and the transaction closed afterwards,
+        // counted on a private connection so other tests' writes don't show up
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        conn.execute(
+            "INSERT INTO runs (id, pipeline_name, status, started_at) VALUES ('r', 'Batch', 'running', datetime('now'))",
+            [],
+        )
+        .unwrap();
+        let total_changes =
+            |conn: &Connection| -> i64 { conn.query_row("SELECT total_changes()", [], |row| row.get(0)).unwrap() };
+        let before = total_changes(&conn);
+        insert_run_metrics(&mut conn, "r", &batch).unwrap();
+        assert_eq!(total_changes(&conn) - before, 1000);
+        assert!(conn.is_autocommit());
+
+        // A failure partway through writes nothing
+        let atomic_run = format!("batch-atomic-{}", uuid::Uuid::new_v4());
+        create_run(&atomic_run, "Batch", "{}", None).unwrap();
+        DB.get().unwrap().lock().unwrap().execute(
+            &format!(
+                "CREATE TEMP TRIGGER poison_{0} BEFORE INSERT ON run_metrics
+                 WHEN NEW.run_id = '{1}' AND NEW.name = 'loss_step_999'
+                 BEGIN SELECT RAISE(ABORT, 'poisoned'); END",
+                atomic_run.replace('-', "_"), atomic_run
+            ),
+            [],
+        )
+        .unwrap();
+        assert!(save_run_metrics(&atomic_run, &batch).is_err());
+        assert!(get_run_metrics(&atomic_run).unwrap().is_empty());
+
+        // Buffered metrics wait for a flush, and stay buffered if it fails
+        assert_eq!(buffer_run_metrics(&atomic_run, batch[990..].to_vec()).unwrap(), 0);
+        assert!(get_run_metrics(&atomic_run).unwrap().is_empty());
+        assert!(flush_run_metrics(&atomic_run).is_err());
+        DB.get().unwrap().lock().unwrap()
+            .execute(&format!("DROP TRIGGER poison_{}", atomic_run.replace('-', "_")), [])
+            .unwrap();
+        assert_eq!(flush_run_metrics(&atomic_run).unwrap(), 10);
+        assert_eq!(get_run_metrics(&atomic_run).unwrap().len(), 10);
+        assert_eq!(flush_run_metrics(&atomic_run).unwrap(), 0);
+
+        // ...or until the run's buffer fills
+        let buffered_run = format!("batch-buffered-{}", uuid::Uuid::new_v4());
+        create_run(&buffered_run, "Batch", "{}", None).unwrap();
+        assert_eq!(buffer_run_metrics(&buffered_run, batch[..METRIC_BUFFER_FLUSH_SIZE - 1].to_vec()).unwrap(), 0);
+        assert_eq!(buffer_run_metrics(&buffered_run, vec![metric(1000)]).unwrap(), METRIC_BUFFER_FLUSH_SIZE);
+        assert_eq!(get_run_metrics(&buffered_run).unwrap().len(), METRIC_BUFFER_FLUSH_SIZE);
+
+        // Deleting a run drops what it still had buffered
+        assert_eq!(buffer_run_metrics(&buffered_run, batch[..10].to_vec()).unwrap(), 0);
+        delete_run(&buffered_run).unwrap();
+        assert_eq!(flush_run_metrics(&buffered_run).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_metric_kinds_round_trip() {
+        setup_test_db();
+        let run_id = format!("kinds-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Metric Kinds", "{}", None).unwrap();
+
+        let metric = |name: &str, value: Option<f64>, json: Option<&str>, kind: Option<&str>| Metric {
+            name: name.to_string(),
+            value,
+            value_json: json.map(String::from),
+            kind: kind.map(String::from),
+        };
+        save_run_metrics(
+            &run_id,
+            &[
+                metric("accuracy", Some(0.9), None, Some("scalar")),
+                metric("feature_importance", None, Some("[0.2, 0.8]"), Some("array")),
+                metric("confusion_matrix", None, Some("[[5, 1], [0, 4]]"), Some("matrix")),
+                metric("roc_curve", None, Some(r#"{"path": "roc.png"}"#), Some("artifact")),
+                // Inferred when the caller doesn't say
+                metric("f1", Some(0.8), None, None),
+                metric("residuals", None, Some("[1, 2]"), None),
+                metric("cm_inferred", None, Some(" [[1]]"), None),
+            ],
+        )
+        .unwrap();
+
+        let kinds: std::collections::HashMap<String, Option<String>> = get_run_metrics(&run_id)
+            .unwrap()
+            .into_iter()
+            .map(|m| (m.name, m.kind))
+            .collect();
+        let kind = |name: &str| kinds[name].as_deref();
+        assert_eq!(kind("accuracy"), Some("scalar"));
+        assert_eq!(kind("feature_importance"), Some("array"));
+        assert_eq!(kind("confusion_matrix"), Some("matrix"));
+        assert_eq!(kind("roc_curve"), Some("artifact"));
+        assert_eq!(kind("f1"), Some("scalar"));
+        assert_eq!(kind("residuals"), Some("array"));
+        assert_eq!(kind("cm_inferred"), Some("matrix"));
+
+        // Unknown kinds are refused whether saved directly or buffered
+        let bad = [metric("loss", Some(0.1), None, Some("histogram"))];
+        let err = save_run_metrics(&run_id, &bad).unwrap_err().to_string();
+        assert!(err.contains("Invalid kind 'histogram' for metric 'loss'"), "{}", err);
+        assert!(buffer_run_metrics(&run_id, bad.to_vec()).is_err());
+        assert_eq!(flush_run_metrics(&run_id).unwrap(), 0);
+        assert_eq!(get_run_metrics(&run_id).unwrap().len(), 7);
+    }
+
+    #[test]
+    fn test_registry_summary() {
+        // A private database so the totals aren't affected by other tests
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+
+        let empty = registry_summary(&conn).unwrap();
+        assert_eq!((empty.model_count, empty.version_count, empty.total_file_bytes), (0, 0, 0));
+        assert_eq!(empty.stage_counts.len(), MODEL_STAGES.len());
+        assert!(empty.stage_counts.values().all(|&count| count == 0));
+        assert!(empty.latest_model.is_none());
+
+        conn.execute_batch(
+            "INSERT INTO models (id, name, updated_at) VALUES
+                ('m-churn', 'churn', '2024-01-01 00:00:00'),
+                ('m-price', 'price', '2024-03-01 00:00:00'),
+                ('m-empty', 'empty', '2024-02-01 00:00:00');
+             INSERT INTO model_versions (id, model_id, version, file_path, file_size, format, stage) VALUES
+                ('v1', 'm-churn', 1, '/models/churn-1.joblib', 1000, 'joblib', 'archived'),
+                ('v2', 'm-churn', 2, '/models/churn-2.joblib', 1500, 'joblib', 'production'),
+                ('v3', 'm-price', 1, '/models/price-1.onnx', 250, 'onnx', 'staging'),
+                ('v4', 'm-price', 2, '/models/price-2.onnx', NULL, 'onnx', 'none');",
+        )
+        .unwrap();
+
+        let summary = registry_summary(&conn).unwrap();
+        assert_eq!(summary.model_count, 3);
+        assert_eq!(summary.version_count, 4);
+        assert_eq!(summary.total_file_bytes, 2750);
+        for stage in MODEL_STAGES {
+            assert_eq!(summary.stage_counts[stage], 1, "stage {}", stage);
+        }
+        let latest = summary.latest_model.unwrap();
+        assert_eq!(latest.name, "price");
+        assert_eq!(latest.version_count, 2);
+        assert_eq!(latest.latest_version, Some(2));
+        assert_eq!(latest.production_version, None);
+    }
+
+    // Held by tests that leave rows pointing at missing files, or that
+    // repair such rows across the whole registry
+    static MODEL_FILES_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_relocate_paths_after_data_dir_move() {
+        setup_test_db();
+        let _serial = MODEL_FILES_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let app_data_dir = APP_DATA_DIR.get().unwrap().clone();
+        let model_id = create_test_model("relocate");
+        let version_id = register_test_version(&model_id, None);
+        let raw_path = |column: &str| -> Option<String> {
+            let conn = DB.get().unwrap().lock().unwrap();
+            conn.query_row(
+                &format!("SELECT {} FROM model_versions WHERE id = ?1", column),
+                [&version_id],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        // New versions are stored relative to the app data directory
+        let stored = raw_path("file_path").unwrap();
+        assert!(Path::new(&stored).is_relative(), "{}", stored);
+        let resolved = get_model_version(&version_id).unwrap().unwrap().file_path;
+        assert_eq!(Path::new(&resolved), app_data_dir.join(&stored));
+        assert!(Path::new(&resolved).exists());
+
+        // A row written on another machine, before paths were relative
+        let old_root = Path::new("/Users/someone/Library/Application Support/mlops-desktop");
+        let old_file = old_root.join(&stored).to_string_lossy().to_string();
+        let old_onnx = old_root.join(&stored).with_extension("onnx").to_string_lossy().to_string();
+        DB.get()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .execute(
+                "UPDATE model_versions SET file_path = ?2, onnx_path = ?3 WHERE id = ?1",
+                rusqlite::params![version_id, old_file, old_onnx],
+            )
+            .unwrap();
+        assert_eq!(get_model_version(&version_id).unwrap().unwrap().file_path, old_file);
+
+        assert!(relocate_paths(&app_data_dir).unwrap() >= 1);
+        assert_eq!(raw_path("file_path").unwrap(), stored);
+        let version = get_model_version(&version_id).unwrap().unwrap();
+        assert_eq!(version.file_path, resolved);
+        assert!(Path::new(&version.file_path).exists());
+        assert_eq!(
+            version.onnx_path.map(std::path::PathBuf::from),
+            Some(app_data_dir.join(&stored).with_extension("onnx"))
+        );
+        assert_eq!(get_model_file_path(&version_id).unwrap(), Some(resolved));
+
+        // Paths outside any models/<model_id> directory are left alone
+        assert_eq!(relocated_path("/tmp/elsewhere/model.pkl", &model_id, &app_data_dir), None);
+    }
+
+    #[test]
+    fn test_audit_and_repair_model_files() {
+        setup_test_db();
+        let _serial = MODEL_FILES_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let model_id = create_test_model("audit");
+        let intact = register_test_version(&model_id, None);
+        let gone = register_test_version(&model_id, None);
+        let stale_export = register_test_version(&model_id, None);
+        promote_model(&gone, "production").unwrap();
+        let gone_promoted_at = get_model_version(&gone).unwrap().unwrap().promoted_at;
+        let ours = |audits: Vec<FileAudit>| -> Vec<FileAudit> {
+            audits.into_iter().filter(|a| a.model_id == model_id).collect()
+        };
+
+        assert!(ours(audit_model_files().unwrap()).is_empty());
+
+        let gone_file = get_model_file_path(&gone).unwrap().unwrap();
+        std::fs::remove_file(&gone_file).unwrap();
+        let onnx = Path::new(&get_model_file_path(&stale_export).unwrap().unwrap()).with_extension("onnx");
+        update_model_version_export_path(&stale_export, Some(&onnx.to_string_lossy()), None).unwrap();
+
+        let audits = ours(audit_model_files().unwrap());
+        assert_eq!(audits.len(), 2);
+        assert_eq!(audits[0].version_id, gone);
+        assert!(audits[0].model_file_missing);
+        assert_eq!(audits[0].stage, "production");
+        assert_eq!(audits[0].missing_paths, vec![gone_file]);
+        assert_eq!(audits[1].version_id, stale_export);
+        assert!(!audits[1].model_file_missing);
+        assert!(audits[1].onnx_missing);
+        assert!(!audits[1].coreml_missing);
+
+        // Archive keeps the row; the dangling export path is cleared
+        let repaired = ours(repair_model_files(RepairStrategy::Archive).unwrap());
+        assert_eq!(repaired.len(), 2);
+        let archived = get_model_version(&gone).unwrap().unwrap();
+        assert_eq!(archived.stage, "archived");
+        assert_eq!(archived.promoted_at, gone_promoted_at);
+        assert_eq!(get_model_version(&stale_export).unwrap().unwrap().onnx_path, None);
+        assert_eq!(get_model_version(&intact).unwrap().unwrap().stage, "none");
+
+        // The archived version is still reported until deleted
+        let audits = ours(audit_model_files().unwrap());
+        assert_eq!(audits.len(), 1);
+        assert_eq!(audits[0].stage, "archived");
+        let repaired = ours(repair_model_files(RepairStrategy::Delete).unwrap());
+        assert_eq!(repaired.len(), 1);
+        assert!(get_model_version(&gone).unwrap().is_none());
+        assert!(get_model_version(&intact).unwrap().is_some());
+        assert!(ours(audit_model_files().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_classification_report() {
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        let close = |a: f64, b: f64| assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+
+        // Multiclass: cat 2/3 found, one dog called bird, one cat called dog
+        let labels = strings(&["cat", "cat", "cat", "dog", "dog", "bird"]);
+        let predictions = strings(&["cat", "cat", "dog", "dog", "bird", "bird"]);
+        let report = classification_report(&labels, &predictions).unwrap();
+        let classes: Vec<&str> = report.classes.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(classes, vec!["bird", "cat", "dog"]);
+        let (bird, cat, dog) = (&report.classes[0], &report.classes[1], &report.classes[2]);
+        close(cat.precision, 1.0);
+        close(cat.recall, 2.0 / 3.0);
+        close(cat.f1, 0.8);
+        assert_eq!(cat.support, 3);
+        close(dog.precision, 0.5);
+        close(dog.recall, 0.5);
+        close(bird.precision, 0.5);
+        close(bird.recall, 1.0);
+        close(bird.f1, 2.0 / 3.0);
+        close(report.macro_avg.precision, 2.0 / 3.0);
+        close(report.macro_avg.recall, 13.0 / 18.0);
+        close(report.macro_avg.f1, (0.8 + 0.5 + 2.0 / 3.0) / 3.0);
+        close(report.micro_avg.f1, 4.0 / 6.0);
+        close(report.accuracy, 4.0 / 6.0);
+        assert_eq!(report.support, 6);
+
+        let err = classification_report(&labels, &predictions[..5]).unwrap_err().to_string();
+        assert!(err.contains("6 labels but 5 predictions"), "{}", err);
+        assert!(classification_report(&[], &[]).is_err());
+    }
+
+    #[test]
+    fn test_compute_classification_report_from_run() {
+        setup_test_db();
+        let run_id = format!("report-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Report Run", "{}", None).unwrap();
+        let array = |name: &str, json: &str| Metric {
+            name: name.to_string(),
+            value: None,
+            value_json: Some(json.to_string()),
+            kind: None,
+        };
+        // Binary, with float predictions against integer labels
+        save_run_metrics(
+            &run_id,
+            &[array("y_true", "[1, 0, 1, 1, 0]"), array("y_pred", "[1.0, 0.0, 0.0, 1.0, 1.0]")],
+        )
+        .unwrap();
+
+        let report = compute_classification_report(&run_id, "y_pred", "y_true").unwrap();
+        let labels: Vec<&str> = report.classes.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(labels, vec!["0", "1"]);
+        assert_eq!(report.classes[0].precision, 0.5);
+        assert_eq!(report.classes[0].recall, 0.5);
+        assert!((report.classes[1].precision - 2.0 / 3.0).abs() < 1e-9);
+        assert!((report.classes[1].recall - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.accuracy, 0.6);
+
+        let err = compute_classification_report(&run_id, "y_pred", "missing").unwrap_err().to_string();
+        assert!(err.contains("no metric 'missing'"), "{}", err);
+        delete_run(&run_id).unwrap();
+    }
+
+    #[test]
+    fn test_experiment_metric_correlations() {
+        setup_test_db();
+        let experiment_id = uuid::Uuid::new_v4().to_string();
+        create_experiment(&experiment_id, &format!("Correlations {}", experiment_id), None).unwrap();
+        let scalar = |name: &str, value: f64| Metric { name: name.to_string(), value: Some(value), value_json: None, kind: None };
+        for (i, accuracy) in [0.7, 0.8, 0.85, 0.9].into_iter().enumerate() {
+            let run_id = format!("corr-{}-{}", experiment_id, i);
+            create_run(&run_id, "Correlation Pipeline", "{}", Some(&experiment_id)).unwrap();
+            let mut metrics = vec![
+                scalar("accuracy", accuracy),
+                scalar("f1", 2.0 * accuracy - 0.5), // moves exactly with accuracy
+                scalar("error", 1.0 - accuracy),    // exactly against it
+                scalar("epochs", 10.0),             // constant
+                Metric { name: "confusion".to_string(), value: None, value_json: Some("[[1]]".to_string()), kind: None },
+            ];
+            if i < 2 {
+                metrics.push(scalar("rare", i as f64));
+            }
+            save_run_metrics(&run_id, &metrics).unwrap();
+        }
+        // Runs outside the experiment don't count
+        let outsider = format!("corr-outsider-{}", experiment_id);
+        create_run(&outsider, "Correlation Pipeline", "{}", None).unwrap();
+        save_run_metrics(&outsider, &[scalar("accuracy", 0.1), scalar("f1", 0.9)]).unwrap();
+
+        let result = get_experiment_metric_correlations(&experiment_id).unwrap();
+        assert_eq!(result.run_count, 4);
+        assert_eq!(result.columns, ["accuracy", "epochs", "error", "f1", "rare"]);
+        let cell = |a: &str, b: &str| {
+            let index = |name: &str| result.columns.iter().position(|c| c == name).unwrap();
+            result.matrix[index(a)][index(b)]
+        };
+        let close = |value: Option<f64>, expected: f64| {
+            let value = value.unwrap();
+            assert!((value - expected).abs() < 1e-9, "{} != {}", value, expected);
+        };
+        close(cell("accuracy", "f1"), 1.0);
+        close(cell("f1", "accuracy"), 1.0);
+        close(cell("accuracy", "error"), -1.0);
+        close(cell("f1", "error"), -1.0);
+        close(cell("accuracy", "accuracy"), 1.0);
+        assert_eq!(cell("accuracy", "epochs"), None); // constant
+        assert_eq!(cell("epochs", "epochs"), None);
+        assert_eq!(cell("accuracy", "rare"), None); // only two runs in common
+        assert_eq!(cell("rare", "rare"), None);
+
+        let empty = uuid::Uuid::new_v4().to_string();
+        create_experiment(&empty, &format!("Empty {}", empty), None).unwrap();
+        let result = get_experiment_metric_correlations(&empty).unwrap();
+        assert!(result.columns.is_empty() && result.matrix.is_empty());
+        assert_eq!(result.run_count, 0);
+        assert!(get_experiment_metric_correlations("no-such-experiment").is_err());
+    }
+
+    #[test]
+    fn test_hyperparameter_impact() {
+        setup_test_db();
+        let experiment_id = uuid::Uuid::new_v4().to_string();
+        create_experiment(&experiment_id, &format!("Impact {}", experiment_id), None).unwrap();
+        let scalar = |name: &str, value: f64| Metric { name: name.to_string(), value: Some(value), value_json: None, kind: None };
+        // learning_rate drives accuracy; seed and optimizer are noise;
+        // dropout was only set on two runs; tag is different on every run
+        let runs = [
+            (r#"{"learning_rate": 0.001, "seed": 7, "optimizer": "adam", "dropout": 0.1, "tag": "a"}"#, 0.95),
+            (r#"{"learning_rate": 0.01, "seed": 3, "optimizer": "sgd", "dropout": 0.5, "tag": "b"}"#, 0.85),
+            (r#"{"learning_rate": 0.1, "seed": 7, "optimizer": "adam", "tag": "c"}"#, 0.74),
+            (r#"{"learning_rate": 0.2, "seed": 3, "optimizer": "sgd", "tag": "d"}"#, 0.61),
+            (r#"{"learning_rate": 0.3, "seed": 5, "optimizer": "adam", "verbose": null, "tag": "e"}"#, 0.52),
+        ];
+        for (i, (hyperparameters, accuracy)) in runs.iter().enumerate() {
+            let run_id = format!("impact-{}-{}", experiment_id, i);
+            create_run(&run_id, "Impact Pipeline", hyperparameters, Some(&experiment_id)).unwrap();
+            save_run_metrics(&run_id, &[scalar("accuracy", *accuracy)]).unwrap();
+        }
+        // A run without the metric doesn't count
+        let unscored = format!("impact-unscored-{}", experiment_id);
+        create_run(&unscored, "Impact Pipeline", r#"{"learning_rate": 5.0}"#, Some(&experiment_id)).unwrap();
+
+        let impacts = get_hyperparameter_impact(&experiment_id, "accuracy").unwrap();
+        let names: Vec<&str> = impacts.iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(names[0], "learning_rate");
+        assert!(!names.contains(&"dropout")); // only two runs set it
+        assert!(!names.contains(&"verbose")); // null is treated as unset
+        assert!(!names.contains(&"tag")); // one run per value explains nothing
+        assert!(impacts.windows(2).all(|w| w[0].impact >= w[1].impact));
+
+        let lr = &impacts[0];
+        assert_eq!(lr.kind, HyperparamKind::Numeric);
+        assert_eq!(lr.run_count, 5);
+        assert!(lr.correlation.unwrap() < -0.9, "{:?}", lr);
+        assert!((lr.impact - lr.correlation.unwrap().powi(2)).abs() < 1e-12);
+
+        let optimizer = impacts.iter().find(|i| i.name == "optimizer").unwrap();
+        assert_eq!(optimizer.kind, HyperparamKind::Categorical);
+        assert_eq!(optimizer.correlation, None);
+        // adam mean 0.7367, sgd mean 0.73, overall 0.734: almost no signal
+        assert!(optimizer.impact < 0.01, "{:?}", optimizer);
+        let seed = impacts.iter().find(|i| i.name == "seed").unwrap();
+        assert!(seed.impact < lr.impact);
+
+        assert!(get_hyperparameter_impact(&experiment_id, "missing").unwrap().is_empty());
+        assert!(get_hyperparameter_impact("no-such-experiment", "accuracy").is_err());
+    }
+
+    #[test]
+    fn test_export_experiment_ndjson() {
+        setup_test_db();
+        let experiment_id = uuid::Uuid::new_v4().to_string();
+        create_experiment(&experiment_id, &format!("Export {}", experiment_id), Some("sweep")).unwrap();
+        let run_count = 120;
+        for i in 0..run_count {
+            let run_id = format!("export-{}-{:03}", experiment_id, i);
+            create_run(&run_id, "Export Pipeline", &format!(r#"{{"seed": {}}}"#, i), Some(&experiment_id)).unwrap();
+            save_run_metrics(
+                &run_id,
+                &[
+                    Metric { name: "accuracy".to_string(), value: Some(i as f64 / 200.0), value_json: None, kind: None },
+                    Metric { name: "loss".to_string(), value: None, value_json: Some("[0.9, 0.5]".to_string()), kind: None },
+                ],
+            )
+            .unwrap();
+        }
+
+        let dest = std::env::temp_dir().join(format!("experiment-{}.ndjson", experiment_id));
+        assert_eq!(export_experiment_ndjson(&experiment_id, dest.to_str().unwrap()).unwrap(), run_count);
+
+        let contents = std::fs::read_to_string(&dest).unwrap();
+        let lines: Vec<serde_json::Value> = contents.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines.len(), run_count + 1);
+        assert_eq!(lines[0]["type"], "experiment");
+        assert_eq!(lines[0]["id"], experiment_id.as_str());
+        assert_eq!(lines[0]["run_count"], run_count as i64);
+
+        let mut run_ids = std::collections::HashSet::new();
+        for line in &lines[1..] {
+            assert_eq!(line["type"], "run");
+            assert_eq!(line["run"]["experiment_id"], experiment_id.as_str());
+            assert_eq!(line["metrics"].as_array().unwrap().len(), 2);
+            assert_eq!(line["metrics"][1]["value_json"], "[0.9, 0.5]");
+            run_ids.insert(line["run"]["id"].as_str().unwrap().to_string());
+        }
+        assert_eq!(run_ids.len(), run_count);
+
+        assert!(export_experiment_ndjson("no-such-experiment", dest.to_str().unwrap()).is_err());
+        std::fs::remove_file(&dest).unwrap();
+        for run_id in run_ids {
+            delete_run(&run_id).unwrap();
+        }
+        delete_experiment(&experiment_id).unwrap();
+    }
+
+    #[test]
+    fn test_find_duplicate_versions() {
+        setup_test_db();
+        let model_id = create_test_model("duplicates");
+        let register = |source: &str| {
+            let version_id = uuid::Uuid::new_v4().to_string();
+            register_model_version(&version_id, &model_id, None, source, "joblib", None, None, false).unwrap();
+            version_id
+        };
+        let same = create_test_model_file(b"identical model bytes");
+        let v1 = register(&same);
+        register(&create_test_model_file(b"other model"));
+        let v3 = register(&same);
+        assert_eq!(find_duplicate_versions(&model_id).unwrap(), vec![vec![v1, v3.clone()]]);
+
+        // Versions without a checksum can't be compared
+        DB.get()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .execute("UPDATE model_versions SET sha256 = NULL WHERE id = ?1", [&v3])
+            .unwrap();
+        assert!(find_duplicate_versions(&model_id).unwrap().is_empty());
+
+        // Identical files under another model are not duplicates of these
+        let other_model = create_test_model("duplicates-other");
+        register_model_version(&uuid::Uuid::new_v4().to_string(), &other_model, None, &same, "joblib", None, None, false)
+            .unwrap();
+        assert!(find_duplicate_versions(&model_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_validate_model_version_schema() {
+        setup_test_db();
+        let model_id = create_test_model("schema");
+        let version_id = register_test_version(&model_id, None);
+
+        update_model_version_training_info(&version_id, Some(3), Some(r#"["a","b","c"]"#)).unwrap();
+        validate_model_version_schema(&version_id).unwrap();
+
+        let err = update_model_version_training_info(&version_id, Some(4), Some(r#"["a","b","c"]"#)).unwrap_err().to_string();
+        assert!(err.contains("lists 3 features but n_features is 4"), "{}", err);
+        assert_eq!(get_model_version(&version_id).unwrap().unwrap().n_features, Some(3));
+        assert!(update_model_version_training_info(&version_id, Some(1), Some("a")).is_err());
+
+        // Rows written before the check could already disagree
+        DB.get()
+            .unwrap()
+            .lock()
+            .unwrap()
+            .execute("UPDATE model_versions SET n_features = 5 WHERE id = ?1", [&version_id])
+            .unwrap();
+        let err = validate_model_version_schema(&version_id).unwrap_err().to_string();
+        assert!(err.contains("lists 3 features but n_features is 5"), "{}", err);
+
+        update_model_version_training_info(&version_id, Some(5), None).unwrap();
+        validate_model_version_schema(&version_id).unwrap();
+        assert!(validate_model_version_schema("missing").is_err());
+    }
+
+    #[test]
+    fn test_register_versions_from_run() {
+        setup_test_db();
+        let model_id = create_test_model("ensemble");
+        register_test_version(&model_id, None);
+        let run_id = format!("run-{}", uuid::Uuid::new_v4());
+        create_run(&run_id, "Ensemble Run", "{}", None).unwrap();
+        save_run_metrics(
+            &run_id,
+            &[Metric { name: "accuracy".to_string(), value: Some(0.88), value_json: None, kind: None }],
+        )
+        .unwrap();
+        let run_dir = get_artifacts_dir().unwrap().join(&run_id);
+        std::fs::create_dir_all(&run_dir).unwrap();
+        let spec = |file_name: &str| ArtifactSpec {
+            file_name: file_name.to_string(),
+            format: "joblib".to_string(),
+            trusted: false,
+        };
+        let specs: Vec<ArtifactSpec> = (1..=3)
+            .map(|i| {
+                std::fs::write(run_dir.join(format!("member{}.joblib", i)), format!("member {}", i)).unwrap();
+                spec(&format!("member{}.joblib", i))
+            })
+            .collect();
+
+        let version_ids = register_versions_from_run(&model_id, &run_id, &specs).unwrap();
+        assert_eq!(version_ids.len(), 3);
+        for (expected, version_id) in (2..=4).zip(&version_ids) {
+            let version = get_model_version(version_id).unwrap().unwrap();
+            assert_eq!(version.version, expected);
+            assert_eq!(version.run_id.as_deref(), Some(run_id.as_str()));
+            assert_eq!(version.metrics_snapshot.as_deref(), Some(r#"{"accuracy":0.88}"#));
+            assert_eq!(
+                std::fs::read_to_string(&version.file_path).unwrap(),
+                format!("member {}", expected - 1)
+            );
+        }
+
+        // Invalid specs are rejected before anything is written
+        assert!(register_versions_from_run(&model_id, &run_id, &[]).is_err());
+        assert!(register_versions_from_run(&model_id, &run_id, &[spec("../escape.joblib")]).is_err());
+        assert!(register_versions_from_run(&model_id, &run_id, &[spec("missing.joblib")]).is_err());
+
+        // A file where v6's directory goes makes the second registration fail
+        let models_dir = get_models_dir().unwrap().join(&model_id);
+        std::fs::write(models_dir.join("v6"), b"in the way").unwrap();
+        let err = register_versions_from_run(&model_id, &run_id, &specs).unwrap_err().to_string();
+        assert!(err.contains("Failed to register artifacts"), "{}", err);
+        assert_eq!(list_model_versions(&model_id).unwrap().len(), 4);
+        assert!(!models_dir.join("v5").exists());
+
+        std::fs::remove_file(models_dir.join("v6")).unwrap();
+        let version_ids = register_versions_from_run(&model_id, &run_id, &specs[..1]).unwrap();
+        assert_eq!(get_model_version(&version_ids[0]).unwrap().unwrap().version, 5);
+    }
+
+    #[test]
+    fn test_compare_candidate_to_production() {
+        setup_test_db();
+        let model_id = create_test_model("candidate");
+        let production = register_test_version(&model_id, Some(r#"{"accuracy": 0.9, "rmse": 0.5, "train_time": 12.0, "f1": 0.8}"#));
+        let candidate = register_test_version(&model_id, Some(r#"{"accuracy": 0.85, "rmse": 0.25, "train_time": 20.0, "r2": 0.7}"#));
+
+        let err = compare_candidate_to_production(&candidate).unwrap_err().to_string();
+        assert!(err.contains("no production version"), "{}", err);
+
+        promote_model(&production, "production").unwrap();
+        let deltas = compare_candidate_to_production(&candidate).unwrap();
+        let delta = |metric: &str| deltas.iter().find(|d| d.metric == metric).unwrap();
+        assert_eq!(
+            deltas.iter().map(|d| d.metric.as_str()).collect::<Vec<_>>(),
+            vec!["accuracy", "f1", "r2", "rmse", "train_time"]
+        );
+
+        // Lower accuracy is a regression, lower rmse an improvement
+        assert!((delta("accuracy").delta.unwrap() + 0.05).abs() < 1e-9);
+        assert!(delta("accuracy").regression);
+        assert_eq!(delta("rmse").delta, Some(-0.25));
+        assert!(!delta("rmse").regression);
+        // No known direction, so never flagged
+        assert_eq!(delta("train_time").delta, Some(8.0));
+        assert!(!delta("train_time").regression);
+        // Recorded by only one side
+        assert_eq!((delta("f1").candidate, delta("f1").production, delta("f1").delta), (None, Some(0.8), None));
+        assert_eq!((delta("r2").candidate, delta("r2").delta), (Some(0.7), None));
+        assert!(!delta("f1").regression);
+
+        assert!(compare_candidate_to_production(&production).unwrap_err().to_string().contains("already in production"));
+        assert!(compare_candidate_to_production("missing").is_err());
+    }
 }
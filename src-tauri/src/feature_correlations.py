@@ -0,0 +1,81 @@
+#!/usr/bin/env python3
+"""
+Feature Correlations for MLOps Desktop
+
+Computes the Pearson correlation matrix of a CSV's numeric columns.
+Uses only the standard library so it runs on any Python the app finds.
+
+Usage: feature_correlations.py <input.csv>
+
+Output: a single JSON line on stdout
+- {"columns": [...], "matrix": [[...], ...], "excluded": [...]}
+
+A column is numeric if every non-empty value parses as a float; other
+columns are listed in "excluded". Correlations use pairwise-complete rows
+and are null when either column is constant over those rows.
+"""
+
+import csv
+import json
+import math
+import sys
+
+
+def parse_float(value):
+    try:
+        result = float(value)
+    except ValueError:
+        return None
+    return result if math.isfinite(result) else None
+
+
+def read_numeric_columns(path):
+    with open(path, newline="") as f:
+        reader = csv.reader(f)
+        header = next(reader, [])
+        values = [[] for _ in header]
+        numeric = [True] * len(header)
+        for row in reader:
+            for i in range(len(header)):
+                raw = row[i].strip() if i < len(row) else ""
+                if raw == "":
+                    values[i].append(None)
+                    continue
+                parsed = parse_float(raw)
+                if parsed is None:
+                    numeric[i] = False
+                values[i].append(parsed)
+
+    columns = [name for name, ok in zip(header, numeric) if ok]
+    excluded = [name for name, ok in zip(header, numeric) if not ok]
+    data = [col for col, ok in zip(values, numeric) if ok]
+    return columns, data, excluded
+
+
+def pearson(xs, ys):
+    pairs = [(x, y) for x, y in zip(xs, ys) if x is not None and y is not None]
+    if len(pairs) < 2:
+        return None
+    n = len(pairs)
+    mean_x = sum(x for x, _ in pairs) / n
+    mean_y = sum(y for _, y in pairs) / n
+    cov = sum((x - mean_x) * (y - mean_y) for x, y in pairs)
+    var_x = sum((x - mean_x) ** 2 for x, _ in pairs)
+    var_y = sum((y - mean_y) ** 2 for _, y in pairs)
+    if var_x == 0 or var_y == 0:
+        return None
+    return max(-1.0, min(1.0, cov / math.sqrt(var_x * var_y)))
+
+
+def main():
+    if len(sys.argv) < 2:
+        print("Usage: feature_correlations.py <input.csv>", file=sys.stderr)
+        sys.exit(1)
+
+    columns, data, excluded = read_numeric_columns(sys.argv[1])
+    matrix = [[pearson(a, b) for b in data] for a in data]
+    print(json.dumps({"columns": columns, "matrix": matrix, "excluded": excluded}))
+
+
+if __name__ == "__main__":
+    main()
@@ -12,6 +12,8 @@ For production deployment, use dedicated serving infrastructure
 
 Security Controls:
 - Default bind to 127.0.0.1 only
+- Optional API key (MLOPS_HTTP_API_KEY env var), required on every route
+  but /health as an X-API-Key header or Bearer token
 - Rate limiting: 100 requests/minute per IP
 - Request size: Max 1MB JSON payload
 - Batch size: Max 1000 samples per request
@@ -30,7 +32,9 @@ Usage:
 
 import argparse
 import asyncio
+import hmac
 import json
+import os
 import sys
 import time
 import uuid
@@ -62,9 +66,10 @@ def check_dependencies():
         emit_error("MISSING_DEPS", f"Missing required packages: {', '.join(missing)}. Install with: pip install {' '.join(missing)}")
         sys.exit(1)
 
-def emit_log(message: str) -> None:
-    """Emit a log message."""
-    print(f"__LOG__:{json.dumps({'message': message})}", flush=True)
+def emit_log(message: str, level: str = "info") -> None:
+    """Emit a structured log message."""
+    entry = {"level": level, "message": message, "timestamp": int(time.time() * 1000)}
+    print(f"__LOG__:{json.dumps(entry)}", flush=True)
 
 def emit_error(code: str, message: str, details: dict | None = None) -> None:
     """Emit an error event."""
@@ -104,6 +109,7 @@ MAX_REQUEST_SIZE_BYTES = 1_000_000  # 1MB
 MAX_BATCH_SIZE = 1000
 REQUEST_TIMEOUT_SECONDS = 30
 RATE_LIMIT_PER_MINUTE = 100
+API_KEY_ENV = "MLOPS_HTTP_API_KEY"
 
 
 class PredictRequest(BaseModel):
@@ -177,9 +183,9 @@ class ModelServer:
                 self._extract_onnx_info()
                 return
             except ImportError:
-                emit_log("ONNX Runtime not installed, falling back to sklearn")
+                emit_log("ONNX Runtime not installed, falling back to sklearn", "warning")
             except Exception as e:
-                emit_log(f"Failed to load ONNX model: {e}, falling back to sklearn")
+                emit_log(f"Failed to load ONNX model: {e}, falling back to sklearn", "warning")
 
         # Fall back to sklearn
         try:
@@ -327,7 +333,22 @@ class ModelServer:
 model_server: ModelServer | None = None
 
 
-def create_app(cors_origins: list[str] | None = None) -> FastAPI:
+def request_api_key(request: Request) -> str | None:
+    """API key sent as X-API-Key or as an Authorization Bearer token."""
+    key = request.headers.get("x-api-key")
+    if key:
+        return key
+    scheme, _, token = request.headers.get("authorization", "").partition(" ")
+    if scheme.lower() == "bearer" and token:
+        return token.strip()
+    return None
+
+
+def create_app(
+    cors_origins: list[str] | None = None,
+    rate_limit: str = f"{RATE_LIMIT_PER_MINUTE}/minute",
+    api_key: str | None = None,
+) -> FastAPI:
     """Create FastAPI application."""
 
     @asynccontextmanager
@@ -350,6 +371,23 @@ def create_app(cors_origins: list[str] | None = None) -> FastAPI:
     app.state.limiter = limiter
     app.add_exception_handler(RateLimitExceeded, _rate_limit_exceeded_handler)
 
+    # API key check; added before CORS so 401s still carry CORS headers
+    if api_key:
+        @app.middleware("http")
+        async def require_api_key(request: Request, call_next):
+            if request.url.path != "/health":
+                provided = request_api_key(request)
+                if provided is None or not hmac.compare_digest(provided.encode(), api_key.encode()):
+                    return JSONResponse(
+                        status_code=401,
+                        content={"error": {
+                            "code": "UNAUTHORIZED",
+                            "message": "Missing or invalid API key"
+                        }},
+                        headers={"WWW-Authenticate": "Bearer"},
+                    )
+            return await call_next(request)
+
     # CORS
     if cors_origins:
         app.add_middleware(
@@ -418,7 +456,7 @@ def create_app(cors_origins: list[str] | None = None) -> FastAPI:
         )
 
     @app.post("/predict", response_model=PredictResponse)
-    @limiter.limit(f"{RATE_LIMIT_PER_MINUTE}/minute")
+    @limiter.limit(rate_limit)
     async def predict(request: Request, body: PredictRequest):
         """Run prediction on input data."""
         if model_server is None:
@@ -477,6 +515,11 @@ def main():
     parser.add_argument("--port", type=int, default=8080, help="Port to bind to (default: 8080)")
     parser.add_argument("--onnx", help="Path to ONNX model file (optional)")
     parser.add_argument("--cors", help="Comma-separated list of allowed CORS origins")
+    parser.add_argument(
+        "--rate-limit",
+        default=f"{RATE_LIMIT_PER_MINUTE}/minute",
+        help=f"Rate limit for /predict, e.g. 100/minute (default: {RATE_LIMIT_PER_MINUTE}/minute)",
+    )
 
     args = parser.parse_args()
 
@@ -496,7 +539,10 @@ def main():
         sys.exit(1)
 
     # Create app
-    app = create_app(cors_origins)
+    api_key = os.environ.get(API_KEY_ENV) or None
+    if api_key:
+        emit_log("API key authentication enabled")
+    app = create_app(cors_origins, args.rate_limit, api_key)
 
     # Emit ready signal
     emit_ready(
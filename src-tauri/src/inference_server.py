@@ -16,7 +16,10 @@ Commands:
 
 Responses:
 - __RESPONSE__:{"request_id": "...", "status": "ok", ...}
-- __RESPONSE__:{"request_id": "...", "status": "error", "message": "..."}
+- __RESPONSE__:{"request_id": "...", "status": "error", "code": "...", "message": "..."}
+
+Error codes: model_not_loaded, feature_mismatch, invalid_dtype, empty_input,
+invalid_request, prediction_failed, internal_error
 """
 
 import json
@@ -32,9 +35,9 @@ def respond(obj: dict) -> None:
     print("__RESPONSE__:" + json.dumps(obj), flush=True)
 
 
-def respond_error(request_id: str, message: str) -> None:
-    """Send an error response."""
-    respond({"request_id": request_id, "status": "error", "message": message})
+def respond_error(request_id: str, message: str, code: str = "internal_error") -> None:
+    """Send an error response with a machine-readable code."""
+    respond({"request_id": request_id, "status": "error", "code": code, "message": message})
 
 
 def respond_ok(request_id: str, **kwargs) -> None:
@@ -100,6 +103,10 @@ class InferenceServer:
         """
         import numpy as np
 
+        if self.model is None:
+            respond_error(request_id, "Model is not loaded", code="model_not_loaded")
+            return
+
         try:
             # Detect single vs batch input
             if isinstance(input_data, list):
@@ -110,7 +117,7 @@ class InferenceServer:
                 samples = [input_data]
 
             if not samples:
-                respond_error(request_id, "No input data provided")
+                respond_error(request_id, "No input data provided", code="empty_input")
                 return
 
             # Build feature matrix
@@ -121,7 +128,11 @@ class InferenceServer:
                 for i, sample in enumerate(samples):
                     missing = [f for f in feature_names if f not in sample]
                     if missing:
-                        respond_error(request_id, f"Row {i}: Missing features: {', '.join(missing)}")
+                        respond_error(
+                            request_id,
+                            f"Row {i}: Missing features: {', '.join(missing)}",
+                            code="feature_mismatch",
+                        )
                         return
                     values.append([sample[f] for f in feature_names])
             else:
@@ -133,8 +144,19 @@ class InferenceServer:
                     else:
                         values.append(sample)
 
-            X = np.array(values, dtype=np.float64)
-            predictions = self.model.predict(X)
+            try:
+                X = np.array(values, dtype=np.float64)
+            except (TypeError, ValueError) as e:
+                respond_error(request_id, f"Input values must be numeric: {e}", code="invalid_dtype")
+                return
+
+            try:
+                predictions = self.model.predict(X)
+            except ValueError as e:
+                # sklearn reports a wrong column count as "X has N features, but ..."
+                code = "feature_mismatch" if "features" in str(e) else "prediction_failed"
+                respond_error(request_id, str(e), code=code)
+                return
 
             result = {
                 "prediction": predictions.tolist() if hasattr(predictions, "tolist") else list(predictions)
@@ -159,7 +181,7 @@ class InferenceServer:
         try:
             cmd = json.loads(cmd_str)
         except json.JSONDecodeError as e:
-            respond_error("unknown", f"Invalid JSON: {e}")
+            respond_error("unknown", f"Invalid JSON: {e}", code="invalid_request")
             return True
 
         request_id = cmd.get("request_id", "unknown")
@@ -176,7 +198,7 @@ class InferenceServer:
             respond_ok(request_id, message="Shutting down")
             return False
         else:
-            respond_error(request_id, f"Unknown command: {cmd_type}")
+            respond_error(request_id, f"Unknown command: {cmd_type}", code="invalid_request")
 
         return True
 
@@ -192,7 +214,7 @@ def main():
         server = InferenceServer(model_path)
     except Exception as e:
         # Send startup error with sentinel
-        respond({"request_id": "startup", "status": "error", "message": str(e)})
+        respond({"request_id": "startup", "status": "error", "code": "model_not_loaded", "message": str(e)})
         sys.exit(1)
 
     # Send ready signal
@@ -3,13 +3,12 @@ mod db;
 mod lsp;
 mod ollama;
 mod python;
+mod rag;
 
 use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    tracing_subscriber::fmt::init();
-
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
@@ -18,47 +17,90 @@ pub fn run() {
             // Initialize database
             let app_data_dir = app.path().app_data_dir()?;
             std::fs::create_dir_all(&app_data_dir)?;
+            commands::init_logging(&app_data_dir);
             db::init_db(&app_data_dir)?;
             // Clean up any orphaned servers from previous crash
             commands::cleanup_orphan_inference_server(&app_data_dir);
             commands::cleanup_orphan_http_server(&app_data_dir);
+            commands::cleanup_old_scripts(&app_data_dir);
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::get_python_path,
             commands::set_python_path,
             commands::find_python,
+            commands::explain_python_resolution,
+            commands::get_python_diagnostics,
+            commands::create_venv,
             commands::run_script,
             commands::cancel_script,
+            commands::send_script_input,
+            commands::close_script_input,
+            commands::cleanup_scripts_dir,
             commands::save_pipeline,
+            commands::validate_pipeline_import,
             commands::load_pipeline,
+            commands::get_pipeline_execution_order,
             commands::list_pipelines,
             commands::delete_pipeline,
+            commands::purge_pipeline,
             commands::get_example_data_path,
             commands::list_example_datasets,
+            commands::preview_dataset,
+            commands::compute_feature_correlations,
+            commands::get_feature_correlations,
             commands::create_run,
             commands::complete_run,
             commands::fail_run,
             commands::save_run_metrics,
+            commands::flush_run_metrics,
             commands::list_runs,
             commands::get_run_metrics,
+            commands::get_latest_metric_for_runs,
+            commands::get_run_metric_smoothed,
+            commands::get_run_environment,
             commands::delete_run,
+            commands::delete_runs_for_pipeline,
+            commands::purge_failed_runs,
+            commands::compress_run_artifacts,
+            commands::decompress_run_artifacts,
             // Model Registry
             commands::create_model,
             commands::list_models,
             commands::get_model,
+            commands::get_registry_summary,
             commands::delete_model,
             commands::register_model_version,
+            commands::register_and_promote,
+            commands::import_external_model,
+            commands::register_version_from_run,
+            commands::register_versions_from_run,
+            commands::detect_model_format,
             commands::list_model_versions,
+            commands::get_run_model_versions,
+            commands::list_unused_versions,
             commands::promote_model,
+            commands::rollback_production,
+            commands::set_promotion_rule,
+            commands::get_promotion_rule,
+            commands::compare_candidate_to_production,
             commands::delete_model_version,
+            commands::set_model_max_versions,
+            commands::prune_old_versions,
             commands::get_model_file_path,
+            commands::find_duplicate_versions,
             commands::get_model_version,
+            commands::get_model_version_provenance,
             // Inference Server
             commands::start_inference_server,
             commands::stop_inference_server,
+            commands::restart_inference_server,
             commands::get_inference_server_status,
+            commands::assert_serving_version,
             commands::run_inference,
+            commands::get_inference_audit,
+            commands::benchmark_inference,
+            commands::predict_csv,
             // Tuning
             commands::check_python_package,
             commands::create_tuning_session,
@@ -68,13 +110,19 @@ pub fn run() {
             commands::get_tuning_session_by_run,
             commands::save_tuning_trial,
             commands::list_tuning_trials,
+            commands::export_tuning_trials_csv,
             commands::get_best_trial,
+            commands::apply_best_trial_to_pipeline,
             // Experiments
             commands::create_experiment,
+            commands::get_or_create_experiment,
             commands::update_experiment,
             commands::list_experiments,
             commands::get_experiment,
             commands::delete_experiment,
+            commands::export_experiment_ndjson,
+            commands::get_experiment_metric_correlations,
+            commands::get_hyperparameter_impact,
             // Run Annotations
             commands::update_run_display_name,
             commands::set_run_experiment,
@@ -85,11 +133,19 @@ pub fn run() {
             commands::remove_run_tag,
             commands::get_run_tags,
             commands::list_all_tags,
+            commands::list_runs_by_tag,
+            commands::list_runs_by_tags,
             commands::get_runs_for_comparison,
+            commands::diff_confusion_matrices,
+            commands::compute_classification_report,
+            commands::find_similar_runs,
             // Model Metadata & Tags (v9)
             commands::update_model_version_metadata,
             commands::update_model_version_training_info,
+            commands::validate_model_version_schema,
             commands::update_model_version_export_path,
+            commands::set_model_version_trusted,
+            commands::export_model_to_coreml,
             commands::add_model_tag,
             commands::remove_model_tag,
             commands::get_model_tags,
@@ -99,25 +155,39 @@ pub fn run() {
             commands::get_comparable_versions,
             // HTTP Server (v10)
             commands::start_http_server,
+            commands::start_http_server_async,
             commands::stop_http_server,
             commands::get_http_server_status,
+            commands::probe_http_server,
+            commands::get_serving_readiness,
             commands::get_http_server_metrics,
+            commands::get_http_server_logs,
             commands::reset_http_server_metrics,
             commands::get_serving_version_id,
             commands::delete_model_version_safe,
             // Ollama
             commands::check_ollama,
             commands::list_ollama_models,
+            commands::ensure_ollama_model,
             commands::generate_completion,
             commands::cancel_completion,
             // LSP
             lsp::check_pyright,
             lsp::start_lsp_server,
             lsp::stop_lsp_server,
+            lsp::cancel_lsp_start,
+            lsp::resolve_lsp_workspace,
             lsp::lsp_request,
             lsp::lsp_notify,
+            lsp::lsp_open_document,
+            lsp::lsp_change_document,
+            lsp::lsp_close_document,
+            lsp::export_lsp_diagnostics,
+            lsp::lsp_signature_help,
+            lsp::lsp_completion,
             lsp::lsp_cancel_request,
             lsp::get_lsp_status,
+            lsp::get_lsp_capabilities,
             // Chunk Embeddings (RAG)
             commands::upsert_chunk_embedding,
             commands::get_chunk_embedding_hash,
@@ -125,6 +195,32 @@ pub fn run() {
             commands::delete_orphan_chunks,
             commands::delete_chunks_for_node,
             commands::delete_chunks_for_pipeline,
+            commands::index_node_chunks,
+            commands::reindex_pipeline_incremental,
+            commands::search_similar_chunks,
+            commands::search_similar_nodes,
+            commands::search_similar_nodes_reranked,
+            commands::get_embedding_config,
+            commands::rag_get_status,
+            commands::rag_get_global_status,
+            commands::rag_estimate_dedup_savings,
+            commands::rag_needs_reindex,
+            commands::rag_preprocess_query,
+            // Completion Events
+            commands::log_completion_event,
+            commands::get_completion_acceptance_stats,
+            // Database maintenance
+            commands::get_db_version,
+            commands::relocate_paths,
+            commands::audit_model_files,
+            commands::repair_model_files,
+            commands::checkpoint_database,
+            commands::optimize_database,
+            // System status
+            commands::list_running_processes,
+            commands::set_log_level,
+            commands::get_recent_logs,
+            commands::create_support_bundle,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -7,6 +7,7 @@ use serde::Serialize;
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
 use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
@@ -14,11 +15,13 @@ use std::thread;
 use std::time::Duration;
 use tauri::{AppHandle, Emitter, Manager};
 
-use crate::python;
+use crate::{db, python};
 
 // Constants
 const REQUEST_TIMEOUT_MS: u64 = 5000;
 const INITIALIZE_TIMEOUT_MS: u64 = 30000; // Longer timeout for initialize (pyright can be slow)
+const CANCEL_POLL_MS: u64 = 50; // How often a cancellable wait checks its flag
+const DIAGNOSTICS_TIMEOUT_MS: u64 = 10000;
 const MAX_RESTARTS: i32 = 3;
 const RESTART_BACKOFF_MS: [u64; 3] = [1000, 3000, 10000];
 
@@ -39,6 +42,40 @@ pub struct LspStatus {
     pub restart_count: i32,
 }
 
+/// Parameter hints for a call, from `textDocument/signatureHelp`
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureHelp {
+    pub signatures: Vec<SignatureInformation>,
+    pub active_signature: u32,
+    pub active_parameter: Option<u32>,
+}
+
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SignatureInformation {
+    pub label: String,
+    pub documentation: Option<String>,
+    pub parameters: Vec<ParameterInformation>,
+    pub active_parameter: Option<u32>,
+}
+
+#[derive(Clone, Serialize, Debug, PartialEq)]
+pub struct ParameterInformation {
+    pub label: String,
+    pub documentation: Option<String>,
+}
+
+/// A symbol completion from `textDocument/completion`
+#[derive(Clone, Serialize, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionItem {
+    pub label: String,
+    pub kind: Option<u32>, // LSP CompletionItemKind
+    pub detail: Option<String>,
+    pub insert_text: Option<String>,
+}
+
 /// Response sender for pending requests
 type ResponseSender = std::sync::mpsc::Sender<Result<Value, String>>;
 
@@ -50,13 +87,38 @@ struct LspProcess {
     next_request_id: AtomicI32,
     is_initialized: AtomicBool,
     pyright_version: Option<String>,
+    capabilities: Option<Value>, // ServerCapabilities from the initialize response
     shutdown_tx: Option<std::sync::mpsc::Sender<()>>,
+    workspace_root: Option<String>,
+    started_at: std::time::Instant,
 }
 
 // Global state
 static LSP_PROCESS: OnceLock<Mutex<Option<LspProcess>>> = OnceLock::new();
 static RESTART_COUNT: AtomicI32 = AtomicI32::new(0);
 static APP_HANDLE: OnceLock<Mutex<Option<AppHandle>>> = OnceLock::new();
+static PIPELINE_WORKSPACE: OnceLock<Mutex<Option<PathBuf>>> = OnceLock::new();
+static DOCUMENT_VERSIONS: OnceLock<Mutex<DocumentVersions>> = OnceLock::new();
+static LSP_STARTING: AtomicBool = AtomicBool::new(false);
+static START_CANCELLED: AtomicBool = AtomicBool::new(false);
+static DIAGNOSTIC_WAITERS: OnceLock<Mutex<HashMap<String, std::sync::mpsc::Sender<Value>>>> = OnceLock::new();
+
+/// Marks an LSP start as in progress so cancel_start can abort it
+struct StartInProgress;
+
+impl StartInProgress {
+    fn begin() -> Self {
+        START_CANCELLED.store(false, Ordering::SeqCst);
+        LSP_STARTING.store(true, Ordering::SeqCst);
+        StartInProgress
+    }
+}
+
+impl Drop for StartInProgress {
+    fn drop(&mut self) {
+        LSP_STARTING.store(false, Ordering::SeqCst);
+    }
+}
 
 fn get_lsp_mutex() -> &'static Mutex<Option<LspProcess>> {
     LSP_PROCESS.get_or_init(|| Mutex::new(None))
@@ -66,6 +128,221 @@ fn get_app_handle_mutex() -> &'static Mutex<Option<AppHandle>> {
     APP_HANDLE.get_or_init(|| Mutex::new(None))
 }
 
+fn get_document_versions() -> &'static Mutex<DocumentVersions> {
+    DOCUMENT_VERSIONS.get_or_init(|| Mutex::new(DocumentVersions::default()))
+}
+
+/// Version numbers of the documents open on the server, by uri
+#[derive(Default)]
+struct DocumentVersions {
+    versions: HashMap<String, i32>,
+}
+
+impl DocumentVersions {
+    /// Version a document is opened at
+    const OPEN_VERSION: i32 = 1;
+
+    /// Start tracking `uri` at OPEN_VERSION (re-opening restarts it)
+    fn open(&mut self, uri: &str) -> i32 {
+        self.versions.insert(uri.to_string(), Self::OPEN_VERSION);
+        Self::OPEN_VERSION
+    }
+
+    /// Next version for a change. A caller-supplied version is used only if
+    /// it's newer than the tracked one, so stale numbers can't be sent.
+    fn change(&mut self, uri: &str, requested: Option<i32>) -> Result<i32, String> {
+        let current = self
+            .versions
+            .get_mut(uri)
+            .ok_or_else(|| format!("Document {} is not open", uri))?;
+        *current = requested.map_or(*current + 1, |v| v.max(*current + 1));
+        Ok(*current)
+    }
+
+    fn close(&mut self, uri: &str) -> bool {
+        self.versions.remove(uri).is_some()
+    }
+
+    fn is_open(&self, uri: &str) -> bool {
+        self.versions.contains_key(uri)
+    }
+
+    fn clear(&mut self) {
+        self.versions.clear();
+    }
+}
+
+fn get_workspace_mutex() -> &'static Mutex<Option<PathBuf>> {
+    PIPELINE_WORKSPACE.get_or_init(|| Mutex::new(None))
+}
+
+fn get_diagnostic_waiters() -> &'static Mutex<HashMap<String, std::sync::mpsc::Sender<Value>>> {
+    DIAGNOSTIC_WAITERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Register interest in the next publishDiagnostics for `uri`
+fn expect_diagnostics(uri: &str) -> Result<std::sync::mpsc::Receiver<Value>, String> {
+    let mut waiters = get_diagnostic_waiters().lock().map_err(|e| e.to_string())?;
+    if waiters.contains_key(uri) {
+        return Err(format!("Already waiting for diagnostics on {}", uri));
+    }
+    let (tx, rx) = std::sync::mpsc::channel();
+    waiters.insert(uri.to_string(), tx);
+    Ok(rx)
+}
+
+fn forget_diagnostics_wait(uri: &str) {
+    if let Ok(mut waiters) = get_diagnostic_waiters().lock() {
+        waiters.remove(uri);
+    }
+}
+
+/// Hand a publishDiagnostics payload to whoever is waiting on its uri.
+/// Returns true if a waiter took it.
+fn deliver_diagnostics(params: &Value) -> bool {
+    let Some(uri) = params.get("uri").and_then(|u| u.as_str()) else {
+        return false;
+    };
+    let waiter = match get_diagnostic_waiters().lock() {
+        Ok(mut waiters) => waiters.remove(uri),
+        Err(_) => return false,
+    };
+    waiter.is_some_and(|tx| tx.send(params.clone()).is_ok())
+}
+
+fn await_diagnostics(
+    uri: &str,
+    rx: &std::sync::mpsc::Receiver<Value>,
+    timeout_ms: u64,
+) -> Result<Value, String> {
+    rx.recv_timeout(Duration::from_millis(timeout_ms)).map_err(|_| {
+        forget_diagnostics_wait(uri);
+        format!("No diagnostics received for {} after {}ms", uri, timeout_ms)
+    })
+}
+
+/// Write a publishDiagnostics payload as pretty JSON and return how many
+/// diagnostics it held
+fn write_diagnostics(dest: &Path, params: &Value) -> Result<usize, String> {
+    let json = serde_json::to_string_pretty(params).map_err(|e| e.to_string())?;
+    std::fs::write(dest, json).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    Ok(params
+        .get("diagnostics")
+        .and_then(|d| d.as_array())
+        .map_or(0, |d| d.len()))
+}
+
+/// Turn an id into something usable as a file or module name. Ids that had
+/// to be rewritten get a short hash of the raw id appended, so "a-b" and
+/// "a_b" don't end up sharing a file.
+fn sanitize_name(id: &str) -> String {
+    if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return id.to_string();
+    }
+    let name: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_{}", name, &crate::rag::content_hash(id)[..8])
+}
+
+/// Module name for a node's code in the pipeline workspace, so other nodes
+/// can `import node_<id>`
+pub fn node_module_name(node_id: &str) -> String {
+    format!("node_{}", sanitize_name(node_id))
+}
+
+fn workspace_dir(base_dir: &Path, pipeline_id: &str) -> PathBuf {
+    base_dir.join(sanitize_name(pipeline_id))
+}
+
+/// Write each node's code to `<base_dir>/<pipeline_id>/node_<id>.py` and
+/// return the workspace directory. The directory is updated in place, so a
+/// running server rooted in it keeps working; modules of removed nodes are
+/// deleted.
+pub fn materialize_workspace(
+    base_dir: &Path,
+    pipeline_id: &str,
+    pipeline_data: &str,
+) -> Result<PathBuf, String> {
+    let data: Value = serde_json::from_str(pipeline_data)
+        .map_err(|e| format!("Pipeline data is not valid JSON: {}", e))?;
+
+    let dir = workspace_dir(base_dir, pipeline_id);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let mut written = std::collections::HashSet::new();
+    for node in data["nodes"].as_array().into_iter().flatten() {
+        let (Some(id), Some(code)) = (node["id"].as_str(), node["data"]["code"].as_str()) else {
+            continue;
+        };
+        let file_name = format!("{}.py", node_module_name(id));
+        std::fs::write(dir.join(&file_name), code).map_err(|e| e.to_string())?;
+        written.insert(std::ffi::OsString::from(file_name));
+    }
+
+    for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())?.flatten() {
+        let name = entry.file_name();
+        let stale = name.to_string_lossy().starts_with("node_") && !written.contains(&name);
+        if stale {
+            std::fs::remove_file(entry.path()).map_err(|e| e.to_string())?;
+        }
+    }
+
+    Ok(dir)
+}
+
+/// True while a server is running or starting; its workspace must stay put
+fn server_active() -> bool {
+    LSP_STARTING.load(Ordering::SeqCst) || get_lsp_mutex().lock().map_or(true, |guard| guard.is_some())
+}
+
+/// Materialize a saved pipeline's workspace and remember it so stop_lsp can
+/// remove it. A previous workspace is removed only when no server uses it;
+/// while a server runs, only its own workspace can be refreshed.
+fn resolve_pipeline_workspace(app: &AppHandle, pipeline_id: &str) -> Result<PathBuf, String> {
+    let data = db::load_pipeline(pipeline_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Pipeline {} not found", pipeline_id))?;
+    let base_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("lsp_workspaces");
+    switch_workspace(&base_dir, pipeline_id, &data, server_active())
+}
+
+fn switch_workspace(
+    base_dir: &Path,
+    pipeline_id: &str,
+    pipeline_data: &str,
+    server_active: bool,
+) -> Result<PathBuf, String> {
+    let dir = workspace_dir(base_dir, pipeline_id);
+    let mut guard = get_workspace_mutex().lock().map_err(|e| e.to_string())?;
+    match guard.as_ref() {
+        Some(current) if *current != dir && server_active => {
+            return Err("LSP server is running on another pipeline's workspace. Stop it first.".to_string());
+        }
+        Some(current) if *current != dir => {
+            let _ = std::fs::remove_dir_all(current);
+        }
+        _ => {}
+    }
+    let dir = materialize_workspace(base_dir, pipeline_id, pipeline_data)?;
+    *guard = Some(dir.clone());
+    Ok(dir)
+}
+
+/// Remove the current pipeline workspace, if any
+fn cleanup_workspace() {
+    if let Ok(mut guard) = get_workspace_mutex().lock() {
+        if let Some(dir) = guard.take() {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
 /// Encode a JSON-RPC message with Content-Length header
 fn encode_message(msg: &Value) -> Vec<u8> {
     let body = serde_json::to_string(msg).unwrap();
@@ -130,6 +407,97 @@ pub fn check_pyright_installed(python_path: &str) -> Result<PyrightInfo, String>
     })
 }
 
+/// Documentation may be a plain string or MarkupContent
+fn parse_documentation(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .or_else(|| value["value"].as_str())
+        .map(|s| s.to_string())
+}
+
+/// Parameter labels are either strings or [start, end) UTF-16 offsets into
+/// the signature label
+fn parse_parameter_label(value: &Value, signature_label: &str) -> Option<String> {
+    if let Some(label) = value.as_str() {
+        return Some(label.to_string());
+    }
+    let offsets = value.as_array()?;
+    let start = offsets.first()?.as_u64()? as usize;
+    let end = offsets.get(1)?.as_u64()? as usize;
+    let utf16: Vec<u16> = signature_label.encode_utf16().collect();
+    utf16.get(start..end).map(String::from_utf16_lossy)
+}
+
+/// Parse a signatureHelp result; null or no signatures means no hints
+fn parse_signature_help(value: &Value) -> Option<SignatureHelp> {
+    let signatures: Vec<SignatureInformation> = value["signatures"]
+        .as_array()?
+        .iter()
+        .filter_map(|sig| {
+            let label = sig["label"].as_str()?.to_string();
+            let parameters = sig["parameters"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|param| {
+                    Some(ParameterInformation {
+                        label: parse_parameter_label(&param["label"], &label)?,
+                        documentation: parse_documentation(&param["documentation"]),
+                    })
+                })
+                .collect();
+            Some(SignatureInformation {
+                documentation: parse_documentation(&sig["documentation"]),
+                parameters,
+                active_parameter: sig["activeParameter"].as_u64().map(|v| v as u32),
+                label,
+            })
+        })
+        .collect();
+    if signatures.is_empty() {
+        return None;
+    }
+
+    Some(SignatureHelp {
+        signatures,
+        active_signature: value["activeSignature"].as_u64().unwrap_or(0) as u32,
+        active_parameter: value["activeParameter"].as_u64().map(|v| v as u32),
+    })
+}
+
+/// Parse a completion result (a CompletionList or a bare item array) into
+/// items ordered by `sortText`, falling back to the label
+fn parse_completion_items(value: &Value) -> Vec<CompletionItem> {
+    let items = value
+        .as_array()
+        .or_else(|| value["items"].as_array())
+        .cloned()
+        .unwrap_or_default();
+
+    let mut keyed: Vec<(String, CompletionItem)> = items
+        .iter()
+        .filter_map(|item| {
+            let label = item["label"].as_str()?.to_string();
+            let sort_key = item["sortText"].as_str().unwrap_or(&label).to_string();
+            let insert_text = item["insertText"]
+                .as_str()
+                .or_else(|| item["textEdit"]["newText"].as_str())
+                .map(|s| s.to_string());
+            Some((
+                sort_key,
+                CompletionItem {
+                    kind: item["kind"].as_u64().map(|k| k as u32),
+                    detail: item["detail"].as_str().map(|s| s.to_string()),
+                    insert_text,
+                    label,
+                },
+            ))
+        })
+        .collect();
+    keyed.sort_by(|a, b| a.0.cmp(&b.0));
+    keyed.into_iter().map(|(_, item)| item).collect()
+}
+
 /// Start the LSP server
 pub fn start_lsp(
     app_handle: &AppHandle,
@@ -143,6 +511,7 @@ pub fn start_lsp(
             return Err("LSP server already running".to_string());
         }
     }
+    let _starting = StartInProgress::begin();
 
     // Store app handle for events
     {
@@ -155,6 +524,9 @@ pub fn start_lsp(
     if !pyright_info.installed {
         return Err("Pyright not installed. Run: pip install pyright".to_string());
     }
+    if START_CANCELLED.load(Ordering::SeqCst) {
+        return Err("LSP start cancelled".to_string());
+    }
 
     // Spawn pyright-langserver
     // The correct module is pyright.langserver (not pyright --langserver)
@@ -232,7 +604,10 @@ pub fn start_lsp(
         next_request_id: AtomicI32::new(1),
         is_initialized: AtomicBool::new(false),
         pyright_version: pyright_info.version.clone(),
+        capabilities: None,
         shutdown_tx: Some(shutdown_tx),
+        workspace_root: workspace_root.map(str::to_string),
+        started_at: std::time::Instant::now(),
     };
 
     // Store process
@@ -260,7 +635,14 @@ pub fn start_lsp(
                     "relatedInformation": true,
                     "codeDescriptionSupport": true
                 },
-                "definition": { "linkSupport": true }
+                "definition": { "linkSupport": true },
+                "signatureHelp": {
+                    "signatureInformation": {
+                        "documentationFormat": ["markdown", "plaintext"],
+                        "parameterInformation": { "labelOffsetSupport": true },
+                        "activeParameterSupport": true
+                    }
+                }
             }
         },
         "initializationOptions": {
@@ -270,27 +652,7 @@ pub fn start_lsp(
         }
     });
 
-    // Send initialize and wait for response (use longer timeout for pyright startup)
-    // If this fails, we need to clean up the stored process
-    tracing::info!("Sending LSP initialize request...");
-    let response = match send_request_sync_with_timeout("initialize", init_params, INITIALIZE_TIMEOUT_MS) {
-        Ok(resp) => resp,
-        Err(e) => {
-            tracing::error!("LSP initialize request failed: {}", e);
-            // Clean up the stored process since initialization failed
-            if let Ok(mut guard) = get_lsp_mutex().lock() {
-                if let Some(mut proc) = guard.take() {
-                    // Signal shutdown to reader threads
-                    if let Some(shutdown_tx) = proc.shutdown_tx.take() {
-                        let _ = shutdown_tx.send(());
-                    }
-                    // Kill the process
-                    let _ = proc.child.kill();
-                }
-            }
-            return Err(format!("LSP initialization failed: {}", e));
-        }
-    };
+    let response = initialize_server(init_params)?;
     tracing::info!("LSP initialized: {:?}", response.get("capabilities").map(|_| "..."));
 
     // Send initialized notification
@@ -331,6 +693,36 @@ pub fn start_lsp(
     Ok(())
 }
 
+/// Send initialize to the stored process and wait for the reply (with a longer
+/// timeout for pyright startup), giving up early if cancel_start is called.
+/// The reply's capabilities are kept on the process. On failure the stored
+/// process is killed and removed.
+fn initialize_server(init_params: Value) -> Result<Value, String> {
+    tracing::info!("Sending LSP initialize request...");
+    match send_request_cancellable("initialize", init_params, INITIALIZE_TIMEOUT_MS, Some(&START_CANCELLED)) {
+        Ok(response) => {
+            if let Some(proc) = get_lsp_mutex().lock().map_err(|e| e.to_string())?.as_mut() {
+                proc.capabilities = response.get("capabilities").cloned();
+            }
+            Ok(response)
+        }
+        Err(e) => {
+            tracing::error!("LSP initialize request failed: {}", e);
+            if let Ok(mut guard) = get_lsp_mutex().lock() {
+                if let Some(mut proc) = guard.take() {
+                    // Signal shutdown to reader threads
+                    if let Some(shutdown_tx) = proc.shutdown_tx.take() {
+                        let _ = shutdown_tx.send(());
+                    }
+                    let _ = proc.child.kill();
+                    let _ = proc.child.wait();
+                }
+            }
+            Err(format!("LSP initialization failed: {}", e))
+        }
+    }
+}
+
 /// Monitor the LSP process and restart if it crashes
 fn monitor_process(app_handle: AppHandle, python_path: String, workspace_root: Option<String>) {
     loop {
@@ -407,6 +799,7 @@ fn handle_lsp_message(
             "textDocument/publishDiagnostics" => {
                 // Forward to frontend via Tauri event
                 if let Some(params) = msg.get("params") {
+                    deliver_diagnostics(params);
                     let _ = app_handle.emit("lsp-diagnostics", params);
                 }
             }
@@ -469,6 +862,16 @@ fn reject_pending_requests(reason: &str) {
 
 /// Send a request and wait for response (synchronous) with custom timeout
 pub fn send_request_sync_with_timeout(method: &str, params: Value, timeout_ms: u64) -> Result<Value, String> {
+    send_request_cancellable(method, params, timeout_ms, None)
+}
+
+/// Like send_request_sync_with_timeout, but stops waiting as soon as `cancel` is set
+fn send_request_cancellable(
+    method: &str,
+    params: Value,
+    timeout_ms: u64,
+    cancel: Option<&AtomicBool>,
+) -> Result<Value, String> {
     let (tx, rx) = std::sync::mpsc::channel();
     let request_id: i32;
 
@@ -506,21 +909,31 @@ pub fn send_request_sync_with_timeout(method: &str, params: Value, timeout_ms: u
         tracing::debug!("LSP request sent, waiting for response...");
     }
 
-    // Wait with timeout
-    match rx.recv_timeout(Duration::from_millis(timeout_ms)) {
-        Ok(result) => {
-            tracing::debug!("LSP response received for {}", method);
-            result
+    // Wait with timeout, in short slices so a cancel is noticed promptly
+    let deadline = std::time::Instant::now() + Duration::from_millis(timeout_ms);
+    loop {
+        if cancel.is_some_and(|flag| flag.load(Ordering::SeqCst)) {
+            tracing::info!("LSP request {} cancelled while waiting", method);
+            cancel_request(request_id);
+            return Err(format!("Request {} cancelled", method));
         }
-        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
             tracing::error!("LSP request {} timed out after {}ms", method, timeout_ms);
             // Cancel the request
             cancel_request(request_id);
-            Err(format!("Request {} timed out after {}ms", method, timeout_ms))
+            return Err(format!("Request {} timed out after {}ms", method, timeout_ms));
         }
-        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
-            tracing::error!("LSP request {} channel disconnected", method);
-            Err("Request cancelled - channel disconnected".to_string())
+        match rx.recv_timeout(remaining.min(Duration::from_millis(CANCEL_POLL_MS))) {
+            Ok(result) => {
+                tracing::debug!("LSP response received for {}", method);
+                return result;
+            }
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => {
+                tracing::error!("LSP request {} channel disconnected", method);
+                return Err("Request cancelled - channel disconnected".to_string());
+            }
         }
     }
 }
@@ -572,6 +985,24 @@ pub fn cancel_request(request_id: i32) {
     );
 }
 
+/// Abort a start that's waiting on initialize. Returns false if no start is
+/// in progress.
+pub fn cancel_start() -> bool {
+    if !LSP_STARTING.load(Ordering::SeqCst) {
+        return false;
+    }
+    START_CANCELLED.store(true, Ordering::SeqCst);
+    true
+}
+
+/// PID, uptime and workspace root of the running server, if any
+pub fn process_info() -> Option<(u32, Duration, Option<String>)> {
+    let guard = get_lsp_mutex().lock().ok()?;
+    guard
+        .as_ref()
+        .map(|proc| (proc.child.id(), proc.started_at.elapsed(), proc.workspace_root.clone()))
+}
+
 /// Stop the LSP server
 pub fn stop_lsp() -> Result<(), String> {
     let mut guard = get_lsp_mutex().lock().map_err(|e| e.to_string())?;
@@ -612,6 +1043,11 @@ pub fn stop_lsp() -> Result<(), String> {
         tracing::info!("LSP server stopped");
     }
 
+    cleanup_workspace();
+    // A new server starts with no open documents
+    if let Ok(mut versions) = get_document_versions().lock() {
+        versions.clear();
+    }
     Ok(())
 }
 
@@ -659,17 +1095,38 @@ pub fn check_pyright(app: AppHandle) -> Result<PyrightInfo, String> {
     check_pyright_installed(&python_info.path.to_string_lossy())
 }
 
-/// Start the LSP server
+/// Start the LSP server. With `pipeline_id`, the workspace root is a temp
+/// directory holding each node's code so cross-node imports resolve.
 #[tauri::command]
 pub fn start_lsp_server(
     app: AppHandle,
     workspace_root: Option<String>,
+    pipeline_id: Option<String>,
 ) -> Result<(), String> {
     let resource_dir = app.path().resource_dir().ok();
     let python_info = python::find_python(resource_dir.as_ref())
         .ok_or_else(|| "No Python installation found".to_string())?;
 
-    start_lsp(&app, &python_info.path.to_string_lossy(), workspace_root.as_deref())
+    // Checked before touching disk so a live server's workspace is left alone
+    if server_active() {
+        return Err("LSP server already running".to_string());
+    }
+    let workspace_root = match pipeline_id {
+        Some(id) => Some(resolve_pipeline_workspace(&app, &id)?.to_string_lossy().to_string()),
+        None => workspace_root,
+    };
+
+    let result = start_lsp(&app, &python_info.path.to_string_lossy(), workspace_root.as_deref());
+    if result.is_err() && !server_active() {
+        cleanup_workspace();
+    }
+    result
+}
+
+/// Write a pipeline's node code to its LSP workspace and return the path
+#[tauri::command]
+pub fn resolve_lsp_workspace(app: AppHandle, pipeline_id: String) -> Result<String, String> {
+    resolve_pipeline_workspace(&app, &pipeline_id).map(|dir| dir.to_string_lossy().to_string())
 }
 
 /// Stop the LSP server
@@ -678,6 +1135,12 @@ pub fn stop_lsp_server() -> Result<(), String> {
     stop_lsp()
 }
 
+/// Abort a start_lsp_server that's still waiting for pyright to initialize
+#[tauri::command]
+pub fn cancel_lsp_start() -> bool {
+    cancel_start()
+}
+
 /// Send an LSP request and wait for response
 #[tauri::command]
 pub fn lsp_request(method: String, params: Value) -> Result<Value, String> {
@@ -690,6 +1153,105 @@ pub fn lsp_notify(method: String, params: Value) -> Result<(), String> {
     send_notification(&method, params)
 }
 
+/// Open a Python document on the server at version 1
+#[tauri::command]
+pub fn lsp_open_document(uri: String, text: String) -> Result<i32, String> {
+    send_notification(
+        "textDocument/didOpen",
+        json!({
+            "textDocument": {
+                "uri": uri,
+                "languageId": "python",
+                "version": DocumentVersions::OPEN_VERSION,
+                "text": text
+            }
+        }),
+    )?;
+    // Tracked only once pyright has the document, so a failed open doesn't
+    // leave a version for later changes to build on
+    Ok(get_document_versions().lock().map_err(|e| e.to_string())?.open(&uri))
+}
+
+/// Replace a document's full text; returns the version sent
+#[tauri::command]
+pub fn lsp_change_document(uri: String, version: Option<i32>, text: String) -> Result<i32, String> {
+    let version = get_document_versions()
+        .lock()
+        .map_err(|e| e.to_string())?
+        .change(&uri, version)?;
+    send_notification(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": uri, "version": version },
+            "contentChanges": [{ "text": text }]
+        }),
+    )?;
+    Ok(version)
+}
+
+#[tauri::command]
+pub fn lsp_close_document(uri: String) -> Result<(), String> {
+    if !get_document_versions().lock().map_err(|e| e.to_string())?.close(&uri) {
+        return Ok(());
+    }
+    send_notification("textDocument/didClose", json!({ "textDocument": { "uri": uri } }))
+}
+
+/// Open a document, wait for pyright's first diagnostics on it, write them to
+/// `dest_path` as JSON and close it again. Without `text` the uri must be a
+/// file:// path whose contents are read from disk. Returns the number of
+/// diagnostics written.
+#[tauri::command]
+pub fn export_lsp_diagnostics(uri: String, dest_path: String, text: Option<String>) -> Result<usize, String> {
+    let text = match text {
+        Some(text) => text,
+        None => {
+            let path = uri
+                .strip_prefix("file://")
+                .ok_or_else(|| format!("No text given and {} is not a file:// uri", uri))?;
+            std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?
+        }
+    };
+    if get_document_versions().lock().map_err(|e| e.to_string())?.is_open(&uri) {
+        return Err(format!("Document {} is already open; close it before exporting", uri));
+    }
+
+    let rx = expect_diagnostics(&uri)?;
+    if let Err(e) = lsp_open_document(uri.clone(), text) {
+        forget_diagnostics_wait(&uri);
+        return Err(e);
+    }
+    let result = await_diagnostics(&uri, &rx, DIAGNOSTICS_TIMEOUT_MS);
+    let _ = lsp_close_document(uri);
+    write_diagnostics(Path::new(&dest_path), &result?)
+}
+
+/// Parameter hints at a position (0-based line and UTF-16 character)
+#[tauri::command]
+pub fn lsp_signature_help(uri: String, line: u32, character: u32) -> Result<Option<SignatureHelp>, String> {
+    let response = send_request_sync(
+        "textDocument/signatureHelp",
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character }
+        }),
+    )?;
+    Ok(parse_signature_help(&response))
+}
+
+/// Symbol completions at a position, in the server's preferred order
+#[tauri::command]
+pub fn lsp_completion(uri: String, line: u32, character: u32) -> Result<Vec<CompletionItem>, String> {
+    let response = send_request_sync(
+        "textDocument/completion",
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": line, "character": character }
+        }),
+    )?;
+    Ok(parse_completion_items(&response))
+}
+
 /// Cancel a pending LSP request
 #[tauri::command]
 pub fn lsp_cancel_request(request_id: i32) {
@@ -702,4 +1264,352 @@ pub fn get_lsp_status() -> LspStatus {
     get_status()
 }
 
+/// Capabilities the running server reported in its initialize response, as
+/// sent. None until initialize has completed.
+#[tauri::command]
+pub fn get_lsp_capabilities() -> Option<Value> {
+    get_lsp_mutex().lock().ok()?.as_ref()?.capabilities.clone()
+}
+
 use std::io::Read;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Serializes tests that touch the global LSP process
+    static LSP_TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_parse_signature_help() {
+        let response = json!({
+            "signatures": [{
+                "label": "RandomForestClassifier(n_estimators: int = 100, max_depth: int | None = None)",
+                "documentation": { "kind": "markdown", "value": "A random forest classifier." },
+                "parameters": [
+                    { "label": [23, 46], "documentation": "Number of trees." },
+                    { "label": "max_depth: int | None = None" }
+                ]
+            }],
+            "activeSignature": 0,
+            "activeParameter": 1
+        });
+
+        let help = parse_signature_help(&response).unwrap();
+        assert_eq!(help.active_signature, 0);
+        assert_eq!(help.active_parameter, Some(1));
+        let sig = &help.signatures[0];
+        assert_eq!(sig.documentation.as_deref(), Some("A random forest classifier."));
+        assert_eq!(
+            sig.parameters,
+            vec![
+                ParameterInformation {
+                    label: "n_estimators: int = 100".to_string(),
+                    documentation: Some("Number of trees.".to_string()),
+                },
+                ParameterInformation { label: "max_depth: int | None = None".to_string(), documentation: None },
+            ]
+        );
+
+        assert_eq!(parse_signature_help(&Value::Null), None);
+        assert_eq!(parse_signature_help(&json!({ "signatures": [] })), None);
+    }
+
+    #[test]
+    fn test_parse_completion_items() {
+        let list = json!({
+            "isIncomplete": false,
+            "items": [
+                { "label": "fit_transform", "kind": 2, "sortText": "09.0002", "detail": "(X, y=None)" },
+                { "label": "fit", "kind": 2, "sortText": "09.0001", "textEdit": { "newText": "fit", "range": {} } },
+                { "label": "_private", "kind": 5, "sortText": "10.0001", "insertText": "_private" },
+                { "kind": 2 }
+            ]
+        });
+        let items = parse_completion_items(&list);
+        let labels: Vec<&str> = items.iter().map(|i| i.label.as_str()).collect();
+        assert_eq!(labels, vec!["fit", "fit_transform", "_private"]);
+        assert_eq!(items[0].insert_text.as_deref(), Some("fit"));
+        assert_eq!(items[1].detail.as_deref(), Some("(X, y=None)"));
+        assert_eq!(items[2].kind, Some(5));
+
+        // Bare arrays without sortText sort by label
+        let array = json!([{ "label": "train_test_split" }, { "label": "StandardScaler", "kind": 7 }]);
+        let items = parse_completion_items(&array);
+        assert_eq!(
+            items,
+            vec![
+                CompletionItem { label: "StandardScaler".to_string(), kind: Some(7), detail: None, insert_text: None },
+                CompletionItem { label: "train_test_split".to_string(), kind: None, detail: None, insert_text: None },
+            ]
+        );
+
+        assert!(parse_completion_items(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn test_document_versions_per_uri() {
+        let mut docs = DocumentVersions::default();
+        let (a, b) = ("inmemory://script/a", "inmemory://script/b");
+
+        assert!(docs.change(a, None).is_err());
+        assert_eq!(docs.open(a), 1);
+        assert_eq!(docs.open(b), 1);
+        assert_eq!(docs.change(a, None).unwrap(), 2);
+        assert_eq!(docs.change(a, None).unwrap(), 3);
+        assert_eq!(docs.change(b, None).unwrap(), 2);
+
+        // Stale caller versions are bumped, newer ones are kept
+        assert_eq!(docs.change(a, Some(2)).unwrap(), 4);
+        assert_eq!(docs.change(a, Some(10)).unwrap(), 10);
+
+        // Closing resets the uri
+        assert!(docs.close(a));
+        assert!(!docs.close(a));
+        assert!(docs.change(a, None).is_err());
+        assert_eq!(docs.open(a), 1);
+        assert_eq!(docs.change(b, None).unwrap(), 3);
+
+        docs.clear();
+        assert!(docs.change(b, None).is_err());
+    }
+
+    #[test]
+    fn test_failed_open_is_not_tracked() {
+        let _lock = LSP_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let uri = format!("inmemory://script/{}", uuid::Uuid::new_v4());
+
+        // No server is running, so didOpen can't be sent
+        assert!(lsp_open_document(uri.clone(), "x = 1".to_string()).is_err());
+        assert!(!get_document_versions().lock().unwrap().is_open(&uri));
+        assert!(lsp_change_document(uri, None, "x = 2".to_string()).unwrap_err().contains("not open"));
+    }
+
+    #[test]
+    fn test_pipeline_workspace_written_and_removed() {
+        let _lock = LSP_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let base_dir = std::env::temp_dir().join(format!("lsp-ws-{}", uuid::Uuid::new_v4()));
+        let pipeline = json!({
+            "nodes": [
+                {"id": "script-1", "data": {"label": "Features", "code": "def add_features(df):\n    return df\n"}},
+                {"id": "script-2", "data": {"label": "Train", "code": "from node_script_1 import add_features\n"}},
+                {"id": "loader-1", "data": {"label": "Load", "filePath": "data.csv"}}
+            ],
+            "edges": []
+        })
+        .to_string();
+
+        let dir = materialize_workspace(&base_dir, "pipeline/1", &pipeline).unwrap();
+        assert_eq!(dir, base_dir.join(sanitize_name("pipeline/1")));
+        let module = |id: &str| dir.join(format!("{}.py", node_module_name(id)));
+        assert_eq!(
+            std::fs::read_to_string(module("script-1")).unwrap(),
+            "def add_features(df):\n    return df\n"
+        );
+        assert!(module("script-2").exists());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        // Re-materializing drops files for removed nodes
+        let pipeline = json!({"nodes": [{"id": "script-2", "data": {"code": "x = 1"}}], "edges": []});
+        materialize_workspace(&base_dir, "pipeline/1", &pipeline.to_string()).unwrap();
+        assert!(!module("script-1").exists());
+
+        // Stopping the LSP tears the workspace down
+        *get_workspace_mutex().lock().unwrap() = Some(dir.clone());
+        stop_lsp().unwrap();
+        assert!(!dir.exists());
+        assert!(get_workspace_mutex().lock().unwrap().is_none());
+
+        std::fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_sanitized_names_dont_collide() {
+        assert_eq!(sanitize_name("abc123"), "abc123");
+        assert_ne!(sanitize_name("a-b"), sanitize_name("a_b"));
+        assert_ne!(node_module_name("script-1"), node_module_name("script_1"));
+        assert!(sanitize_name("a-b").starts_with("a_b_"));
+        assert!(node_module_name("script-1").chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    }
+
+    #[test]
+    fn test_running_server_workspace_is_kept() {
+        let _lock = LSP_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let base_dir = std::env::temp_dir().join(format!("lsp-ws-{}", uuid::Uuid::new_v4()));
+        let pipeline = |code: &str| json!({"nodes": [{"id": "script-1", "data": {"code": code}}], "edges": []}).to_string();
+
+        let dir = switch_workspace(&base_dir, "p1", &pipeline("x = 1"), false).unwrap();
+        let module = dir.join(format!("{}.py", node_module_name("script-1")));
+
+        // With a server running, its own workspace is refreshed in place...
+        let same = switch_workspace(&base_dir, "p1", &pipeline("x = 2"), true).unwrap();
+        assert_eq!(same, dir);
+        assert_eq!(std::fs::read_to_string(&module).unwrap(), "x = 2");
+
+        // ...and another pipeline can't replace it
+        let err = switch_workspace(&base_dir, "p2", &pipeline("y = 1"), true).unwrap_err();
+        assert!(err.contains("Stop it first"), "{}", err);
+        assert!(module.exists());
+        assert_eq!(get_workspace_mutex().lock().unwrap().as_ref(), Some(&dir));
+
+        // Once stopped, switching removes the old workspace
+        let other = switch_workspace(&base_dir, "p2", &pipeline("y = 1"), false).unwrap();
+        assert!(!dir.exists());
+        assert!(other.exists());
+
+        cleanup_workspace();
+        std::fs::remove_dir_all(&base_dir).unwrap();
+    }
+
+    #[test]
+    fn test_cancel_hung_initialize() {
+        let _lock = LSP_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        assert!(!cancel_start());
+
+        // A server that accepts requests but never answers them
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        *get_lsp_mutex().lock().unwrap() = Some(LspProcess {
+            child,
+            stdin,
+            pending_requests: Arc::new(Mutex::new(HashMap::new())),
+            next_request_id: AtomicI32::new(1),
+            is_initialized: AtomicBool::new(false),
+            pyright_version: None,
+            capabilities: None,
+            shutdown_tx: None,
+            workspace_root: None,
+            started_at: std::time::Instant::now(),
+        });
+
+        let started = std::time::Instant::now();
+        {
+            let _starting = StartInProgress::begin();
+            let canceller = thread::spawn(|| {
+                thread::sleep(Duration::from_millis(200));
+                cancel_start()
+            });
+            let err = initialize_server(json!({})).unwrap_err();
+            assert!(canceller.join().unwrap());
+            assert!(err.contains("cancelled"), "unexpected error: {}", err);
+        }
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(get_lsp_mutex().lock().unwrap().is_none());
+
+        // Nothing left to cancel once the start has returned
+        assert!(!cancel_start());
+    }
+
+    #[test]
+    fn test_capabilities_kept_from_initialize() {
+        let _lock = LSP_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        assert_eq!(get_lsp_capabilities(), None);
+
+        let mut child = Command::new("sleep")
+            .arg("30")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let pending_requests: Arc<Mutex<HashMap<i32, ResponseSender>>> = Arc::new(Mutex::new(HashMap::new()));
+        *get_lsp_mutex().lock().unwrap() = Some(LspProcess {
+            child,
+            stdin,
+            pending_requests: pending_requests.clone(),
+            next_request_id: AtomicI32::new(1),
+            is_initialized: AtomicBool::new(false),
+            pyright_version: None,
+            capabilities: None,
+            shutdown_tx: None,
+            workspace_root: None,
+            started_at: std::time::Instant::now(),
+        });
+
+        // Stand in for the stdout reader: answer the initialize request
+        let capabilities = json!({
+            "hoverProvider": true,
+            "completionProvider": { "triggerCharacters": [".", "["], "resolveProvider": true },
+            "signatureHelpProvider": { "triggerCharacters": ["(", ","] },
+            "renameProvider": false
+        });
+        let reply = json!({ "capabilities": capabilities, "serverInfo": { "name": "Pyright" } });
+        let responder = thread::spawn(move || {
+            let deadline = std::time::Instant::now() + Duration::from_secs(5);
+            while std::time::Instant::now() < deadline {
+                if let Some(sender) = pending_requests.lock().unwrap().remove(&1) {
+                    sender.send(Ok(reply)).unwrap();
+                    return;
+                }
+                thread::sleep(Duration::from_millis(10));
+            }
+            panic!("initialize request never arrived");
+        });
+
+        let response = {
+            let _starting = StartInProgress::begin();
+            initialize_server(json!({})).unwrap()
+        };
+        responder.join().unwrap();
+        assert_eq!(response["serverInfo"]["name"], "Pyright");
+        assert_eq!(get_lsp_capabilities(), Some(capabilities));
+
+        // Gone with the process
+        let mut proc = get_lsp_mutex().lock().unwrap().take().unwrap();
+        let _ = proc.child.kill();
+        let _ = proc.child.wait();
+        assert_eq!(get_lsp_capabilities(), None);
+    }
+
+    #[test]
+    fn test_diagnostics_exported_to_file() {
+        let uri = format!("inmemory://script/{}", uuid::Uuid::new_v4());
+        let params = json!({
+            "uri": uri,
+            "version": 1,
+            "diagnostics": [
+                {
+                    "range": { "start": { "line": 0, "character": 7 }, "end": { "line": 0, "character": 13 } },
+                    "severity": 1,
+                    "source": "Pyright",
+                    "message": "Import \"pandas\" could not be resolved"
+                },
+                {
+                    "range": { "start": { "line": 2, "character": 0 }, "end": { "line": 2, "character": 1 } },
+                    "severity": 2,
+                    "message": "\"x\" is not accessed"
+                }
+            ]
+        });
+
+        let rx = expect_diagnostics(&uri).unwrap();
+        assert!(expect_diagnostics(&uri).is_err());
+        let delivered = params.clone();
+        let publisher = thread::spawn(move || {
+            // Diagnostics for other documents are left alone
+            assert!(!deliver_diagnostics(&json!({ "uri": "inmemory://script/other", "diagnostics": [] })));
+            assert!(deliver_diagnostics(&delivered));
+        });
+        let received = await_diagnostics(&uri, &rx, 5000).unwrap();
+        publisher.join().unwrap();
+        // Only the first publish is collected
+        assert!(!deliver_diagnostics(&params));
+
+        let dest = std::env::temp_dir().join(format!("lsp-diagnostics-{}.json", uuid::Uuid::new_v4()));
+        assert_eq!(write_diagnostics(&dest, &received).unwrap(), 2);
+        let written: Value = serde_json::from_str(&std::fs::read_to_string(&dest).unwrap()).unwrap();
+        assert_eq!(written, params);
+        std::fs::remove_file(&dest).unwrap();
+
+        // A timed-out wait stops listening
+        let rx = expect_diagnostics(&uri).unwrap();
+        let err = await_diagnostics(&uri, &rx, 50).unwrap_err();
+        assert!(err.contains("No diagnostics"), "unexpected error: {}", err);
+        assert!(!deliver_diagnostics(&params));
+    }
+}
@@ -10,6 +10,45 @@ fn get_active_requests() -> &'static Mutex<HashSet<String>> {
     ACTIVE_REQUESTS.get_or_init(|| Mutex::new(HashSet::new()))
 }
 
+/// Which API the completion and embedding server speaks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Backend {
+    #[default]
+    #[serde(rename = "ollama")]
+    Ollama,
+    /// `/v1/completions` and `/v1/embeddings`, as served by vLLM or LM Studio
+    #[serde(rename = "openai_compatible")]
+    OpenAiCompatible,
+}
+
+impl Backend {
+    /// Host used when the caller doesn't give one
+    pub fn default_host(self) -> &'static str {
+        match self {
+            Backend::Ollama => "http://localhost:11434",
+            Backend::OpenAiCompatible => "http://localhost:8000",
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Backend::Ollama => "Ollama",
+            Backend::OpenAiCompatible => "OpenAI-compatible server",
+        }
+    }
+}
+
+/// URL of an OpenAI API endpoint. Hosts are accepted with or without the
+/// `/v1` suffix, since servers document their base URL both ways.
+fn openai_url(host: &str, endpoint: &str) -> String {
+    let base = host.trim_end_matches('/');
+    let base = base.strip_suffix("/v1").unwrap_or(base);
+    format!("{}/v1/{}", base, endpoint)
+}
+
+// The OpenAI API defaults max_tokens to 16, far too short for a completion
+const OPENAI_MAX_TOKENS: u32 = 256;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OllamaModel {
     pub name: String,
@@ -39,6 +78,17 @@ struct OllamaOptions {
     num_predict: Option<i32>,
 }
 
+#[derive(Debug, Serialize)]
+struct OllamaEmbeddingRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaEmbeddingResponse {
+    embedding: Vec<f32>,
+}
+
 #[derive(Debug, Deserialize)]
 struct OllamaGenerateResponse {
     response: String,
@@ -46,6 +96,40 @@ struct OllamaGenerateResponse {
     done: bool,
 }
 
+#[derive(Debug, Serialize)]
+struct OpenAiCompletionRequest<'a> {
+    model: &'a str,
+    prompt: &'a str,
+    max_tokens: u32,
+    stream: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiCompletionResponse {
+    choices: Vec<OpenAiCompletionChoice>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiCompletionChoice {
+    text: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAiEmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbeddingResponse {
+    data: Vec<OpenAiEmbedding>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiEmbedding {
+    embedding: Vec<f32>,
+}
+
 /// Check if Ollama is running and accessible
 pub async fn check_status(host: &str) -> bool {
     let client = match reqwest::Client::builder()
@@ -88,36 +172,181 @@ pub async fn list_models(host: &str) -> Result<Vec<String>, String> {
     Ok(models.models.into_iter().map(|m| m.name).collect())
 }
 
+/// Ollama lists pulled models with their tag ("nomic-embed-text:latest");
+/// a bare name matches its `:latest` tag
+fn model_listed(models: &[String], model: &str) -> bool {
+    models.iter().any(|name| {
+        name == model || (!model.contains(':') && name.strip_suffix(":latest") == Some(model))
+    })
+}
 
-/// Build a prompt for the given model
-fn build_prompt(model: &str, context: &str, cursor_line: &str, columns: &[String]) -> String {
-    let model_lower = model.to_lowercase();
-
-    let columns_comment = if columns.is_empty() {
-        String::new()
+/// Fail early with a clear message when `model` hasn't been pulled
+pub async fn ensure_model(host: &str, model: &str) -> Result<(), String> {
+    let models = list_models(host).await?;
+    if model_listed(&models, model) {
+        Ok(())
     } else {
-        format!("# columns: {}\n", columns.join(", "))
+        Err(format!(
+            "Model '{}' not found in Ollama, pull it first (ollama pull {})",
+            model, model
+        ))
+    }
+}
+
+/// Generate an embedding vector for `text` using an embedding model
+pub async fn generate_embedding(backend: Backend, host: &str, model: &str, text: &str) -> Result<Vec<f32>, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+
+    let request = match backend {
+        Backend::Ollama => client
+            .post(format!("{}/api/embeddings", host))
+            .json(&OllamaEmbeddingRequest { model, prompt: text }),
+        Backend::OpenAiCompatible => client
+            .post(openai_url(host, "embeddings"))
+            .json(&OpenAiEmbeddingRequest { model, input: text }),
     };
+    let resp = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to connect to {}: {}", backend.name(), e))?;
 
-    let prefix = format!("{}{}{}", columns_comment, context, cursor_line);
+    if !resp.status().is_success() {
+        return Err(format!("{} returned error: {}", backend.name(), resp.status()));
+    }
 
-    // Use Fill-in-Middle (FIM) format for code models
+    let embedding = match backend {
+        Backend::Ollama => resp.json::<OllamaEmbeddingResponse>().await.map(|r| r.embedding),
+        Backend::OpenAiCompatible => resp
+            .json::<OpenAiEmbeddingResponse>()
+            .await
+            .map(|r| r.data.into_iter().next().map(|d| d.embedding).unwrap_or_default()),
+    }
+    .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    if embedding.is_empty() {
+        return Err(format!("Model '{}' returned an empty embedding", model));
+    }
+    Ok(embedding)
+}
+
+/// Whether to prompt in fill-in-middle or instruction format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptMode {
+    Fim,
+    Instruction,
+    /// Pick by model family: FIM for known code models, instruction otherwise
+    #[default]
+    Auto,
+}
+
+/// How the completion prompt is built. A template overrides the mode.
+#[derive(Debug, Clone, Default)]
+pub struct PromptOptions {
+    pub mode: PromptMode,
+    pub template: Option<String>,
+}
+
+/// Fill `{prefix}` and `{columns}` in a user template. Substitution is a single
+/// pass, so placeholder-like text inside the code itself is left alone.
+fn fill_template(template: &str, prefix: &str, columns: &[String]) -> String {
+    let columns = columns.join(", ");
+    let mut out = String::with_capacity(template.len() + prefix.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        if let Some(after) = tail.strip_prefix("{prefix}") {
+            out.push_str(prefix);
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("{columns}") {
+            out.push_str(&columns);
+            rest = after;
+        } else {
+            out.push('{');
+            rest = &tail[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// FIM prompt in the model family's format, if it's a known code model
+fn fim_prompt(model_lower: &str, prefix: &str) -> Option<String> {
     if model_lower.contains("deepseek") {
         // DeepSeek Coder FIM format
-        format!("<｜fim▁begin｜>{}<｜fim▁hole｜><｜fim▁end｜>", prefix)
+        Some(format!("<｜fim▁begin｜>{}<｜fim▁hole｜><｜fim▁end｜>", prefix))
     } else if model_lower.contains("qwen") && model_lower.contains("coder") {
         // Qwen Coder FIM format
-        format!("<|fim_prefix|>{}<|fim_suffix|><|fim_middle|>", prefix)
+        Some(format!("<|fim_prefix|>{}<|fim_suffix|><|fim_middle|>", prefix))
     } else if model_lower.contains("starcoder") || model_lower.contains("codellama") {
         // StarCoder/CodeLlama FIM format
-        format!("<fim_prefix>{}<fim_suffix><fim_middle>", prefix)
+        Some(format!("<fim_prefix>{}<fim_suffix><fim_middle>", prefix))
+    } else {
+        None
+    }
+}
+
+/// Build a prompt for the given model
+fn build_prompt(
+    model: &str,
+    context: &str,
+    cursor_line: &str,
+    columns: &[String],
+    options: &PromptOptions,
+) -> String {
+    if let Some(template) = &options.template {
+        return fill_template(template, &format!("{}{}", context, cursor_line), columns);
+    }
+
+    let model_lower = model.to_lowercase();
+
+    let columns_comment = if columns.is_empty() {
+        String::new()
     } else {
-        // Generic instruction format for chat models
+        format!("# columns: {}\n", columns.join(", "))
+    };
+
+    let prefix = format!("{}{}{}", columns_comment, context, cursor_line);
+
+    // Generic instruction format for chat models
+    let instruction = || {
         format!(
             "Complete this Python code. Reply with ONLY the completion, no explanation.\n\n{}\n\nCompletion:",
             prefix
         )
+    };
+
+    match options.mode {
+        PromptMode::Auto => fim_prompt(&model_lower, &prefix).unwrap_or_else(instruction),
+        // Unrecognized models forced into FIM get the StarCoder markers
+        PromptMode::Fim => fim_prompt(&model_lower, &prefix)
+            .unwrap_or_else(|| format!("<fim_prefix>{}<fim_suffix><fim_middle>", prefix)),
+        PromptMode::Instruction => instruction(),
+    }
+}
+
+/// Whether a leading response line is prose rather than code. Sentence openers
+/// only count when followed by another word, so `I = 1` or `This = x` are kept.
+fn is_explanatory_line(trimmed: &str) -> bool {
+    if trimmed.is_empty()
+        || trimmed.starts_with("Note:")
+        || trimmed.starts_with("Explanation:")
+        || trimmed.starts_with("Code completion:")
+    {
+        return true;
     }
+
+    let mut words = trimmed.split_whitespace();
+    let first = words.next().unwrap_or("").trim_end_matches([',', ':']);
+    let second_is_word = words
+        .next()
+        .and_then(|w| w.chars().next())
+        .is_some_and(|c| c.is_alphabetic());
+    matches!(first, "Here" | "Here's" | "I" | "This" | "The") && second_is_word
 }
 
 /// Clean up model response by removing FIM markers, markdown, and explanatory text
@@ -139,6 +368,11 @@ fn clean_response(raw: &str, model: &str) -> String {
         result = result.replace("<|fim_suffix|>", "");
         result = result.replace("<|fim_prefix|>", "");
         result = result.replace("<|endoftext|>", "");
+    } else {
+        // Generic FIM markers, used when FIM is forced for an unknown model
+        result = result.replace("<fim_middle>", "");
+        result = result.replace("<fim_suffix>", "");
+        result = result.replace("<fim_prefix>", "");
     }
 
     // Try to extract code from markdown code blocks first
@@ -168,16 +402,7 @@ fn clean_response(raw: &str, model: &str) -> String {
     let lines: Vec<&str> = result.lines().collect();
     let mut start_idx = 0;
     for (i, line) in lines.iter().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.is_empty()
-            || trimmed.starts_with("Here")
-            || trimmed.starts_with("I ")
-            || trimmed.starts_with("This ")
-            || trimmed.starts_with("The ")
-            || trimmed.starts_with("Note:")
-            || trimmed.starts_with("Explanation:")
-            || trimmed.starts_with("Code completion:")
-        {
+        if is_explanatory_line(line.trim()) {
             start_idx = i + 1;
         } else {
             break;
@@ -219,13 +444,78 @@ pub fn cancel_request(request_id: &str) {
     unregister_request(request_id);
 }
 
-/// Generate a completion using Ollama
+/// Non-streaming completion request for `prompt`
+fn completion_request(
+    client: &reqwest::Client,
+    backend: Backend,
+    host: &str,
+    model: &str,
+    prompt: &str,
+) -> reqwest::RequestBuilder {
+    match backend {
+        // Don't send options - some remote models don't support them
+        Backend::Ollama => client.post(format!("{}/api/generate", host)).json(&OllamaGenerateRequest {
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+            stream: false,
+            options: None,
+        }),
+        Backend::OpenAiCompatible => client.post(openai_url(host, "completions")).json(&OpenAiCompletionRequest {
+            model,
+            prompt,
+            max_tokens: OPENAI_MAX_TOKENS,
+            stream: false,
+        }),
+    }
+}
+
+fn send_error(backend: Backend, e: reqwest::Error) -> String {
+    if e.is_timeout() {
+        "Request timed out".to_string()
+    } else {
+        format!("Failed to connect to {}: {}", backend.name(), e)
+    }
+}
+
+/// Generated text of a completion response
+async fn completion_text(backend: Backend, resp: reqwest::Response) -> Result<String, String> {
+    match backend {
+        Backend::Ollama => resp.json::<OllamaGenerateResponse>().await.map(|r| r.response),
+        Backend::OpenAiCompatible => resp
+            .json::<OpenAiCompletionResponse>()
+            .await
+            .map(|r| r.choices.into_iter().next().map(|c| c.text).unwrap_or_default()),
+    }
+    .map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+/// Run `prompt` as-is and return the model's raw answer, without the code
+/// completion prompt building and cleanup
+pub async fn generate_text(backend: Backend, host: &str, model: &str, prompt: &str) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to create client: {}", e))?;
+    let resp = completion_request(&client, backend, host, model, prompt)
+        .send()
+        .await
+        .map_err(|e| send_error(backend, e))?;
+    if !resp.status().is_success() {
+        return Err(format!("{} returned error: {}", backend.name(), resp.status()));
+    }
+    completion_text(backend, resp).await
+}
+
+/// Generate a completion using Ollama or an OpenAI-compatible server
+#[allow(clippy::too_many_arguments)]
 pub async fn generate_completion(
+    backend: Backend,
     host: &str,
     model: &str,
     context: &str,
     cursor_line: &str,
     columns: &[String],
+    prompt_options: &PromptOptions,
     request_id: &str,
 ) -> Result<String, String> {
     // Check if already cancelled
@@ -233,37 +523,26 @@ pub async fn generate_completion(
         return Err("cancelled".to_string());
     }
 
+    if let Some(template) = &prompt_options.template {
+        if !template.contains("{prefix}") {
+            return Err("Prompt template must contain {prefix}".to_string());
+        }
+    }
+
     let client = reqwest::Client::builder()
         .timeout(Duration::from_secs(30))
         .build()
         .map_err(|e| format!("Failed to create client: {}", e))?;
 
-    let prompt = build_prompt(model, context, cursor_line, columns);
+    let prompt = build_prompt(model, context, cursor_line, columns, prompt_options);
 
     // Log the prompt for debugging
-    tracing::info!("Ollama model={}, prompt ({} chars): {:?}", model, prompt.len(), prompt.chars().take(200).collect::<String>());
-
-    // Don't send options - some remote models don't support them
-    let request = OllamaGenerateRequest {
-        model: model.to_string(),
-        prompt,
-        stream: false,
-        options: None,
-    };
+    tracing::info!("{} model={}, prompt ({} chars): {:?}", backend.name(), model, prompt.len(), prompt.chars().take(200).collect::<String>());
 
-    let url = format!("{}/api/generate", host);
-    let resp = client
-        .post(&url)
-        .json(&request)
+    let resp = completion_request(&client, backend, host, model, &prompt)
         .send()
         .await
-        .map_err(|e| {
-            if e.is_timeout() {
-                "Request timed out".to_string()
-            } else {
-                format!("Failed to connect to Ollama: {}", e)
-            }
-        })?;
+        .map_err(|e| send_error(backend, e))?;
 
     // Check for cancellation again after network call
     if !is_request_active(request_id) {
@@ -271,29 +550,234 @@ pub async fn generate_completion(
     }
 
     if !resp.status().is_success() {
-        return Err(format!("Ollama returned error: {}", resp.status()));
+        return Err(format!("{} returned error: {}", backend.name(), resp.status()));
     }
 
-    let response: OllamaGenerateResponse = resp
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    let raw = completion_text(backend, resp).await?;
 
     // Log raw response for debugging
-    tracing::info!("Ollama raw response ({} chars): {:?}", response.response.len(), response.response.chars().take(200).collect::<String>());
+    tracing::info!("{} raw response ({} chars): {:?}", backend.name(), raw.len(), raw.chars().take(200).collect::<String>());
 
-    let cleaned = clean_response(&response.response, model);
+    let cleaned = clean_response(&raw, model);
 
-    tracing::info!("Ollama cleaned response ({} chars): {:?}", cleaned.len(), cleaned.chars().take(200).collect::<String>());
+    tracing::info!("{} cleaned response ({} chars): {:?}", backend.name(), cleaned.len(), cleaned.chars().take(200).collect::<String>());
 
     // Don't return empty completions - but show what we got
     if cleaned.is_empty() {
-        if response.response.is_empty() {
+        if raw.is_empty() {
             return Err("Model returned empty response".to_string());
         }
         return Err(format!("Response cleaned to empty. Raw: {}",
-            response.response.chars().take(100).collect::<String>()));
+            raw.chars().take(100).collect::<String>()));
     }
 
     Ok(cleaned)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<String> {
+        vec!["age".to_string(), "income".to_string()]
+    }
+
+    #[test]
+    fn test_prompt_template_substitution() {
+        let options = PromptOptions {
+            mode: PromptMode::Auto,
+            template: Some("<PRE> # cols: {columns}\n{prefix} <MID>".to_string()),
+        };
+        let prompt = build_prompt("codegemma", "df = load()\n", "df[", &columns(), &options);
+        assert_eq!(prompt, "<PRE> # cols: age, income\ndf = load()\ndf[ <MID>");
+
+        // Braces in the code itself are not treated as placeholders
+        assert_eq!(
+            fill_template("{prefix}|{other}", "f\"{columns}\"", &columns()),
+            "f\"{columns}\"|{other}"
+        );
+    }
+
+    #[test]
+    fn test_prompt_mode_override() {
+        let auto = PromptOptions::default();
+        let fim = PromptOptions { mode: PromptMode::Fim, template: None };
+        let instruction = PromptOptions { mode: PromptMode::Instruction, template: None };
+
+        // Auto keeps family detection
+        assert_eq!(
+            build_prompt("qwen2.5-coder:7b", "x = ", "", &[], &auto),
+            "<|fim_prefix|>x = <|fim_suffix|><|fim_middle|>"
+        );
+        assert!(build_prompt("llama3", "x = ", "", &[], &auto).starts_with("Complete this Python code."));
+
+        // Fim forces FIM for an unrecognized model
+        assert_eq!(
+            build_prompt("my-finetune", "x = ", "", &[], &fim),
+            "<fim_prefix>x = <fim_suffix><fim_middle>"
+        );
+        assert_eq!(clean_response("<fim_middle>1", "my-finetune"), "1");
+
+        // Instruction forces the instruction prompt for a code model
+        let prompt = build_prompt("deepseek-coder", "x = ", "", &columns(), &instruction);
+        assert!(prompt.starts_with("Complete this Python code."));
+        assert!(prompt.contains("# columns: age, income\nx = "));
+    }
+
+    #[test]
+    fn test_clean_response_keeps_unicode_code() {
+        let code = "label = \"温度\"  # 摂氏\nprint(f\"{label}: {value}°C\")";
+        assert_eq!(clean_response(code, "llama3"), code);
+        assert_eq!(clean_response(&format!("```python\n{}\n```", code), "llama3"), code);
+    }
+
+    #[test]
+    fn test_clean_response_only_strips_leading_prose() {
+        // Identifiers that start with sentence openers are code
+        assert_eq!(clean_response("I = np.eye(3)", "llama3"), "I = np.eye(3)");
+        assert_eq!(clean_response("Here_df = df.copy()", "llama3"), "Here_df = df.copy()");
+        assert_eq!(clean_response("This = model.fit(X)", "llama3"), "This = model.fit(X)");
+
+        // A leading explanation is dropped, but matching lines inside the code stay
+        let raw = "Here is the completion:\n\nx = 1\nThe = x\nI = 2";
+        assert_eq!(clean_response(raw, "llama3"), "x = 1\nThe = x\nI = 2");
+        assert_eq!(
+            clean_response("The model is trained below.\nmodel.fit(X, y)", "llama3"),
+            "model.fit(X, y)"
+        );
+    }
+
+    /// Answer one request on a free local port with `body`, and hand back the
+    /// request line and body the client sent
+    fn mock_server(body: &'static str) -> (String, std::sync::mpsc::Receiver<(String, serde_json::Value)>) {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let host = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            let header_end = loop {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let headers = String::from_utf8_lossy(&request[..header_end]).to_string();
+            let content_length: usize = headers
+                .lines()
+                .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap()))
+                .unwrap_or(0);
+            while request.len() < header_end + content_length {
+                let n = stream.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let request_line = headers.lines().next().unwrap().to_string();
+            let sent = serde_json::from_slice(&request[header_end..]).unwrap_or(serde_json::Value::Null);
+            tx.send((request_line, sent)).unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        (host, rx)
+    }
+
+    fn complete(backend: Backend, host: &str, request_id: &str) -> Result<String, String> {
+        register_request(request_id);
+        let options = PromptOptions { mode: PromptMode::Instruction, template: None };
+        let result = tokio::runtime::Runtime::new().unwrap().block_on(generate_completion(
+            backend, host, "llama3", "x = ", "", &[], &options, request_id,
+        ));
+        unregister_request(request_id);
+        result
+    }
+
+    #[test]
+    fn test_completion_backends() {
+        let (host, requests) = mock_server(r#"{"response": "```python\n1 + 1\n```", "done": true}"#);
+        assert_eq!(complete(Backend::Ollama, &host, "ollama-backend").unwrap(), "1 + 1");
+        let (request_line, body) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /api/generate HTTP/1.1");
+        assert_eq!(body["model"], "llama3");
+        assert_eq!(body["stream"], false);
+        assert!(body["prompt"].as_str().unwrap().starts_with("Complete this Python code."));
+
+        let (host, requests) = mock_server(
+            r#"{"id": "cmpl-1", "object": "text_completion", "choices": [{"index": 0, "text": "Here is the code:\n2 + 2", "finish_reason": "stop"}]}"#,
+        );
+        // Base URLs given with /v1 aren't doubled
+        let result = complete(Backend::OpenAiCompatible, &format!("{}/v1/", host), "openai-backend");
+        assert_eq!(result.unwrap(), "2 + 2");
+        let (request_line, body) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /v1/completions HTTP/1.1");
+        assert_eq!(body["model"], "llama3");
+        assert_eq!(body["max_tokens"], OPENAI_MAX_TOKENS);
+        assert_eq!(body["stream"], false);
+        assert!(body["prompt"].as_str().unwrap().contains("x = "));
+
+        // Cancelled requests never reach the server
+        assert_eq!(
+            tokio::runtime::Runtime::new().unwrap().block_on(generate_completion(
+                Backend::OpenAiCompatible, &host, "llama3", "x = ", "", &[], &PromptOptions::default(), "never-registered",
+            )),
+            Err("cancelled".to_string())
+        );
+    }
+
+    #[test]
+    fn test_embedding_backends() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let (host, requests) = mock_server(r#"{"embedding": [0.5, 0.25]}"#);
+        let embedding = rt.block_on(generate_embedding(Backend::Ollama, &host, "nomic-embed-text", "def f(): pass"));
+        assert_eq!(embedding.unwrap(), vec![0.5, 0.25]);
+        let (request_line, body) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /api/embeddings HTTP/1.1");
+        assert_eq!(body, serde_json::json!({"model": "nomic-embed-text", "prompt": "def f(): pass"}));
+
+        let (host, requests) =
+            mock_server(r#"{"object": "list", "data": [{"object": "embedding", "index": 0, "embedding": [1.0, 0.0, 2.0]}]}"#);
+        let embedding =
+            rt.block_on(generate_embedding(Backend::OpenAiCompatible, &host, "nomic-embed-text", "def f(): pass"));
+        assert_eq!(embedding.unwrap(), vec![1.0, 0.0, 2.0]);
+        let (request_line, body) = requests.recv().unwrap();
+        assert_eq!(request_line, "POST /v1/embeddings HTTP/1.1");
+        assert_eq!(body, serde_json::json!({"model": "nomic-embed-text", "input": "def f(): pass"}));
+
+        let (host, _requests) = mock_server(r#"{"object": "list", "data": []}"#);
+        let err = rt
+            .block_on(generate_embedding(Backend::OpenAiCompatible, &host, "nomic-embed-text", "x"))
+            .unwrap_err();
+        assert!(err.contains("empty embedding"), "{}", err);
+    }
+
+    #[test]
+    fn test_ensure_model() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let tags = r#"{"models": [{"name": "nomic-embed-text:latest"}, {"name": "llama3:8b"}]}"#;
+
+        let (host, requests) = mock_server(tags);
+        assert_eq!(rt.block_on(ensure_model(&host, "nomic-embed-text")), Ok(()));
+        assert_eq!(requests.recv().unwrap().0, "GET /api/tags HTTP/1.1");
+
+        let (host, _requests) = mock_server(tags);
+        assert_eq!(rt.block_on(ensure_model(&host, "llama3:8b")), Ok(()));
+
+        // A different tag of a pulled model doesn't count
+        let (host, _requests) = mock_server(tags);
+        let err = rt.block_on(ensure_model(&host, "llama3")).unwrap_err();
+        assert!(err.contains("pull it first"), "{}", err);
+
+        let (host, _requests) = mock_server(tags);
+        let err = rt.block_on(ensure_model(&host, "mxbai-embed-large")).unwrap_err();
+        assert_eq!(
+            err,
+            "Model 'mxbai-embed-large' not found in Ollama, pull it first (ollama pull mxbai-embed-large)"
+        );
+    }
+}
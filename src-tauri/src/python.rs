@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use thiserror::Error;
 
@@ -29,10 +29,92 @@ pub enum PythonBundleError {
     ImportFailed(String),
     #[error("Manifest missing or invalid")]
     ManifestInvalid,
+    #[error("Architecture mismatch: bundle is {bundle}, expected {expected}")]
+    ArchMismatch { bundle: String, expected: String },
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
 
+/// Why the bundled Python is (or isn't) usable, for the UI to show guidance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PythonDiagnosticCode {
+    Ok,
+    NotBundled,
+    ManifestInvalid,
+    BinaryMissing,
+    NotExecutable,
+    ImportFailed,
+    Corrupted,
+    ArchMismatch,
+    Io,
+}
+
+impl PythonBundleError {
+    pub fn diagnostic_code(&self) -> PythonDiagnosticCode {
+        match self {
+            PythonBundleError::Corrupted(_) => PythonDiagnosticCode::Corrupted,
+            PythonBundleError::BinaryMissing => PythonDiagnosticCode::BinaryMissing,
+            PythonBundleError::NotExecutable => PythonDiagnosticCode::NotExecutable,
+            PythonBundleError::ImportFailed(_) => PythonDiagnosticCode::ImportFailed,
+            PythonBundleError::ManifestInvalid => PythonDiagnosticCode::ManifestInvalid,
+            PythonBundleError::ArchMismatch { .. } => PythonDiagnosticCode::ArchMismatch,
+            PythonBundleError::Io(_) => PythonDiagnosticCode::Io,
+        }
+    }
+}
+
+/// Where a Python was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PythonSource {
+    Bundled,
+    SavedSetting,
+    VirtualEnv,
+    SystemPath,
+    KnownLocation,
+}
+
+/// Bundled Python status plus the fallback in use when the bundle is rejected
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PythonDiagnostics {
+    pub code: PythonDiagnosticCode,
+    /// Rejection reason, including import stderr when imports failed
+    pub message: Option<String>,
+    pub bundle_path: Option<PathBuf>,
+    pub fallback: Option<PythonInfo>,
+    pub fallback_source: Option<PythonSource>,
+}
+
+/// One candidate interpreter considered while resolving Python
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolutionStep {
+    pub source: PythonSource,
+    /// None when the source had nothing to offer (e.g. no saved setting)
+    pub path: Option<PathBuf>,
+    pub accepted: bool,
+    /// Why the candidate was skipped
+    pub reason: Option<String>,
+}
+
+impl ResolutionStep {
+    fn skipped(source: PythonSource, path: Option<PathBuf>, reason: &str) -> Self {
+        ResolutionStep {
+            source,
+            path,
+            accepted: false,
+            reason: Some(reason.to_string()),
+        }
+    }
+}
+
+/// The candidates `find_python` tried, ending with the one it chose (if any)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PythonResolution {
+    pub steps: Vec<ResolutionStep>,
+    pub winner: Option<PythonInfo>,
+}
+
 /// Verify the bundled Python installation is intact and functional
 pub fn verify_bundled_python(bundle_path: &PathBuf) -> Result<(), PythonBundleError> {
     let manifest_path = bundle_path.join("BUNDLE_MANIFEST.json");
@@ -123,10 +205,10 @@ pub fn verify_bundled_python(bundle_path: &PathBuf) -> Result<(), PythonBundleEr
                 all(target_os = "windows", target_arch = "x86_64")
             ))]
             if target != EXPECTED_ARCH {
-                return Err(PythonBundleError::Corrupted(format!(
-                    "Architecture mismatch: bundle is {}, expected {}",
-                    target, EXPECTED_ARCH
-                )));
+                return Err(PythonBundleError::ArchMismatch {
+                    bundle: target.to_string(),
+                    expected: EXPECTED_ARCH.to_string(),
+                });
             }
         }
     }
@@ -134,122 +216,125 @@ pub fn verify_bundled_python(bundle_path: &PathBuf) -> Result<(), PythonBundleEr
     Ok(())
 }
 
-/// Detect bundled Python from Tauri's resource directory
-pub fn detect_bundled_python(resource_dir: &PathBuf) -> Option<PythonInfo> {
-    let bundle_path = resource_dir.join("python");
-
-    // Verify bundle integrity before using
-    if let Err(e) = verify_bundled_python(&bundle_path) {
-        tracing::warn!("Bundled Python verification failed: {}", e);
-        return None;
+/// Find a working Python installation
+/// Priority: 1. Bundled Python, 2. Saved setting, 3. System Python
+pub fn find_python(resource_dir: Option<&PathBuf>) -> Option<PythonInfo> {
+    let python = resolve_python(resource_dir, &mut Vec::new());
+    if let Some(bundled) = python.as_ref().filter(|info| info.is_bundled) {
+        tracing::info!("Using bundled Python: {:?}", bundled.path);
     }
+    python
+}
 
-    #[cfg(unix)]
-    let python_path = bundle_path.join("bin/python3");
-    #[cfg(windows)]
-    let python_path = bundle_path.join("python.exe");
-
-    let output = Command::new(&python_path).arg("--version").output().ok()?;
+/// Every candidate `find_python` tried, in order, and the one it picked
+pub fn explain_python_resolution(resource_dir: Option<&PathBuf>) -> PythonResolution {
+    let mut steps = Vec::new();
+    let winner = resolve_python(resource_dir, &mut steps);
+    PythonResolution { steps, winner }
+}
 
-    if output.status.success() {
-        let version = String::from_utf8_lossy(&output.stdout)
-            .trim()
-            .replace("Python ", "");
-        return Some(PythonInfo {
-            path: python_path,
-            version,
-            is_bundled: true,
-        });
+fn resolve_python(resource_dir: Option<&PathBuf>, steps: &mut Vec<ResolutionStep>) -> Option<PythonInfo> {
+    // 1. FIRST: Check for bundled Python (highest priority)
+    match resource_dir.map(|dir| dir.join("python")) {
+        None => steps.push(ResolutionStep::skipped(PythonSource::Bundled, None, "No resource directory")),
+        Some(bundle_path) => match verify_bundled_python(&bundle_path) {
+            // Verify bundle integrity before using
+            Err(e) => {
+                tracing::warn!("Bundled Python verification failed: {}", e);
+                steps.push(ResolutionStep::skipped(PythonSource::Bundled, Some(bundle_path), &e.to_string()));
+            }
+            Ok(()) => {
+                #[cfg(unix)]
+                let python_path = bundle_path.join("bin/python3");
+                #[cfg(windows)]
+                let python_path = bundle_path.join("python.exe");
+
+                if let Some(info) = try_candidate(steps, PythonSource::Bundled, python_path) {
+                    return Some(info);
+                }
+            }
+        },
     }
 
-    None
+    find_fallback_python(steps).map(|(info, _)| info)
 }
 
-/// Find a working Python installation
-/// Priority: 1. Bundled Python, 2. Saved setting, 3. System Python
-pub fn find_python(resource_dir: Option<&PathBuf>) -> Option<PythonInfo> {
-    // 1. FIRST: Check for bundled Python (highest priority)
-    if let Some(res_dir) = resource_dir {
-        if let Some(bundled) = detect_bundled_python(res_dir) {
-            tracing::info!("Using bundled Python: {:?}", bundled.path);
-            return Some(bundled);
+/// Report why the bundled Python was accepted or rejected, and which
+/// fallback `find_python` would use instead
+pub fn python_diagnostics(resource_dir: Option<&PathBuf>) -> PythonDiagnostics {
+    let bundle_path = resource_dir.map(|dir| dir.join("python"));
+
+    let (code, message) = match &bundle_path {
+        Some(path) if path.exists() => match verify_bundled_python(path) {
+            Ok(()) => (PythonDiagnosticCode::Ok, None),
+            Err(e) => (e.diagnostic_code(), Some(e.to_string())),
+        },
+        _ => (PythonDiagnosticCode::NotBundled, None),
+    };
+
+    let (fallback, fallback_source) = if code == PythonDiagnosticCode::Ok {
+        (None, None)
+    } else {
+        match find_fallback_python(&mut Vec::new()) {
+            Some((info, source)) => (Some(info), Some(source)),
+            None => (None, None),
         }
+    };
+
+    PythonDiagnostics {
+        code,
+        message,
+        bundle_path,
+        fallback,
+        fallback_source,
     }
+}
 
+/// Find a non-bundled Python, in `find_python`'s priority order, recording
+/// each candidate in `steps`
+fn find_fallback_python(steps: &mut Vec<ResolutionStep>) -> Option<(PythonInfo, PythonSource)> {
     // 2. Check saved setting
-    if let Some(saved) = db::get_setting("python_path") {
-        let path = PathBuf::from(&saved);
-        if is_valid_python(&path) {
-            if let Some(version) = get_python_version(&path) {
-                return Some(PythonInfo {
-                    path,
-                    version,
-                    is_bundled: false,
-                });
+    match db::get_setting("python_path") {
+        Some(saved) => {
+            if let Some(found) = try_source(steps, PythonSource::SavedSetting, PathBuf::from(&saved)) {
+                return Some(found);
             }
         }
+        None => steps.push(ResolutionStep::skipped(PythonSource::SavedSetting, None, "No saved Python path")),
     }
 
     // 3. Check VIRTUAL_ENV env var (active venv)
-    if let Ok(venv) = std::env::var("VIRTUAL_ENV") {
-        #[cfg(unix)]
-        let python = PathBuf::from(&venv).join("bin/python3");
-        #[cfg(windows)]
-        let python = PathBuf::from(&venv).join("Scripts/python.exe");
-
-        if python.exists() && is_valid_python(&python) {
-            if let Some(version) = get_python_version(&python) {
-                return Some(PythonInfo {
-                    path: python,
-                    version,
-                    is_bundled: false,
-                });
+    match std::env::var("VIRTUAL_ENV") {
+        Ok(venv) => {
+            if let Some(found) = try_source(steps, PythonSource::VirtualEnv, venv_python_path(Path::new(&venv))) {
+                return Some(found);
             }
         }
+        Err(_) => steps.push(ResolutionStep::skipped(PythonSource::VirtualEnv, None, "VIRTUAL_ENV is not set")),
     }
 
     // 4. Check `which python3` (Unix) or `where python` (Windows)
     #[cfg(unix)]
-    {
-        if let Ok(output) = Command::new("which").arg("python3").output() {
-            let path = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            if !path.is_empty() {
-                let python = PathBuf::from(&path);
-                if is_valid_python(&python) {
-                    if let Some(version) = get_python_version(&python) {
-                        return Some(PythonInfo {
-                            path: python,
-                            version,
-                            is_bundled: false,
-                        });
-                    }
-                }
-            }
-        }
-    }
-
+    let on_path = Command::new("which")
+        .arg("python3")
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
     #[cfg(windows)]
-    {
-        if let Ok(output) = Command::new("where").arg("python").output() {
-            let path = String::from_utf8_lossy(&output.stdout)
-                .lines()
-                .next()
-                .unwrap_or("")
-                .trim()
-                .to_string();
-            if !path.is_empty() {
-                let python = PathBuf::from(&path);
-                if is_valid_python(&python) {
-                    if let Some(version) = get_python_version(&python) {
-                        return Some(PythonInfo {
-                            path: python,
-                            version,
-                            is_bundled: false,
-                        });
-                    }
-                }
+    let on_path = Command::new("where").arg("python").output().map(|output| {
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .unwrap_or("")
+            .trim()
+            .to_string()
+    });
+    match on_path {
+        Ok(path) if !path.is_empty() => {
+            if let Some(found) = try_source(steps, PythonSource::SystemPath, PathBuf::from(&path)) {
+                return Some(found);
             }
         }
+        _ => steps.push(ResolutionStep::skipped(PythonSource::SystemPath, None, "No Python found on PATH")),
     }
 
     // 5. Hardcoded fallbacks for macOS/Linux
@@ -260,15 +345,8 @@ pub fn find_python(resource_dir: Option<&PathBuf>) -> Option<PythonInfo> {
             "/usr/local/bin/python3",
             "/usr/bin/python3",
         ] {
-            let python = PathBuf::from(path);
-            if python.exists() && is_valid_python(&python) {
-                if let Some(version) = get_python_version(&python) {
-                    return Some(PythonInfo {
-                        path: python,
-                        version,
-                        is_bundled: false,
-                    });
-                }
+            if let Some(found) = try_source(steps, PythonSource::KnownLocation, PathBuf::from(path)) {
+                return Some(found);
             }
         }
     }
@@ -276,6 +354,136 @@ pub fn find_python(resource_dir: Option<&PathBuf>) -> Option<PythonInfo> {
     None
 }
 
+fn try_source(steps: &mut Vec<ResolutionStep>, source: PythonSource, path: PathBuf) -> Option<(PythonInfo, PythonSource)> {
+    try_candidate(steps, source, path).map(|info| (info, source))
+}
+
+/// Check one interpreter, recording why it was accepted or skipped
+fn try_candidate(steps: &mut Vec<ResolutionStep>, source: PythonSource, path: PathBuf) -> Option<PythonInfo> {
+    let rejection = if !path.exists() {
+        "Does not exist"
+    } else if !is_valid_python(&path) {
+        "Failed to run --version"
+    } else if let Some(version) = get_python_version(&path) {
+        steps.push(ResolutionStep {
+            source,
+            path: Some(path.clone()),
+            accepted: true,
+            reason: None,
+        });
+        return Some(PythonInfo {
+            path,
+            version,
+            is_bundled: source == PythonSource::Bundled,
+        });
+    } else {
+        "Could not read its version"
+    };
+    steps.push(ResolutionStep::skipped(source, Some(path), rejection));
+    None
+}
+
+/// pip names of the packages the app's generated scripts import
+pub const VENV_PACKAGES: &[&str] = &[
+    "scikit-learn",
+    "pandas",
+    "numpy",
+    "joblib",
+    "optuna",
+    "shap",
+    "matplotlib",
+    "fastapi",
+    "uvicorn",
+    "slowapi",
+    "onnxruntime",
+    "skl2onnx",
+];
+
+/// Path of the interpreter inside a virtualenv
+pub fn venv_python_path(venv_dir: &Path) -> PathBuf {
+    #[cfg(unix)]
+    return venv_dir.join("bin/python3");
+    #[cfg(windows)]
+    return venv_dir.join("Scripts/python.exe");
+}
+
+/// Check that `venv_dir` is a virtualenv with a working interpreter
+pub fn verify_venv(venv_dir: &Path) -> Result<PythonInfo, String> {
+    if !venv_dir.join("pyvenv.cfg").exists() {
+        return Err(format!("{} is not a virtualenv (no pyvenv.cfg)", venv_dir.display()));
+    }
+    let python = venv_python_path(venv_dir);
+    if !is_valid_python(&python) {
+        return Err(format!("Virtualenv interpreter {} does not run", python.display()));
+    }
+    let version = get_python_version(&python)
+        .ok_or_else(|| format!("Could not read version of {}", python.display()))?;
+    Ok(PythonInfo {
+        path: python,
+        version,
+        is_bundled: false,
+    })
+}
+
+/// Create a virtualenv at `dest` using `base_python` and verify it
+pub fn create_venv(base_python: &Path, dest: &Path) -> Result<PythonInfo, String> {
+    if dest.exists() && std::fs::read_dir(dest).map_err(|e| e.to_string())?.next().is_some() {
+        return Err(format!("{} already exists and is not empty", dest.display()));
+    }
+
+    let output = Command::new(base_python)
+        .args(["-m", "venv"])
+        .arg(dest)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", base_python.display(), e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // Debian/Ubuntu ship venv and ensurepip as separate packages
+        if stderr.contains("No module named venv") || stderr.contains("ensurepip is not available") {
+            return Err(format!(
+                "The venv module is not available for {}; install it (e.g. python3-venv) and try again",
+                base_python.display()
+            ));
+        }
+        return Err(format!("Failed to create virtualenv: {}", stderr.trim()));
+    }
+
+    verify_venv(dest)
+}
+
+/// pip-install `packages` into the virtualenv one at a time, calling
+/// `on_package(index, package)` before each
+pub fn install_venv_packages(
+    venv_python: &Path,
+    packages: &[&str],
+    mut on_package: impl FnMut(usize, &str),
+) -> Result<(), String> {
+    let pip_ok = Command::new(venv_python)
+        .args(["-m", "pip", "--version"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if !pip_ok {
+        return Err("pip is not available in the virtualenv".to_string());
+    }
+
+    for (i, package) in packages.iter().enumerate() {
+        on_package(i, package);
+        let output = Command::new(venv_python)
+            .args(["-m", "pip", "install", "--disable-pip-version-check", package])
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to install {}: {}",
+                package,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+    }
+    Ok(())
+}
+
 /// Validate that a Python executable works
 fn is_valid_python(path: &PathBuf) -> bool {
     if !path.exists() {
@@ -319,4 +527,160 @@ mod tests {
         let fake = PathBuf::from("/nonexistent/python3");
         assert!(!is_valid_python(&fake));
     }
+
+    #[test]
+    fn test_venv_python_path() {
+        let venv = PathBuf::from("/home/user/ml-env");
+        #[cfg(unix)]
+        assert_eq!(venv_python_path(&venv), PathBuf::from("/home/user/ml-env/bin/python3"));
+        #[cfg(windows)]
+        assert_eq!(venv_python_path(&venv), venv.join("Scripts/python.exe"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_verify_venv() {
+        let venv = std::env::temp_dir().join(format!("venv-verify-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(venv.join("bin")).unwrap();
+        assert!(verify_venv(&venv).unwrap_err().contains("not a virtualenv"));
+
+        std::fs::write(venv.join("pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+        assert!(verify_venv(&venv).unwrap_err().contains("does not run"));
+
+        let system_python = PathBuf::from("/usr/bin/python3");
+        if system_python.exists() {
+            std::os::unix::fs::symlink(&system_python, venv.join("bin/python3")).unwrap();
+            let info = verify_venv(&venv).unwrap();
+            assert_eq!(info.path, venv.join("bin/python3"));
+            assert!(!info.is_bundled);
+            assert_eq!(Some(info.version), get_python_version(&system_python));
+        }
+
+        // Refuses to create over a non-empty directory
+        assert!(create_venv(&system_python, &venv).unwrap_err().contains("not empty"));
+
+        std::fs::remove_dir_all(&venv).unwrap();
+    }
+
+    #[test]
+    fn test_bundle_error_diagnostic_codes() {
+        let cases = [
+            (PythonBundleError::Corrupted("Missing package: numpy".to_string()), PythonDiagnosticCode::Corrupted),
+            (PythonBundleError::BinaryMissing, PythonDiagnosticCode::BinaryMissing),
+            (PythonBundleError::NotExecutable, PythonDiagnosticCode::NotExecutable),
+            (PythonBundleError::ImportFailed("No module named 'shap'".to_string()), PythonDiagnosticCode::ImportFailed),
+            (PythonBundleError::ManifestInvalid, PythonDiagnosticCode::ManifestInvalid),
+            (
+                PythonBundleError::ArchMismatch {
+                    bundle: "x86_64-apple-darwin".to_string(),
+                    expected: "aarch64-apple-darwin".to_string(),
+                },
+                PythonDiagnosticCode::ArchMismatch,
+            ),
+            (
+                PythonBundleError::Io(std::io::Error::other("denied")),
+                PythonDiagnosticCode::Io,
+            ),
+        ];
+        for (error, code) in cases {
+            assert_eq!(error.diagnostic_code(), code, "{}", error);
+        }
+    }
+
+    #[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+    #[test]
+    fn test_python_diagnostics_reports_bundle_rejection() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        crate::db::tests::setup_test_db();
+        assert_eq!(python_diagnostics(None).code, PythonDiagnosticCode::NotBundled);
+
+        let resource_dir = std::env::temp_dir().join(format!("python-diag-{}", uuid::Uuid::new_v4()));
+        let bundle = resource_dir.join("python");
+        std::fs::create_dir_all(bundle.join("bin")).unwrap();
+        let diagnose = || python_diagnostics(Some(&resource_dir));
+
+        assert_eq!(diagnose().code, PythonDiagnosticCode::ManifestInvalid);
+
+        std::fs::write(
+            bundle.join("BUNDLE_MANIFEST.json"),
+            r#"{"target": "aarch64-apple-darwin"}"#,
+        )
+        .unwrap();
+        assert_eq!(diagnose().code, PythonDiagnosticCode::BinaryMissing);
+
+        let python_bin = bundle.join("bin/python3");
+        std::fs::write(&python_bin, "").unwrap();
+        std::fs::set_permissions(&python_bin, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(diagnose().code, PythonDiagnosticCode::NotExecutable);
+
+        // sh can't run the import line, so its stderr is reported
+        std::fs::remove_file(&python_bin).unwrap();
+        symlink("/bin/sh", &python_bin).unwrap();
+        let diagnostics = diagnose();
+        assert_eq!(diagnostics.code, PythonDiagnosticCode::ImportFailed);
+        let message = diagnostics.message.unwrap();
+        assert!(message.len() > "Import verification failed: ".len(), "{}", message);
+
+        // echo prints the script back, BUNDLE_OK included
+        std::fs::remove_file(&python_bin).unwrap();
+        symlink("/bin/echo", &python_bin).unwrap();
+        assert_eq!(diagnose().code, PythonDiagnosticCode::Corrupted);
+
+        for pkg in ["sklearn", "pandas", "numpy"] {
+            let pkg_dir = bundle.join("lib/python3.11/site-packages").join(pkg);
+            std::fs::create_dir_all(&pkg_dir).unwrap();
+            std::fs::write(pkg_dir.join("__init__.py"), "").unwrap();
+        }
+        let diagnostics = diagnose();
+        assert_eq!(diagnostics.code, PythonDiagnosticCode::ArchMismatch);
+        assert_eq!(
+            diagnostics.message.as_deref(),
+            Some("Architecture mismatch: bundle is aarch64-apple-darwin, expected x86_64-unknown-linux-gnu")
+        );
+        assert_eq!(diagnostics.bundle_path, Some(bundle.clone()));
+        // The fallback is whatever find_python would use without the bundle
+        assert_eq!(diagnostics.fallback.is_some(), diagnostics.fallback_source.is_some());
+
+        std::fs::remove_dir_all(&resource_dir).unwrap();
+    }
+
+    // Held by tests that set the global python_path setting
+    static PYTHON_PATH_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    #[test]
+    fn test_explain_python_resolution_skips_invalid_saved_path() {
+        crate::db::tests::setup_test_db();
+        let _serial = PYTHON_PATH_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let saved = crate::db::get_setting("python_path");
+        crate::db::set_setting("python_path", "/nonexistent/bin/python3").unwrap();
+
+        let resolution = explain_python_resolution(None);
+        assert_eq!(resolution.steps[0].source, PythonSource::Bundled);
+        assert_eq!(resolution.steps[0].reason.as_deref(), Some("No resource directory"));
+        let saved_step = &resolution.steps[1];
+        assert_eq!(saved_step.source, PythonSource::SavedSetting);
+        assert_eq!(saved_step.path, Some(PathBuf::from("/nonexistent/bin/python3")));
+        assert!(!saved_step.accepted);
+        assert_eq!(saved_step.reason.as_deref(), Some("Does not exist"));
+
+        // Resolution fell through to a later source, which is the last step
+        // and the winner
+        let last = resolution.steps.last().unwrap();
+        assert!(resolution.steps[..resolution.steps.len() - 1].iter().all(|step| !step.accepted));
+        match &resolution.winner {
+            Some(winner) => {
+                assert!(last.accepted);
+                assert_ne!(last.source, PythonSource::SavedSetting);
+                assert_eq!(last.path.as_ref(), Some(&winner.path));
+            }
+            None => assert!(!last.accepted),
+        }
+        assert_eq!(resolution.winner.map(|w| w.path), find_python(None).map(|p| p.path));
+
+        match saved {
+            Some(path) => crate::db::set_setting("python_path", &path).unwrap(),
+            None => crate::db::delete_setting("python_path").unwrap(),
+        }
+    }
 }
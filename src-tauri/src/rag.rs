@@ -0,0 +1,1037 @@
+//! RAG indexing for Script node code.
+//!
+//! Code chunks (extracted by the frontend via LSP document symbols) are
+//! embedded with an Ollama embedding model and stored in `chunk_embeddings`.
+//! Chunks whose content hash is unchanged are skipped, so re-indexing a
+//! pipeline only pays for the code that changed.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use crate::db;
+
+/// A chunk of code from a Script node, as produced by the frontend chunker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeChunk {
+    pub chunk_id: String, // e.g., "func:train_model" or "toplevel:0"
+    pub content: String,
+    pub symbol_name: Option<String>,
+    pub symbol_type: Option<String>, // function, class, method, toplevel
+    pub start_line: Option<i32>,
+    pub end_line: Option<i32>,
+}
+
+/// All current chunks of one node
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeChunks {
+    pub node_id: String,
+    pub chunks: Vec<CodeChunk>,
+}
+
+/// A stored chunk ranked against a query
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ChunkMatch {
+    pub node_id: String,
+    pub chunk_id: String,
+    pub symbol_name: Option<String>,
+    pub symbol_type: Option<String>,
+    pub start_line: Option<i32>,
+    pub end_line: Option<i32>,
+    pub score: f32,
+}
+
+/// A node ranked by its best-matching chunk
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeMatch {
+    pub node_id: String,
+    pub score: f32,
+    pub matched_chunks: usize,
+}
+
+/// A vector search candidate re-scored by an LLM relevance judgement
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RerankedNode {
+    pub node_id: String,
+    pub vector_score: f32,
+    pub rerank_score: f32, // 0-1, from the model's 0-10 rating
+    pub matched_chunks: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct IndexSummary {
+    pub indexed: usize,
+    pub skipped: usize,
+    pub deleted: usize,
+}
+
+/// How chunk text is rewritten before it is hashed and embedded. The mode is
+/// recorded in the pipeline's embedding config so queries get the same treatment.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreprocessMode {
+    #[default]
+    None,
+    StripComments,
+    SymbolPrefixed,
+}
+
+impl PreprocessMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreprocessMode::None => "none",
+            PreprocessMode::StripComments => "strip_comments",
+            PreprocessMode::SymbolPrefixed => "symbol_prefixed",
+        }
+    }
+}
+
+impl FromStr for PreprocessMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(PreprocessMode::None),
+            "strip_comments" => Ok(PreprocessMode::StripComments),
+            "symbol_prefixed" => Ok(PreprocessMode::SymbolPrefixed),
+            other => Err(format!("Unknown preprocess mode '{}'", other)),
+        }
+    }
+}
+
+/// Remove `#` comments (leaving string literals alone), trailing whitespace and
+/// blank lines from Python source
+pub fn strip_comments(code: &str) -> String {
+    let chars: Vec<char> = code.chars().collect();
+    let mut out = String::with_capacity(code.len());
+    // (quote char, triple-quoted) while inside a string literal
+    let mut string: Option<(char, bool)> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let is_triple = |q: char| chars.get(i + 1) == Some(&q) && chars.get(i + 2) == Some(&q);
+        match string {
+            None => {
+                if c == '#' {
+                    while i < chars.len() && chars[i] != '\n' {
+                        i += 1;
+                    }
+                    continue;
+                }
+                if c == '\'' || c == '"' {
+                    let triple = is_triple(c);
+                    string = Some((c, triple));
+                    if triple {
+                        out.extend([c, c]);
+                        i += 2;
+                    }
+                }
+                out.push(c);
+            }
+            Some((q, triple)) => {
+                if c == '\\' {
+                    out.push(c);
+                    if let Some(&next) = chars.get(i + 1) {
+                        out.push(next);
+                    }
+                    i += 2;
+                    continue;
+                }
+                if c == q && (!triple || is_triple(q)) {
+                    if triple {
+                        out.extend([q, q]);
+                        i += 2;
+                    }
+                    string = None;
+                } else if c == '\n' && !triple {
+                    // Unterminated single-line string; don't let it swallow the file
+                    string = None;
+                }
+                out.push(c);
+            }
+        }
+        i += 1;
+    }
+
+    out.lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The text that is hashed and embedded for `chunk` under `mode`
+pub fn preprocess_chunk(mode: PreprocessMode, chunk: &CodeChunk) -> String {
+    match mode {
+        PreprocessMode::None => chunk.content.clone(),
+        PreprocessMode::StripComments => strip_comments(&chunk.content),
+        PreprocessMode::SymbolPrefixed => match &chunk.symbol_name {
+            Some(name) => format!(
+                "# {}: {}\n{}",
+                chunk.symbol_type.as_deref().unwrap_or("symbol"),
+                name,
+                chunk.content
+            ),
+            None => chunk.content.clone(),
+        },
+    }
+}
+
+/// Apply the pipeline's preprocessing to a search query. Queries have no
+/// symbol, so `symbol_prefixed` leaves them as-is.
+pub fn preprocess_query(mode: PreprocessMode, query: &str) -> String {
+    match mode {
+        PreprocessMode::StripComments => strip_comments(query),
+        PreprocessMode::None | PreprocessMode::SymbolPrefixed => query.to_string(),
+    }
+}
+
+/// Preprocess a query with the mode the pipeline was indexed with (`none`
+/// when the pipeline has no index yet)
+pub fn preprocess_query_for_pipeline(pipeline_id: &str, query: &str) -> Result<String, String> {
+    let mode = match db::get_embedding_config(pipeline_id).map_err(|e| e.to_string())? {
+        Some(config) => config.preprocess.parse()?,
+        None => PreprocessMode::None,
+    };
+    Ok(preprocess_query(mode, query))
+}
+
+/// SHA-256 of chunk content, hex encoded
+pub fn content_hash(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn normalize(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let scale = if norm > 0.0 { 1.0 / norm } else { 1.0 };
+    embedding.iter().map(|v| v * scale).collect()
+}
+
+/// L2-normalize an embedding and encode it as little-endian f32 bytes, so
+/// search can use a plain dot product
+pub fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+    normalize(embedding)
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+/// Decode a blob written by `embedding_to_blob` for a `dim`-dimensional
+/// embedding. A blob of any other length (e.g. cut short by a crash mid-write)
+/// is an error rather than a shorter vector.
+pub fn blob_to_embedding(blob: &[u8], dim: usize) -> Result<Vec<f32>, String> {
+    if !blob.len().is_multiple_of(4) || blob.len() / 4 != dim {
+        return Err(format!(
+            "embedding blob is {} bytes, expected {} for {} dimensions",
+            blob.len(),
+            dim * 4,
+            dim
+        ));
+    }
+    Ok(blob
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/// Score every chunk against `query` by cosine similarity, drop ones below
+/// `min_score`, and return the best `top_k`. Chunks with a different
+/// dimension than the query are skipped, as are chunks whose stored blob
+/// doesn't decode to their recorded dimension.
+pub fn rank_chunks(
+    query: &[f32],
+    chunks: Vec<db::ChunkEmbedding>,
+    top_k: usize,
+    min_score: Option<f32>,
+) -> Vec<ChunkMatch> {
+    let query = normalize(query);
+    let mut matches: Vec<ChunkMatch> = chunks
+        .into_iter()
+        .filter(|c| c.embedding_dim as usize == query.len())
+        .filter_map(|c| {
+            let embedding = match blob_to_embedding(&c.embedding, query.len()) {
+                Ok(embedding) => embedding,
+                Err(e) => {
+                    tracing::warn!("Skipping corrupt embedding for {}/{}: {}", c.node_id, c.chunk_id, e);
+                    return None;
+                }
+            };
+            let score = embedding.iter().zip(&query).map(|(a, b)| a * b).sum();
+            Some(ChunkMatch {
+                node_id: c.node_id,
+                chunk_id: c.chunk_id,
+                symbol_name: c.symbol_name,
+                symbol_type: c.symbol_type,
+                start_line: c.start_line,
+                end_line: c.end_line,
+                score,
+            })
+        })
+        .filter(|m| min_score.is_none_or(|min| m.score >= min))
+        .collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches.truncate(top_k);
+    matches
+}
+
+/// Group chunk matches by node, scoring each node by its best chunk
+pub fn rank_nodes(matches: &[ChunkMatch], top_k: usize) -> Vec<NodeMatch> {
+    let mut nodes: Vec<NodeMatch> = Vec::new();
+    for m in matches {
+        match nodes.iter_mut().find(|n| n.node_id == m.node_id) {
+            Some(node) => {
+                node.score = node.score.max(m.score);
+                node.matched_chunks += 1;
+            }
+            None => nodes.push(NodeMatch {
+                node_id: m.node_id.clone(),
+                score: m.score,
+                matched_chunks: 1,
+            }),
+        }
+    }
+    nodes.sort_by(|a, b| b.score.total_cmp(&a.score));
+    nodes.truncate(top_k);
+    nodes
+}
+
+// Number of distinct queries whose rankings are kept
+const SEARCH_CACHE_CAPACITY: usize = 128;
+
+/// Identifies a search: pipeline, embedding model, and hash of the
+/// preprocessed query text
+type SearchKey = (String, String, String);
+
+struct CachedSearch {
+    key: SearchKey,
+    generation: u64, // the pipeline's chunk index generation it was ranked against
+    ranked: Vec<ChunkMatch>, // every chunk, best first
+}
+
+// Recently used searches, most recent first. Entries ranked against an older
+// index generation are dropped when next looked up.
+static SEARCH_CACHE: std::sync::OnceLock<Mutex<VecDeque<CachedSearch>>> = std::sync::OnceLock::new();
+
+fn search_cache() -> &'static Mutex<VecDeque<CachedSearch>> {
+    SEARCH_CACHE.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+fn cached_ranking(key: &SearchKey, generation: u64) -> Option<Vec<ChunkMatch>> {
+    let mut cache = search_cache().lock().ok()?;
+    let index = cache.iter().position(|entry| entry.key == *key)?;
+    let entry = cache.remove(index)?;
+    if entry.generation != generation {
+        return None;
+    }
+    let ranked = entry.ranked.clone();
+    cache.push_front(entry);
+    Some(ranked)
+}
+
+fn cache_ranking(key: SearchKey, generation: u64, ranked: Vec<ChunkMatch>) {
+    if let Ok(mut cache) = search_cache().lock() {
+        cache.retain(|entry| entry.key != key);
+        cache.push_front(CachedSearch { key, generation, ranked });
+        cache.truncate(SEARCH_CACHE_CAPACITY);
+    }
+}
+
+/// The `top_k` chunks most similar to `query` that score at least `min_score`.
+/// The query gets the same preprocessing the pipeline was indexed with.
+/// Rankings are cached until the pipeline's index changes, so repeating a
+/// query doesn't embed it again.
+pub async fn search_similar_chunks<F, Fut>(
+    embed: &F,
+    pipeline_id: &str,
+    model: &str,
+    query: &str,
+    top_k: usize,
+    min_score: Option<f32>,
+) -> Result<Vec<ChunkMatch>, String>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Vec<f32>, String>>,
+{
+    if db::rag_needs_reindex(pipeline_id, model).map_err(|e| e.to_string())? {
+        return Err(format!(
+            "Pipeline index can't be searched with '{}'; reindex it first",
+            model
+        ));
+    }
+    // Read before the chunks, so a ranking computed while the index changes
+    // is cached as already stale
+    let generation = db::chunk_index_generation(pipeline_id);
+    let query = preprocess_query_for_pipeline(pipeline_id, query)?;
+    let key = (pipeline_id.to_string(), model.to_string(), content_hash(&query));
+    let ranked = match cached_ranking(&key, generation) {
+        Some(ranked) => ranked,
+        None => {
+            let chunks = db::list_chunk_embeddings_for_pipeline(pipeline_id).map_err(|e| e.to_string())?;
+            if chunks.is_empty() {
+                return Ok(vec![]);
+            }
+            let embedding = embed(query).await?;
+            let ranked = rank_chunks(&embedding, chunks, usize::MAX, None);
+            cache_ranking(key, generation, ranked.clone());
+            ranked
+        }
+    };
+    Ok(ranked
+        .into_iter()
+        .filter(|m| min_score.is_none_or(|min| m.score >= min))
+        .take(top_k)
+        .collect())
+}
+
+/// The `top_k` nodes whose best chunk scores at least `min_score` against `query`
+pub async fn search_similar_nodes<F, Fut>(
+    embed: &F,
+    pipeline_id: &str,
+    model: &str,
+    query: &str,
+    top_k: usize,
+    min_score: Option<f32>,
+) -> Result<Vec<NodeMatch>, String>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Vec<f32>, String>>,
+{
+    let matches = search_similar_chunks(embed, pipeline_id, model, query, usize::MAX, min_score).await?;
+    Ok(rank_nodes(&matches, top_k))
+}
+
+// Node code sent to the reranker is cut to this many characters, so one long
+// script can't overflow the model's context
+const RERANK_MAX_CHARS: usize = 4000;
+
+// Number of per-candidate rerank scores kept
+const RERANK_CACHE_CAPACITY: usize = 1024;
+
+/// Identifies a relevance judgement: rerank model, hash of the query, and
+/// hash of the candidate code it was shown
+type RerankKey = (String, String, String);
+
+// Recently used rerank scores, most recent first. Keyed by content, so an
+// edited node is simply judged again.
+static RERANK_CACHE: std::sync::OnceLock<Mutex<VecDeque<(RerankKey, f32)>>> = std::sync::OnceLock::new();
+
+fn rerank_cache() -> &'static Mutex<VecDeque<(RerankKey, f32)>> {
+    RERANK_CACHE.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+fn cached_rerank_score(key: &RerankKey) -> Option<f32> {
+    let mut cache = rerank_cache().lock().ok()?;
+    let index = cache.iter().position(|(k, _)| k == key)?;
+    let entry = cache.remove(index)?;
+    let score = entry.1;
+    cache.push_front(entry);
+    Some(score)
+}
+
+fn cache_rerank_score(key: RerankKey, score: f32) {
+    if let Ok(mut cache) = rerank_cache().lock() {
+        cache.retain(|(k, _)| *k != key);
+        cache.push_front((key, score));
+        cache.truncate(RERANK_CACHE_CAPACITY);
+    }
+}
+
+/// Prompt asking the model to rate `code`'s relevance to `query`
+pub fn rerank_prompt(query: &str, code: &str) -> String {
+    format!(
+        "Rate how relevant the following Python code is to the search query, on a scale from 0 (unrelated) to 10 (exactly what is being searched for). Answer with the number only.\n\nQuery: {}\n\nCode:\n```python\n{}\n```\n\nRelevance (0-10):",
+        query, code
+    )
+}
+
+/// The first number in a model's answer, clamped to 0-10 and scaled to 0-1
+pub fn parse_rerank_score(answer: &str) -> Option<f32> {
+    answer
+        .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .find_map(|token| token.trim_end_matches('.').parse::<f32>().ok())
+        .map(|score| score.clamp(0.0, 10.0) / 10.0)
+}
+
+/// Code of each node in the saved pipeline, by node id
+fn pipeline_node_code(pipeline_id: &str) -> Result<HashMap<String, String>, String> {
+    let data = db::load_pipeline(pipeline_id)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Pipeline {} not found", pipeline_id))?;
+    let value: serde_json::Value = serde_json::from_str(&data).map_err(|e| e.to_string())?;
+    Ok(value["nodes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|node| Some((node["id"].as_str()?.to_string(), node["data"]["code"].as_str()?.to_string())))
+        .collect())
+}
+
+/// Vector search for the best `rerank_top_n` nodes, then re-score each by
+/// asking the LLM behind `rerank` how relevant the node's code is to `query`,
+/// and return the `top_k` by that score. `rerank` receives the prompt and
+/// returns the model's raw answer. Scores are cached per rerank model, query
+/// and code, so repeating a search only asks about nodes that changed.
+/// Candidates with no saved code, or whose answer has no number, score 0.
+#[allow(clippy::too_many_arguments)]
+pub async fn search_similar_nodes_reranked<F, Fut, G, GFut>(
+    embed: &F,
+    rerank: &G,
+    pipeline_id: &str,
+    model: &str,
+    rerank_model: &str,
+    query: &str,
+    top_k: usize,
+    rerank_top_n: usize,
+) -> Result<Vec<RerankedNode>, String>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Vec<f32>, String>>,
+    G: Fn(String) -> GFut,
+    GFut: Future<Output = Result<String, String>>,
+{
+    let candidates = search_similar_nodes(embed, pipeline_id, model, query, rerank_top_n.max(top_k), None).await?;
+    if candidates.is_empty() {
+        return Ok(vec![]);
+    }
+    let code = pipeline_node_code(pipeline_id)?;
+    let query_hash = content_hash(query);
+
+    let mut reranked = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        let rerank_score = match code.get(&candidate.node_id) {
+            Some(code) => {
+                let code: String = code.chars().take(RERANK_MAX_CHARS).collect();
+                let key = (rerank_model.to_string(), query_hash.clone(), content_hash(&code));
+                match cached_rerank_score(&key) {
+                    Some(score) => score,
+                    None => {
+                        let answer = rerank(rerank_prompt(query, &code)).await?;
+                        match parse_rerank_score(&answer) {
+                            Some(score) => {
+                                cache_rerank_score(key, score);
+                                score
+                            }
+                            None => {
+                                tracing::warn!("Rerank answer for node {} has no score: {:?}", candidate.node_id, answer);
+                                0.0
+                            }
+                        }
+                    }
+                }
+            }
+            None => {
+                tracing::warn!("Node {} has no saved code to rerank", candidate.node_id);
+                0.0
+            }
+        };
+        reranked.push(RerankedNode {
+            node_id: candidate.node_id,
+            vector_score: candidate.score,
+            rerank_score,
+            matched_chunks: candidate.matched_chunks,
+        });
+    }
+    // Stable, so equal rerank scores keep their vector order
+    reranked.sort_by(|a, b| b.rerank_score.total_cmp(&a.rerank_score));
+    reranked.truncate(top_k);
+    Ok(reranked)
+}
+
+/// Pin the pipeline to `model`/`dim`/`preprocess`, failing if it's indexed
+/// differently unless `force_reindex` is set (which clears the index first)
+pub fn ensure_embedding_pin(
+    pipeline_id: &str,
+    model: &str,
+    dim: i32,
+    preprocess: PreprocessMode,
+    force_reindex: bool,
+) -> Result<(), String> {
+    let conflict = db::pin_embedding_model(pipeline_id, model, dim, preprocess.as_str(), force_reindex)
+        .map_err(|e| e.to_string())?;
+    match conflict {
+        Some(pinned) => Err(format!(
+            "Pipeline is indexed with embedding model '{}' ({} dims, preprocess '{}'); reindex with force_reindex to switch to '{}' ({} dims, preprocess '{}')",
+            pinned.model, pinned.dim, pinned.preprocess, model, dim, preprocess.as_str()
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Embed and store the node's changed chunks, skipping ones whose content
+/// hash matches what's already indexed. The hash covers the preprocessed
+/// text, so switching modes re-embeds every chunk.
+pub async fn index_node_chunks<F, Fut>(
+    embed: &F,
+    pipeline_id: &str,
+    model: &str,
+    preprocess: PreprocessMode,
+    node: &NodeChunks,
+) -> Result<IndexSummary, String>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Vec<f32>, String>>,
+{
+    let mut summary = IndexSummary::default();
+
+    for chunk in &node.chunks {
+        let text = preprocess_chunk(preprocess, chunk);
+        let hash = content_hash(&text);
+        let existing = db::get_chunk_embedding_hash(&node.node_id, &chunk.chunk_id)
+            .map_err(|e| e.to_string())?;
+        if existing.as_deref() == Some(hash.as_str()) {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let embedding = embed(text).await?;
+        let dim = embedding.len() as i32;
+        ensure_embedding_pin(pipeline_id, model, dim, preprocess, false)?;
+
+        db::upsert_chunk_embedding(
+            &node.node_id,
+            pipeline_id,
+            &chunk.chunk_id,
+            &hash,
+            &embedding_to_blob(&embedding),
+            model,
+            dim,
+            chunk.symbol_name.as_deref(),
+            chunk.symbol_type.as_deref(),
+            chunk.start_line,
+            chunk.end_line,
+        )
+        .map_err(|e| e.to_string())?;
+        summary.indexed += 1;
+    }
+
+    Ok(summary)
+}
+
+/// Bring the pipeline's index in line with `nodes` in one pass: index changed
+/// chunks, drop chunks a node no longer has, and drop nodes no longer present
+pub async fn reindex_pipeline_incremental<F, Fut>(
+    embed: &F,
+    pipeline_id: &str,
+    model: &str,
+    preprocess: PreprocessMode,
+    nodes: &[NodeChunks],
+) -> Result<IndexSummary, String>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Vec<f32>, String>>,
+{
+    let mut summary = IndexSummary::default();
+
+    for node in nodes {
+        let node_summary = index_node_chunks(embed, pipeline_id, model, preprocess, node).await?;
+        summary.indexed += node_summary.indexed;
+        summary.skipped += node_summary.skipped;
+
+        let keep: Vec<String> = node.chunks.iter().map(|c| c.chunk_id.clone()).collect();
+        summary.deleted += db::delete_orphan_chunks(&node.node_id, keep).map_err(|e| e.to_string())?;
+    }
+
+    let node_ids: Vec<String> = nodes.iter().map(|n| n.node_id.clone()).collect();
+    summary.deleted +=
+        db::delete_chunks_for_missing_nodes(pipeline_id, &node_ids).map_err(|e| e.to_string())?;
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::setup_test_db;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn chunk(chunk_id: &str, content: &str) -> CodeChunk {
+        CodeChunk {
+            chunk_id: chunk_id.to_string(),
+            content: content.to_string(),
+            symbol_name: None,
+            symbol_type: Some("toplevel".to_string()),
+            start_line: None,
+            end_line: None,
+        }
+    }
+
+    #[test]
+    fn test_embedding_blob_is_normalized() {
+        let decoded = blob_to_embedding(&embedding_to_blob(&[3.0, 4.0]), 2).unwrap();
+        assert_eq!(decoded, vec![0.6, 0.8]);
+        let blob = embedding_to_blob(&[3.0, 4.0]);
+        assert!(blob_to_embedding(&blob[..6], 2).is_err());
+        assert!(blob_to_embedding(&blob[..4], 2).is_err());
+        assert!(blob_to_embedding(&blob, 3).is_err());
+        assert_eq!(content_hash("abc").len(), 64);
+        assert_ne!(content_hash("abc"), content_hash("abd"));
+    }
+
+    #[test]
+    fn test_preprocess_modes() {
+        let code = "def train(df):  # fit the model\n    # split first\n\n    label = '#target'\n    return \"\"\"doc # kept\"\"\"";
+        let chunk = CodeChunk {
+            chunk_id: "func:train".to_string(),
+            content: code.to_string(),
+            symbol_name: Some("train".to_string()),
+            symbol_type: Some("function".to_string()),
+            start_line: Some(1),
+            end_line: Some(5),
+        };
+
+        assert_eq!(preprocess_chunk(PreprocessMode::None, &chunk), code);
+        assert_eq!(
+            preprocess_chunk(PreprocessMode::StripComments, &chunk),
+            "def train(df):\n    label = '#target'\n    return \"\"\"doc # kept\"\"\""
+        );
+        assert_eq!(
+            preprocess_chunk(PreprocessMode::SymbolPrefixed, &chunk),
+            format!("# function: train\n{}", code)
+        );
+        // Unnamed chunks have nothing to prefix
+        assert_eq!(preprocess_chunk(PreprocessMode::SymbolPrefixed, &self::chunk("toplevel:0", "x = 1")), "x = 1");
+
+        assert_eq!(preprocess_query(PreprocessMode::StripComments, "load csv # pandas"), "load csv");
+        assert_eq!(preprocess_query(PreprocessMode::SymbolPrefixed, "load csv"), "load csv");
+        assert_eq!("strip_comments".parse::<PreprocessMode>(), Ok(PreprocessMode::StripComments));
+        assert!("lowercase".parse::<PreprocessMode>().is_err());
+    }
+
+    #[test]
+    fn test_preprocess_is_reflected_in_hash() {
+        setup_test_db();
+        let pipeline_id = format!("preprocess-{}", uuid::Uuid::new_v4());
+        let node_id = format!("{}-node", pipeline_id);
+        let node = NodeChunks {
+            node_id: node_id.clone(),
+            chunks: vec![chunk("toplevel:0", "x = 1  # one")],
+        };
+
+        let embedded = std::sync::Mutex::new(Vec::new());
+        let embed = |text: String| {
+            embedded.lock().unwrap().push(text);
+            async { Ok(vec![1.0, 0.0]) }
+        };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        rt.block_on(index_node_chunks(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::StripComments, &node))
+            .unwrap();
+        assert_eq!(*embedded.lock().unwrap(), vec!["x = 1".to_string()]);
+        assert_eq!(
+            db::get_chunk_embedding_hash(&node_id, "toplevel:0").unwrap(),
+            Some(content_hash("x = 1"))
+        );
+        let config = db::get_embedding_config(&pipeline_id).unwrap().unwrap();
+        assert_eq!(config.preprocess, "strip_comments");
+        assert_eq!(preprocess_query_for_pipeline(&pipeline_id, "x = 1  # query").unwrap(), "x = 1");
+
+        // A different mode conflicts with the pinned one
+        let err = rt
+            .block_on(index_node_chunks(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::None, &node))
+            .unwrap_err();
+        assert!(err.contains("strip_comments"), "{}", err);
+    }
+
+    #[test]
+    fn test_search_min_score_excludes_weak_matches() {
+        setup_test_db();
+        let pipeline_id = format!("search-{}", uuid::Uuid::new_v4());
+        let node_a = format!("{}-a", pipeline_id);
+        let node_b = format!("{}-b", pipeline_id);
+
+        // Embed by keyword so similarities are predictable
+        let embed = |text: String| async move {
+            Ok(if text.contains("train") {
+                vec![1.0, 0.0]
+            } else if text.contains("fit") {
+                vec![0.8, 0.6]
+            } else {
+                vec![0.0, 1.0]
+            })
+        };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let nodes = vec![
+            NodeChunks {
+                node_id: node_a.clone(),
+                chunks: vec![chunk("func:train", "def train(): pass"), chunk("func:plot", "def plot(): pass")],
+            },
+            NodeChunks {
+                node_id: node_b.clone(),
+                chunks: vec![chunk("func:fit", "def fit(): pass")],
+            },
+        ];
+        rt.block_on(reindex_pipeline_incremental(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::None, &nodes))
+            .unwrap();
+
+        let search = |min_score| {
+            rt.block_on(search_similar_chunks(&embed, &pipeline_id, "nomic-embed-text", "train", 5, min_score))
+                .unwrap()
+        };
+        // Without a threshold every chunk comes back, best first
+        let all: Vec<String> = search(None).into_iter().map(|m| m.chunk_id).collect();
+        assert_eq!(all, vec!["func:train", "func:fit", "func:plot"]);
+
+        // plot (score 0) is dropped, fit (0.8) survives a 0.5 threshold
+        let relevant = search(Some(0.5));
+        assert_eq!(relevant.len(), 2);
+        assert!(relevant.iter().all(|m| m.score >= 0.5));
+        assert!(search(Some(0.9)).iter().all(|m| m.chunk_id == "func:train"));
+        assert!(search(Some(1.1)).is_empty());
+
+        // Nodes are thresholded on their chunks before top_k is applied
+        let nodes = rt
+            .block_on(search_similar_nodes(&embed, &pipeline_id, "nomic-embed-text", "train", 1, Some(0.5)))
+            .unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!((nodes[0].node_id.as_str(), nodes[0].matched_chunks), (node_a.as_str(), 1));
+        let nodes = rt
+            .block_on(search_similar_nodes(&embed, &pipeline_id, "nomic-embed-text", "train", 5, Some(0.9)))
+            .unwrap();
+        assert_eq!(nodes.iter().map(|n| n.node_id.clone()).collect::<Vec<_>>(), vec![node_a]);
+    }
+
+    #[test]
+    fn test_parse_rerank_score() {
+        assert_eq!(parse_rerank_score("8"), Some(0.8));
+        assert_eq!(parse_rerank_score(" Relevance: 7/10."), Some(0.7));
+        assert_eq!(parse_rerank_score("10."), Some(1.0));
+        assert_eq!(parse_rerank_score("2.5"), Some(0.25));
+        assert_eq!(parse_rerank_score("15"), Some(1.0));
+        assert_eq!(parse_rerank_score("Not relevant."), None);
+    }
+
+    #[test]
+    fn test_rerank_reorders_vector_results() {
+        setup_test_db();
+        let pipeline_id = format!("rerank-{}", uuid::Uuid::new_v4());
+        let node = |name: &str| format!("{}-{}", pipeline_id, name);
+        // Code is unique per test run so scores cached by other runs don't apply
+        let code = |body: &str| format!("# {}\n{}", pipeline_id, body);
+        let nodes: Vec<(String, String)> = vec![
+            (node("load"), code("def train_loader(): pass")),
+            (node("fit"), code("def fit(X, y): model.fit(X, y)")),
+            (node("plot"), code("def plot(): pass")),
+        ];
+        let data = serde_json::json!({
+            "nodes": nodes.iter().map(|(id, code)| serde_json::json!({"id": id, "data": {"label": id, "code": code}})).collect::<Vec<_>>(),
+            "edges": [],
+        });
+        db::save_pipeline(&pipeline_id, "rerank", &data.to_string()).unwrap();
+
+        // By vector the loader wins (it says "train"), fit is second
+        let embed = |text: String| async move {
+            Ok(if text.contains("train") {
+                vec![1.0, 0.0]
+            } else if text.contains("fit") {
+                vec![0.8, 0.6]
+            } else {
+                vec![0.0, 1.0]
+            })
+        };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let indexed: Vec<NodeChunks> = nodes
+            .iter()
+            .map(|(id, code)| NodeChunks { node_id: id.clone(), chunks: vec![chunk("toplevel:0", code)] })
+            .collect();
+        rt.block_on(reindex_pipeline_incremental(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::None, &indexed))
+            .unwrap();
+
+        // The judge knows fitting is what training means
+        let calls = AtomicUsize::new(0);
+        let rerank = |prompt: String| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(if prompt.contains("model.fit") { "9".to_string() } else if prompt.contains("train_loader") { "Relevance: 4/10".to_string() } else { "I can't tell".to_string() }) }
+        };
+        let search = |top_k, rerank_top_n| {
+            rt.block_on(search_similar_nodes_reranked(&embed, &rerank, &pipeline_id, "nomic-embed-text", "llama3", "train", top_k, rerank_top_n))
+                .unwrap()
+        };
+
+        let by_vector = rt
+            .block_on(search_similar_nodes(&embed, &pipeline_id, "nomic-embed-text", "train", 3, None))
+            .unwrap();
+        assert_eq!(by_vector.iter().map(|n| n.node_id.clone()).collect::<Vec<_>>(), vec![node("load"), node("fit"), node("plot")]);
+
+        let reranked = search(2, 3);
+        assert_eq!(reranked.iter().map(|n| n.node_id.clone()).collect::<Vec<_>>(), vec![node("fit"), node("load")]);
+        assert_eq!((reranked[0].rerank_score, reranked[1].rerank_score), (0.9, 0.4));
+        assert_eq!(reranked[1].vector_score, by_vector[0].score);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+
+        // Parsed scores are cached; the unparseable answer is asked again
+        let again = search(3, 3);
+        assert_eq!(again.iter().map(|n| n.node_id.clone()).collect::<Vec<_>>(), vec![node("fit"), node("load"), node("plot")]);
+        assert_eq!(again[2].rerank_score, 0.0);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+
+        // Only the top vector candidates are reranked
+        calls.store(0, Ordering::SeqCst);
+        let narrow = search(1, 1);
+        assert_eq!(narrow.iter().map(|n| n.node_id.clone()).collect::<Vec<_>>(), vec![node("load")]);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_reindex_pipeline_incremental() {
+        setup_test_db();
+        let pipeline_id = format!("reindex-{}", uuid::Uuid::new_v4());
+        let node_a = format!("{}-a", pipeline_id);
+        let node_b = format!("{}-b", pipeline_id);
+
+        let calls = AtomicUsize::new(0);
+        let embed = |text: String| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(vec![text.len() as f32, 1.0]) }
+        };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+
+        let first = vec![
+            NodeChunks {
+                node_id: node_a.clone(),
+                chunks: vec![chunk("func:load", "def load(): pass"), chunk("func:train", "def train(): pass")],
+            },
+            NodeChunks {
+                node_id: node_b.clone(),
+                chunks: vec![chunk("toplevel:0", "import pandas")],
+            },
+        ];
+        let summary = rt
+            .block_on(reindex_pipeline_incremental(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::None, &first))
+            .unwrap();
+        assert_eq!(summary, IndexSummary { indexed: 3, skipped: 0, deleted: 0 });
+
+        // load unchanged, train changed, evaluate added, and node b removed
+        let second = vec![NodeChunks {
+            node_id: node_a.clone(),
+            chunks: vec![
+                chunk("func:load", "def load(): pass"),
+                chunk("func:train", "def train(): return 1"),
+                chunk("func:evaluate", "def evaluate(): pass"),
+            ],
+        }];
+        calls.store(0, Ordering::SeqCst);
+        let summary = rt
+            .block_on(reindex_pipeline_incremental(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::None, &second))
+            .unwrap();
+        assert_eq!(summary, IndexSummary { indexed: 2, skipped: 1, deleted: 1 });
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Chunk removed from a surviving node
+        let third = vec![NodeChunks {
+            node_id: node_a.clone(),
+            chunks: vec![chunk("func:load", "def load(): pass")],
+        }];
+        let summary = rt
+            .block_on(reindex_pipeline_incremental(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::None, &third))
+            .unwrap();
+        assert_eq!(summary, IndexSummary { indexed: 0, skipped: 1, deleted: 2 });
+
+        let status = db::get_rag_status(&pipeline_id).unwrap();
+        assert_eq!((status.chunk_count, status.node_count), (1, 1));
+    }
+
+    #[test]
+    fn test_truncated_embedding_is_skipped_and_reindexed() {
+        setup_test_db();
+        let pipeline_id = format!("truncated-{}", uuid::Uuid::new_v4());
+        let node_id = format!("{}-a", pipeline_id);
+        let embed = |text: String| async move { Ok(if text.contains("train") { vec![1.0, 0.0] } else { vec![0.6, 0.8] }) };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let nodes = vec![NodeChunks {
+            node_id: node_id.clone(),
+            chunks: vec![chunk("func:train", "def train(): pass"), chunk("func:fit", "def fit(): pass")],
+        }];
+        rt.block_on(reindex_pipeline_incremental(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::None, &nodes))
+            .unwrap();
+
+        // Simulate a write cut short: same hash, but only 6 of 8 bytes stored
+        let hash = content_hash(&preprocess_chunk(PreprocessMode::None, &nodes[0].chunks[0]));
+        let blob = embedding_to_blob(&[1.0, 0.0]);
+        db::upsert_chunk_embedding(&node_id, &pipeline_id, "func:train", &hash, &blob[..6], "nomic-embed-text", 2, None, None, None, None)
+            .unwrap();
+
+        let listed = db::list_chunk_embeddings_for_pipeline(&pipeline_id).unwrap();
+        assert_eq!(listed.iter().map(|c| c.chunk_id.as_str()).collect::<Vec<_>>(), vec!["func:fit"]);
+        assert_eq!(db::get_chunk_embedding_hash(&node_id, "func:train").unwrap(), None);
+
+        // rank_chunks drops it too, rather than scoring a partial vector
+        let mut corrupt = listed[0].clone();
+        corrupt.chunk_id = "func:train".to_string();
+        corrupt.embedding = blob[..6].to_vec();
+        let ranked = rank_chunks(&[1.0, 0.0], vec![corrupt, listed[0].clone()], 5, None);
+        assert_eq!(ranked.iter().map(|m| m.chunk_id.as_str()).collect::<Vec<_>>(), vec!["func:fit"]);
+
+        // The next index re-embeds the corrupt chunk and search sees it again
+        let summary = rt
+            .block_on(reindex_pipeline_incremental(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::None, &nodes))
+            .unwrap();
+        assert_eq!(summary, IndexSummary { indexed: 1, skipped: 1, deleted: 0 });
+        let found = rt
+            .block_on(search_similar_chunks(&embed, &pipeline_id, "nomic-embed-text", "train", 5, None))
+            .unwrap();
+        assert_eq!(found[0].chunk_id, "func:train");
+    }
+
+    #[test]
+    fn test_search_cache_hits_until_reindex() {
+        setup_test_db();
+        let pipeline_id = format!("cache-{}", uuid::Uuid::new_v4());
+        let node_id = format!("{}-a", pipeline_id);
+
+        let calls = AtomicUsize::new(0);
+        let embed = |text: String| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(if text.contains("train") { vec![1.0, 0.0] } else { vec![0.0, 1.0] }) }
+        };
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let index = |chunks: Vec<CodeChunk>| {
+            let nodes = vec![NodeChunks { node_id: node_id.clone(), chunks }];
+            rt.block_on(reindex_pipeline_incremental(&embed, &pipeline_id, "nomic-embed-text", PreprocessMode::None, &nodes))
+                .unwrap()
+        };
+        let search = |top_k| {
+            rt.block_on(search_similar_chunks(&embed, &pipeline_id, "nomic-embed-text", "train", top_k, None))
+                .unwrap()
+        };
+        index(vec![chunk("func:train", "def train(): pass"), chunk("func:plot", "def plot(): pass")]);
+
+        calls.store(0, Ordering::SeqCst);
+        let first = search(5);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(search(5), first);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        // top_k is applied to the cached ranking
+        assert_eq!(search(1), first[..1].to_vec());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // A reindex that changes nothing leaves the cache valid
+        index(vec![chunk("func:train", "def train(): pass"), chunk("func:plot", "def plot(): pass")]);
+        calls.store(0, Ordering::SeqCst);
+        search(5);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+
+        // Changing the index invalidates it
+        index(vec![chunk("func:train", "def train(): pass")]);
+        calls.store(0, Ordering::SeqCst);
+        let after = search(5);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(after.iter().map(|m| m.chunk_id.as_str()).collect::<Vec<_>>(), vec!["func:train"]);
+        search(5);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        db::delete_chunks_for_node(&node_id).unwrap();
+        assert!(search(5).is_empty());
+    }
+}
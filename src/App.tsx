@@ -27,7 +27,7 @@ import {
   completeTuningSession,
   saveTuningTrial,
 } from "./lib/tauri";
-import { generateTuningCode, generateTuningCodeWithSplit } from "./lib/tunerCodeGen";
+import { generateTuningCode, generateTuningCodeWithSplit, samplerSeed } from "./lib/tunerCodeGen";
 import { generateDataSplitCode } from "./lib/dataSplitCodeGen";
 import { generateEvaluatorCodeWithSplit, generateEvaluatorCode } from "./lib/evaluatorCodeGen";
 import { MODEL_FILE } from "./lib/constants";
@@ -161,7 +161,8 @@ function App() {
           JSON.stringify(config.searchSpace),
           config.sampler === "grid" ? null : config.nTrials,
           config.cvFolds,
-          config.scoringMetric
+          config.scoringMetric,
+          samplerSeed(config)
         );
         setTuningSessionId(sessionId);
 
@@ -215,7 +216,7 @@ function App() {
           appendLog("");
 
           const splitCode = generateDataSplitCode(dataSplitNode.data, inputPath);
-          await runScriptAndWait(splitCode, inputPath, handleOutput);
+          await runScriptAndWait(splitCode, inputPath, handleOutput, undefined, runId ?? undefined);
         }
 
         // Run tuning
@@ -234,7 +235,7 @@ function App() {
           tuningCode = generateTuningCode(trainerNode.data, inputPath, config);
         }
 
-        await runScriptAndWait(tuningCode, inputPath, handleOutput);
+        await runScriptAndWait(tuningCode, inputPath, handleOutput, sessionId ?? undefined, runId ?? undefined);
 
         // Complete tuning session
         if (sessionId) {
@@ -262,7 +263,7 @@ function App() {
               evalCode = generateEvaluatorCode(trainerNode.data, MODEL_FILE, inputPath);
             }
 
-            await runScriptAndWait(evalCode, inputPath, handleOutput);
+            await runScriptAndWait(evalCode, inputPath, handleOutput, undefined, runId ?? undefined);
           }
         }
 
@@ -52,6 +52,7 @@ export function RegisterModelDialog({
   const [query, setQuery] = useState("");
   const [description, setDescription] = useState("");
   const [featureNamesInput, setFeatureNamesInput] = useState("");
+  const [trusted, setTrusted] = useState(false);
 
   const format = detectFormat(modelPath);
   const isNewModel = query.length > 0 && !models.some((m) => m.name === query);
@@ -66,6 +67,7 @@ export function RegisterModelDialog({
       setQuery("");
       setDescription("");
       setFeatureNamesInput("");
+      setTrusted(false);
       setError(null);
     }
   }, [isOpen]);
@@ -131,7 +133,8 @@ export function RegisterModelDialog({
         format,
         run.id,
         metricsSnapshot,
-        featureNames.length > 0 ? featureNames : undefined
+        featureNames.length > 0 ? featureNames : undefined,
+        trusted
       );
 
       onSuccess?.();
@@ -312,6 +315,20 @@ export function RegisterModelDialog({
                     </div>
                   </div>
 
+                  {format === "pickle" && (
+                    <label className="flex items-start gap-2 text-sm text-text-secondary">
+                      <input
+                        type="checkbox"
+                        checked={trusted}
+                        onChange={(e) => setTrusted(e.target.checked)}
+                        className="mt-0.5"
+                      />
+                      <span>
+                        I trust this file. Loading a pickle model can run arbitrary code.
+                      </span>
+                    </label>
+                  )}
+
                   {error && (
                     <div className="text-sm text-state-error bg-state-error/10 rounded-lg px-3 py-2">
                       {error}
@@ -326,7 +343,7 @@ export function RegisterModelDialog({
                   <button
                     onClick={handleRegister}
                     className="btn-primary"
-                    disabled={registering || !query.trim()}
+                    disabled={registering || !query.trim() || (format === "pickle" && !trusted)}
                   >
                     {registering ? (
                       <>
@@ -109,11 +109,13 @@ export function ServerConfigDialog({
 
   const handleNetworkWarningConfirm = () => {
     setShowNetworkWarning(false);
+    // The backend refuses unauthenticated public binds without this opt-in
+    setLocalConfig((prev) => ({ ...prev, allow_public: true }));
   };
 
   const handleNetworkWarningCancel = () => {
     setShowNetworkWarning(false);
-    setLocalConfig((prev) => ({ ...prev, host: "127.0.0.1" }));
+    setLocalConfig((prev) => ({ ...prev, host: "127.0.0.1", allow_public: false }));
   };
 
   return (
@@ -233,6 +233,7 @@ export function Toolbar({
         if (data.rmse !== undefined) collectedMetrics.push({ name: "rmse", value: data.rmse });
         if (data.mae !== undefined) collectedMetrics.push({ name: "mae", value: data.mae });
         if (data.confusionMatrix) collectedMetrics.push({ name: "confusion_matrix", valueJson: JSON.stringify(data.confusionMatrix) });
+        if (data.classLabels) collectedMetrics.push({ name: "class_labels", valueJson: JSON.stringify(data.classLabels) });
       }
     };
 
@@ -248,7 +249,7 @@ export function Toolbar({
         appendLog("");
 
         const splitCode = generateDataSplitCode(dataSplitNode.data, inputPath);
-        await runScriptAndWait(splitCode, inputPath, handleOutput);
+        await runScriptAndWait(splitCode, inputPath, handleOutput, undefined, runId ?? undefined);
       }
 
       if (trainerNode) {
@@ -272,7 +273,7 @@ export function Toolbar({
           appendLog("");
 
           const loadCode = generateLoadModelCode(trainerNode.data.modelFilePath!);
-          await runScriptAndWait(loadCode, inputPath, handleOutput);
+          await runScriptAndWait(loadCode, inputPath, handleOutput, undefined, runId ?? undefined);
         } else {
           appendLog("");
           appendLog("--- Running Trainer ---");
@@ -290,14 +291,14 @@ export function Toolbar({
           } else {
             trainerCode = generateTrainerCode(trainerNode.data, inputPath);
           }
-          await runScriptAndWait(trainerCode, inputPath, handleOutput);
+          await runScriptAndWait(trainerCode, inputPath, handleOutput, undefined, runId ?? undefined);
         }
       } else if (scriptNode) {
         appendLog("--- Running Script ---");
         appendLog(`Input: ${inputPath}`);
         appendLog("");
 
-        await runScriptAndWait(scriptNode.data.code!, inputPath, handleOutput);
+        await runScriptAndWait(scriptNode.data.code!, inputPath, handleOutput, undefined, runId ?? undefined);
       }
 
       if (evaluatorNode) {
@@ -324,7 +325,7 @@ export function Toolbar({
           } else {
             evalCode = generateAutoEvaluatorCode(MODEL_FILE, inputPath);
           }
-          await runScriptAndWait(evalCode, inputPath, handleOutput);
+          await runScriptAndWait(evalCode, inputPath, handleOutput, undefined, runId ?? undefined);
         }
       }
 
@@ -338,7 +339,7 @@ export function Toolbar({
             appendLog("--- Running Model Exporter ---");
 
             const exportCode = generateExporterCode(modelExporterNode.data, MODEL_FILE);
-            await runScriptAndWait(exportCode, inputPath, handleOutput);
+            await runScriptAndWait(exportCode, inputPath, handleOutput, undefined, runId ?? undefined);
           }
         }
       }
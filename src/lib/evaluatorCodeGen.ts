@@ -115,6 +115,7 @@ try:
 
     if is_classifier(model):
         from sklearn.metrics import accuracy_score, precision_score, recall_score, f1_score, confusion_matrix
+        from sklearn.utils.multiclass import unique_labels
 
         print("Model Type: Classifier")
         print("-" * 40)
@@ -143,7 +144,8 @@ try:
                 "precision": float(prec),
                 "recall": float(rec),
                 "f1": float(f1),
-                "confusionMatrix": cm.tolist()
+                "confusionMatrix": cm.tolist(),
+                "classLabels": [str(c) for c in unique_labels(y_test, y_pred)]
             }
         }))
 
@@ -248,6 +250,7 @@ try:
 
     if is_classifier(model):
         from sklearn.metrics import accuracy_score, precision_score, recall_score, f1_score, confusion_matrix
+        from sklearn.utils.multiclass import unique_labels
 
         print("Model Type: Classifier")
         print("-" * 40)
@@ -276,7 +279,8 @@ try:
                 "precision": float(prec),
                 "recall": float(rec),
                 "f1": float(f1),
-                "confusionMatrix": cm.tolist()
+                "confusionMatrix": cm.tolist(),
+                "classLabels": [str(c) for c in unique_labels(y_test, y_pred)]
             }
         }))
 
@@ -378,6 +382,7 @@ try:
 
     if is_classifier(model):
         from sklearn.metrics import accuracy_score, precision_score, recall_score, f1_score, confusion_matrix
+        from sklearn.utils.multiclass import unique_labels
 
         print("Model Type: Classifier")
         print("-" * 40)
@@ -406,7 +411,8 @@ try:
                 "precision": float(prec),
                 "recall": float(rec),
                 "f1": float(f1),
-                "confusionMatrix": cm.tolist()
+                "confusionMatrix": cm.tolist(),
+                "classLabels": [str(c) for c in unique_labels(y_test, y_pred)]
             }
         }))
 
@@ -18,6 +18,7 @@ export interface MetricsData {
   recall?: number;
   f1?: number;
   confusionMatrix?: number[][];
+  classLabels?: string[];
   // Regression metrics
   r2?: number;
   mse?: number;
@@ -42,7 +43,7 @@ export type ScriptEvent =
   | { type: "metrics"; modelType: string; data: MetricsData }
   | { type: "dataProfile"; nodeId: string; data: DataProfile }
   | { type: "complete" }
-  | { type: "exit"; code: number }
+  | { type: "exit"; code: number; scriptPath?: string }
   | { type: "trial"; trialNumber: number; params: Record<string, unknown>; score: number; durationMs?: number }
   | { type: "tuningComplete"; bestParams: Record<string, unknown>; bestScore: number; totalTrials: number; durationMs?: number }
   // Explain events
@@ -72,8 +73,72 @@ export async function findPython(): Promise<PythonInfo | null> {
   return invoke<PythonInfo | null>("find_python");
 }
 
-export async function runScript(scriptCode: string, inputPath: string): Promise<void> {
-  return invoke("run_script", { scriptCode, inputPath });
+export type PythonDiagnosticCode =
+  | "ok"
+  | "not_bundled"
+  | "manifest_invalid"
+  | "binary_missing"
+  | "not_executable"
+  | "import_failed"
+  | "corrupted"
+  | "arch_mismatch"
+  | "io";
+
+export type PythonSource = "bundled" | "saved_setting" | "virtual_env" | "system_path" | "known_location";
+
+export interface PythonDiagnostics {
+  code: PythonDiagnosticCode;
+  message: string | null;
+  bundle_path: string | null;
+  fallback: PythonInfo | null;
+  fallback_source: PythonSource | null;
+}
+
+export async function getPythonDiagnostics(): Promise<PythonDiagnostics> {
+  return invoke<PythonDiagnostics>("get_python_diagnostics");
+}
+
+export interface ResolutionStep {
+  source: PythonSource;
+  path: string | null;
+  accepted: boolean;
+  reason: string | null; // why the candidate was skipped
+}
+
+export interface PythonResolution {
+  steps: ResolutionStep[];
+  winner: PythonInfo | null;
+}
+
+export async function explainPythonResolution(): Promise<PythonResolution> {
+  return invoke<PythonResolution>("explain_python_resolution");
+}
+
+export type VenvProgress =
+  | { stage: "creating" }
+  | { stage: "installing"; package: string; current: number; total: number }
+  | { stage: "done" };
+
+export async function createVenv(destPath: string, installPackages?: boolean): Promise<PythonInfo> {
+  return invoke<PythonInfo>("create_venv", { destPath, installPackages });
+}
+
+export async function listenToVenvProgress(
+  callback: (progress: VenvProgress) => void
+): Promise<UnlistenFn> {
+  return listen<VenvProgress>("venv-progress", (event) => callback(event.payload));
+}
+
+export async function runScript(
+  scriptCode: string,
+  inputPath: string,
+  keepScript?: boolean,
+  keepScriptOnFailure?: boolean,
+  env?: Record<string, string>,
+  runId?: string,
+  sessionId?: string // tuning session to store trial events in as they arrive
+): Promise<void> {
+  return invoke("run_script", { scriptCode, inputPath, keepScript, keepScriptOnFailure, env, runId, sessionId });
 }
 
 export async function cancelScript(): Promise<void> {
@@ -91,7 +156,9 @@ export async function listenToScriptOutput(
 export async function runScriptAndWait(
   scriptCode: string,
   inputPath: string,
-  onOutput?: (event: ScriptEvent) => void
+  onOutput?: (event: ScriptEvent) => void,
+  sessionId?: string,
+  runId?: string // run to record the Python environment against
 ): Promise<number> {
   // Create deferred promise handlers
   let resolvePromise: (code: number) => void;
@@ -117,7 +184,7 @@ export async function runScriptAndWait(
 
   // NOW start script - listener is ready, unlistener is defined
   try {
-    await runScript(scriptCode, inputPath);
+    await runScript(scriptCode, inputPath, undefined, undefined, undefined, runId, sessionId);
   } catch (err) {
     unlistener(); // Cleanup on error
     throw err; // Re-throw to caller
@@ -139,6 +206,26 @@ export async function savePipeline(id: string, name: string, data: string): Prom
   return invoke("save_pipeline", { id, name, data });
 }
 
+export interface NodeTypeSchema {
+  node_type: string;
+  required_fields: string[];
+}
+
+export interface ValidationIssue {
+  node_id: string | null; // null for pipeline-level problems
+  field: string | null;
+  message: string;
+}
+
+// Checks pipeline JSON before import without saving it. nodeTypes defaults to
+// the built-in node types.
+export async function validatePipelineImport(
+  data: string,
+  nodeTypes?: NodeTypeSchema[]
+): Promise<ValidationIssue[]> {
+  return invoke<ValidationIssue[]>("validate_pipeline_import", { data, nodeTypes });
+}
+
 export async function loadPipeline(id: string): Promise<string | null> {
   return invoke<string | null>("load_pipeline", { id });
 }
@@ -151,6 +238,17 @@ export async function deletePipeline(id: string): Promise<void> {
   return invoke("delete_pipeline", { id });
 }
 
+export interface PurgeSummary {
+  pipelines_deleted: number;
+  runs_deleted: number;
+  chunks_deleted: number;
+}
+
+// Deletes the pipeline along with its runs, run artifacts and RAG embeddings
+export async function purgePipeline(pipelineId: string, pipelineName: string): Promise<PurgeSummary> {
+  return invoke<PurgeSummary>("purge_pipeline", { pipelineId, pipelineName });
+}
+
 // Example datasets
 
 export interface ExampleDataset {
@@ -170,6 +268,17 @@ export async function listExampleDatasets(): Promise<ExampleDataset[]> {
   return invoke<ExampleDataset[]>("list_example_datasets");
 }
 
+export interface DatasetPreview {
+  columns: string[];
+  column_types: ("integer" | "float" | "boolean" | "string")[];
+  rows: string[][];
+  truncated: boolean; // the file has more rows than were read
+}
+
+export async function previewDataset(path: string, nRows?: number): Promise<DatasetPreview> {
+  return invoke<DatasetPreview>("preview_dataset", { path, nRows });
+}
+
 // Run history
 
 export interface RunMetadata {
@@ -188,16 +297,20 @@ export interface RunMetadata {
   tags?: string[];          // Joined from run_tags table
 }
 
+export type MetricKind = "scalar" | "array" | "matrix" | "artifact";
+
 export interface Metric {
   name: string;
   value?: number;
   value_json?: string;
+  kind?: MetricKind;
 }
 
 export interface MetricInput {
   name: string;
   value?: number;
   valueJson?: string;
+  kind?: MetricKind; // inferred from value/valueJson when omitted
 }
 
 export async function createRun(pipelineName: string, hyperparameters: object, experimentId?: string): Promise<string> {
@@ -216,14 +329,24 @@ export async function failRun(id: string, error: string): Promise<void> {
   return invoke("fail_run", { id, error });
 }
 
-export async function saveRunMetrics(runId: string, metrics: MetricInput[]): Promise<void> {
+export async function saveRunMetrics(
+  runId: string,
+  metrics: MetricInput[],
+  buffered?: boolean
+): Promise<void> {
   // Convert camelCase valueJson to snake_case value_json for Rust
   const rustMetrics = metrics.map((m) => ({
     name: m.name,
     value: m.value,
     value_json: m.valueJson,
+    kind: m.kind,
   }));
-  return invoke("save_run_metrics", { runId, metrics: rustMetrics });
+  return invoke("save_run_metrics", { runId, metrics: rustMetrics, buffered });
+}
+
+// Writes any metrics buffered via saveRunMetrics(..., true); returns how many were written
+export async function flushRunMetrics(runId: string): Promise<number> {
+  return invoke<number>("flush_run_metrics", { runId });
 }
 
 export async function listRuns(pipelineName?: string, experimentId?: string): Promise<RunMetadata[]> {
@@ -234,10 +357,95 @@ export async function getRunMetrics(runId: string): Promise<Metric[]> {
   return invoke<Metric[]>("get_run_metrics", { runId });
 }
 
+// One metric for many runs in a single call, keyed by run id (null = not recorded)
+export async function getLatestMetricForRuns(
+  runIds: string[],
+  metricName: string
+): Promise<Record<string, number | null>> {
+  return invoke<Record<string, number | null>>("get_latest_metric_for_runs", { runIds, metricName });
+}
+
+export type SmoothingMethod = "simple" | "exponential";
+
+export interface SmoothedPoint {
+  step: number;
+  value: number;
+  smoothed: number;
+  window_min: number;
+  window_max: number;
+}
+
+export interface SmoothedMetric {
+  name: string;
+  window: number;
+  method: SmoothingMethod;
+  points: SmoothedPoint[];
+}
+
+// Smooths an array metric, one element per step
+export async function getRunMetricSmoothed(
+  runId: string,
+  name: string,
+  window: number,
+  method?: SmoothingMethod
+): Promise<SmoothedMetric> {
+  return invoke<SmoothedMetric>("get_run_metric_smoothed", { runId, name, window, method });
+}
+
+export interface ClassMetrics {
+  label: string;
+  precision: number;
+  recall: number;
+  f1: number;
+  support: number;
+}
+
+export interface AveragedMetrics {
+  precision: number;
+  recall: number;
+  f1: number;
+}
+
+export interface ClassificationReport {
+  classes: ClassMetrics[];
+  macro_avg: AveragedMetrics;
+  micro_avg: AveragedMetrics;
+  accuracy: number;
+  support: number;
+}
+
+export async function computeClassificationReport(
+  runId: string,
+  predictionsMetric: string,
+  labelsMetric: string
+): Promise<ClassificationReport> {
+  return invoke<ClassificationReport>("compute_classification_report", {
+    runId,
+    predictionsMetric,
+    labelsMetric,
+  });
+}
+
 export async function deleteRun(id: string): Promise<void> {
   return invoke("delete_run", { id });
 }
 
+export async function deleteRunsForPipeline(pipelineName: string): Promise<number> {
+  return invoke<number>("delete_runs_for_pipeline", { pipelineName });
+}
+
+export async function purgeFailedRuns(olderThanDays: number): Promise<number> {
+  return invoke<number>("purge_failed_runs", { olderThanDays });
+}
+
+export async function compressRunArtifacts(runId: string): Promise<void> {
+  return invoke("compress_run_artifacts", { runId });
+}
+
+export async function decompressRunArtifacts(runId: string): Promise<void> {
+  return invoke("decompress_run_artifacts", { runId });
+}
+
 // Model Registry
 
 export interface ModelMetadata {
@@ -249,6 +457,15 @@ export interface ModelMetadata {
   version_count: number;
   latest_version?: number;
   production_version?: number;
+  max_versions?: number; // retention count for pruneOldVersions, unset = keep all
+}
+
+export interface RegistrySummary {
+  model_count: number;
+  version_count: number;
+  stage_counts: Record<string, number>; // every stage, zero if unused
+  total_file_bytes: number;
+  latest_model?: ModelMetadata;
 }
 
 export interface ModelVersion {
@@ -271,6 +488,8 @@ export interface ModelVersion {
   coreml_path?: string;
   n_features?: number;
   tags?: string[];
+  trusted: boolean; // pickle versions must be trusted to be served
+  last_used_at?: string; // v19: last time a server loaded this version
 }
 
 export interface RegisterVersionResult {
@@ -290,6 +509,10 @@ export async function getModel(modelId: string): Promise<ModelMetadata | null> {
   return invoke<ModelMetadata | null>("get_model", { modelId });
 }
 
+export async function getRegistrySummary(): Promise<RegistrySummary> {
+  return invoke<RegistrySummary>("get_registry_summary");
+}
+
 export async function deleteModel(modelId: string): Promise<void> {
   return invoke("delete_model", { modelId });
 }
@@ -300,7 +523,9 @@ export async function registerModelVersion(
   format: string,
   runId?: string,
   metricsSnapshot?: string,
-  featureNames?: string[]
+  featureNames?: string[],
+  trusted?: boolean,
+  snapshotFromRun?: boolean
 ): Promise<RegisterVersionResult> {
   return invoke<RegisterVersionResult>("register_model_version", {
     modelId,
@@ -309,9 +534,72 @@ export async function registerModelVersion(
     format,
     metricsSnapshot,
     featureNames: featureNames ? JSON.stringify(featureNames) : undefined,
+    trusted,
+    snapshotFromRun,
+  });
+}
+
+// Registers the version directly in the given stage; nothing is registered
+// if the promotion fails (e.g. the promotion rule rejects it)
+export async function registerAndPromote(
+  modelId: string,
+  sourcePath: string,
+  format: string,
+  stage: string,
+  runId?: string,
+  metricsSnapshot?: string,
+  featureNames?: string[],
+  trusted?: boolean,
+  snapshotFromRun?: boolean
+): Promise<RegisterVersionResult> {
+  return invoke<RegisterVersionResult>("register_and_promote", {
+    modelId,
+    runId,
+    sourcePath,
+    format,
+    stage,
+    metricsSnapshot,
+    featureNames: featureNames ? JSON.stringify(featureNames) : undefined,
+    trusted,
+    snapshotFromRun,
   });
 }
 
+// Registers a model file trained outside the app (e.g. in Colab), creating
+// the model if no model has this name
+export async function importExternalModel(
+  modelName: string,
+  sourcePath: string,
+  format: string,
+  featureNames?: string[],
+  metricsJson?: string,
+  trusted?: boolean
+): Promise<RegisterVersionResult> {
+  return invoke<RegisterVersionResult>("import_external_model", {
+    modelName,
+    sourcePath,
+    format,
+    featureNames,
+    metricsJson,
+    trusted,
+  });
+}
+
+export interface ArtifactSpec {
+  file_name: string; // relative to the run's artifact directory
+  format: string;
+  trusted?: boolean;
+}
+
+// Registers all artifacts as consecutive versions, or none on failure
+export async function registerVersionsFromRun(
+  modelId: string,
+  runId: string,
+  artifacts: ArtifactSpec[]
+): Promise<string[]> {
+  return invoke<string[]>("register_versions_from_run", { modelId, runId, artifacts });
+}
+
 export async function listModelVersions(modelId: string): Promise<ModelVersion[]> {
   return invoke<ModelVersion[]>("list_model_versions", { modelId });
 }
@@ -320,14 +608,41 @@ export async function promoteModel(versionId: string, stage: string): Promise<vo
   return invoke("promote_model", { versionId, stage });
 }
 
+export interface MetricDelta {
+  metric: string;
+  candidate: number | null;
+  production: number | null;
+  delta: number | null; // candidate - production
+  regression: boolean;
+}
+
+export async function compareCandidateToProduction(versionId: string): Promise<MetricDelta[]> {
+  return invoke<MetricDelta[]>("compare_candidate_to_production", { versionId });
+}
+
 export async function deleteModelVersion(versionId: string): Promise<void> {
   return invoke("delete_model_version", { versionId });
 }
 
+export async function setModelMaxVersions(modelId: string, maxVersions: number | null): Promise<void> {
+  return invoke("set_model_max_versions", { modelId, maxVersions });
+}
+
+// Archives (or with deleteVersions, deletes) the oldest versions beyond the
+// model's max_versions, never production/staging. Returns the pruned ids.
+export async function pruneOldVersions(modelId: string, deleteVersions = false): Promise<string[]> {
+  return invoke<string[]>("prune_old_versions", { modelId, delete: deleteVersions });
+}
+
 export async function getModelFilePath(versionId: string): Promise<string | null> {
   return invoke<string | null>("get_model_file_path", { versionId });
 }
 
+// Groups of version ids whose model files are byte-identical
+export async function findDuplicateVersions(modelId: string): Promise<string[][]> {
+  return invoke<string[][]>("find_duplicate_versions", { modelId });
+}
+
 export async function getModelVersion(versionId: string): Promise<ModelVersion | null> {
   return invoke<ModelVersion | null>("get_model_version", { versionId });
 }
@@ -355,20 +670,46 @@ export interface PredictionResult {
   probabilities?: number[][];
   classes?: (string | number)[];
   message?: string;
+  code?: InferenceErrorCode;
+  guidance?: string;
 }
 
-export async function startInferenceServer(versionId: string): Promise<ServerStatus> {
-  return invoke<ServerStatus>("start_inference_server", { versionId });
+export type InferenceErrorCode =
+  | "model_not_loaded"
+  | "feature_mismatch"
+  | "invalid_dtype"
+  | "empty_input"
+  | "invalid_request"
+  | "prediction_failed"
+  | "unknown";
+
+export type InferenceAuditMode = "full" | "hashed";
+
+export async function startInferenceServer(
+  versionId: string,
+  audit?: boolean,
+  auditMode?: InferenceAuditMode
+): Promise<ServerStatus> {
+  return invoke<ServerStatus>("start_inference_server", { versionId, audit, auditMode });
 }
 
 export async function stopInferenceServer(): Promise<void> {
   return invoke("stop_inference_server");
 }
 
+// Reload the running server's version, e.g. after its model file was re-exported
+export async function restartInferenceServer(): Promise<ServerStatus> {
+  return invoke<ServerStatus>("restart_inference_server");
+}
+
 export async function getInferenceServerStatus(versionId?: string): Promise<ServerStatus> {
   return invoke<ServerStatus>("get_inference_server_status", { versionId });
 }
 
+export async function assertServingVersion(expectedVersionId: string): Promise<void> {
+  return invoke("assert_serving_version", { expectedVersionId });
+}
+
 export async function runInference(
   requestId: string,
   input: Record<string, unknown> | Record<string, unknown>[]
@@ -470,6 +811,24 @@ export async function runBatchInference(
   };
 }
 
+export interface PredictCsvProgress {
+  rows_done: number;
+  bytes_read: number;
+  total_bytes: number;
+}
+
+// Scores a CSV file with the running inference server, streaming rows plus a
+// prediction column to destPath. Resolves to the number of rows scored.
+export async function predictCsv(versionId: string, csvPath: string, destPath: string): Promise<number> {
+  return invoke<number>("predict_csv", { versionId, csvPath, destPath });
+}
+
+export async function listenToPredictCsvProgress(
+  callback: (progress: PredictCsvProgress) => void
+): Promise<UnlistenFn> {
+  return listen<PredictCsvProgress>("predict-csv-progress", (event) => callback(event.payload));
+}
+
 // Tuning
 
 export async function checkPythonPackage(packageName: string): Promise<boolean> {
@@ -488,6 +847,7 @@ export interface TuningSession {
   best_trial_id?: string;
   created_at: string;
   completed_at?: string;
+  seed?: number;
 }
 
 export interface TuningTrial {
@@ -508,7 +868,8 @@ export async function createTuningSession(
   searchSpace: string,
   nTrials: number | null,
   cvFolds: number,
-  scoringMetric: string
+  scoringMetric: string,
+  seed: number | null = null
 ): Promise<string> {
   return invoke<string>("create_tuning_session", {
     runId,
@@ -517,6 +878,7 @@ export async function createTuningSession(
     nTrials,
     cvFolds,
     scoringMetric,
+    seed,
   });
 }
 
@@ -579,6 +941,10 @@ export async function createExperiment(name: string, description?: string): Prom
   return invoke<string>("create_experiment", { name, description });
 }
 
+export async function getOrCreateExperiment(name: string, description?: string): Promise<string> {
+  return invoke<string>("get_or_create_experiment", { name, description });
+}
+
 export async function updateExperiment(
   id: string,
   name?: string,
@@ -600,6 +966,36 @@ export async function deleteExperiment(id: string): Promise<void> {
   return invoke("delete_experiment", { id });
 }
 
+export async function exportExperimentNdjson(experimentId: string, destPath: string): Promise<number> {
+  return invoke<number>("export_experiment_ndjson", { experimentId, destPath });
+}
+
+export interface CorrelationMatrix {
+  columns: string[]; // metric names, sorted
+  matrix: (number | null)[][]; // null when fewer than 3 runs share the pair or a metric is constant
+  run_count: number;
+}
+
+export async function getExperimentMetricCorrelations(experimentId: string): Promise<CorrelationMatrix> {
+  return invoke<CorrelationMatrix>("get_experiment_metric_correlations", { experimentId });
+}
+
+export interface HyperparamImpact {
+  name: string;
+  kind: "numeric" | "categorical";
+  impact: number; // share of metric variance explained (r² or eta²), 0..1
+  correlation: number | null; // signed Pearson r, numeric only
+  run_count: number;
+}
+
+// Ranked by impact; hyperparameters set on fewer than 3 scored runs are left out
+export async function getHyperparameterImpact(
+  experimentId: string,
+  metricName: string
+): Promise<HyperparamImpact[]> {
+  return invoke<HyperparamImpact[]>("get_hyperparameter_impact", { experimentId, metricName });
+}
+
 // Run Annotations
 
 export async function updateRunDisplayName(id: string, displayName?: string): Promise<void> {
@@ -690,6 +1086,10 @@ export async function updateModelVersionTrainingInfo(
   return invoke("update_model_version_training_info", { versionId, nFeatures, featureNames });
 }
 
+export async function validateModelVersionSchema(versionId: string): Promise<void> {
+  return invoke("validate_model_version_schema", { versionId });
+}
+
 export async function updateModelVersionExportPath(
   versionId: string,
   onnxPath?: string,
@@ -737,10 +1137,15 @@ export interface HttpServerConfig {
   port: number;
   use_onnx: boolean;
   cors_origins?: string[];
+  recent_buffer_size?: number; // recent requests kept for the metrics panel (default 100, max 10000)
+  rate_limit?: string; // e.g. "100/minute"
+  api_key?: string; // required as X-API-Key or Bearer token on every route but /health
+  allow_public?: boolean; // bind to a non-loopback host without an api_key
 }
 
 export interface HttpServerStatus {
   running: boolean;
+  starting: boolean; // started with startHttpServerAsync and not ready yet
   host?: string;
   port?: number;
   version_id?: string;
@@ -748,6 +1153,7 @@ export interface HttpServerStatus {
   runtime?: string;
   model_info?: ModelInfo;
   url?: string;
+  healthy?: boolean; // result of the last probeHttpServer, if any
 }
 
 export interface HttpRequestLog {
@@ -764,6 +1170,7 @@ export interface HttpServerMetrics {
   total_requests: number;
   successful_requests: number;
   failed_requests: number;
+  rate_limited_requests: number; // 429s, included in failed_requests
   avg_latency_ms: number;
   requests_per_minute: number;
   recent_requests: HttpRequestLog[];
@@ -783,10 +1190,40 @@ export async function startHttpServer(
     port: config?.port ?? 8080,
     use_onnx: config?.use_onnx ?? false,
     cors_origins: config?.cors_origins,
+    recent_buffer_size: config?.recent_buffer_size,
+    rate_limit: config?.rate_limit,
+    api_key: config?.api_key,
+    allow_public: config?.allow_public,
   };
   return invoke<HttpServerStatus>("start_http_server", { versionId, config: fullConfig });
 }
 
+// Returns once the process is spawned; listen for http-server-ready / http-server-error
+export async function startHttpServerAsync(
+  versionId: string,
+  config?: Partial<HttpServerConfig>
+): Promise<HttpServerStatus> {
+  const fullConfig: HttpServerConfig = {
+    host: config?.host ?? "127.0.0.1",
+    port: config?.port ?? 8080,
+    use_onnx: config?.use_onnx ?? false,
+    cors_origins: config?.cors_origins,
+    recent_buffer_size: config?.recent_buffer_size,
+    rate_limit: config?.rate_limit,
+    api_key: config?.api_key,
+    allow_public: config?.allow_public,
+  };
+  return invoke<HttpServerStatus>("start_http_server_async", { versionId, config: fullConfig });
+}
+
+export async function listenToHttpServerReady(
+  callback: (status: HttpServerStatus) => void
+): Promise<UnlistenFn> {
+  return listen<HttpServerStatus>("http-server-ready", (event) => {
+    callback(event.payload);
+  });
+}
+
 export async function stopHttpServer(): Promise<void> {
   return invoke("stop_http_server");
 }
@@ -795,6 +1232,21 @@ export async function getHttpServerStatus(): Promise<HttpServerStatus> {
   return invoke<HttpServerStatus>("get_http_server_status");
 }
 
+export async function probeHttpServer(): Promise<boolean> {
+  return invoke<boolean>("probe_http_server");
+}
+
+export interface ServingReadiness {
+  process_running: boolean;
+  model_loaded: boolean;
+  warmup_done: boolean; // answered its first prediction
+  last_health_ok: boolean | null; // HTTP server's last probe
+}
+
+export async function getServingReadiness(versionId: string): Promise<ServingReadiness> {
+  return invoke<ServingReadiness>("get_serving_readiness", { versionId });
+}
+
 export async function getHttpServerMetrics(): Promise<HttpServerMetrics> {
   return invoke<HttpServerMetrics>("get_http_server_metrics");
 }
@@ -827,10 +1279,20 @@ export async function listenToHttpServerError(
   });
 }
 
+export interface HttpLogEntry {
+  level: string;
+  message: string;
+  timestamp: number;
+}
+
+export async function getHttpServerLogs(limit?: number): Promise<HttpLogEntry[]> {
+  return invoke<HttpLogEntry[]>("get_http_server_logs", { limit });
+}
+
 export async function listenToHttpServerLog(
-  callback: (log: string) => void
+  callback: (log: HttpLogEntry) => void
 ): Promise<UnlistenFn> {
-  return listen<string>("http-server-log", (event) => {
+  return listen<HttpLogEntry>("http-server-log", (event) => {
     callback(event.payload);
   });
 }
@@ -916,14 +1378,18 @@ export async function checkPyright(): Promise<PyrightInfo> {
   return invoke<PyrightInfo>("check_pyright");
 }
 
-export async function startLspServer(workspaceRoot?: string): Promise<void> {
-  return invoke("start_lsp_server", { workspaceRoot });
+export async function startLspServer(workspaceRoot?: string, pipelineId?: string): Promise<void> {
+  return invoke("start_lsp_server", { workspaceRoot, pipelineId });
 }
 
 export async function stopLspServer(): Promise<void> {
   return invoke("stop_lsp_server");
 }
 
+export async function cancelLspStart(): Promise<boolean> {
+  return invoke<boolean>("cancel_lsp_start");
+}
+
 export async function lspRequest<T = unknown>(method: string, params: unknown): Promise<T> {
   return invoke<T>("lsp_request", { method, params });
 }
@@ -936,10 +1402,23 @@ export async function lspCancelRequest(requestId: number): Promise<void> {
   return invoke("lsp_cancel_request", { requestId });
 }
 
+export async function exportLspDiagnostics(
+  uri: string,
+  destPath: string,
+  text?: string
+): Promise<number> {
+  return invoke<number>("export_lsp_diagnostics", { uri, destPath, text });
+}
+
 export async function getLspStatus(): Promise<LspStatus> {
   return invoke<LspStatus>("get_lsp_status");
 }
 
+// Raw ServerCapabilities from pyright's initialize response; null until initialized
+export async function getLspCapabilities(): Promise<Record<string, unknown> | null> {
+  return invoke<Record<string, unknown> | null>("get_lsp_capabilities");
+}
+
 export async function listenToLspDiagnostics(
   callback: (params: LspPublishDiagnosticsParams) => void
 ): Promise<UnlistenFn> {
@@ -1023,3 +1502,99 @@ export async function deleteChunksForNode(nodeId: string): Promise<number> {
 export async function deleteChunksForPipeline(pipelineId: string): Promise<number> {
   return invoke<number>("delete_chunks_for_pipeline", { pipelineId });
 }
+
+export interface RerankedNode {
+  nodeId: string;
+  vectorScore: number;
+  rerankScore: number; // 0-1
+  matchedChunks: number;
+}
+
+// Vector search for the best rerankTopN nodes, re-scored by asking rerankModel
+// how relevant each node's saved code is to the query
+export async function searchSimilarNodesReranked(
+  model: string,
+  rerankModel: string,
+  pipelineId: string,
+  query: string,
+  topK?: number,
+  rerankTopN?: number,
+  host?: string,
+  backend?: "ollama" | "openai_compatible"
+): Promise<RerankedNode[]> {
+  return invoke<RerankedNode[]>("search_similar_nodes_reranked", {
+    host,
+    model,
+    rerankModel,
+    pipelineId,
+    query,
+    topK,
+    rerankTopN,
+    backend,
+  });
+}
+
+// Database
+
+export async function getDbVersion(): Promise<number> {
+  return invoke<number>("get_db_version");
+}
+
+export async function relocatePaths(newAppDataDir: string): Promise<number> {
+  return invoke<number>("relocate_paths", { newAppDataDir });
+}
+
+export interface FileAudit {
+  version_id: string;
+  model_id: string;
+  version: number;
+  stage: string;
+  model_file_missing: boolean;
+  onnx_missing: boolean;
+  coreml_missing: boolean;
+  missing_paths: string[];
+}
+
+export type RepairStrategy = "archive" | "delete";
+
+export async function auditModelFiles(): Promise<FileAudit[]> {
+  return invoke<FileAudit[]>("audit_model_files");
+}
+
+// Missing exports are always just unlinked; versions without a model file are
+// archived or deleted per strategy. Returns the audits found.
+export async function repairModelFiles(strategy: RepairStrategy): Promise<FileAudit[]> {
+  return invoke<FileAudit[]>("repair_model_files", { strategy });
+}
+
+// System status
+
+export interface ProcessInfo {
+  subsystem: "script" | "inference_server" | "http_server" | "lsp";
+  running: boolean;
+  pid?: number;
+  version_id?: string;
+  run_id?: string;
+  pipeline?: string; // the run's pipeline, or the LSP workspace root
+  uptime_secs?: number;
+}
+
+export async function listRunningProcesses(): Promise<ProcessInfo[]> {
+  return invoke<ProcessInfo[]>("list_running_processes");
+}
+
+export type LogLevel = "off" | "error" | "warn" | "info" | "debug" | "trace";
+
+export async function setLogLevel(level: LogLevel): Promise<void> {
+  return invoke("set_log_level", { level });
+}
+
+export async function getRecentLogs(nLines: number): Promise<string[]> {
+  return invoke<string[]>("get_recent_logs", { nLines });
+}
+
+// Writes a .zip of the database, recent logs, Python diagnostics and a
+// process manifest, with app data and home paths redacted
+export async function createSupportBundle(destPath: string): Promise<void> {
+  return invoke("create_support_bundle", { destPath });
+}
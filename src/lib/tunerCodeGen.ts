@@ -135,16 +135,23 @@ function generateGridSearchSpaceDict(searchSpace: Record<string, ParamSpec>): st
   return `{${entries.join(", ")}}`;
 }
 
+export const DEFAULT_SAMPLER_SEED = 42;
+
+// Seed the sampler runs with (grid search has no randomness to seed)
+export function samplerSeed(config: TuningConfig): number | null {
+  return config.sampler === "grid" ? null : config.seed ?? DEFAULT_SAMPLER_SEED;
+}
+
 // Generate sampler initialization code
 function generateSamplerCode(config: TuningConfig): string {
   if (config.sampler === "grid") {
     const searchSpaceDict = generateGridSearchSpaceDict(config.searchSpace);
     return `sampler = optuna.samplers.GridSampler(${searchSpaceDict})`;
   } else if (config.sampler === "random") {
-    return `sampler = optuna.samplers.RandomSampler(seed=42)`;
+    return `sampler = optuna.samplers.RandomSampler(seed=${samplerSeed(config)})`;
   } else {
     // bayesian (TPE)
-    return `sampler = optuna.samplers.TPESampler(seed=42)`;
+    return `sampler = optuna.samplers.TPESampler(seed=${samplerSeed(config)})`;
   }
 }
 
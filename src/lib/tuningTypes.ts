@@ -23,6 +23,7 @@ export interface TuningConfig {
   cvFolds: number;
   scoringMetric: string;
   searchSpace: Record<string, ParamSpec>;
+  seed?: number; // For Random/Bayesian only; defaults to DEFAULT_SAMPLER_SEED
 }
 
 // Trial result from a single hyperparameter combination